@@ -0,0 +1,74 @@
+//! Adaptive chunk sizing (`--adaptive`).
+//!
+//! Statement sizes differ widely between datasets and within one (a run of
+//! long literals, then short type statements), so a fixed record count can
+//! produce chunks of very different sizes.  [`AdaptiveSize`] keeps a moving
+//! average of the bytes per record of the chunks written so far, measured
+//! on disk after compression and encryption, and sizes every next chunk so
+//! it lands near the target.
+
+/// Weight of the newest chunk in the moving average.
+const WEIGHT: f64 = 0.5;
+
+/// Record count estimate for chunks of about `target` bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptiveSize {
+    target: u64,
+    /// Moving average of bytes per record; `None` before the first chunk.
+    bytes_per_record: Option<f64>,
+}
+
+impl AdaptiveSize {
+    pub fn new(target: u64) -> Self {
+        Self {
+            target,
+            bytes_per_record: None,
+        }
+    }
+
+    /// Take a finished chunk of `records` records and `bytes` bytes into
+    /// the average.
+    pub fn observe(&mut self, bytes: u64, records: usize) {
+        if records == 0 {
+            return;
+        }
+        let size = bytes as f64 / records as f64;
+        self.bytes_per_record = Some(match self.bytes_per_record {
+            Some(avg) => WEIGHT * size + (1.0 - WEIGHT) * avg,
+            None => size,
+        });
+    }
+
+    /// Records for the next chunk; `initial` until a chunk was observed.
+    pub fn records(&self, initial: usize) -> usize {
+        match self.bytes_per_record {
+            Some(avg) if avg > 0.0 => ((self.target as f64 / avg).round() as usize).max(1),
+            _ => initial,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_the_record_size() {
+        let mut size = AdaptiveSize::new(1000);
+        assert_eq!(size.records(7), 7);
+        size.observe(500, 10);
+        assert_eq!(size.records(7), 20);
+        // records twice as large halve the next chunk's share of the average
+        size.observe(1000, 10);
+        assert_eq!(size.records(7), 13);
+        size.observe(0, 0);
+        assert_eq!(size.records(7), 13);
+    }
+
+    #[test]
+    fn never_asks_for_an_empty_chunk() {
+        let mut size = AdaptiveSize::new(10);
+        size.observe(1000, 1);
+        assert_eq!(size.records(7), 1);
+    }
+}
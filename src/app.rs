@@ -0,0 +1,714 @@
+//! The `rdfsplitter` command: argument parsing, the subcommands and the
+//! split run over all inputs, behind [`crate::run_cli`].
+
+use std::{
+    cell::RefCell,
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::Arc,
+    time::{Instant, SystemTime},
+};
+
+use clap::Parser;
+use log::{error, info};
+
+use crate::{
+    checks, checksum, chunksize,
+    compress::CompressionTuning,
+    convert::{self, ConvertSummary},
+    count, dedupe, delta,
+    expr::FilterExpr,
+    filter::StatementFilter,
+    generate,
+    graphname::GraphFromFilename,
+    inputs::{expand_inputs_skipping, input_format, read_iri_list, SkipReason, SkippedInput},
+    jsonld::Compaction,
+    jsonmap::JsonMapping,
+    layout, manifest,
+    metadata::DatasetMetadata,
+    partition::Partitioning,
+    progress,
+    rejects::BadRecords,
+    remote, repack,
+    report::{RunReport, RunTotals},
+    serialise::OwnedQuad,
+    space, splitter, stale, stats, split_file, RdfFormat, SplitOptions, SplitterError,
+};
+
+use crate::{
+    cli::{Cli, Command},
+    explain, intake, profile, queue,
+};
+
+pub fn main() {
+    let cli = match expand_command(Cli::parse()) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            process::exit(1);
+        }
+    };
+    init_logging(&cli);
+    let code = match run_command(&cli) {
+        Some(code) => code,
+        None => run_split(cli),
+    };
+    if code != 0 {
+        process::exit(code);
+    }
+}
+
+/// A profile, `split` and `merge` are expanded into ordinary arguments
+/// and parsed again.
+fn expand_command(cli: Cli) -> anyhow::Result<Cli> {
+    let args = match &cli.command {
+        Some(Command::Run { profile, profile_file }) => {
+            profile::profile_args(profile_file, profile)?
+        }
+        Some(Command::Split { args }) => args.clone(),
+        Some(Command::Merge { name, args }) => {
+            ["--merge".to_owned(), name.clone()].into_iter().chain(args.clone()).collect()
+        }
+        _ => return Ok(cli),
+    };
+    Ok(Cli::parse_from(std::iter::once("rdfsplitter".into()).chain(args)))
+}
+
+fn init_logging(cli: &Cli) {
+    let level = if cli.verbose { "debug" } else { "info" };
+    env_logger::Builder::new()
+        .filter_level(level.parse().unwrap())
+        .format_target(false)
+        .format_timestamp(None)
+        .target(env_logger::Target::Pipe(Box::new(progress::Stderr)))
+        .init();
+    if cli.no_progress {
+        progress::disable();
+    }
+}
+
+/// Exit code of a command returning how many of its inputs failed: 0, 2
+/// when some failed, or 1 when it could not run.
+fn exit_code<E: std::fmt::Display>(result: Result<usize, E>) -> i32 {
+    match result {
+        Ok(0) => 0,
+        Ok(_) => 2,
+        Err(e) => {
+            error!("{e:#}");
+            1
+        }
+    }
+}
+
+/// Run a subcommand, the job queue or `--stdin-list` and give its exit
+/// code; `None` for a split run.
+fn run_command(cli: &Cli) -> Option<i32> {
+    let code = match &cli.command {
+        Some(Command::Queue { dir }) => exit_code(queue::run_queue(dir)),
+        _ if cli.stdin_list => exit_code(intake::run_intake(cli)),
+        Some(Command::Count { inputs, json, format, recursive }) => {
+            exit_code(count::run_count(inputs, *recursive, *format, *json))
+        }
+        Some(Command::Validate { inputs, format, recursive }) => {
+            exit_code(count::run_validate(inputs, *recursive, *format))
+        }
+        Some(Command::Check { dir, manifest }) => exit_code(manifest::run_check(dir, manifest)),
+        Some(Command::Repack { dir, max_bytes, output, force }) => {
+            let opts = SplitOptions {
+                output_dir: output.clone(),
+                max_bytes: Some(*max_bytes),
+                force: *force,
+                ..Default::default()
+            };
+            exit_code(repack::run_repack(dir, opts))
+        }
+        Some(Command::Convert { .. }) => {
+            exit_code(run_convert_command(cli)?.map(|summary| summary.failed))
+        }
+        Some(Command::Generate { triples, classes, format, seed, output, force }) => {
+            let shape = generate::Shape {
+                triples: *triples,
+                classes: *classes as usize,
+                graphs: format.has_graphs(),
+                seed: *seed,
+            };
+            exit_code(write_generated(shape, *format, output.as_deref(), *force).map(|()| 0))
+        }
+        Some(Command::Stats { inputs, top, classes_csv, recursive }) => exit_code(
+            stats::run_stats(inputs, *recursive, *top, classes_csv.as_deref()).map(|()| 0),
+        ),
+        Some(Command::Delta {
+            old,
+            new,
+            chunk_size,
+            delta_format,
+            max_bytes,
+            output,
+            force,
+        }) => {
+            let opts = SplitOptions {
+                output_dir: output.clone(),
+                chunk_size: (*chunk_size).max(1),
+                force: *force,
+                ..Default::default()
+            };
+            let delta_output = delta::DeltaOutput {
+                format: *delta_format,
+                max_bytes: *max_bytes,
+            };
+            exit_code(delta::run_delta(old, new, &opts, delta_output).map(|_| 0))
+        }
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Split the inputs and give the exit code; `--json` adds a summary line.
+fn run_split(cli: Cli) -> i32 {
+    let json = cli.json;
+    let started = Instant::now();
+    let result = run(cli);
+    let code = match &result {
+        Ok(totals) if totals.errors > 0 => 2,
+        Ok(_) => 0,
+        Err(e) => {
+            error!("{e}");
+            1
+        }
+    };
+    if json {
+        let errors = result.as_ref().map_or(1, |totals| totals.errors);
+        let mut summary = queue::job_report(result.map_err(Into::into));
+        summary["type"] = "summary".into();
+        summary["errors"] = errors.into();
+        summary["seconds"] = started.elapsed().as_secs_f64().into();
+        println!("{summary}");
+    }
+    code
+}
+
+
+/// `convert`: write every input whole in the `--to` format; `None` for
+/// another command.
+pub fn run_convert_command(cli: &Cli) -> Option<Result<ConvertSummary, SplitterError>> {
+    let Some(Command::Convert { inputs, to, format, prefixes, output, force, recursive }) =
+        &cli.command
+    else {
+        return None;
+    };
+    let opts = SplitOptions {
+        output_dir: output.clone(),
+        output_format: Some(*to),
+        prefixes: prefixes.clone(),
+        force: *force,
+        ..Default::default()
+    };
+    Some(convert::run_convert(inputs, *recursive, *format, opts))
+}
+
+/// `generate`: write the synthetic statements to `output` or standard output.
+fn write_generated(
+    shape: generate::Shape,
+    format: RdfFormat,
+    output: Option<&Path>,
+    force: bool,
+) -> Result<(), SplitterError> {
+    let w: Box<dyn Write> = match output {
+        Some(path) if path.exists() && !force => {
+            return Err(SplitterError::OutputExists(path.display().to_string()));
+        }
+        Some(path) => Box::new(BufWriter::new(fs::File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    generate::write(shape, format, w)?.flush()?;
+    Ok(())
+}
+
+/// --dedupe-memory under --low-memory, in MiB.
+const LOW_MEMORY_DEDUPE_MIB: usize = 32;
+/// --max-line-bytes under --low-memory.
+const LOW_MEMORY_MAX_LINE_BYTES: usize = 16 << 20;
+
+/// Descriptors left to inputs, spill files and reports under the default
+/// `--max-open-files`.
+const RESERVED_FILES: usize = 32;
+
+/// The default `--max-open-files`: the soft `ulimit -n` less
+/// [`RESERVED_FILES`]; `None` where there is no such limit.
+fn open_file_budget() -> Option<usize> {
+    #[cfg(unix)]
+    {
+        let mut limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+        // SAFETY: getrlimit only writes the struct it is given
+        if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0
+            || limit.rlim_cur == libc::RLIM_INFINITY
+        {
+            return None;
+        }
+        let soft = usize::try_from(limit.rlim_cur).unwrap_or(usize::MAX);
+        Some(soft.saturating_sub(RESERVED_FILES).max(4))
+    }
+    #[cfg(not(unix))]
+    None
+}
+
+/// `--low-memory`: shrink the buffers that can be shrunk.  Options holding
+/// whole inputs in memory are refused by the argument parser.
+fn apply_low_memory(cli: &mut Cli) {
+    cli.dedupe_memory = cli.dedupe_memory.min(LOW_MEMORY_DEDUPE_MIB);
+    if cli.max_line_bytes == 0 || cli.max_line_bytes > LOW_MEMORY_MAX_LINE_BYTES {
+        cli.max_line_bytes = LOW_MEMORY_MAX_LINE_BYTES;
+    }
+    cli.compress_threads = Some(1);
+}
+
+pub fn run(mut cli: Cli) -> Result<RunTotals, SplitterError> {
+    let started = SystemTime::now();
+    if cli.low_memory {
+        apply_low_memory(&mut cli);
+    }
+    let (files, skipped) = resolve_inputs(&cli)?;
+    let metadata = dataset_metadata(&cli);
+    let mut opts = split_options(&cli, &files, &metadata)?;
+    let mut report = RunReport::new(&cli, started, metadata, skipped);
+    // statements counted for --exact-chunks / --file-count, deduplicated
+    // across inputs as the split will
+    let counting = cli.exact_chunks.is_some() || cli.file_count.is_some();
+    let mut counted_seen = (counting && cli.dedupe_inputs)
+        .then(|| dedupe::SeenSet::new(cli.dedupe_memory, cli.dedupe_key, cli.hash));
+
+    // appended partitions accumulate the inputs sharing a stem
+    if cli.merge.is_none() && cli.stdout.is_none() && !cli.append {
+        checks::check_collisions(&files, &opts)?;
+    }
+
+    if let Some(keep_free) = cli.min_free_space {
+        let known: Vec<(PathBuf, RdfFormat)> = files
+            .iter()
+            .filter_map(|f| Some((f.clone(), cli.format.or_else(|| RdfFormat::from_path(f))?)))
+            .collect();
+        space::check(&known, keep_free, &opts)?;
+    }
+
+    if cli.dry_run {
+        println!("chunk\trecords\tbytes");
+    }
+
+    // every input is split on its own, or all of them as one with --merge
+    let units: Vec<&[PathBuf]> = match &cli.merge {
+        Some(_) => vec![files.as_slice()],
+        None => files.chunks(1).collect(),
+    };
+
+    for (i, &unit) in units.iter().enumerate() {
+        if cli.fail_fast && report.errors() > 0 {
+            let left: usize = units[i..].iter().map(|u| u.len()).sum();
+            log::warn!("Stopped at the first error (--fail-fast); {left} input(s) not split");
+            break;
+        }
+        let unit_started = Instant::now();
+        let inputs = unit_inputs(&cli, unit, &mut report, unit_started);
+        let Some((first, _)) = inputs.first() else {
+            continue;
+        };
+        let label = cli.merge.as_ref().map_or(first.clone(), PathBuf::from);
+
+        let counted = counted_seen.as_mut();
+        let chunk_size = chunksize::chunk_size(&cli, &inputs, &label, &opts.filter, counted);
+        opts.chunk_size = match chunk_size {
+            Ok(chunk_size) => chunk_size,
+            Err(e) => {
+                report.failed(&label, &e, unit_started);
+                continue;
+            }
+        };
+        let existing = existing_chunks(&cli, first, &opts)?;
+        match split_unit(&cli, &inputs, &label, &opts) {
+            Ok(summary) => {
+                let sources = inputs.into_iter().map(|(input, _)| input).collect();
+                report.done(&label, sources, existing, summary, unit_started);
+            }
+            Err(e) => report.failed(&label, &e, unit_started),
+        }
+    }
+
+    report.finish(&cli, &opts)
+}
+
+/// The input files of the run, with the inputs left out, once the options
+/// are checked against them.
+fn resolve_inputs(cli: &Cli) -> Result<(Vec<PathBuf>, Vec<SkippedInput>), SplitterError> {
+    // Expand glob patterns / directories into concrete file paths
+    let mut skipped = Vec::new();
+    let files = expand_inputs_skipping(&cli.inputs, cli.recursive, &mut skipped)
+        .map_err(SplitterError::Other)?;
+
+    if files.is_empty() {
+        return Err(SplitterError::Parse(
+            "No input files found. Check your patterns or paths.".into(),
+        ));
+    }
+    checks::check_inputs(cli, &files)?;
+    Ok((files, skipped))
+}
+
+/// `--title`, `--license` and `--publisher`.
+fn dataset_metadata(cli: &Cli) -> DatasetMetadata {
+    let metadata = DatasetMetadata {
+        title: cli.title.clone(),
+        license: cli.license.clone(),
+        publisher: cli.publisher.clone(),
+    };
+    if !metadata.is_empty() && !cli.ro_crate && !cli.describe_fragments && !cli.metadata_triples {
+        log::warn!("--title, --license and --publisher are only recorded with --ro-crate, --describe-fragments or --metadata-triples");
+    }
+    metadata
+}
+
+/// The statements written at the head of every chunk: `--ontology-header`
+/// and `--metadata-triples`.
+fn chunk_statements(
+    cli: &Cli,
+    metadata: &DatasetMetadata,
+) -> Result<Vec<OwnedQuad>, SplitterError> {
+    let mut header = match &cli.ontology_header {
+        Some(path) => splitter::load_statements(path)?,
+        None => Vec::new(),
+    };
+    if cli.metadata_triples {
+        if metadata.is_empty() {
+            log::warn!("--metadata-triples without --title, --license or --publisher only types the dataset");
+        }
+        header.extend(metadata.statements());
+    }
+    Ok(header)
+}
+
+/// The `--filter-expr` and predicate and prefix filters.
+fn statement_filter(cli: &Cli) -> Result<StatementFilter, SplitterError> {
+    let expr = match &cli.filter_expr {
+        Some(text) => Some(
+            FilterExpr::parse(text, &cli.prefixes)
+                .map_err(|e| SplitterError::Other(anyhow::anyhow!("--filter-expr: {e}")))?,
+        ),
+        None => None,
+    };
+    Ok(StatementFilter {
+        include_predicates: cli.include_predicate.iter().map(|p| format!("<{p}>")).collect(),
+        exclude_predicates: cli.exclude_predicate.iter().map(|p| format!("<{p}>")).collect(),
+        subject_prefixes: cli.subject_prefix.clone(),
+        object_prefixes: cli.object_prefix.clone(),
+        expr,
+    })
+}
+
+/// `--output`, or a new directory in it for the run with `--dated-output`.
+fn output_dir(cli: &Cli) -> Result<PathBuf, SplitterError> {
+    if !cli.dated_output {
+        return Ok(cli.output.clone());
+    }
+    if !cli.output.exists() && !cli.force {
+        return Err(SplitterError::OutputDirMissing(cli.output.display().to_string()));
+    }
+    let dir = layout::dated_run_dir(&cli.output, SystemTime::now(), cli.dir_mode)?;
+    info!("Writing to {}", dir.display());
+    Ok(dir)
+}
+
+/// The split options of the command line; the chunk size is resolved per
+/// input (see [`chunksize::chunk_size`]).
+fn split_options(
+    cli: &Cli,
+    files: &[PathBuf],
+    metadata: &DatasetMetadata,
+) -> Result<SplitOptions, SplitterError> {
+    let hot_predicates = match &cli.partition_predicates {
+        Some(path) => Some(read_iri_list(path).map_err(SplitterError::Other)?),
+        None => None,
+    };
+    let header = chunk_statements(cli, metadata)?;
+
+    let read_text = |option: &str, path: &Option<PathBuf>| match path {
+        Some(path) => fs::read_to_string(path).map(Some).map_err(|e| {
+            SplitterError::Other(anyhow::anyhow!("{option}: {}: {e}", path.display()))
+        }),
+        None => Ok(None),
+    };
+    let chunk_header = read_text("--chunk-header", &cli.chunk_header)?;
+    let chunk_footer = read_text("--chunk-footer", &cli.chunk_footer)?;
+
+    let json_mapping = match &cli.json_map {
+        Some(template) => Some(
+            JsonMapping::new(&cli.json_pointer, template)
+                .map_err(|e| SplitterError::Other(anyhow::anyhow!("--json-map: {e}")))?,
+        ),
+        None => None,
+    };
+    let filter = statement_filter(cli)?;
+    let jsonld_context = match &cli.jsonld_context {
+        Some(source) => Some(Arc::new(load_jsonld_context(source)?)),
+        None => None,
+    };
+
+    if let Some(encryption) = &cli.encrypt {
+        encryption.check_tool()?;
+    }
+
+    if !cfg!(unix) && (cli.mode.is_some() || cli.dir_mode.is_some()) {
+        log::warn!("--mode and --dir-mode only apply on Unix; ignoring");
+    }
+
+    if cli.explain {
+        print!("{}", explain::explain(cli, files, header.len()));
+    }
+
+    let mut opts = SplitOptions {
+        output_dir: output_dir(cli)?,
+        chunk_size: 0,
+        max_bytes: cli.max_bytes,
+        adaptive: cli.adaptive,
+        force: cli.force,
+        hot_predicates,
+        separate_tbox: cli.separate_tbox,
+        header,
+        chunk_header,
+        chunk_footer,
+        keep_axioms: cli.keep_axioms,
+        bnode_strategy: cli.bnode_strategy,
+        keep_annotations: cli.keep_annotations,
+        collect_links: cli.link_report.is_some() || cli.load_order.is_some(),
+        iri_encoding: cli.iri_encoding,
+        literal_index: cli.literal_index,
+        line_map: cli.line_map,
+        dedupe: cli.dedupe.then_some(cli.dedupe_memory),
+        dedupe_key: cli.dedupe_key,
+        filter,
+        seen: cli
+            .dedupe_inputs
+            .then(|| RefCell::new(dedupe::SeenSet::new(cli.dedupe_memory, cli.dedupe_key, cli.hash))),
+        checkpoint: None,
+        bad_records: None,
+        max_line_bytes: (cli.max_line_bytes > 0).then_some(cli.max_line_bytes),
+        chunk_idle_timeout: cli.chunk_idle_timeout,
+        output_format: cli.output_format,
+        jsonld_context,
+        prefixes: cli.prefixes.clone(),
+        group_by: cli.group_by,
+        connect_predicates: cli.connect_predicate.iter().map(|p| format!("<{p}>")).collect(),
+        hash: cli.hash,
+        partition: cli.partition_by.zip(cli.partitions).map(|(by, count)| Partitioning {
+            by,
+            count: count as usize,
+            hash: cli.hash,
+        }),
+        route_by_authority: cli.route_by_authority,
+        append: cli.append,
+        max_open_files: cli.max_open_files.map(|n| n as usize).or_else(open_file_budget),
+        unnumbered: false,
+        file_mode: cli.mode,
+        dir_mode: cli.dir_mode,
+        graph_from_filename: cli
+            .graph_from_filename
+            .clone()
+            .zip(cli.graph_iri.clone())
+            .map(|(pattern, template)| GraphFromFilename::new(pattern, template)),
+        compress: cli.compress,
+        compress_tuning: CompressionTuning {
+            level: cli.compress_level,
+            threads: cli.compress_threads.unwrap_or(1) as usize,
+        },
+        encrypt: cli.encrypt.clone(),
+        stdout: cli.stdout,
+        staged: cli.staged,
+        exact_chunks: cli.exact_chunks,
+        preserve_structure: cli.preserve_structure,
+        suffix_input_hash: cli.suffix_input_hash,
+        tail: cli.tail,
+        dry_run: cli.dry_run,
+        json_mapping,
+        fault_injection: cli.fault_inject,
+        stratified_sample: cli.stratified_sample.clone(),
+        filter_types: cli.filter_type.iter().map(|c| format!("<{c}>")).collect(),
+    };
+    if cli.resume {
+        opts = opts.with_checkpoint(&options_digest(cli))?;
+    }
+    if let Some(limit) = cli.skip_bad_records {
+        let rejects = cli.rejects.as_deref();
+        let bad = BadRecords::new(limit, rejects, cli.force || cli.resume, opts.file_mode)?;
+        opts.bad_records = Some(RefCell::new(bad));
+    }
+    Ok(opts)
+}
+
+/// The files of `unit` with their formats.  Inputs of unknown format are
+/// skipped (and fail with `--strict`), those failing `--verify-input` fail.
+fn unit_inputs(
+    cli: &Cli,
+    unit: &[PathBuf],
+    report: &mut RunReport,
+    started: Instant,
+) -> Vec<(PathBuf, RdfFormat)> {
+    let mut inputs = Vec::new();
+    for path in unit {
+        let fmt = match input_format(path, cli.format, cli.trust_extension) {
+            Some(f) => f,
+            None if cli.strict => {
+                let msg = "unrecognised RDF extension and content (use --format)";
+                report.failed(path, &msg, started);
+                report.skipped(SkippedInput::new(path, SkipReason::UnknownExtension));
+                continue;
+            }
+            None => {
+                log::warn!(
+                    "Skipping '{}': unrecognised RDF extension and content (use --format)",
+                    path.display()
+                );
+                report.skipped(SkippedInput::new(path, SkipReason::UnknownExtension));
+                continue;
+            }
+        };
+
+        if cli.low_memory && fmt == RdfFormat::JsonLd {
+            log::warn!(
+                "{}: {} inputs are read into memory as a whole, even with --low-memory",
+                path.display(),
+                fmt.label()
+            );
+        }
+
+        if let Some(digest) = &cli.verify_input {
+            if let Err(e) = checksum::verify(path, digest) {
+                report.failed(path, &e, started);
+                continue;
+            }
+        }
+        inputs.push((path.clone(), fmt));
+    }
+    inputs
+}
+
+/// Files named like the chunks of the unit starting with `first`, found
+/// before it is split.
+fn existing_chunks(
+    cli: &Cli,
+    first: &Path,
+    opts: &SplitOptions,
+) -> Result<Vec<PathBuf>, SplitterError> {
+    // earlier chunks of a --tail input are part of the set, not stale
+    match (&cli.stdout, &cli.merge) {
+        (Some(_), _) => Ok(Vec::new()),
+        (None, _) if cli.tail => Ok(Vec::new()),
+        (None, Some(name)) => Ok(stale::chunk_files(&opts.output_dir, name)?),
+        (None, None) => Ok(stale::chunk_files(
+            &splitter::input_output_dir(first, opts),
+            &splitter::chunk_stem(first, opts),
+        )?),
+    }
+}
+
+/// Split `inputs`, the files of one unit (reported as `label`): the only
+/// one on its own, or all of them as one with `--merge`.
+fn split_unit(
+    cli: &Cli,
+    inputs: &[(PathBuf, RdfFormat)],
+    label: &Path,
+    opts: &SplitOptions,
+) -> Result<splitter::SplitSummary, SplitterError> {
+    let (first, fmt) = &inputs[0];
+    let summary = match &cli.merge {
+        Some(name) => splitter::merge_files(inputs, name, opts)?,
+        None => split_file(first, *fmt, opts)?,
+    };
+    let unit = if fmt.is_sparql_results() { "row(s)" } else { "triple(s)" };
+    let limit = match (opts.chunk_size, cli.max_bytes, cli.adaptive) {
+        (_, _, Some(target)) => format!("about {target} bytes"),
+        (usize::MAX, Some(max), None) => format!("{max} bytes"),
+        (n, Some(max), None) => format!("{n}, at most {max} bytes"),
+        (n, None, None) => n.to_string(),
+    };
+    info!("{}: {} {} → chunks of {}", label.display(), summary.records, unit, limit);
+    Ok(summary)
+}
+
+/// The `--jsonld-context` compaction: a local JSON-LD document whose
+/// `@context` (or the document itself) gives the terms.
+fn load_jsonld_context(source: &str) -> Result<Compaction, SplitterError> {
+    let raw = if remote::is_remote(Path::new(source)) {
+        remote::fetch(source, "application/ld+json, application/json")
+            .map_err(|e| SplitterError::Other(anyhow::anyhow!("--jsonld-context: {e}")))?
+    } else {
+        fs::read_to_string(source)?
+    };
+    let doc: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|e| SplitterError::Other(anyhow::anyhow!("--jsonld-context: {source}: {e}")))?;
+    Compaction::new(&doc)
+        .map_err(|e| SplitterError::Other(anyhow::anyhow!("--jsonld-context: {source}: {e}")))
+}
+
+/// Digest of the options a `--resume` manifest was written with: the ones
+/// that decide how inputs are read, where chunks are cut, what they hold
+/// and how they are named and written.  Options left out (logging, error
+/// handling, memory and file limits, reports) may change between runs; an
+/// option added to [`Cli`] that shapes the chunks must be listed here.
+fn options_digest(cli: &Cli) -> String {
+    let options: [(&str, &dyn std::fmt::Debug); 52] = [
+        ("format", &cli.format),
+        ("json-map", &cli.json_map),
+        ("json-pointer", &cli.json_pointer),
+        ("chunk-size", &cli.chunk_size),
+        ("file-count", &cli.file_count),
+        ("chunk-size-max", &cli.chunk_size_max),
+        ("exact-chunks", &cli.exact_chunks),
+        ("max-bytes", &cli.max_bytes),
+        ("adaptive", &cli.adaptive),
+        ("partition-predicates", &cli.partition_predicates),
+        ("separate-tbox", &cli.separate_tbox),
+        ("ontology-header", &cli.ontology_header),
+        ("chunk-header", &cli.chunk_header),
+        ("chunk-footer", &cli.chunk_footer),
+        ("keep-axioms", &cli.keep_axioms),
+        ("bnode-strategy", &cli.bnode_strategy),
+        ("keep-annotations", &cli.keep_annotations),
+        ("iri-encoding", &cli.iri_encoding),
+        ("literal-index", &cli.literal_index),
+        ("line-map", &cli.line_map),
+        ("dedupe", &cli.dedupe),
+        ("dedupe-inputs", &cli.dedupe_inputs),
+        ("dedupe-key", &cli.dedupe_key),
+        ("hash", &cli.hash),
+        ("skip-bad-records", &cli.skip_bad_records),
+        ("output-format", &cli.output_format),
+        ("jsonld-context", &cli.jsonld_context),
+        ("prefix", &cli.prefixes),
+        ("group-by", &cli.group_by),
+        ("connect-predicate", &cli.connect_predicate),
+        ("partition-by", &cli.partition_by),
+        ("partitions", &cli.partitions),
+        ("route-by-authority", &cli.route_by_authority),
+        ("merge", &cli.merge),
+        ("include-predicate", &cli.include_predicate),
+        ("exclude-predicate", &cli.exclude_predicate),
+        ("subject-prefix", &cli.subject_prefix),
+        ("object-prefix", &cli.object_prefix),
+        ("filter-expr", &cli.filter_expr),
+        ("filter-type", &cli.filter_type),
+        ("stratified-sample", &cli.stratified_sample),
+        ("graph-from-filename", &cli.graph_from_filename),
+        ("graph-iri", &cli.graph_iri),
+        ("compress", &cli.compress),
+        ("compress-level", &cli.compress_level),
+        ("encrypt", &cli.encrypt),
+        ("metadata-triples", &cli.metadata_triples),
+        ("title", &cli.title),
+        ("license", &cli.license),
+        ("publisher", &cli.publisher),
+        ("preserve-structure", &cli.preserve_structure),
+        ("suffix-input-hash", &cli.suffix_input_hash),
+    ];
+    let listed: String =
+        options.iter().map(|(name, value)| format!("--{name} {value:?}\n")).collect();
+    checksum::sha256_hex(listed.as_bytes()).unwrap_or_default()
+}
@@ -0,0 +1,57 @@
+//! Per-publisher output series (`--route-by-authority`).
+//!
+//! Aggregated datasets mix the resources of many publishers, each minting
+//! subject IRIs under its own host.  Routing by the authority of the subject
+//! IRI writes every publisher's statements to its own chunk series
+//! (`<stem>_<host>_NNNN.<ext>`), ready to be handed back to them.  There is
+//! no bound on the number of hosts: the chunks of hosts not seen for a
+//! while are closed under `--max-open-files` and reopened when they are.
+
+/// Series name for a subject: the host (and port) of an IRI subject,
+/// lowercased and safe to use in a file name on every platform.  `None` for
+/// blank nodes, quoted triples and IRIs without an authority (`urn:`).
+pub fn series_name(subject: &str) -> Option<String> {
+    let iri = subject.strip_prefix('<')?.strip_suffix('>')?;
+    if iri.starts_with('<') {
+        return None;
+    }
+    let (_, rest) = iri.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    // user information is not part of the publisher
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    if host.is_empty() {
+        return None;
+    }
+    Some(
+        host.chars()
+            .map(|c| match c {
+                'a'..='z' | '0'..='9' | '.' | '-' => c,
+                'A'..='Z' => c.to_ascii_lowercase(),
+                _ => '_',
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_the_host() {
+        assert_eq!(
+            series_name("<https://data.deutschebahn.com/op/1>").as_deref(),
+            Some("data.deutschebahn.com")
+        );
+        assert_eq!(
+            series_name("<http://user@Data.SNCF.fr:8080?x>").as_deref(),
+            Some("data.sncf.fr_8080")
+        );
+        assert_eq!(series_name("<urn:uuid:1234>"), None);
+        assert_eq!(series_name("_:b0"), None);
+        assert_eq!(series_name("<< <http://a.org/s> <http://a.org/p> \"o\" >>"), None);
+        assert_eq!(series_name("<file:///tmp/x>"), None);
+    }
+}
@@ -0,0 +1,248 @@
+//! Blank nodes across chunk boundaries (`--bnode-strategy`).
+//!
+//! A blank-node label only identifies a node within one document.  Once the
+//! statements about a blank node land in different chunks, each chunk holds
+//! a different node when loaded on its own, while a store that concatenates
+//! chunks of several inputs may merge unrelated nodes that happen to share a
+//! label.  `cluster` keeps statements connected by a blank node in one chunk
+//! (as `--keep-axioms` does); `rename` prefixes every label with the chunk's
+//! name, so no two chunks share a label and nodes never merge by accident.
+//!
+//! Inputs read as one stream (`--merge`, the files of a `delta` dump) are
+//! kept apart the same way: the labels of the n-th input get the prefix
+//! `i<n>_`, so `_:b0` of two JSON-LD files stays two nodes.
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+};
+
+use crate::{
+    iri::literal_end,
+    serialise::{OwnedQuad, OwnedTriple},
+};
+
+/// How blank nodes are written across chunks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum BnodeStrategy {
+    /// Write labels as parsed
+    #[default]
+    Preserve,
+    /// Keep statements sharing a blank node in one chunk, even if it exceeds
+    /// the chunk size
+    Cluster,
+    /// Prefix labels with the chunk name, unique to every chunk
+    Rename,
+}
+
+/// Label prefix of the chunk at `file_name`: the name up to its first `.`,
+/// reduced to characters valid in Turtle labels and XML names.
+pub fn chunk_prefix(file_name: &str) -> String {
+    let stem = file_name.split('.').next().unwrap_or_default();
+    let mut prefix: String = stem
+        .chars()
+        .map(|c| match c {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '_' | '-' => c,
+            _ => '_',
+        })
+        .collect();
+    if !prefix.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        prefix.insert(0, '_');
+    }
+    prefix.push('_');
+    prefix
+}
+
+/// Where every blank node seen as an object goes, by the key (a partition,
+/// or whether it is schema) of the resource naming it, and the statements
+/// about blank nodes whose owner has not been read yet: a Turtle list is
+/// parsed before the statement naming it.  A blank node named by several
+/// resources stays with the first.
+pub struct BlankOwners<K> {
+    owner: HashMap<String, K>,
+    /// Statements waiting for their blank subject's owner, by label.
+    pending: HashMap<String, Vec<OwnedQuad>>,
+    /// The waiting labels in the order they were first seen.
+    waiting: Vec<String>,
+}
+
+impl<K> Default for BlankOwners<K> {
+    fn default() -> Self {
+        Self {
+            owner: HashMap::new(),
+            pending: HashMap::new(),
+            waiting: Vec::new(),
+        }
+    }
+}
+
+impl<K: Clone> BlankOwners<K> {
+    /// Route `q`, appending it and the statements it releases to `ready`
+    /// with their key; `key_of` keys a statement about a named resource.  A
+    /// statement about a blank node no owner has named yet waits.
+    pub fn route(
+        &mut self,
+        q: OwnedQuad,
+        key_of: impl Fn(&OwnedQuad) -> K,
+        ready: &mut Vec<(K, OwnedQuad)>,
+    ) {
+        let subject = &q.triple.subject;
+        let key = if subject.starts_with("_:") {
+            match self.owner.get(subject) {
+                Some(key) => key.clone(),
+                None => {
+                    if !self.pending.contains_key(subject) {
+                        self.waiting.push(subject.clone());
+                    }
+                    self.pending.entry(subject.clone()).or_default().push(q);
+                    return;
+                }
+            }
+        } else {
+            key_of(&q)
+        };
+        self.place(key, q, ready);
+    }
+
+    /// The statements still waiting at the end of the input: a blank node
+    /// no resource names is keyed by its first statement, and takes the
+    /// blank nodes it names along.
+    pub fn finish(&mut self, key_of: impl Fn(&OwnedQuad) -> K, ready: &mut Vec<(K, OwnedQuad)>) {
+        for label in std::mem::take(&mut self.waiting) {
+            let Some(statements) = self.pending.remove(&label) else {
+                // released by an owner after all
+                continue;
+            };
+            let key = key_of(&statements[0]);
+            self.owner.insert(label, key.clone());
+            for q in statements {
+                self.place(key.clone(), q, ready);
+            }
+        }
+    }
+
+    /// Send `q` to `key`, claiming its blank object for the key along with
+    /// the statements about it that were waiting.
+    fn place(&mut self, key: K, q: OwnedQuad, ready: &mut Vec<(K, OwnedQuad)>) {
+        let mut queue = VecDeque::from([(key, q)]);
+        while let Some((key, q)) = queue.pop_front() {
+            let object = &q.triple.object;
+            if object.starts_with("_:") && !self.owner.contains_key(object) {
+                self.owner.insert(object.clone(), key.clone());
+                if let Some(waiting) = self.pending.remove(object) {
+                    queue.extend(waiting.into_iter().map(|w| (key.clone(), w)));
+                }
+            }
+            ready.push((key, q));
+        }
+    }
+}
+
+/// Label prefix of the `n`-th input of a stream of several inputs.
+pub fn input_prefix(n: usize) -> String {
+    format!("i{n}_")
+}
+
+/// Whether `q` may name a blank node; literals that merely contain `_:`
+/// can give a false positive.
+pub fn mentions_blank_node(q: &OwnedQuad) -> bool {
+    let t = &q.triple;
+    [&t.subject, &t.object]
+        .into_iter()
+        .chain(&q.graph_name)
+        .any(|term| term.starts_with("_:") || term.contains(" _:"))
+}
+
+/// `quad` with every blank-node label prefixed by `prefix`.
+pub fn relabel(quad: &OwnedQuad, prefix: &str) -> OwnedQuad {
+    let t = &quad.triple;
+    OwnedQuad {
+        triple: OwnedTriple {
+            subject: relabel_term(&t.subject, prefix).into_owned(),
+            predicate: t.predicate.clone(),
+            object: relabel_term(&t.object, prefix).into_owned(),
+        },
+        graph_name: quad
+            .graph_name
+            .as_ref()
+            .map(|g| relabel_term(g, prefix).into_owned()),
+    }
+}
+
+/// Prefix the blank-node labels of an N-Triples term, including those in
+/// RDF-star quoted triples; IRIs and literals are left alone.
+pub fn relabel_term<'a>(term: &'a str, prefix: &str) -> Cow<'a, str> {
+    if !term.contains("_:") {
+        return Cow::Borrowed(term);
+    }
+    let mut out = String::with_capacity(term.len() + prefix.len());
+    let mut rest = term;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '"' => {
+                let end = literal_end(rest);
+                out.push_str(&rest[..end]);
+                rest = &rest[end..];
+            }
+            '<' if rest.starts_with("<<") => {
+                out.push_str("<<");
+                rest = &rest[2..];
+            }
+            '<' => {
+                let end = rest.find('>').map_or(rest.len(), |i| i + 1);
+                out.push_str(&rest[..end]);
+                rest = &rest[end..];
+            }
+            '_' if rest.starts_with("_:") => {
+                out.push_str("_:");
+                out.push_str(prefix);
+                rest = &rest[2..];
+            }
+            c => {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_labels_only() {
+        let p = "dump_0001_";
+        assert_eq!(relabel_term("_:b0", p), "_:dump_0001_b0");
+        assert_eq!(
+            relabel_term("<http://ex.org/_:x>", p),
+            "<http://ex.org/_:x>"
+        );
+        assert_eq!(relabel_term("\"_:b0\"@en", p), "\"_:b0\"@en");
+        assert_eq!(
+            relabel_term("<< _:b1 <http://ex.org/p> \"a _:b\" >>", p),
+            "<< _:dump_0001_b1 <http://ex.org/p> \"a _:b\" >>"
+        );
+    }
+
+    #[test]
+    fn keeps_inputs_apart() {
+        let q = OwnedQuad::from_triple(OwnedTriple {
+            subject: "_:b0".into(),
+            predicate: "<http://ex.org/q>".into(),
+            object: "\"x\"".into(),
+        });
+        let first = relabel(&q, &input_prefix(0));
+        let second = relabel(&q, &input_prefix(1));
+        assert_eq!(first.triple.subject, "_:i0_b0");
+        assert_eq!(second.triple.subject, "_:i1_b0");
+        assert!(mentions_blank_node(&q));
+    }
+
+    #[test]
+    fn chunk_prefix_is_a_valid_label_start() {
+        assert_eq!(chunk_prefix("dump_0001.nt.gz"), "dump_0001_");
+        assert_eq!(chunk_prefix("2024 export_0002.ttl"), "_2024_export_0002_");
+    }
+}
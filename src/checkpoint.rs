@@ -0,0 +1,309 @@
+//! Resuming interrupted splits (`--resume`).
+//!
+//! With `--resume` the run records its progress in
+//! `.rdfsplitter-manifest.json` in the output directory: a digest of the
+//! options, and per input a fingerprint of the file and the chunks completed
+//! so far.  A later run with the same options picks the manifest up: inputs
+//! already split are passed over, and an input split only partly is parsed
+//! again from the start, but the chunks recorded as complete (still present
+//! with their recorded size) are not written again.  Chunk boundaries only
+//! depend on the input and the options, so the remaining chunks come out as
+//! an uninterrupted run would have written them.
+//!
+//! The fingerprint of an input is a SHA-256 of its size, modification time
+//! and first MiB rather than of the whole file, so that resuming a 200 GB
+//! input does not start with reading it once more.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use serde_json::{json, Value};
+
+use crate::{checksum::sha256_hex, paths, splitter::ChunkInfo};
+
+/// Name of the manifest in the output directory.
+pub const MANIFEST_FILE: &str = ".rdfsplitter-manifest.json";
+
+/// Bytes at the start of an input that go into its fingerprint.
+const FINGERPRINT_PREFIX: u64 = 1024 * 1024;
+
+/// The manifest is rewritten at most this often while chunks complete;
+/// chunks of a crash in between are written again on resume.
+const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A chunk completed by an earlier run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RecordedChunk {
+    path: PathBuf,
+    records: usize,
+    bytes: u64,
+}
+
+impl RecordedChunk {
+    /// Still on disk as it was written.
+    fn is_intact(&self) -> bool {
+        fs::metadata(&self.path).is_ok_and(|m| m.len() == self.bytes)
+    }
+}
+
+#[derive(Debug, Default)]
+struct InputProgress {
+    fingerprint: String,
+    complete: bool,
+    chunks: Vec<RecordedChunk>,
+}
+
+/// What an earlier run left of an input.
+#[derive(Debug, Default)]
+pub struct Resumed {
+    /// The input was split completely.
+    pub complete: bool,
+    /// Its chunks, in the order they were completed.
+    pub chunks: Vec<ChunkInfo>,
+}
+
+/// Progress of a `--resume` run, kept in [`MANIFEST_FILE`].
+#[derive(Debug)]
+pub struct Checkpoint {
+    path: PathBuf,
+    options: String,
+    file_mode: Option<u32>,
+    inputs: BTreeMap<String, InputProgress>,
+    /// Chunks of the current input that need not be written again.
+    kept: HashMap<PathBuf, RecordedChunk>,
+    /// Progress of an earlier run was found.
+    resuming: bool,
+    last_saved: Instant,
+}
+
+impl Checkpoint {
+    /// The progress recorded in `output_dir`, if made with the options
+    /// digested as `options`; a fresh start otherwise.
+    pub fn open(output_dir: &Path, options: &str, file_mode: Option<u32>) -> io::Result<Self> {
+        let path = output_dir.join(MANIFEST_FILE);
+        let mut checkpoint = Self {
+            path,
+            options: options.to_owned(),
+            file_mode,
+            inputs: BTreeMap::new(),
+            kept: HashMap::new(),
+            resuming: false,
+            last_saved: Instant::now(),
+        };
+        let raw = match fs::read_to_string(&checkpoint.path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(checkpoint),
+            Err(e) => return Err(e),
+        };
+        let doc: Value = serde_json::from_str(&raw)
+            .map_err(|e| io::Error::other(format!("{}: {e}", checkpoint.path.display())))?;
+        if doc["options"] != options {
+            log::warn!(
+                "{}: written with other options; starting from the beginning",
+                checkpoint.path.display()
+            );
+            return Ok(checkpoint);
+        }
+        let inputs = doc["inputs"].as_object().into_iter().flatten();
+        for (input, progress) in inputs {
+            let chunks = progress["chunks"].as_array().into_iter().flatten();
+            let progress = InputProgress {
+                fingerprint: progress["fingerprint"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_owned(),
+                complete: progress["complete"].as_bool().unwrap_or_default(),
+                chunks: chunks
+                    .filter_map(|c| {
+                        Some(RecordedChunk {
+                            path: paths::output_path(output_dir.join(c["path"].as_str()?)),
+                            records: c["records"].as_u64()? as usize,
+                            bytes: c["bytes"].as_u64()?,
+                        })
+                    })
+                    .collect(),
+            };
+            checkpoint.inputs.insert(input.clone(), progress);
+        }
+        checkpoint.resuming = true;
+        Ok(checkpoint)
+    }
+
+    /// True when continuing an earlier run, whose unrecorded chunk files
+    /// (the ones it was writing when interrupted) are overwritten.
+    pub fn is_resuming(&self) -> bool {
+        self.resuming
+    }
+
+    /// Start on `input` and return what an earlier run with the same input
+    /// left of it.  A completely split input is passed over if `may_skip`;
+    /// otherwise its intact chunks are kept and the rest is written again.
+    pub fn begin(&mut self, input: &Path, may_skip: bool) -> io::Result<Resumed> {
+        let fingerprint = fingerprint(input)?;
+        let key = input.display().to_string();
+        let earlier = self
+            .inputs
+            .remove(&key)
+            .filter(|p| p.fingerprint == fingerprint);
+        let mut progress = InputProgress {
+            fingerprint,
+            ..Default::default()
+        };
+        let mut resumed = Resumed::default();
+        self.kept.clear();
+        if let Some(earlier) = earlier {
+            let complete = earlier.complete && earlier.chunks.iter().all(RecordedChunk::is_intact);
+            if complete && may_skip {
+                resumed.complete = true;
+                progress = earlier;
+            } else {
+                self.kept.extend(
+                    earlier
+                        .chunks
+                        .into_iter()
+                        .filter(RecordedChunk::is_intact)
+                        .map(|c| (c.path.clone(), c)),
+                );
+            }
+        }
+        resumed.chunks = match resumed.complete {
+            true => progress.chunks.iter().map(RecordedChunk::info).collect(),
+            false => self.kept.values().map(RecordedChunk::info).collect(),
+        };
+        self.inputs.insert(key, progress);
+        Ok(resumed)
+    }
+
+    /// Records and size of `path` if an earlier run completed it.
+    pub fn kept(&self, path: &Path) -> Option<(usize, u64)> {
+        self.kept.get(path).map(|c| (c.records, c.bytes))
+    }
+
+    /// Record a completed chunk of `input`.
+    pub fn chunk_done(&mut self, input: &Path, chunk: &ChunkInfo) -> io::Result<()> {
+        let progress = self.inputs.entry(input.display().to_string()).or_default();
+        progress.chunks.push(RecordedChunk {
+            path: chunk.path.clone(),
+            records: chunk.records,
+            bytes: chunk.bytes,
+        });
+        if self.last_saved.elapsed() >= SAVE_INTERVAL {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Record `input` as split completely.
+    pub fn input_done(&mut self, input: &Path) -> io::Result<()> {
+        self.inputs
+            .entry(input.display().to_string())
+            .or_default()
+            .complete = true;
+        self.kept.clear();
+        self.save()
+    }
+
+    /// Write the manifest, replacing the previous one in one step.
+    pub fn save(&mut self) -> io::Result<()> {
+        // chunks are named relative to the output directory
+        let dir = self.path.parent().unwrap_or(Path::new(""));
+        let inputs: serde_json::Map<String, Value> = self
+            .inputs
+            .iter()
+            .map(|(input, p)| {
+                let chunks: Vec<Value> = p
+                    .chunks
+                    .iter()
+                    .map(|c| {
+                        json!({
+                            "path": c.path.strip_prefix(dir).unwrap_or(&c.path).display().to_string(),
+                            "records": c.records,
+                            "bytes": c.bytes,
+                        })
+                    })
+                    .collect();
+                let progress = json!({
+                    "fingerprint": p.fingerprint,
+                    "complete": p.complete,
+                    "chunks": chunks,
+                });
+                (input.clone(), progress)
+            })
+            .collect();
+        let doc = json!({ "options": self.options, "inputs": inputs });
+        let partial = self.path.with_extension("json.partial");
+        fs::write(&partial, format!("{doc:#}\n"))?;
+        paths::set_mode(&partial, self.file_mode)?;
+        fs::rename(&partial, &self.path)?;
+        self.last_saved = Instant::now();
+        Ok(())
+    }
+}
+
+impl RecordedChunk {
+    fn info(&self) -> ChunkInfo {
+        ChunkInfo {
+            path: self.path.clone(),
+            records: self.records,
+            bytes: self.bytes,
+            links: None,
+        }
+    }
+}
+
+/// SHA-256 of the size, modification time and first MiB of `input`.
+fn fingerprint(input: &Path) -> io::Result<String> {
+    let file = fs::File::open(input)?;
+    let meta = file.metadata()?;
+    let modified = meta
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let mut head = format!("{} {modified}\n", meta.len()).into_bytes();
+    file.take(FINGERPRINT_PREFIX).read_to_end(&mut head)?;
+    sha256_hex(head.as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_changed_input_starts_from_the_beginning() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let input = dir.path().join("data.nt");
+        fs::write(&input, "<a> <b> <c> .\n").unwrap();
+        let chunk = dir.path().join("data_0000.nt");
+        fs::write(&chunk, "<a> <b> <c> .\n").unwrap();
+        let info = ChunkInfo {
+            path: chunk.clone(),
+            records: 1,
+            bytes: 14,
+            links: None,
+        };
+
+        let mut checkpoint = Checkpoint::open(dir.path(), "opts", None).unwrap();
+        assert!(!checkpoint.is_resuming());
+        checkpoint.begin(&input, true).unwrap();
+        checkpoint.chunk_done(&input, &info).unwrap();
+        checkpoint.save().unwrap();
+
+        let mut checkpoint = Checkpoint::open(dir.path(), "opts", None).unwrap();
+        assert!(checkpoint.is_resuming());
+        let resumed = checkpoint.begin(&input, true).unwrap();
+        assert!(!resumed.complete);
+        assert_eq!(checkpoint.kept(&chunk), Some((1, 14)));
+
+        fs::write(&input, "<a> <b> <d> .\n<a> <b> <e> .\n").unwrap();
+        let mut checkpoint = Checkpoint::open(dir.path(), "opts", None).unwrap();
+        let resumed = checkpoint.begin(&input, true).unwrap();
+        assert!(resumed.chunks.is_empty());
+        assert_eq!(checkpoint.kept(&chunk), None);
+    }
+}
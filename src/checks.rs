@@ -0,0 +1,296 @@
+//! Option checks of a split run, made before any input is read: options
+//! that cannot go together, or with the inputs given.
+
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::{
+    checksum, cli::Cli, grouping::GroupBy, remote, splitter, stdio, RdfFormat, SplitOptions,
+    SplitterError,
+};
+
+/// Refuse options that do not apply to `files`: the combinations of
+/// [`check_combinations`], and options needing local inputs or reading
+/// them twice for standard input and URLs.
+pub fn check_inputs(cli: &Cli, files: &[PathBuf]) -> Result<(), SplitterError> {
+    check_combinations(cli, files)?;
+    if files.iter().any(|f| stdio::is_stdin(f)) {
+        check_stdin_options(cli)?;
+    }
+    if let Some(url) = files.iter().find(|f| remote::is_remote(f)) {
+        check_single_pass(cli, &format!("a URL input ({})", url.display()))?;
+        if cli.preserve_structure || cli.tail {
+            return Err(SplitterError::Other(anyhow::anyhow!(
+                "--preserve-structure and --tail need local inputs, not {}",
+                url.display()
+            )));
+        }
+        remote::check_tool()?;
+    }
+    if cli.resume && files.iter().any(|f| stdio::is_stdin(f) || remote::is_remote(f)) {
+        return Err(SplitterError::Other(anyhow::anyhow!(
+            "--resume needs local input files"
+        )));
+    }
+    Ok(())
+}
+
+/// Standard input is read once, as it arrives: refuse options that need
+/// to know its format up front or read it twice.
+fn check_stdin_options(cli: &Cli) -> Result<(), SplitterError> {
+    if cli.format.is_none() {
+        return Err(SplitterError::Other(anyhow::anyhow!(
+            "reading standard input ('-') requires --format"
+        )));
+    }
+    check_single_pass(cli, "standard input ('-')")
+}
+
+/// Refuse options that read an input twice for an input that can be read
+/// only once: standard input, or a URL that would be downloaded twice.
+fn check_single_pass(cli: &Cli, input: &str) -> Result<(), SplitterError> {
+    let multi_pass = [
+        ("--file-count", cli.file_count.is_some()),
+        ("--exact-chunks", cli.exact_chunks.is_some()),
+        ("--stratified-sample", !cli.stratified_sample.is_empty()),
+        ("--filter-type", !cli.filter_type.is_empty()),
+        ("--verify-input", cli.verify_input.is_some()),
+    ];
+    match multi_pass.iter().find(|(_, set)| *set) {
+        Some((flag, _)) => Err(SplitterError::Other(anyhow::anyhow!(
+            "{flag} cannot be used with {input}"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Refuse option combinations that would fail halfway through or quietly
+/// give other output than asked for, suggesting what to use instead.  Input
+/// formats are taken from `--format` or the extensions (of URLs only with
+/// `--trust-extension`, as the server's Content-Type goes first); checks
+/// about them only fail when every input's format is known.
+fn check_combinations(cli: &Cli, files: &[PathBuf]) -> Result<(), SplitterError> {
+    let by_extension = |f: &PathBuf| {
+        (cli.trust_extension || !remote::is_remote(f)).then(|| RdfFormat::from_path(f))?
+    };
+    let formats: Vec<Option<RdfFormat>> =
+        files.iter().map(|f| cli.format.or_else(|| by_extension(f))).collect();
+    check_graph_options(cli, &formats)?;
+    check_output_options(cli, &formats)?;
+    check_line_options(cli, files, &formats)?;
+    check_exact_chunks(cli)?;
+    check_results_options(cli, &formats)
+}
+
+fn refuse(msg: String) -> Result<(), SplitterError> {
+    Err(SplitterError::Other(anyhow::anyhow!(msg)))
+}
+
+/// True when the format of every input is known and `pred` holds for it.
+fn all_inputs(formats: &[Option<RdfFormat>], pred: fn(RdfFormat) -> bool) -> bool {
+    formats.iter().all(|f| f.is_some_and(pred))
+}
+
+/// `--group-by graph`, `--graph-from-filename` and `--connect-predicate`.
+fn check_graph_options(cli: &Cli, formats: &[Option<RdfFormat>]) -> Result<(), SplitterError> {
+    if let Some(out) = cli.output_format.filter(|f| !f.has_graphs()) {
+        if cli.group_by == Some(GroupBy::Graph) {
+            return refuse(format!(
+                "--group-by graph keeps named graphs together, but {} output has no named \
+                 graphs; use --output-format nq (or trig), or --group-by subject",
+                out.label()
+            ));
+        }
+        if cli.graph_from_filename.is_some() {
+            return refuse(format!(
+                "--graph-from-filename moves statements into named graphs, but {} output has \
+                 none; use --output-format nq (or trig)",
+                out.label()
+            ));
+        }
+    }
+    if cli.group_by == Some(GroupBy::Graph)
+        && cli.graph_from_filename.is_none()
+        && all_inputs(formats, |f| !f.has_graphs())
+    {
+        return refuse(
+            "--group-by graph needs inputs with named graphs (nq, trig); without them every \
+             input is a single group. Use --group-by subject, or --graph-from-filename to give \
+             each file its own graph"
+                .into(),
+        );
+    }
+    if !cli.connect_predicate.is_empty() && cli.group_by != Some(GroupBy::Connected) {
+        return refuse(
+            "--connect-predicate only applies to --group-by connected; use --group-by connected"
+                .into(),
+        );
+    }
+    Ok(())
+}
+
+/// `--jsonld-context`, `--describe-fragments` and `--append`, which need
+/// chunks of a given format or hash.
+fn check_output_options(cli: &Cli, formats: &[Option<RdfFormat>]) -> Result<(), SplitterError> {
+    if cli.jsonld_context.is_some() {
+        let chunks_jsonld = match cli.output_format {
+            Some(out) => out == RdfFormat::JsonLd,
+            None => !all_inputs(formats, |f| f != RdfFormat::JsonLd),
+        };
+        if !chunks_jsonld {
+            return refuse(
+                "--jsonld-context compacts JSON-LD chunks, but the chunks are not written as \
+                 JSON-LD; add --output-format jsonld"
+                    .into(),
+            );
+        }
+    }
+    if cli.describe_fragments && cli.hash == Some(checksum::HashAlgorithm::Xxh3) {
+        return refuse(
+            "--describe-fragments names the chunk checksum algorithm in SPDX, which has no XXH3; \
+             use --hash blake3 (or sha256)"
+                .into(),
+        );
+    }
+    if cli.append {
+        let appendable = |f: RdfFormat| {
+            use RdfFormat::*;
+            matches!(f, NTriples | NQuads | Turtle | TriG)
+        };
+        let closed = match cli.output_format {
+            Some(out) => !appendable(out),
+            None => formats.iter().any(|f| f.is_some_and(|f| !appendable(f))),
+        };
+        if closed {
+            return refuse(
+                "--append cannot continue RDF/XML, JSON-LD or results files, which end in a \
+                 closing tag or bracket; add --output-format nt (or nq, ttl, trig)"
+                    .into(),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// `--chunk-idle-timeout`, `--line-map` and `--skip-bad-records`, which
+/// read N-Triples or N-Quads line by line.
+fn check_line_options(
+    cli: &Cli,
+    files: &[PathBuf],
+    formats: &[Option<RdfFormat>],
+) -> Result<(), SplitterError> {
+    if cli.chunk_idle_timeout.is_some() {
+        if !files.iter().all(|f| stdio::is_stdin(f)) {
+            return refuse(
+                "--chunk-idle-timeout applies to standard input ('-'): files and URLs never \
+                 wait on a producer, and --tail reads what was appended and stops. Pipe the \
+                 producer into `rdfsplitter -`"
+                    .into(),
+            );
+        }
+        if !all_inputs(formats, |f| matches!(f, RdfFormat::NTriples | RdfFormat::NQuads)) {
+            return refuse(
+                "--chunk-idle-timeout reads standard input line by line; use --format nt or \
+                 --format nq and have the producer write N-Triples or N-Quads"
+                    .into(),
+            );
+        }
+    }
+    let line_options = [
+        (cli.line_map, "--line-map"),
+        (cli.skip_bad_records.is_some(), "--skip-bad-records"),
+    ];
+    for (set, flag) in line_options {
+        if set && all_inputs(formats, |f| !matches!(f, RdfFormat::NTriples | RdfFormat::NQuads)) {
+            return refuse(format!(
+                "{flag} needs N-Triples or N-Quads input; convert the inputs first with \
+                 `rdfsplitter convert --to nt` (or nq)"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `--exact-chunks` with options that keep statements together, route
+/// them elsewhere or drop them after counting.
+fn check_exact_chunks(cli: &Cli) -> Result<(), SplitterError> {
+    if cli.exact_chunks.is_none() {
+        return Ok(());
+    }
+    let uneven = [
+        ("--group-by", cli.group_by.is_some()),
+        ("--keep-axioms", cli.keep_axioms),
+        ("--keep-annotations", cli.keep_annotations.is_some()),
+        ("--separate-tbox", cli.separate_tbox),
+        ("--partition-predicates", cli.partition_predicates.is_some()),
+        ("--route-by-authority", cli.route_by_authority),
+        ("--partitions", cli.partitions.is_some()),
+        ("--filter-type", !cli.filter_type.is_empty()),
+        ("--stratified-sample", !cli.stratified_sample.is_empty()),
+    ];
+    match uneven.iter().find(|(_, set)| *set) {
+        Some((flag, _)) => refuse(format!(
+            "--exact-chunks cannot give equal chunks with {flag}, which keeps statements \
+             together, routes them to other series or drops them after counting; use \
+             --file-count for about equal chunks"
+        )),
+        None => Ok(()),
+    }
+}
+
+/// SPARQL results inputs with `--merge` or statement filters.
+fn check_results_options(cli: &Cli, formats: &[Option<RdfFormat>]) -> Result<(), SplitterError> {
+    let Some(results) = formats.iter().flatten().find(|f| f.is_sparql_results()) else {
+        return Ok(());
+    };
+    if cli.merge.is_some() {
+        return refuse(format!(
+            "--merge chunks RDF statements, and {} inputs hold result rows; split them \
+             without --merge",
+            results.label()
+        ));
+    }
+    let statement_filters = [
+        ("--include-predicate", !cli.include_predicate.is_empty()),
+        ("--exclude-predicate", !cli.exclude_predicate.is_empty()),
+        ("--subject-prefix", !cli.subject_prefix.is_empty()),
+        ("--object-prefix", !cli.object_prefix.is_empty()),
+        ("--filter-expr", cli.filter_expr.is_some()),
+        ("--filter-type", !cli.filter_type.is_empty()),
+        ("--stratified-sample", !cli.stratified_sample.is_empty()),
+    ];
+    match statement_filters.iter().find(|(_, set)| *set) {
+        Some((flag, _)) => refuse(format!(
+            "{flag} selects RDF statements, and {} inputs hold result rows it would pass \
+             through untouched; narrow the rows in the query (FILTER, VALUES or LIMIT), or \
+             split the results files in a run without {flag}",
+            results.label()
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Refuse inputs whose chunks would get the same names: the same chunk stem
+/// and extension in the same output directory.
+pub fn check_collisions(files: &[PathBuf], opts: &SplitOptions) -> Result<(), SplitterError> {
+    let mut seen = HashMap::new();
+    for file in files {
+        let key = (
+            splitter::input_output_dir(file, opts),
+            splitter::chunk_stem(file, opts),
+            opts.output_format.or_else(|| RdfFormat::from_path(file)).map(RdfFormat::extension),
+        );
+        if let Some(other) = seen.insert(key, file) {
+            let hint = if opts.preserve_structure {
+                "use --suffix-input-hash"
+            } else {
+                "use --preserve-structure or --suffix-input-hash"
+            };
+            return Err(SplitterError::Other(anyhow::anyhow!(
+                "'{}' and '{}' would write chunks of the same names ({hint})",
+                other.display(),
+                file.display()
+            )));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,194 @@
+//! Input checksum verification (`--verify-input`) and the digests of chunk
+//! checksums (`--hash`).
+//!
+//! The digest is checked in a separate streaming pass before any chunk is
+//! written, so a truncated or corrupted download fails fast instead of
+//! producing partial output.
+
+use std::{
+    fs,
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use sha2::{Digest, Sha256};
+use xxhash_rust::xxh3::{xxh3_128, Xxh3};
+
+use crate::format::SplitterError;
+
+/// Digest of chunk checksums, and of statements when deduplicating and
+/// hash partitioning (`--hash`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum HashAlgorithm {
+    /// SHA-256, for manifests that must name a FIPS-approved digest
+    #[default]
+    Sha256,
+    /// BLAKE3, several times faster than SHA-256 on large outputs
+    Blake3,
+    /// XXH3 (128-bit), fastest, for integrity checks that need no
+    /// cryptographic strength
+    Xxh3,
+}
+
+impl HashAlgorithm {
+    /// Lowercase name, as in `sha256sum` / `b3sum` and SPDX.
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Xxh3 => "xxh3",
+        }
+    }
+
+    /// The digest of `bytes`: 32 bytes, 16 for XXH3.
+    pub fn digest(self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha256 => Sha256::digest(bytes).to_vec(),
+            HashAlgorithm::Blake3 => blake3::hash(bytes).as_bytes().to_vec(),
+            HashAlgorithm::Xxh3 => xxh3_128(bytes).to_be_bytes().to_vec(),
+        }
+    }
+
+    /// Hex digest of everything `r` yields, as `sha256sum`, `b3sum` and
+    /// `xxh128sum` print it.
+    pub fn hex_digest(self, mut r: impl Read) -> io::Result<String> {
+        let mut sha256 = Sha256::new();
+        let mut blake3 = blake3::Hasher::new();
+        let mut xxh3 = Xxh3::new();
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = r.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            match self {
+                HashAlgorithm::Sha256 => sha256.update(&buf[..n]),
+                HashAlgorithm::Blake3 => {
+                    blake3.update(&buf[..n]);
+                }
+                HashAlgorithm::Xxh3 => xxh3.update(&buf[..n]),
+            }
+        }
+        let digest: Vec<u8> = match self {
+            HashAlgorithm::Sha256 => sha256.finalize().to_vec(),
+            HashAlgorithm::Blake3 => blake3.finalize().as_bytes().to_vec(),
+            HashAlgorithm::Xxh3 => xxh3.digest128().to_be_bytes().to_vec(),
+        };
+        Ok(digest.iter().map(|b| format!("{b:02x}")).collect())
+    }
+}
+
+/// Where the expected digest comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputDigest {
+    /// `sha256:<hex>` given on the command line.
+    Sha256(String),
+    /// `sidecar`: read `<input>.sha256` (`sha256sum` output format).
+    Sidecar,
+}
+
+impl FromStr for InputDigest {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "sidecar" {
+            return Ok(InputDigest::Sidecar);
+        }
+        match s.split_once(':') {
+            Some(("sha256", hex)) if is_sha256_hex(hex) => {
+                Ok(InputDigest::Sha256(hex.to_ascii_lowercase()))
+            }
+            _ => Err("expected 'sha256:<64 hex digits>' or 'sidecar'".into()),
+        }
+    }
+}
+
+fn is_sha256_hex(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Path of the sidecar file for `input` (`data.nt` → `data.nt.sha256`).
+pub fn sidecar_path(input: &Path) -> PathBuf {
+    let mut name = input.as_os_str().to_owned();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+/// Hex SHA-256 of everything `r` yields.
+pub fn sha256_hex(r: impl Read) -> io::Result<String> {
+    HashAlgorithm::Sha256.hex_digest(r)
+}
+
+/// Check `input` against `digest`.
+pub fn verify(input: &Path, digest: &InputDigest) -> Result<(), SplitterError> {
+    let expected = match digest {
+        InputDigest::Sha256(hex) => hex.clone(),
+        InputDigest::Sidecar => read_sidecar(&sidecar_path(input))?,
+    };
+    let actual = sha256_hex(BufReader::new(fs::File::open(input)?))?;
+    if actual != expected {
+        return Err(SplitterError::ChecksumMismatch {
+            path: input.display().to_string(),
+            expected,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// First token of the sidecar (`<hex>  <file name>` or just `<hex>`).
+fn read_sidecar(path: &Path) -> Result<String, SplitterError> {
+    let raw = fs::read_to_string(path).map_err(|e| {
+        SplitterError::Other(anyhow::anyhow!(
+            "cannot read checksum file '{}': {e}",
+            path.display()
+        ))
+    })?;
+    match raw.split_whitespace().next() {
+        Some(hex) if is_sha256_hex(hex) => Ok(hex.to_ascii_lowercase()),
+        _ => Err(SplitterError::Parse(format!(
+            "'{}' does not contain a SHA-256 digest",
+            path.display()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn hashes_known_vector() {
+        assert_eq!(sha256_hex(&b"abc"[..]).unwrap(), ABC);
+    }
+
+    #[test]
+    fn algorithms_hash_known_vectors() {
+        let blake3 = "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85";
+        assert_eq!(HashAlgorithm::Blake3.hex_digest(&b"abc"[..]).unwrap(), blake3);
+        assert_eq!(HashAlgorithm::Sha256.digest(b"abc")[..4], [0xba, 0x78, 0x16, 0xbf]);
+        assert_eq!(HashAlgorithm::Blake3.digest(b"abc")[..4], [0x64, 0x37, 0xb3, 0xac]);
+        let xxh3 = "06b05ab6733a618578af5f94892f3950";
+        assert_eq!(HashAlgorithm::Xxh3.hex_digest(&b"abc"[..]).unwrap(), xxh3);
+        assert_eq!(HashAlgorithm::Xxh3.digest(b"abc").len(), 16);
+    }
+
+    #[test]
+    fn parses_digest_specs() {
+        assert_eq!(
+            format!("sha256:{}", ABC.to_uppercase()).parse(),
+            Ok(InputDigest::Sha256(ABC.into()))
+        );
+        assert_eq!("sidecar".parse(), Ok(InputDigest::Sidecar));
+        assert!("md5:abc".parse::<InputDigest>().is_err());
+        assert!("sha256:abc".parse::<InputDigest>().is_err());
+    }
+
+    #[test]
+    fn sidecar_sits_next_to_input() {
+        assert_eq!(sidecar_path(Path::new("d/x.nt")), PathBuf::from("d/x.nt.sha256"));
+    }
+}
@@ -0,0 +1,97 @@
+//! The records per chunk of each input (or `--merge` group): `--chunk-size`,
+//! or derived from `--file-count` or `--exact-chunks` by counting the
+//! records first.
+
+use std::path::{Path, PathBuf};
+
+use log::info;
+
+use crate::{cli::Cli, dedupe, filter::StatementFilter, splitter, RdfFormat, SplitterError};
+
+/// Records per chunk of `inputs` (reported as `label`):
+///
+/// - `--exact-chunks`: the records left after the filters and `--dedupe`
+///   divided by the chunk count, failing unless they divide evenly;
+/// - `--file-count`: the records divided by the file count, rounded up,
+///   or `--chunk-size-max` when that gives more files;
+/// - otherwise `--chunk-size`, unbounded with only `--max-bytes`, or 10 000.
+///
+/// `counted_seen` holds the statements counted for `--dedupe-inputs`.
+pub fn chunk_size(
+    cli: &Cli,
+    inputs: &[(PathBuf, RdfFormat)],
+    label: &Path,
+    filter: &StatementFilter,
+    counted_seen: Option<&mut dedupe::SeenSet>,
+) -> Result<usize, SplitterError> {
+    let fail = |msg: String| Err(SplitterError::Other(anyhow::anyhow!(msg)));
+    match (cli.chunk_size, cli.file_count, cli.exact_chunks) {
+        (_, _, Some(0)) => fail("--exact-chunks must be at least 1".into()),
+        (_, _, Some(ec)) => match count_distinct(inputs, filter, cli, counted_seen)? {
+            total if total > 0 && total % ec == 0 => Ok(total / ec),
+            total => fail(format!("{total} record(s) cannot be split into {ec} equal chunks")),
+        },
+        (_, Some(0), _) => fail("--file-count must be at least 1".into()),
+        (_, Some(fc), _) => {
+            info!("Counting records in {} …", label.display());
+            // past fc × max records the cap decides, whatever the total
+            let limit = cli.chunk_size_max.map_or(usize::MAX, |max| fc.saturating_mul(max));
+            let total = if cli.dedupe || cli.dedupe_inputs {
+                count_distinct(inputs, filter, cli, counted_seen)?
+            } else {
+                count_inputs(inputs, filter, limit)?
+            };
+            let cs = if total > limit {
+                let cs = cli.chunk_size_max.unwrap_or(limit);
+                log::debug!("  more than {} records → chunk size {}", limit, cs);
+                cs
+            } else {
+                let cs = total.div_ceil(fc);
+                log::debug!("  {} records → chunk size {}", total, cs);
+                cs
+            };
+            Ok(cs.max(1))
+        }
+        (Some(cs), _, _) => Ok(cs),
+        (None, None, None) if cli.max_bytes.is_some() => Ok(usize::MAX),
+        (None, None, None) => Ok(10_000),
+    }
+}
+
+/// Records of all `inputs` that `filter` keeps; counting stops once the
+/// total exceeds `limit` (see [`splitter::count_records_up_to`]).
+fn count_inputs(
+    inputs: &[(PathBuf, RdfFormat)],
+    filter: &StatementFilter,
+    limit: usize,
+) -> Result<usize, SplitterError> {
+    let mut total = 0usize;
+    for (input, fmt) in inputs {
+        let left = limit.saturating_sub(total);
+        total = total.saturating_add(splitter::count_records_up_to(input, *fmt, filter, left)?);
+        if total > limit {
+            break;
+        }
+    }
+    Ok(total)
+}
+
+/// Statements of `inputs` left after `--dedupe` (within the unit) and
+/// `--dedupe-inputs` (`across`, shared by the whole run).
+fn count_distinct(
+    inputs: &[(PathBuf, RdfFormat)],
+    filter: &StatementFilter,
+    cli: &Cli,
+    mut across: Option<&mut dedupe::SeenSet>,
+) -> Result<usize, SplitterError> {
+    let mut within = cli
+        .dedupe
+        .then(|| dedupe::SeenSet::new(cli.dedupe_memory, cli.dedupe_key, cli.hash));
+    let mut total = 0usize;
+    for (input, fmt) in inputs {
+        let mut seen: Vec<&mut dedupe::SeenSet> =
+            within.as_mut().into_iter().chain(across.as_deref_mut()).collect();
+        total += splitter::count_distinct_records(input, *fmt, filter, &mut seen)?;
+    }
+    Ok(total)
+}
@@ -1,23 +1,73 @@
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use regex::Regex;
+use std::{path::PathBuf, time::Duration};
+
+use crate::{
+    bnodes::BnodeStrategy,
+    checksum::{HashAlgorithm, InputDigest}, compress::Compression, dedupe::DedupeKey, delta::DeltaFormat,
+    encrypt::Encryption,
+    faults::FaultInjection,
+    format::RdfFormat,
+    grouping::{AnnotationKinds, GroupBy},
+    iri::IriEncoding, linelimit::DEFAULT_MAX_LINE_BYTES, literals::LiteralIndex,
+    partition::PartitionBy, paths::parse_mode, sample::StratifiedSample, stdio::{self, StdoutMode},
+};
 
 /// Split RDF files into smaller chunks.
 ///
 /// Supported formats: Turtle (.ttl), N-Triples (.nt), N-Quads (.nq),
-/// RDF/XML (.rdf, .owl, .xml), TriG (.trig), JSON-LD (.jsonld, .json-ld).
-#[derive(Parser, Debug)]
+/// RDF/XML (.rdf, .owl, .xml), TriG (.trig), JSON-LD (.jsonld, .json-ld),
+/// and SPARQL query results (.srj, .srx), each optionally gzip-compressed
+/// (.nt.gz, .ttl.gz, …).
+#[derive(Parser, Debug, Clone)]
 #[command(
     name = "rdfsplitter",
     version,
     about,
     long_about = None,
-    after_help = "EXAMPLES:\n  rdfsplitter data.ttl -n 1000\n  rdfsplitter data.ttl -c 4\n  rdfsplitter *.nt -n 5000 -o out/ -f\n  rdfsplitter -r src/ -c 10 -o split/"
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true,
+    after_help = "EXAMPLES:\n  rdfsplitter data.ttl -n 1000\n  rdfsplitter data.ttl -c 4\n  rdfsplitter *.nt -n 5000 -o out/ -f\n  rdfsplitter -r src/ -c 10 -o split/\n  rdfsplitter count data/*.nt\n  rdfsplitter convert data.ttl --to nt -o out/\n  rdfsplitter run --profile era-infra\n\nEXIT STATUS:\n  0  every input was split\n  1  fatal error (bad options, no input, output not writable); the run was abandoned\n  2  partial: some inputs failed (or were skipped, with --strict); the others were split"
 )]
 pub struct Cli {
-    /// Input file(s) or glob patterns (e.g. *.ttl, data/**/*.nt)
-    #[arg(required = true)]
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Input file(s) or glob patterns (e.g. *.ttl, data/**/*.nt); `-` reads
+    /// standard input (requires --format)
+    #[arg(required_unless_present = "stdin_list")]
     pub inputs: Vec<String>,
 
+    /// Read input paths from standard input, one per line, and split each
+    /// as it arrives; a JSON result line per input is written to standard
+    /// output
+    #[arg(long, conflicts_with_all = ["inputs", "stdout", "merge", "explain"])]
+    pub stdin_list: bool,
+
+    /// Read every input as FORMAT (ttl, nt, nq, trig, rdf, jsonld, srj, srx)
+    /// instead of detecting it from the file extension; required for `-`
+    #[arg(long, value_name = "FORMAT", value_parser = parse_input_format)]
+    pub format: Option<RdfFormat>,
+
+    /// Read URL inputs by their extension without asking the server for
+    /// their Content-Type first
+    #[arg(long)]
+    pub trust_extension: bool,
+
+    /// Lift plain JSON inputs into statements: an N-Triples TEMPLATE written
+    /// per element of the --json-pointer array, with {key}, {/pointer} and
+    /// {#} (index) replaced by the element's values
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        conflicts_with_all = ["file_count", "exact_chunks", "stratified_sample", "filter_type"]
+    )]
+    pub json_map: Option<String>,
+
+    /// JSON pointer to the array --json-map lifts (default: the document)
+    #[arg(long, value_name = "POINTER", default_value = "", requires = "json_map")]
+    pub json_pointer: String,
+
     /// Number of triples per output chunk [default: 10000, conflicts with --file-count]
     #[arg(
         short = 'n',
@@ -36,10 +86,442 @@ pub struct Cli {
     )]
     pub file_count: Option<usize>,
 
+    /// Cap the chunk size derived from --file-count at RECORDS, writing more
+    /// files when needed; counting stops once the cap is certain to apply
+    #[arg(long, value_name = "RECORDS", requires = "file_count")]
+    pub chunk_size_max: Option<usize>,
+
+    /// Split into exactly N equally sized chunks; fails unless the record count divides evenly
+    #[arg(
+        long,
+        value_name = "CHUNKS",
+        conflicts_with_all = ["chunk_size", "file_count"]
+    )]
+    pub exact_chunks: Option<usize>,
+
+    /// Start a new chunk before one would exceed SIZE bytes (e.g. 100MB,
+    /// 512KiB); without --chunk-size the record count is not limited
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = parse_size,
+        conflicts_with_all = ["file_count", "exact_chunks"]
+    )]
+    pub max_bytes: Option<u64>,
+
+    /// Aim every chunk at about SIZE bytes on disk (after compression): the
+    /// record count of each chunk follows a moving average of the record
+    /// sizes written so far, starting from --chunk-size
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = parse_size,
+        conflicts_with_all = ["file_count", "exact_chunks"]
+    )]
+    pub adaptive: Option<u64>,
+
+    /// Route triples whose predicate is listed in FILE (one IRI per line) to a
+    /// `hot` chunk series and all others to a `cold` series
+    #[arg(long, value_name = "FILE")]
+    pub partition_predicates: Option<PathBuf>,
+
+    /// Write schema statements (rdfs:/owl: axioms, class and property
+    /// declarations) to a single unchunked `tbox` series
+    #[arg(long)]
+    pub separate_tbox: bool,
+
+    /// Prepend the statements of FILE (ontology IRI, version, imports) to every
+    /// chunk so each one is a standalone ontology document
+    #[arg(long, value_name = "FILE")]
+    pub ontology_header: Option<PathBuf>,
+
+    /// Write the text of FILE verbatim at the start of every chunk, before
+    /// the format's own prologue (comments, a prefix block, …)
+    #[arg(long, value_name = "FILE")]
+    pub chunk_header: Option<PathBuf>,
+
+    /// Write the text of FILE verbatim at the end of every chunk
+    #[arg(long, value_name = "FILE")]
+    pub chunk_footer: Option<PathBuf>,
+
+    /// Keep blank-node structures (OWL restrictions, axiom annotations, class
+    /// expressions) together in one chunk, even if it exceeds the chunk size
+    #[arg(long)]
+    pub keep_axioms: bool,
+
+    /// How blank nodes are written across chunks: labels as parsed, kept
+    /// together with the statements sharing them, or renamed per chunk
+    #[arg(long, value_enum, value_name = "STRATEGY", default_value_t = BnodeStrategy::Preserve)]
+    pub bnode_strategy: BnodeStrategy,
+
+    /// Keep statement annotations (reification nodes, RDF-star `<< >>`
+    /// annotations) in the chunk of the statement they describe
+    #[arg(long, value_enum, value_name = "KIND")]
+    pub keep_annotations: Option<AnnotationKinds>,
+
+    /// Write a CSV report of object IRIs used in one chunk but described in
+    /// another (cross-chunk references)
+    #[arg(long, value_name = "FILE")]
+    pub link_report: Option<PathBuf>,
+
+    /// Write the chunk paths to FILE in dependency order: chunks describing
+    /// referenced entities are listed before the chunks referencing them
+    #[arg(long, value_name = "FILE")]
+    pub load_order: Option<PathBuf>,
+
+    /// Write a JSON manifest of the chunks to FILE: path, inputs, record
+    /// count, byte size and checksum of every chunk
+    #[arg(long, value_name = "FILE", conflicts_with = "stdout")]
+    pub manifest: Option<PathBuf>,
+
+    /// Verify each input against a SHA-256 digest before splitting:
+    /// `sha256:<hex>`, or `sidecar` to read `<input>.sha256`
+    #[arg(long, value_name = "DIGEST")]
+    pub verify_input: Option<InputDigest>,
+
+    /// How non-ASCII characters in IRIs are written to chunks
+    #[arg(long, value_enum, value_name = "POLICY", default_value_t = IriEncoding::Preserve)]
+    pub iri_encoding: IriEncoding,
+
+    /// Write the string literals of every chunk to a sidecar
+    /// (`<chunk>.literals.jsonl` or `.literals.tsv`) for full-text indexing
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub literal_index: Option<LiteralIndex>,
+
+    /// Write the input line and byte range of every record next to each
+    /// chunk (`<chunk>.lines.tsv`); N-Triples and N-Quads inputs only
+    #[arg(long, conflicts_with_all = ["group_by", "stdout"])]
+    pub line_map: bool,
+
+    /// Drop repeated statements within each input before they are written,
+    /// so duplicates do not inflate the chunk count
+    #[arg(long, group = "dedupe_mode")]
+    pub dedupe: bool,
+
+    /// Drop statements already seen earlier in the run (in an earlier input
+    /// or earlier in the same input), producing a duplicate-free union
+    #[arg(long, group = "dedupe_mode")]
+    pub dedupe_inputs: bool,
+
+    /// Memory budget in MiB for --dedupe / --dedupe-inputs; beyond it
+    /// fingerprints are spilled to temporary files
+    #[arg(long, value_name = "MIB", default_value_t = 256, requires = "dedupe_mode")]
+    pub dedupe_memory: usize,
+
+    /// What makes a statement a repeat for --dedupe / --dedupe-inputs:
+    /// the triple alone, or the triple in its graph
+    #[arg(long, value_enum, default_value_t = DedupeKey::Quad, requires = "dedupe_mode")]
+    pub dedupe_key: DedupeKey,
+
+    /// Digest of the chunk checksums in --manifest, --ro-crate and
+    /// --describe-fragments (default sha256), also used for --dedupe
+    /// fingerprints and --partition-by instead of their built-in hashes
+    #[arg(long, value_enum, value_name = "ALGORITHM")]
+    pub hash: Option<HashAlgorithm>,
+
+    /// Reject N-Triples / N-Quads lines longer than BYTES with a diagnostic
+    /// instead of buffering them (0 disables the limit)
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_MAX_LINE_BYTES)]
+    pub max_line_bytes: usize,
+
+    /// Pass over N-Triples / N-Quads lines that do not parse, logging each,
+    /// instead of failing the input; with =N an input with more than N bad
+    /// lines still fails.  Counting and sampling passes parse strictly.
+    #[arg(
+        long,
+        value_name = "N",
+        require_equals = true,
+        conflicts_with_all = ["file_count", "exact_chunks", "stratified_sample", "filter_type"]
+    )]
+    pub skip_bad_records: Option<Option<usize>>,
+
+    /// Collect the lines passed over by --skip-bad-records in FILE, each
+    /// after a comment naming its input, line and error
+    #[arg(long, value_name = "FILE", requires = "skip_bad_records")]
+    pub rejects: Option<PathBuf>,
+
+    /// Write chunks as FORMAT instead of the input format
+    /// (file extension: ttl, nt, nq, trig, rdf, jsonld)
+    #[arg(long, value_name = "FORMAT", value_parser = parse_output_format)]
+    pub output_format: Option<RdfFormat>,
+
+    /// Compact JSON-LD chunks against the @context in FILE (or at a URL),
+    /// declared at the top of every chunk, instead of writing them in
+    /// expanded form
+    #[arg(long, value_name = "FILE")]
+    pub jsonld_context: Option<String>,
+
+    /// Declare PREFIX=IRI in every Turtle / TriG chunk and write IRIs in
+    /// that namespace as prefixed names; replaces an input prefix of the
+    /// same name (repeatable)
+    #[arg(long = "prefix", value_name = "PREFIX=IRI", value_parser = parse_prefix)]
+    pub prefixes: Vec<(String, String)>,
+
+    /// Never split a group of statements across chunks; chunks may exceed
+    /// --chunk-size to finish a group.  Reads each input fully into memory
+    #[arg(long, value_enum, value_name = "KEY")]
+    pub group_by: Option<GroupBy>,
+
+    /// Link a subject to the IRI objects of predicate IRI for
+    /// --group-by connected, so a record and the resources it points to
+    /// share a chunk (repeatable)
+    #[arg(long, value_name = "IRI", requires = "group_by")]
+    pub connect_predicate: Vec<String>,
+
+    /// Route every statement to one of --partitions files by a hash of KEY
+    /// instead of cutting sequential chunks; a key lands in the same
+    /// partition on every run
+    #[arg(long, value_enum, value_name = "KEY", requires = "partitions")]
+    pub partition_by: Option<PartitionBy>,
+
+    /// Number of --partition-by files (`<stem>_part_NNNN.<ext>`)
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "partition_by",
+        value_parser = clap::value_parser!(u32).range(2..),
+        conflicts_with_all = [
+            "chunk_size", "file_count", "exact_chunks", "max_bytes", "adaptive",
+            "partition_predicates", "separate_tbox", "group_by", "stdout"
+        ]
+    )]
+    pub partitions: Option<u32>,
+
+    /// Write the statements of every subject IRI host (authority) to their
+    /// own chunk series, `<stem>_<host>_NNNN.<ext>`; other subjects stay in
+    /// the default series
+    #[arg(long, conflicts_with_all = ["partitions", "partition_predicates"])]
+    pub route_by_authority: bool,
+
+    /// Append to the --partitions files an earlier input or run wrote
+    /// instead of refusing (or, with -f, replacing) them, so incremental
+    /// runs accumulate into the same partitions
+    #[arg(
+        long,
+        requires = "partitions",
+        conflicts_with_all = [
+            "staged", "resume", "dated_output", "chunk_header", "chunk_footer",
+            "line_map", "literal_index", "encrypt"
+        ]
+    )]
+    pub append: bool,
+
+    /// Files the open chunks may hold at once; past it the least recently
+    /// written chunk is closed and reopened to append [default: `ulimit -n`
+    /// less 32]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_open_files: Option<u32>,
+
+    /// Treat all inputs as one stream and chunk them together as
+    /// `<NAME>_NNNN.<ext>`, e.g. to consolidate many small files
+    #[arg(long, value_name = "NAME", value_parser = parse_merge_name, conflicts_with = "line_map")]
+    pub merge: Option<String>,
+
+    /// Split only statements with predicate IRI, dropping all others
+    /// (repeatable)
+    #[arg(long, value_name = "IRI", value_parser = parse_iri)]
+    pub include_predicate: Vec<String>,
+
+    /// Drop statements with predicate IRI before splitting (repeatable)
+    #[arg(long, value_name = "IRI", value_parser = parse_iri)]
+    pub exclude_predicate: Vec<String>,
+
+    /// Split only statements whose subject IRI starts with PREFIX, e.g. a
+    /// namespace (repeatable)
+    #[arg(long, value_name = "PREFIX", value_parser = parse_iri)]
+    pub subject_prefix: Vec<String>,
+
+    /// Split only statements whose object is an IRI starting with PREFIX
+    /// (repeatable)
+    #[arg(long, value_name = "PREFIX", value_parser = parse_iri)]
+    pub object_prefix: Vec<String>,
+
+    /// Split only statements matching EXPR, e.g. `?p = rdf:type && ?o =
+    /// foaf:Person`: ?s/?p/?o compared with = and != to terms, isIRI,
+    /// isLiteral, isBlank, combined with &&, || and !
+    #[arg(long, value_name = "EXPR")]
+    pub filter_expr: Option<String>,
+
+    /// Split only the descriptions of instances of CLASS (their statements
+    /// and the blank nodes they use); reads each input twice (repeatable)
+    #[arg(
+        long,
+        value_name = "CLASS",
+        value_parser = parse_iri,
+        conflicts_with_all = ["file_count", "exact_chunks"]
+    )]
+    pub filter_type: Vec<String>,
+
+    /// Write only a sample of N instances of a class, with the blank nodes
+    /// they reference: `type=<ClassIRI>:N` (repeat for more classes)
+    #[arg(
+        long,
+        value_name = "SPEC",
+        conflicts_with_all = ["file_count", "exact_chunks"]
+    )]
+    pub stratified_sample: Vec<StratifiedSample>,
+
+    /// Move default-graph statements into a named graph derived from the
+    /// input file name; REGEX is matched against the file name
+    #[arg(long, value_name = "REGEX", requires = "graph_iri")]
+    pub graph_from_filename: Option<Regex>,
+
+    /// Graph IRI template for --graph-from-filename; `$1` or `${name}`
+    /// insert capture groups
+    #[arg(long, value_name = "TEMPLATE", requires = "graph_from_filename")]
+    pub graph_iri: Option<String>,
+
+    /// Compress every chunk (`<chunk>.gz`); the gzip header records the
+    /// source input and chunk index
+    #[arg(long, value_enum, value_name = "CODEC")]
+    pub compress: Option<Compression>,
+
+    /// Compression level, 0 (fastest) to 9 (smallest) [default: 6]
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        requires = "compress",
+        value_parser = clap::value_parser!(u32).range(0..=9)
+    )]
+    pub compress_level: Option<u32>,
+
+    /// Compress every chunk with N threads, as independent gzip members of
+    /// 1 MiB of input each [default: 1]
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "compress",
+        value_parser = clap::value_parser!(u32).range(1..=256)
+    )]
+    pub compress_threads: Option<u32>,
+
+    /// Encrypt every chunk as it is written (`<chunk>.age` / `.gpg`) with
+    /// the `age` or `gpg` tool: `age:<recipient>` or `gpg:<key-id>`
+    #[arg(long, value_name = "RECIPIENT", conflicts_with = "literal_index")]
+    pub encrypt: Option<Encryption>,
+
+    /// Write the chunks to standard output instead of files; `marked`
+    /// precedes each with a `# rdfsplitter-chunk: <name>` line
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        num_args = 0..=1,
+        default_missing_value = "marked",
+        conflicts_with_all = ["compress", "encrypt", "literal_index", "explain"]
+    )]
+    pub stdout: Option<StdoutMode>,
+
+    /// Package the chunks as an RO-Crate: write `ro-crate-metadata.json`
+    /// describing the dataset, its chunks and their provenance
+    #[arg(long, conflicts_with = "stdout")]
+    pub ro_crate: bool,
+
+    /// Write `fragments.ttl` describing the split in RDF: every chunk as a
+    /// `dcat:Distribution` with its size, statement count, checksum and
+    /// source (DCAT, VoID, PROV)
+    #[arg(long, conflicts_with = "stdout")]
+    pub describe_fragments: bool,
+
+    /// Title of the chunk set, recorded in the RO-Crate, the fragment
+    /// description and metadata triples
+    #[arg(long, value_name = "TEXT")]
+    pub title: Option<String>,
+
+    /// Licence of the chunk set (IRI, e.g. a Creative Commons licence)
+    #[arg(long, value_name = "IRI", value_parser = parse_iri)]
+    pub license: Option<String>,
+
+    /// Publisher of the chunk set (IRI)
+    #[arg(long, value_name = "IRI", value_parser = parse_iri)]
+    pub publisher: Option<String>,
+
+    /// Also state --title, --license and --publisher in every chunk, as
+    /// `dcat:Dataset` statements ahead of the data (not counted as records)
+    #[arg(long)]
+    pub metadata_triples: bool,
+
     /// Output directory (defaults to current directory)
     #[arg(short = 'o', long, default_value = ".", value_name = "OUTPUTDIR")]
     pub output: PathBuf,
 
+    /// Write chunks to a new `<OUTPUTDIR>/<YYYY-MM-DD>/<run-id>/` directory
+    /// (run id: UTC start time HHMMSS)
+    #[arg(long)]
+    pub dated_output: bool,
+
+    /// Permission mode for created chunk files, in octal (Unix only)
+    #[arg(long, value_name = "MODE", value_parser = parse_mode)]
+    pub mode: Option<u32>,
+
+    /// Permission mode for created directories, in octal (Unix only)
+    #[arg(long, value_name = "MODE", value_parser = parse_mode)]
+    pub dir_mode: Option<u32>,
+
+    /// Write the chunks of each input to a hidden staging directory and
+    /// move them into OUTPUTDIR only once the whole input has been split
+    #[arg(long, conflicts_with = "stdout")]
+    pub staged: bool,
+
+    /// Mirror the directory of each input below OUTPUTDIR, so chunks of
+    /// `data/a/x.nt` land in `OUTPUTDIR/data/a/x_0000.nt`
+    #[arg(long, conflicts_with_all = ["stdout", "merge"])]
+    pub preserve_structure: bool,
+
+    /// Suffix chunk names with 8 hex digits of the SHA-256 of the input
+    /// path (`data_1a2b3c4d_0000.nt`), so inputs sharing a file stem do
+    /// not collide
+    #[arg(long, conflicts_with = "merge")]
+    pub suffix_input_hash: bool,
+
+    /// Split only the records appended to each input since the last run
+    /// with --tail, numbering the new chunks on (N-Triples / N-Quads files
+    /// still being appended to)
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "stdout", "merge", "file_count", "exact_chunks", "partitions",
+            "route_by_authority", "partition_predicates", "line_map",
+            "stratified_sample", "filter_type"
+        ]
+    )]
+    pub tail: bool,
+
+    /// Close the open chunks of standard input once no statement has
+    /// arrived for DURATION (e.g. 30s, 500ms, 5m), so a slow pipeline's
+    /// statements are written out instead of held; N-Triples / N-Quads
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        conflicts_with_all = ["group_by", "line_map", "skip_bad_records", "tail"]
+    )]
+    pub chunk_idle_timeout: Option<Duration>,
+
+    /// Record progress in OUTPUTDIR/.rdfsplitter-manifest.json; a later run
+    /// with --resume and the same options passes over inputs already split
+    /// and does not write again the chunks an interrupted input completed
+    #[arg(
+        long,
+        conflicts_with_all = ["stdout", "staged", "tail", "dry_run", "merge", "dated_output"]
+    )]
+    pub resume: bool,
+
+    /// Before splitting, estimate the size of the chunks from the inputs and
+    /// fail unless the output file system keeps SIZE (e.g. 10GB) free
+    /// after them
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, conflicts_with_all = ["stdout", "dry_run"])]
+    pub min_free_space: Option<u64>,
+
+    /// Keep memory use small (e.g. 512 MB containers): refuse options that
+    /// hold whole inputs or all chunk IRIs in memory, spill deduplication
+    /// to disk early, cap line buffers and compress on one thread
+    #[arg(
+        long,
+        conflicts_with_all = ["group_by", "link_report", "load_order", "compress_threads"]
+    )]
+    pub low_memory: bool,
+
     /// Recurse into subdirectories
     #[arg(short = 'r', long)]
     pub recursive: bool,
@@ -48,7 +530,418 @@ pub struct Cli {
     #[arg(short = 'f', long)]
     pub force: bool,
 
+    /// Delete files in the output directory that are named like chunks of
+    /// this run's inputs but were not written by it (left by earlier runs)
+    #[arg(long, conflicts_with = "stdout")]
+    pub clean_stale: bool,
+
+    /// Print the effective configuration (chunking, series, per-file format
+    /// and output names) before running
+    #[arg(long)]
+    pub explain: bool,
+
+    /// Go through the split and print the chunks it would write (name,
+    /// records, bytes before compression) without writing any file
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "stdout", "staged", "dated_output", "ro_crate", "describe_fragments",
+            "link_report", "load_order", "manifest", "stdin_list", "rejects"
+        ]
+    )]
+    pub dry_run: bool,
+
+    /// Stop at the first input that fails instead of going on with the
+    /// others (exit status 2 either way)
+    #[arg(long, overrides_with = "keep_going")]
+    pub fail_fast: bool,
+
+    /// Go on with the other inputs when one fails (the default)
+    #[arg(long, overrides_with = "fail_fast")]
+    pub keep_going: bool,
+
+    /// Count inputs that are skipped (unrecognised or non-RDF files, or
+    /// unreadable paths) as failed inputs
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Write a JSON result line per input (records, chunks, seconds or the
+    /// error) and a final summary line to standard output
+    #[arg(long, conflicts_with_all = ["stdout", "dry_run", "explain", "stdin_list"])]
+    pub json: bool,
+
     /// Verbose log output
     #[arg(short = 'v', long)]
     pub verbose: bool,
+
+    /// Draw no progress bars (they are only drawn on a terminal anyway)
+    #[arg(long)]
+    pub no_progress: bool,
+
+    /// Make chunk writes fail or slow down to test error handling:
+    /// fail=P, after=BYTES, delay=MS, seed=N (comma-separated)
+    #[arg(long, value_name = "SPEC", hide = true)]
+    pub fault_inject: Option<FaultInjection>,
+}
+
+fn parse_output_format(s: &str) -> Result<RdfFormat, String> {
+    match RdfFormat::from_extension(s.trim_start_matches('.')) {
+        Some(f) if !f.is_sparql_results() => Ok(f),
+        _ => Err("expected one of: ttl, nt, nq, trig, rdf, jsonld".into()),
+    }
+}
+
+fn parse_input_format(s: &str) -> Result<RdfFormat, String> {
+    RdfFormat::from_extension(s.trim_start_matches('.'))
+        .ok_or_else(|| "expected one of: ttl, nt, nq, trig, rdf, jsonld, srj, srx".into())
+}
+
+fn parse_iri(s: &str) -> Result<String, String> {
+    oxiri::Iri::parse(s.to_owned())
+        .map(|iri| iri.into_inner())
+        .map_err(|e| format!("'{s}' is not an absolute IRI: {e}"))
+}
+
+/// A statement count: digits, optionally with `_` separators, or a
+/// power of ten such as `1e6`.
+fn parse_count(s: &str) -> Result<usize, String> {
+    let err = || format!("'{s}' is not a count (e.g. 5000, 1_000_000, 1e6)");
+    let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+        Some((m, e)) => (m, e.parse::<u32>().map_err(|_| err())?),
+        None => (s, 0),
+    };
+    let mantissa: usize = mantissa.replace('_', "").parse().map_err(|_| err())?;
+    10usize
+        .checked_pow(exponent)
+        .and_then(|p| mantissa.checked_mul(p))
+        .ok_or_else(err)
+}
+
+/// `--merge` names the chunks, so it must be a plain file name stem.
+fn parse_merge_name(s: &str) -> Result<String, String> {
+    if s.is_empty() || s.contains(['/', '\\', '.']) || s == stdio::STDIN {
+        return Err(format!("'{s}': expected a file name without directory or extension"));
+    }
+    Ok(s.to_owned())
+}
+
+/// `ex=http://example.org/` → `("ex", "http://example.org/")`.
+fn parse_prefix(s: &str) -> Result<(String, String), String> {
+    let (prefix, iri) = s
+        .split_once('=')
+        .ok_or_else(|| format!("'{s}': expected PREFIX=IRI"))?;
+    let name_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.');
+    let valid = prefix.chars().all(name_char)
+        && !prefix.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '_' | '-' | '.'))
+        && !prefix.ends_with('.');
+    if !valid {
+        return Err(format!("'{prefix}' is not a valid prefix name"));
+    }
+    Ok((prefix.to_owned(), parse_iri(iri)?))
+}
+
+/// `4096`, `100MB` (decimal) or `512KiB` (binary) → bytes.
+fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, unit) = s.split_at(split);
+    let factor: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1_000,
+        "m" | "mb" => 1_000_000,
+        "g" | "gb" => 1_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        _ => return Err(format!("unknown size unit '{unit}' (use B, KB, MB, GB, KiB, MiB or GiB)")),
+    };
+    match digits.parse::<u64>().ok().and_then(|n| n.checked_mul(factor)) {
+        Some(n) if n > 0 => Ok(n),
+        _ => Err(format!("'{s}' is not a positive size")),
+    }
+}
+
+/// `30`, `30s`, `500ms`, `5m` or `1h` → a positive duration.
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, unit) = s.split_at(split);
+    let millis: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "ms" => 1,
+        "" | "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        _ => return Err(format!("unknown time unit '{unit}' (use ms, s, m or h)")),
+    };
+    match digits.parse::<u64>().ok().and_then(|n| n.checked_mul(millis)) {
+        Some(n) if n > 0 => Ok(Duration::from_millis(n)),
+        _ => Err(format!("'{s}' is not a positive duration")),
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Split inputs into chunks (the default command; options as shown by
+    /// `rdfsplitter --help`)
+    #[command(disable_help_flag = true)]
+    Split {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, value_name = "ARGS")]
+        args: Vec<String>,
+    },
+
+    /// Chunk all inputs together into <NAME>_NNNN.<ext> (same as `split
+    /// --merge NAME`)
+    #[command(disable_help_flag = true)]
+    Merge {
+        /// Name of the merged chunk series
+        #[arg(value_name = "NAME", value_parser = parse_merge_name)]
+        name: String,
+
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, value_name = "ARGS")]
+        args: Vec<String>,
+    },
+
+    /// Convert every input whole to another format, as <stem>.<ext>
+    Convert {
+        /// Input file(s) or glob patterns
+        #[arg(required = true)]
+        inputs: Vec<String>,
+
+        /// Output format (ttl, nt, nq, trig, rdf, jsonld)
+        #[arg(long, value_name = "FORMAT", value_parser = parse_output_format)]
+        to: RdfFormat,
+
+        /// Read every input as FORMAT instead of detecting it
+        #[arg(long, value_name = "FORMAT", value_parser = parse_input_format)]
+        format: Option<RdfFormat>,
+
+        /// Declare PREFIX=IRI in Turtle / TriG output (repeatable)
+        #[arg(long = "prefix", value_name = "PREFIX=IRI", value_parser = parse_prefix)]
+        prefixes: Vec<(String, String)>,
+
+        /// Output directory
+        #[arg(short = 'o', long, default_value = ".", value_name = "OUTPUTDIR")]
+        output: PathBuf,
+
+        /// Overwrite existing output files; create output directory if missing
+        #[arg(short = 'f', long)]
+        force: bool,
+
+        /// Recurse into subdirectories
+        #[arg(short = 'r', long)]
+        recursive: bool,
+    },
+
+    /// Print the number of statements (or result rows), distinct subjects,
+    /// predicates and graphs, and IRI / literal / blank-node objects of
+    /// every input
+    Count {
+        /// Input file(s) or glob patterns
+        #[arg(required = true)]
+        inputs: Vec<String>,
+
+        /// Print a JSON document instead of a table
+        #[arg(long)]
+        json: bool,
+
+        /// Read every input as FORMAT instead of detecting it
+        #[arg(long, value_name = "FORMAT", value_parser = parse_input_format)]
+        format: Option<RdfFormat>,
+
+        /// Recurse into subdirectories
+        #[arg(short = 'r', long)]
+        recursive: bool,
+    },
+
+    /// Parse every input to its end and report the ones that are invalid
+    Validate {
+        /// Input file(s) or glob patterns
+        #[arg(required = true)]
+        inputs: Vec<String>,
+
+        /// Read every input as FORMAT instead of detecting it
+        #[arg(long, value_name = "FORMAT", value_parser = parse_input_format)]
+        format: Option<RdfFormat>,
+
+        /// Recurse into subdirectories
+        #[arg(short = 'r', long)]
+        recursive: bool,
+    },
+
+    /// Verify the chunks in DIR against a --manifest written by an earlier
+    /// split: existence, size, checksum and that they parse
+    Check {
+        /// Directory the manifest's chunk paths are relative to
+        dir: PathBuf,
+
+        /// Manifest written by `--manifest`
+        #[arg(long, value_name = "FILE")]
+        manifest: PathBuf,
+    },
+
+    /// Merge the small chunks in DIR and cut the oversized ones into new
+    /// chunks of at most --max-bytes, series by series
+    Repack {
+        /// Directory holding the chunks
+        dir: PathBuf,
+
+        /// Byte size limit of the repacked chunks (e.g. 256MB)
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_bytes: u64,
+
+        /// Output directory; must not be DIR
+        #[arg(short = 'o', long, value_name = "OUTPUTDIR")]
+        output: PathBuf,
+
+        /// Overwrite existing output files; create output directory if missing
+        #[arg(short = 'f', long)]
+        force: bool,
+    },
+
+    /// Write deterministic synthetic RDF of a given size and shape
+    Generate {
+        /// Number of statements (e.g. 5000, 1e6)
+        #[arg(long, value_name = "N", value_parser = parse_count)]
+        triples: usize,
+
+        /// Number of classes the resources are spread over
+        #[arg(long, value_name = "N", default_value_t = 10,
+              value_parser = clap::value_parser!(u32).range(1..))]
+        classes: u32,
+
+        /// Output format (ttl, nt, nq, trig, rdf, jsonld); quad formats put
+        /// each class into its own graph
+        #[arg(long, value_name = "FORMAT", default_value = "nt", value_parser = parse_output_format)]
+        format: RdfFormat,
+
+        /// Seed of the pseudo-random values
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        seed: u64,
+
+        /// Write to FILE instead of standard output
+        #[arg(short = 'o', long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Overwrite an existing output file
+        #[arg(short = 'f', long)]
+        force: bool,
+    },
+
+    /// Run a named profile (inputs and options) from a TOML profile file
+    Run {
+        /// Name of the profile to run
+        #[arg(long, value_name = "NAME")]
+        profile: String,
+
+        /// Profile file with one table per profile
+        #[arg(long, value_name = "FILE", default_value = "profiles.toml")]
+        profile_file: PathBuf,
+    },
+
+    /// Execute every job descriptor (.toml / .json) in a queue directory and
+    /// move it to `done/` or `failed/` with a report
+    Queue {
+        /// Queue directory
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+    },
+
+    /// Write the statements added and removed between two dumps (files,
+    /// directories or globs) as `delta_added_*` / `delta_removed_*` chunks
+    Delta {
+        /// Old dump
+        old: String,
+
+        /// New dump
+        new: String,
+
+        /// Number of statements per output chunk
+        #[arg(short = 'n', long, value_name = "TRIPLES", default_value_t = 10_000)]
+        chunk_size: usize,
+
+        /// Output format of the delta chunks
+        #[arg(long, value_enum, value_name = "FORMAT", default_value_t = DeltaFormat::Nquads)]
+        delta_format: DeltaFormat,
+
+        /// Close a chunk before it would exceed SIZE bytes (e.g. 10MB)
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_bytes: Option<u64>,
+
+        /// Output directory
+        #[arg(short = 'o', long, default_value = ".", value_name = "OUTPUTDIR")]
+        output: PathBuf,
+
+        /// Overwrite existing output files; create output directory if missing
+        #[arg(short = 'f', long)]
+        force: bool,
+    },
+
+    /// Print statistics about the input statements (named graphs, classes)
+    Stats {
+        /// Input file(s) or glob patterns
+        #[arg(required = true)]
+        inputs: Vec<String>,
+
+        /// Rows shown per table
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        top: usize,
+
+        /// Also write the full class instance table to FILE (CSV)
+        #[arg(long, value_name = "FILE")]
+        classes_csv: Option<PathBuf>,
+
+        /// Recurse into subdirectories
+        #[arg(short = 'r', long)]
+        recursive: bool,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes_with_units() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("100MB"), Ok(100_000_000));
+        assert_eq!(parse_size("512 KiB"), Ok(512 * 1024));
+        assert_eq!(parse_size("2gb"), Ok(2_000_000_000));
+        assert!(parse_size("0").is_err());
+        assert!(parse_size("10 parsecs").is_err());
+        assert!(parse_size("MB").is_err());
+    }
+
+    #[test]
+    fn parses_durations_with_units() {
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("5 M"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("2 weeks").is_err());
+        assert!(parse_duration("s").is_err());
+    }
+
+    #[test]
+    fn parses_counts() {
+        assert_eq!(parse_count("5000"), Ok(5000));
+        assert_eq!(parse_count("1_000_000"), Ok(1_000_000));
+        assert_eq!(parse_count("1e6"), Ok(1_000_000));
+        assert_eq!(parse_count("25E2"), Ok(2500));
+        assert!(parse_count("1.5e6").is_err());
+        assert!(parse_count("1e99").is_err());
+        assert!(parse_count("many").is_err());
+    }
+
+    #[test]
+    fn parses_prefixes() {
+        assert_eq!(
+            parse_prefix("era=http://data.europa.eu/949/"),
+            Ok(("era".into(), "http://data.europa.eu/949/".into()))
+        );
+        assert_eq!(parse_prefix("=http://ex.org/"), Ok(("".into(), "http://ex.org/".into())));
+        assert!(parse_prefix("http://ex.org/").is_err());
+        assert!(parse_prefix("1x=http://ex.org/").is_err());
+        assert!(parse_prefix("ex=relative/").is_err());
+    }
 }
@@ -0,0 +1,490 @@
+//! Compressed chunk output (`--compress`).
+//!
+//! Gzip chunks carry their provenance in the gzip header: FNAME holds the
+//! uncompressed chunk name and the comment names the source input and the
+//! chunk index, so both survive a rename in transit (`gzip -lN` shows them).
+//! With `--encrypt` the compressed stream is encrypted in turn.
+//!
+//! `--compress-level` trades speed for size.  `--compress-threads` cuts a
+//! chunk into blocks compressed side by side into consecutive gzip members,
+//! which `gzip -d` and `zcat` read back as one stream.
+//!
+//! A chunk file can be suspended to release its descriptor while many
+//! series are open at once (`--max-open-files`): the file is closed, ending
+//! the gzip member, and reopened to append on the next write, a gzip chunk
+//! getting a new member.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, BufWriter, Write},
+    mem,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use flate2::{write::GzEncoder, GzBuilder};
+
+use crate::{
+    encrypt::{Encryption, Encryptor},
+    faults::{FaultInjection, Faults},
+};
+
+/// Compression applied to chunk files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Compression {
+    /// gzip (`.gz`)
+    Gzip,
+}
+
+impl Compression {
+    /// Extension added to the names of compressed chunks.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+        }
+    }
+}
+
+/// Speed / size trade-off of the compressor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompressionTuning {
+    /// Compression level (gzip: 0–9); `None` for the codec's default.
+    pub level: Option<u32>,
+    /// Threads compressing one chunk; 0 and 1 compress on the writing thread.
+    pub threads: usize,
+}
+
+/// Input bytes per gzip member with `--compress-threads`.
+const BLOCK_BYTES: usize = 1 << 20;
+
+/// An output chunk file, compressed and encrypted or not, counting the bytes
+/// written to it before compression.
+pub struct ChunkFile {
+    sink: Sink,
+    written: u64,
+    faults: Option<Faults>,
+    /// How the file is reopened after [`ChunkFile::suspend`]; `None` when
+    /// there is no file to reopen or an encrypted one cannot be appended to.
+    reopen: Option<Reopen>,
+}
+
+/// A plain or compressed chunk file, reopened to append.
+struct Reopen {
+    path: PathBuf,
+    compression: Option<Compression>,
+    tuning: CompressionTuning,
+}
+
+enum Sink {
+    Plain(Target),
+    Gzip(GzEncoder<Target>),
+    ParallelGzip(ParallelGzip),
+    /// Closed by [`ChunkFile::suspend`] until the next write.
+    Suspended,
+}
+
+impl Sink {
+    /// Compress into `target` if asked to; `header` (file name, comment)
+    /// goes into the gzip header, a reopened file gets none.
+    fn new(
+        target: Target,
+        compression: Option<Compression>,
+        tuning: CompressionTuning,
+        header: Option<(String, String)>,
+    ) -> Self {
+        let Some(Compression::Gzip) = compression else {
+            return Sink::Plain(target);
+        };
+        let level = tuning
+            .level
+            .map_or_else(flate2::Compression::default, flate2::Compression::new);
+        if tuning.threads > 1 {
+            return Sink::ParallelGzip(ParallelGzip {
+                target,
+                header,
+                level,
+                threads: tuning.threads,
+                blocks: Vec::new(),
+                current: Vec::with_capacity(BLOCK_BYTES),
+            });
+        }
+        let builder = match header {
+            Some((name, comment)) => GzBuilder::new()
+                .filename(name.as_bytes())
+                .comment(comment.as_bytes()),
+            None => GzBuilder::new(),
+        };
+        Sink::Gzip(builder.write(target, level))
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Sink::Plain(target) => target.finish(),
+            Sink::Gzip(encoder) => encoder.finish()?.finish(),
+            Sink::ParallelGzip(gz) => gz.finish(),
+            Sink::Suspended => Ok(()),
+        }
+    }
+}
+
+/// Where the (compressed) bytes end up.
+enum Target {
+    File(BufWriter<fs::File>),
+    Encrypted(Encryptor),
+    Stdout(BufWriter<io::Stdout>),
+    Discard,
+}
+
+impl Target {
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Target::File(mut w) => w.flush(),
+            Target::Encrypted(e) => e.finish(),
+            Target::Stdout(mut w) => w.flush(),
+            Target::Discard => Ok(()),
+        }
+    }
+}
+
+impl Write for Target {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Target::File(w) => w.write(buf),
+            Target::Encrypted(e) => e.write(buf),
+            Target::Stdout(w) => w.write(buf),
+            Target::Discard => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Target::File(w) => w.flush(),
+            Target::Encrypted(e) => e.flush(),
+            Target::Stdout(w) => w.flush(),
+            Target::Discard => Ok(()),
+        }
+    }
+}
+
+impl ChunkFile {
+    /// Create `path`; `comment` is stored in the gzip header.
+    pub fn create(
+        path: &Path,
+        compression: Option<Compression>,
+        tuning: CompressionTuning,
+        encryption: Option<&Encryption>,
+        comment: &str,
+    ) -> io::Result<Self> {
+        let file = match encryption {
+            None => Target::File(BufWriter::new(fs::File::create(path)?)),
+            Some(encryption) => Target::Encrypted(Encryptor::spawn(encryption, path)?),
+        };
+        // the header names the decompressed file: drop `.gz` (and `.age`)
+        let compressed = match encryption {
+            Some(_) => path.with_extension(""),
+            None => path.to_path_buf(),
+        };
+        let name = compressed.file_stem().unwrap_or_default().to_string_lossy();
+        let header = (name.into_owned(), comment.to_owned());
+        Ok(Self {
+            sink: Sink::new(file, compression, tuning, Some(header)),
+            written: 0,
+            faults: None,
+            reopen: encryption.is_none().then(|| Reopen {
+                path: path.to_path_buf(),
+                compression,
+                tuning,
+            }),
+        })
+    }
+
+    /// Open `path` to append, creating it if missing (`--append`); a gzip
+    /// file is continued in a new member, with a header only if it is new.
+    pub fn append(
+        path: &Path,
+        compression: Option<Compression>,
+        tuning: CompressionTuning,
+        comment: &str,
+    ) -> io::Result<Self> {
+        let new = fs::metadata(path).map_or(true, |m| m.len() == 0);
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let header = new.then(|| (name.into_owned(), comment.to_owned()));
+        Ok(Self {
+            sink: Sink::new(Target::File(BufWriter::new(file)), compression, tuning, header),
+            written: 0,
+            faults: None,
+            reopen: Some(Reopen {
+                path: path.to_path_buf(),
+                compression,
+                tuning,
+            }),
+        })
+    }
+
+    /// A chunk written to standard output (`--stdout`), after `marker`.
+    pub fn stdout(marker: Option<&str>) -> io::Result<Self> {
+        let mut out = BufWriter::new(io::stdout());
+        if let Some(marker) = marker {
+            out.write_all(marker.as_bytes())?;
+        }
+        Ok(Self {
+            sink: Sink::Plain(Target::Stdout(out)),
+            written: 0,
+            faults: None,
+            reopen: None,
+        })
+    }
+
+    /// A chunk that is only counted, not written (`--dry-run`).
+    pub fn discard() -> Self {
+        Self {
+            sink: Sink::Plain(Target::Discard),
+            written: 0,
+            faults: None,
+            reopen: None,
+        }
+    }
+
+    /// Inject the faults of `spec` into the writes of chunk number `chunk`.
+    pub fn with_faults(self, spec: FaultInjection, chunk: usize) -> Self {
+        Self {
+            faults: Some(Faults::new(spec, chunk)),
+            ..self
+        }
+    }
+
+    /// Uncompressed bytes written so far.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// True for a file [`ChunkFile::suspend`] can close and reopen; an
+    /// encrypted chunk has to stay open.
+    pub fn is_suspendable(&self) -> bool {
+        self.reopen.is_some()
+    }
+
+    /// Close the file, ending the current gzip member; the next write
+    /// reopens it to append.  Does nothing to a chunk that cannot be
+    /// reopened.
+    pub fn suspend(&mut self) -> io::Result<()> {
+        if self.reopen.is_none() {
+            return Ok(());
+        }
+        mem::replace(&mut self.sink, Sink::Suspended).finish()
+    }
+
+    /// Reopen a suspended file to append, as a new gzip member.
+    fn resume(&mut self) -> io::Result<()> {
+        if let (Sink::Suspended, Some(reopen)) = (&self.sink, &self.reopen) {
+            let file = OpenOptions::new().append(true).open(&reopen.path)?;
+            let target = Target::File(BufWriter::new(file));
+            self.sink = Sink::new(target, reopen.compression, reopen.tuning, None);
+        }
+        Ok(())
+    }
+
+    /// Write the compressed stream's trailer and flush the file.
+    pub fn finish(self) -> io::Result<()> {
+        self.sink.finish()
+    }
+}
+
+/// Gzip compressed by several threads, one gzip member per block of
+/// [`BLOCK_BYTES`]; the first member carries the header.
+struct ParallelGzip {
+    target: Target,
+    /// File name and comment for the first member, until it is written.
+    header: Option<(String, String)>,
+    level: flate2::Compression,
+    threads: usize,
+    /// Full blocks waiting for a thread each.
+    blocks: Vec<Vec<u8>>,
+    current: Vec<u8>,
+}
+
+impl ParallelGzip {
+    /// Compress the waiting blocks in parallel and write them in order.
+    fn compress_blocks(&mut self) -> io::Result<()> {
+        let blocks = mem::take(&mut self.blocks);
+        let header = self.header.take();
+        let level = self.level;
+        let members = thread::scope(|scope| {
+            let handles: Vec<_> = blocks
+                .iter()
+                .enumerate()
+                .map(|(i, block)| {
+                    let header = header.as_ref().filter(|_| i == 0);
+                    scope.spawn(move || gzip_member(block, header, level))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("compression thread panicked"))
+                .collect::<io::Result<Vec<_>>>()
+        })?;
+        for member in members {
+            self.target.write_all(&member)?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        // an empty chunk still gets its (header-only) member
+        if !self.current.is_empty() || self.header.is_some() {
+            self.blocks.push(mem::take(&mut self.current));
+        }
+        self.compress_blocks()?;
+        self.target.finish()
+    }
+}
+
+impl Write for ParallelGzip {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(BLOCK_BYTES - self.current.len());
+        self.current.extend_from_slice(&buf[..n]);
+        if self.current.len() == BLOCK_BYTES {
+            let full = mem::replace(&mut self.current, Vec::with_capacity(BLOCK_BYTES));
+            self.blocks.push(full);
+            if self.blocks.len() == self.threads {
+                self.compress_blocks()?;
+            }
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+}
+
+/// One complete gzip member of `block`, with `header` (name, comment).
+fn gzip_member(
+    block: &[u8],
+    header: Option<&(String, String)>,
+    level: flate2::Compression,
+) -> io::Result<Vec<u8>> {
+    let builder = match header {
+        Some((name, comment)) => GzBuilder::new()
+            .filename(name.as_bytes())
+            .comment(comment.as_bytes()),
+        None => GzBuilder::new(),
+    };
+    let mut encoder = builder.write(Vec::with_capacity(block.len() / 4), level);
+    encoder.write_all(block)?;
+    encoder.finish()
+}
+
+impl Write for ChunkFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(faults) = &mut self.faults {
+            faults.before_write(buf.len())?;
+        }
+        self.resume()?;
+        let n = match &mut self.sink {
+            Sink::Plain(w) => w.write(buf)?,
+            Sink::Gzip(w) => w.write(buf)?,
+            Sink::ParallelGzip(w) => w.write(buf)?,
+            Sink::Suspended => unreachable!("resumed above"),
+        };
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.sink {
+            Sink::Plain(w) => w.flush(),
+            Sink::Gzip(w) => w.flush(),
+            Sink::ParallelGzip(w) => w.flush(),
+            Sink::Suspended => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::{GzDecoder, MultiGzDecoder};
+    use std::io::Read;
+
+    #[test]
+    fn gzip_header_records_name_and_provenance() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("data_0003.nt.gz");
+        let mut w = ChunkFile::create(
+            &path,
+            Some(Compression::Gzip),
+            CompressionTuning::default(),
+            None,
+            "source=data.nt chunk=3",
+        )
+        .unwrap();
+        w.write_all(b"<s> <p> <o> .\n").unwrap();
+        assert_eq!(w.written(), 14);
+        w.finish().unwrap();
+
+        let mut gz = GzDecoder::new(fs::File::open(&path).unwrap());
+        let mut text = String::new();
+        gz.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "<s> <p> <o> .\n");
+        let header = gz.header().unwrap();
+        assert_eq!(header.filename(), Some(&b"data_0003.nt"[..]));
+        assert_eq!(header.comment(), Some(&b"source=data.nt chunk=3"[..]));
+    }
+
+    #[test]
+    fn threaded_gzip_writes_one_member_per_block() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("data_0000.nt.gz");
+        let tuning = CompressionTuning {
+            level: Some(1),
+            threads: 2,
+        };
+        let mut w = ChunkFile::create(&path, Some(Compression::Gzip), tuning, None, "c").unwrap();
+        let line = b"<http://ex.org/s> <http://ex.org/p> \"o\" .\n";
+        let lines = 3 * BLOCK_BYTES / line.len();
+        for _ in 0..lines {
+            w.write_all(line).unwrap();
+        }
+        w.finish().unwrap();
+
+        let mut text = Vec::new();
+        MultiGzDecoder::new(fs::File::open(&path).unwrap())
+            .read_to_end(&mut text)
+            .unwrap();
+        assert_eq!(text.len(), lines * line.len());
+        // the first member alone holds one block, under the chunk's header
+        let mut first = GzDecoder::new(fs::File::open(&path).unwrap());
+        let mut block = Vec::new();
+        first.read_to_end(&mut block).unwrap();
+        assert_eq!(block.len(), BLOCK_BYTES);
+        assert_eq!(first.header().unwrap().filename(), Some(&b"data_0000.nt"[..]));
+    }
+
+    #[test]
+    fn suspended_gzip_chunk_is_continued_in_a_new_member() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("data_0000.nt.gz");
+        let gzip = Some(Compression::Gzip);
+        let tuning = CompressionTuning::default();
+        let mut w = ChunkFile::create(&path, gzip, tuning, None, "c").unwrap();
+        assert!(w.is_suspendable());
+        w.write_all(b"<s> <p> <a> .\n").unwrap();
+        w.suspend().unwrap();
+        w.write_all(b"<s> <p> <b> .\n").unwrap();
+        assert_eq!(w.written(), 28);
+        w.finish().unwrap();
+
+        let mut text = String::new();
+        MultiGzDecoder::new(fs::File::open(&path).unwrap())
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "<s> <p> <a> .\n<s> <p> <b> .\n");
+        let mut first = String::new();
+        GzDecoder::new(fs::File::open(&path).unwrap())
+            .read_to_string(&mut first)
+            .unwrap();
+        assert_eq!(first, "<s> <p> <a> .\n");
+    }
+}
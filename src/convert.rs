@@ -0,0 +1,94 @@
+//! Format conversion without splitting (`rdfsplitter convert`).
+//!
+//! Every input is written whole, as `<stem>.<ext>` in the output format, by
+//! the chunk writer of a split run: Turtle and TriG output keeps the input's
+//! prefixes, and all split options that do not cut (`--prefix`, `--mode`)
+//! apply.
+
+use std::{fs, path::Path};
+
+use log::{error, info};
+
+use crate::{
+    format::{RdfFormat, SplitterError},
+    inputs::{expand_inputs, input_format},
+    splitter::{chunk_path, split_file, ChunkInfo, SplitOptions, SplitSummary},
+};
+
+/// What a conversion wrote.
+#[derive(Debug, Default)]
+pub struct ConvertSummary {
+    /// One file per input converted.
+    pub converted: Vec<ChunkInfo>,
+    /// Inputs that could not be converted.
+    pub failed: usize,
+}
+
+/// Convert every input into `opts.output_format` in `opts.output_dir`.
+pub fn run_convert(
+    inputs: &[String],
+    recursive: bool,
+    format: Option<RdfFormat>,
+    opts: SplitOptions,
+) -> Result<ConvertSummary, SplitterError> {
+    let to = opts
+        .output_format
+        .ok_or_else(|| SplitterError::Other(anyhow::anyhow!("no output format to convert to")))?;
+    let opts = SplitOptions {
+        chunk_size: usize::MAX,
+        unnumbered: true,
+        ..opts
+    };
+    let files = expand_inputs(inputs, recursive).map_err(SplitterError::Other)?;
+    if files.is_empty() {
+        return Err(SplitterError::Parse(
+            "No input files found. Check your patterns or paths.".into(),
+        ));
+    }
+    let mut summary = ConvertSummary::default();
+    for path in &files {
+        match convert_file(path, format, to, &opts) {
+            Ok(converted) => {
+                info!("{}: {} record(s) → {}", path.display(), converted.records, to.label());
+                summary.converted.extend(converted.chunks);
+            }
+            Err(e) => {
+                error!("{}: {e}", path.display());
+                summary.failed += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+fn convert_file(
+    path: &Path,
+    format: Option<RdfFormat>,
+    to: RdfFormat,
+    opts: &SplitOptions,
+) -> Result<SplitSummary, SplitterError> {
+    let fmt = input_format(path, format, false)
+        .ok_or_else(|| SplitterError::UnsupportedFormat(path.display().to_string()))?;
+    if fmt.is_sparql_results() {
+        return Err(SplitterError::Other(anyhow::anyhow!(
+            "{} files hold query result rows, not RDF statements, and cannot be converted; \
+             run a CONSTRUCT query for RDF output",
+            fmt.label()
+        )));
+    }
+    let target = chunk_path(path, to, "", 0, opts);
+    if same_file(path, &target) {
+        return Err(SplitterError::Other(anyhow::anyhow!(
+            "converting to {} would overwrite the input",
+            target.display()
+        )));
+    }
+    split_file(path, fmt, opts)
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
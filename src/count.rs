@@ -0,0 +1,270 @@
+//! Statement counts and syntax checks (`rdfsplitter count`,
+//! `rdfsplitter validate`).
+//!
+//! Both read every input to its end without writing any chunk: `count`
+//! prints the size of each input (statements or result rows, distinct
+//! subjects, predicates and graphs, kinds of objects) to choose chunk
+//! settings by, and `validate` reports the inputs that do not parse, so a
+//! dump can be checked before a long split is started on it.
+
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use log::{error, info};
+use serde_json::{json, Value};
+
+use crate::{
+    format::{RdfFormat, SplitterError},
+    inputs::{expand_inputs, input_format},
+    serialise::OwnedQuad,
+    splitter::{self, count_records},
+};
+
+/// Column names of the `count` table, in order.
+const COLUMNS: [&str; 7] = [
+    "records",
+    "subjects",
+    "predicates",
+    "graphs",
+    "iri_objects",
+    "literal_objects",
+    "blank_objects",
+];
+
+/// Distinct terms and object kinds of the statements of an input.
+#[derive(Debug, Default)]
+struct StatementCounts {
+    subjects: HashSet<String>,
+    predicates: HashSet<String>,
+    graphs: HashSet<String>,
+    iri_objects: usize,
+    literal_objects: usize,
+    blank_objects: usize,
+}
+
+impl StatementCounts {
+    fn observe(&mut self, q: &OwnedQuad) {
+        let t = &q.triple;
+        if !self.subjects.contains(&t.subject) {
+            self.subjects.insert(t.subject.clone());
+        }
+        if !self.predicates.contains(&t.predicate) {
+            self.predicates.insert(t.predicate.clone());
+        }
+        if let Some(g) = &q.graph_name {
+            if !self.graphs.contains(g) {
+                self.graphs.insert(g.clone());
+            }
+        }
+        if t.object.starts_with('"') {
+            self.literal_objects += 1;
+        } else if t.object.starts_with("_:") {
+            self.blank_objects += 1;
+        } else if !t.object.starts_with("<<") {
+            self.iri_objects += 1;
+        }
+    }
+}
+
+/// Records of an input, with statement statistics unless it holds SPARQL
+/// results.
+#[derive(Debug, Default)]
+struct Counts {
+    records: usize,
+    statements: Option<StatementCounts>,
+}
+
+impl Counts {
+    fn values(&self) -> [Option<usize>; 7] {
+        let s = self.statements.as_ref();
+        [
+            Some(self.records),
+            s.map(|s| s.subjects.len()),
+            s.map(|s| s.predicates.len()),
+            s.map(|s| s.graphs.len()),
+            s.map(|s| s.iri_objects),
+            s.map(|s| s.literal_objects),
+            s.map(|s| s.blank_objects),
+        ]
+    }
+
+    fn json(&self) -> serde_json::Map<String, Value> {
+        COLUMNS
+            .iter()
+            .zip(self.values())
+            .filter_map(|(k, v)| Some(((*k).to_owned(), json!(v?))))
+            .collect()
+    }
+
+    fn row(&self, name: &str) -> String {
+        let mut row: Vec<String> = self
+            .values()
+            .iter()
+            .map(|v| v.map_or_else(|| "-".to_owned(), |v| v.to_string()))
+            .collect();
+        row.push(name.to_owned());
+        row.join("\t")
+    }
+}
+
+/// Print a table of record counts and statement statistics (distinct
+/// subjects, predicates and named graphs; IRI, literal and blank-node
+/// objects) for every input, and the totals of several, to standard output;
+/// a JSON document with `json`.  Returns the number of inputs that could not
+/// be read.
+pub fn run_count(
+    inputs: &[String],
+    recursive: bool,
+    format: Option<RdfFormat>,
+    json: bool,
+) -> Result<usize, SplitterError> {
+    let files = input_files(inputs, recursive)?;
+    let mut total = Counts::default();
+    let mut results = Vec::new();
+    for path in &files {
+        let counted = count_input(path, format, &mut total);
+        if let Err(e) = &counted {
+            error!("{}: {e}", path.display());
+        }
+        results.push((path, counted));
+    }
+    let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+
+    let mut out = io::stdout().lock();
+    if json {
+        let inputs: Vec<Value> = results
+            .iter()
+            .map(|(path, counted)| {
+                let mut o = match counted {
+                    Ok(c) => c.json(),
+                    Err(e) => [("error".to_owned(), json!(e.to_string()))]
+                        .into_iter()
+                        .collect(),
+                };
+                o.insert("input".into(), json!(path.display().to_string()));
+                Value::Object(o)
+            })
+            .collect();
+        let doc = json!({ "inputs": inputs, "total": total.json() });
+        writeln!(
+            out,
+            "{}",
+            serde_json::to_string_pretty(&doc).map_err(io::Error::from)?
+        )?;
+    } else {
+        writeln!(out, "{}\tinput", COLUMNS.join("\t"))?;
+        for (path, counted) in &results {
+            if let Ok(c) = counted {
+                writeln!(out, "{}", c.row(&path.display().to_string()))?;
+            }
+        }
+        if files.len() > 1 {
+            writeln!(out, "{}", total.row("total"))?;
+        }
+    }
+    Ok(failed)
+}
+
+/// Count one input, adding its statements to `total` as well.
+fn count_input(
+    path: &Path,
+    format: Option<RdfFormat>,
+    total: &mut Counts,
+) -> Result<Counts, SplitterError> {
+    let fmt = input_format(path, format, false)
+        .ok_or_else(|| SplitterError::UnsupportedFormat(path.display().to_string()))?;
+    if fmt.is_sparql_results() {
+        let records = count_records(path, fmt)?;
+        total.records += records;
+        return Ok(Counts {
+            records,
+            statements: None,
+        });
+    }
+    let mut statements = StatementCounts::default();
+    let totals = total.statements.get_or_insert_with(Default::default);
+    let mut records = 0usize;
+    let parsed = splitter::parse_quads(path, fmt, &mut |q| {
+        records += 1;
+        statements.observe(&q);
+        totals.observe(&q);
+        Ok(())
+    });
+    total.records += records;
+    parsed?;
+    Ok(Counts {
+        records,
+        statements: Some(statements),
+    })
+}
+
+/// Parse every input to its end and log whether it is valid.  Returns the
+/// number of invalid inputs.
+pub fn run_validate(
+    inputs: &[String],
+    recursive: bool,
+    format: Option<RdfFormat>,
+) -> Result<usize, SplitterError> {
+    let files = input_files(inputs, recursive)?;
+    let mut failed = 0usize;
+    for path in &files {
+        match check(path, format) {
+            Ok(n) => info!("{}: valid, {n} record(s)", path.display()),
+            Err(e) => {
+                error!("{}: invalid: {e}", path.display());
+                failed += 1;
+            }
+        }
+    }
+    info!("{} of {} input(s) valid", files.len() - failed, files.len());
+    Ok(failed)
+}
+
+fn input_files(inputs: &[String], recursive: bool) -> Result<Vec<PathBuf>, SplitterError> {
+    let files = expand_inputs(inputs, recursive).map_err(SplitterError::Other)?;
+    if files.is_empty() {
+        return Err(SplitterError::Parse(
+            "No input files found. Check your patterns or paths.".into(),
+        ));
+    }
+    Ok(files)
+}
+
+fn check(path: &Path, format: Option<RdfFormat>) -> Result<usize, SplitterError> {
+    let fmt = input_format(path, format, false)
+        .ok_or_else(|| SplitterError::UnsupportedFormat(path.display().to_string()))?;
+    count_records(path, fmt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialise::OwnedTriple;
+
+    fn q(s: &str, o: &str, g: Option<&str>) -> OwnedQuad {
+        OwnedQuad {
+            triple: OwnedTriple {
+                subject: s.into(),
+                predicate: "<http://ex.org/p>".into(),
+                object: o.into(),
+            },
+            graph_name: g.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn counts_distinct_terms_and_object_kinds() {
+        let mut c = StatementCounts::default();
+        c.observe(&q("<http://ex.org/a>", "\"x\"@en", None));
+        c.observe(&q("<http://ex.org/a>", "_:b", Some("<http://ex.org/g>")));
+        c.observe(&q("_:b", "<http://ex.org/o>", Some("<http://ex.org/g>")));
+        c.observe(&q("_:b", "<< <http://ex.org/a> <http://ex.org/p> \"x\" >>", None));
+        assert_eq!(c.subjects.len(), 2);
+        assert_eq!(c.predicates.len(), 1);
+        assert_eq!(c.graphs.len(), 1);
+        assert_eq!((c.iri_objects, c.literal_objects, c.blank_objects), (1, 1, 1));
+    }
+}
@@ -0,0 +1,316 @@
+//! Cross-input statement deduplication (`--dedupe-inputs`).
+//!
+//! Every statement is reduced to a 128-bit fingerprint, of its graph too
+//! unless keyed on the triple (`--dedupe-key`): two keyed SipHash values, or
+//! the leading bytes of the digest chosen with `--hash`.  Fingerprints are
+//! kept in memory up to a budget; beyond it they are spilled as sorted run
+//! files to a temporary directory and looked up there by binary search.
+//! Runs are merged once there are too many, so lookups stay cheap.
+
+use std::{
+    collections::HashSet,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    checksum::HashAlgorithm,
+    serialise::{OwnedQuad, OwnedTriple},
+};
+
+/// Bytes per fingerprint in memory, including hash set overhead (estimate).
+const BYTES_PER_ENTRY: usize = 32;
+/// Spilled runs are merged into one once this many exist.
+const MAX_RUNS: usize = 8;
+/// Distinguishes the spill directories of sets within one process.
+static SET_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Which parts of a statement make it a repeat (`--dedupe-key`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DedupeKey {
+    /// Subject, predicate and object: a statement asserted in another graph
+    /// is a repeat, the first graph is kept
+    Triple,
+    /// Subject, predicate, object and graph
+    #[default]
+    Quad,
+}
+
+/// The set of statements seen so far in a run.
+pub struct SeenSet {
+    key: DedupeKey,
+    hash: Option<HashAlgorithm>,
+    mem: HashSet<u128>,
+    limit: usize,
+    dir: Option<PathBuf>,
+    runs: Vec<SpillRun>,
+    next_run: usize,
+    /// Inputs started so far (see [`SeenSet::start_input`]).
+    inputs: usize,
+    /// Number of statements rejected as duplicates.
+    pub duplicates: u64,
+}
+
+struct SpillRun {
+    path: PathBuf,
+    file: fs::File,
+    len: u64,
+}
+
+impl SeenSet {
+    /// A set holding roughly `memory_mb` MiB of fingerprints in memory,
+    /// telling statements apart by `key`, fingerprinted with `hash`.
+    pub fn new(memory_mb: usize, key: DedupeKey, hash: Option<HashAlgorithm>) -> Self {
+        Self {
+            key,
+            hash,
+            mem: HashSet::new(),
+            limit: (memory_mb * 1024 * 1024 / BYTES_PER_ENTRY).max(1),
+            dir: None,
+            runs: Vec::new(),
+            next_run: 0,
+            inputs: 0,
+            duplicates: 0,
+        }
+    }
+
+    /// Note that the next statements come from another input, and return
+    /// its ordinal: blank nodes of different inputs are different nodes,
+    /// so their statements are inserted relabelled with the input's prefix.
+    pub fn start_input(&mut self) -> usize {
+        self.inputs += 1;
+        self.inputs - 1
+    }
+
+    /// Record `quad`; returns `false` if it was seen before.
+    pub fn insert(&mut self, quad: &OwnedQuad) -> io::Result<bool> {
+        let graph = match self.key {
+            DedupeKey::Triple => None,
+            DedupeKey::Quad => quad.graph_name.as_ref(),
+        };
+        let fp = match self.hash {
+            Some(alg) => digest128(alg, &quad.triple, graph),
+            None => hash128(&quad.triple, graph),
+        };
+        if self.mem.contains(&fp) || self.in_runs(fp)? {
+            self.duplicates += 1;
+            return Ok(false);
+        }
+        self.mem.insert(fp);
+        if self.mem.len() >= self.limit {
+            self.spill()?;
+        }
+        Ok(true)
+    }
+
+    fn in_runs(&mut self, fp: u128) -> io::Result<bool> {
+        for run in &mut self.runs {
+            if run.contains(fp)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn spill_dir(&mut self) -> io::Result<PathBuf> {
+        if let Some(dir) = &self.dir {
+            return Ok(dir.clone());
+        }
+        let dir = std::env::temp_dir().join(format!(
+            "rdfsplitter-dedupe-{}-{}",
+            std::process::id(),
+            SET_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir)?;
+        self.dir = Some(dir.clone());
+        Ok(dir)
+    }
+
+    fn new_run_path(&mut self) -> io::Result<PathBuf> {
+        let dir = self.spill_dir()?;
+        self.next_run += 1;
+        Ok(dir.join(format!("run-{:04}.bin", self.next_run)))
+    }
+
+    /// Move the in-memory fingerprints to a sorted run file.
+    fn spill(&mut self) -> io::Result<()> {
+        let mut fps: Vec<u128> = self.mem.drain().collect();
+        fps.sort_unstable();
+        let path = self.new_run_path()?;
+        let mut w = BufWriter::new(fs::File::create(&path)?);
+        for fp in &fps {
+            w.write_all(&fp.to_be_bytes())?;
+        }
+        w.flush()?;
+        self.runs.push(SpillRun::open(path, fps.len() as u64)?);
+        if self.runs.len() >= MAX_RUNS {
+            self.merge_runs()?;
+        }
+        Ok(())
+    }
+
+    /// k-way merge of all runs into one.
+    fn merge_runs(&mut self) -> io::Result<()> {
+        let path = self.new_run_path()?;
+        let mut readers = Vec::new();
+        for run in &self.runs {
+            let mut r = BufReader::new(fs::File::open(&run.path)?);
+            let head = read_fp(&mut r)?;
+            readers.push((r, head));
+        }
+        let mut w = BufWriter::new(fs::File::create(&path)?);
+        let mut len = 0u64;
+        loop {
+            let next = readers
+                .iter()
+                .enumerate()
+                .filter_map(|(i, (_, head))| head.map(|h| (h, i)))
+                .min();
+            let Some((fp, i)) = next else { break };
+            w.write_all(&fp.to_be_bytes())?;
+            len += 1;
+            let (r, head) = &mut readers[i];
+            *head = read_fp(r)?;
+        }
+        w.flush()?;
+        for run in self.runs.drain(..) {
+            fs::remove_file(&run.path)?;
+        }
+        self.runs.push(SpillRun::open(path, len)?);
+        Ok(())
+    }
+}
+
+impl Drop for SeenSet {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.dir {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+impl SpillRun {
+    fn open(path: PathBuf, len: u64) -> io::Result<Self> {
+        Ok(Self {
+            file: fs::File::open(&path)?,
+            path,
+            len,
+        })
+    }
+
+    fn contains(&mut self, fp: u128) -> io::Result<bool> {
+        let (mut lo, mut hi) = (0u64, self.len);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            self.file.seek(SeekFrom::Start(mid * 16))?;
+            let v = read_fp(&mut self.file)?.unwrap_or(u128::MAX);
+            match v.cmp(&fp) {
+                std::cmp::Ordering::Equal => return Ok(true),
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+            }
+        }
+        Ok(false)
+    }
+}
+
+fn read_fp(r: &mut impl Read) -> io::Result<Option<u128>> {
+    let mut buf = [0u8; 16];
+    match r.read_exact(&mut buf) {
+        Ok(()) => Ok(Some(u128::from_be_bytes(buf))),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// 128-bit fingerprint from two independently keyed 64-bit hashes.
+pub fn fingerprint(q: &OwnedQuad) -> u128 {
+    hash128(&q.triple, q.graph_name.as_ref())
+}
+
+fn hash128(t: &OwnedTriple, graph: Option<&String>) -> u128 {
+    let half = |seed: u64| {
+        let mut h = DefaultHasher::new();
+        seed.hash(&mut h);
+        t.subject.hash(&mut h);
+        t.predicate.hash(&mut h);
+        t.object.hash(&mut h);
+        graph.hash(&mut h);
+        h.finish()
+    };
+    (u128::from(half(0x5eed_0001)) << 64) | u128::from(half(0x5eed_0002))
+}
+
+/// Fingerprint from the first 16 bytes of the digest of the statement's
+/// terms, one per line (N-Triples terms hold no raw line break).
+fn digest128(alg: HashAlgorithm, t: &OwnedTriple, graph: Option<&String>) -> u128 {
+    let mut text = format!("{}\n{}\n{}", t.subject, t.predicate, t.object);
+    if let Some(g) = graph {
+        text.push('\n');
+        text.push_str(g);
+    }
+    let digest = alg.digest(text.as_bytes());
+    u128::from_be_bytes(digest[..16].try_into().expect("16 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q(i: usize) -> OwnedQuad {
+        OwnedQuad {
+            triple: OwnedTriple {
+                subject: format!("<s{i}>"),
+                predicate: "<p>".into(),
+                object: "<o>".into(),
+            },
+            graph_name: None,
+        }
+    }
+
+    #[test]
+    fn detects_duplicates_in_memory() {
+        let algorithms = [HashAlgorithm::Sha256, HashAlgorithm::Blake3, HashAlgorithm::Xxh3];
+        for hash in [None].into_iter().chain(algorithms.map(Some)) {
+            let mut seen = SeenSet::new(1, DedupeKey::Quad, hash);
+            assert!(seen.insert(&q(1)).unwrap());
+            assert!(seen.insert(&q(2)).unwrap());
+            assert!(!seen.insert(&q(1)).unwrap());
+            assert_eq!(seen.duplicates, 1);
+        }
+    }
+
+    #[test]
+    fn key_decides_whether_the_graph_counts() {
+        let in_graph = OwnedQuad {
+            graph_name: Some("<g>".into()),
+            ..q(1)
+        };
+        for hash in [None, Some(HashAlgorithm::Blake3)] {
+            let mut quads = SeenSet::new(1, DedupeKey::Quad, hash);
+            assert!(quads.insert(&q(1)).unwrap());
+            assert!(quads.insert(&in_graph).unwrap());
+            let mut triples = SeenSet::new(1, DedupeKey::Triple, hash);
+            assert!(triples.insert(&q(1)).unwrap());
+            assert!(!triples.insert(&in_graph).unwrap());
+        }
+    }
+
+    #[test]
+    fn detects_duplicates_after_spilling_and_merging() {
+        let mut seen = SeenSet::new(1, DedupeKey::Quad, None);
+        seen.limit = 3;
+        for i in 0..100 {
+            assert!(seen.insert(&q(i)).unwrap());
+        }
+        assert!(!seen.runs.is_empty());
+        assert!(seen.runs.len() < MAX_RUNS);
+        for i in 0..100 {
+            assert!(!seen.insert(&q(i)).unwrap(), "statement {i} not found");
+        }
+        assert!(seen.insert(&q(100)).unwrap());
+    }
+}
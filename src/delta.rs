@@ -0,0 +1,327 @@
+//! Delta between two dump versions (`rdfsplitter delta OLD NEW`).
+//!
+//! Statements are compared by fingerprint (see [`dedupe`](crate::dedupe)):
+//! one pass over OLD, one over NEW collecting additions, and a second pass
+//! over OLD collecting removals.  Added and removed statements are written as
+//! separate chunk series, `delta_added_NNNN.<ext>` and
+//! `delta_removed_NNNN.<ext>`, as N-Quads, RDF Patch or SPARQL Update
+//! (`DELETE DATA` / `INSERT DATA`).  Apply the removed series before the added
+//! one.
+//!
+//! Blank nodes are compared by label, so statements about blank nodes whose
+//! labels differ between the two dumps show up as removed and re-added.  The
+//! labels of each file of a dump are prefixed with its position (see
+//! [`bnodes`](crate::bnodes)), so equal labels in two files stay two nodes;
+//! two dumps compare alike when their files line up.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use log::{debug, info, warn};
+
+use crate::{
+    bnodes::{self, mentions_blank_node},
+    dedupe::fingerprint,
+    format::{RdfFormat, SplitterError},
+    inputs::expand_inputs,
+    paths,
+    serialise::OwnedQuad,
+    splitter::{check_overwrite, parse_quads, prepare_output_dir, SplitOptions},
+};
+
+/// How delta chunks are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DeltaFormat {
+    /// Plain N-Quads (`.nq`)
+    #[default]
+    Nquads,
+    /// RDF Patch transactions with `A` / `D` rows (`.rdfp`)
+    Patch,
+    /// SPARQL Update `INSERT DATA` / `DELETE DATA` requests (`.ru`)
+    Sparql,
+}
+
+impl DeltaFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            DeltaFormat::Nquads => "nq",
+            DeltaFormat::Patch => "rdfp",
+            DeltaFormat::Sparql => "ru",
+        }
+    }
+
+    /// Text written before and after the statements of a chunk.
+    fn envelope(self, series: &str) -> (&'static str, &'static str) {
+        match (self, series) {
+            (DeltaFormat::Nquads, _) => ("", ""),
+            (DeltaFormat::Patch, _) => ("TX .\n", "TC .\n"),
+            (DeltaFormat::Sparql, REMOVED_SERIES) => ("DELETE DATA {\n", "}\n"),
+            (DeltaFormat::Sparql, _) => ("INSERT DATA {\n", "}\n"),
+        }
+    }
+
+    /// One statement as a line of this format.
+    fn render(self, series: &str, q: &OwnedQuad) -> String {
+        let t = &q.triple;
+        let spo = format!("{} {} {}", t.subject, t.predicate, t.object);
+        match (self, &q.graph_name) {
+            (DeltaFormat::Nquads, Some(g)) => format!("{spo} {g} .\n"),
+            (DeltaFormat::Nquads, None) => format!("{spo} .\n"),
+            (DeltaFormat::Patch, g) => {
+                let op = if series == REMOVED_SERIES { 'D' } else { 'A' };
+                match g {
+                    Some(g) => format!("{op} {spo} {g} .\n"),
+                    None => format!("{op} {spo} .\n"),
+                }
+            }
+            (DeltaFormat::Sparql, Some(g)) => format!("  GRAPH {g} {{ {spo} . }}\n"),
+            (DeltaFormat::Sparql, None) => format!("  {spo} .\n"),
+        }
+    }
+}
+
+/// Chunk limits and format of a delta.
+#[derive(Debug, Clone, Copy)]
+pub struct DeltaOutput {
+    pub format: DeltaFormat,
+    /// Close a chunk before it would grow beyond this many bytes.
+    pub max_bytes: Option<u64>,
+}
+
+pub const ADDED_SERIES: &str = "added";
+pub const REMOVED_SERIES: &str = "removed";
+
+/// Number of statements added and removed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DeltaCounts {
+    pub added: usize,
+    pub removed: usize,
+    /// Removed statements that the output format cannot express.
+    pub skipped: usize,
+}
+
+/// Compute the delta from `old` to `new` (files, directories or globs) and
+/// write it below `opts.output_dir`.
+pub fn run_delta(
+    old: &str,
+    new: &str,
+    opts: &SplitOptions,
+    output: DeltaOutput,
+) -> Result<DeltaCounts, SplitterError> {
+    prepare_output_dir(opts)?;
+    let old_files = dump_files(old)?;
+    let new_files = dump_files(new)?;
+
+    info!("Reading old dump {old} ({} file(s)) …", old_files.len());
+    let mut old_set = HashSet::new();
+    for_each_statement(&old_files, &mut |q| {
+        old_set.insert(fingerprint(&q));
+        Ok(())
+    })?;
+
+    let mut writer = DeltaWriter::new(opts, output);
+    let mut counts = DeltaCounts::default();
+    let mut new_set = HashSet::new();
+
+    info!("Comparing new dump {new} ({} file(s)) …", new_files.len());
+    for_each_statement(&new_files, &mut |q| {
+        let fp = fingerprint(&q);
+        if new_set.insert(fp) && !old_set.contains(&fp) {
+            counts.added += 1;
+            writer.push(ADDED_SERIES, &q)?;
+        }
+        Ok(())
+    })?;
+    drop(old_set);
+
+    info!("Collecting removed statements …");
+    let mut emitted = HashSet::new();
+    for_each_statement(&old_files, &mut |q| {
+        let fp = fingerprint(&q);
+        if !new_set.contains(&fp) && emitted.insert(fp) {
+            if output.format == DeltaFormat::Sparql && mentions_blank_node(&q) {
+                // DELETE DATA cannot name blank nodes
+                counts.skipped += 1;
+                return Ok(());
+            }
+            counts.removed += 1;
+            writer.push(REMOVED_SERIES, &q)?;
+        }
+        Ok(())
+    })?;
+
+    let chunks = writer.finish()?;
+    info!(
+        "Delta: {} added, {} removed, {} chunk(s) written.",
+        counts.added, counts.removed, chunks
+    );
+    if counts.skipped > 0 {
+        warn!(
+            "{} removed statement(s) with blank nodes cannot be expressed as DELETE DATA and were skipped",
+            counts.skipped
+        );
+    }
+    Ok(counts)
+}
+
+/// Statements of one series waiting to be written.
+#[derive(Default)]
+struct Pending {
+    lines: Vec<String>,
+    bytes: u64,
+    chunk: usize,
+}
+
+/// Writes delta series in chunks capped by statement count and byte size.
+struct DeltaWriter<'a> {
+    opts: &'a SplitOptions,
+    output: DeltaOutput,
+    series: HashMap<&'static str, Pending>,
+    chunks: usize,
+}
+
+impl<'a> DeltaWriter<'a> {
+    fn new(opts: &'a SplitOptions, output: DeltaOutput) -> Self {
+        Self {
+            opts,
+            output,
+            series: HashMap::new(),
+            chunks: 0,
+        }
+    }
+
+    fn push(&mut self, series: &'static str, q: &OwnedQuad) -> Result<(), SplitterError> {
+        let line = self.output.format.render(series, q);
+        let (head, tail) = self.output.format.envelope(series);
+        let pending = self.series.entry(series).or_default();
+        let too_many = pending.lines.len() >= self.opts.chunk_size;
+        let too_big = self
+            .output
+            .max_bytes
+            .is_some_and(|max| pending.bytes + (head.len() + line.len() + tail.len()) as u64 > max);
+        if !pending.lines.is_empty() && (too_many || too_big) {
+            self.flush(series)?;
+        }
+        let pending = self.series.entry(series).or_default();
+        pending.bytes += line.len() as u64;
+        pending.lines.push(line);
+        Ok(())
+    }
+
+    fn flush(&mut self, series: &str) -> Result<(), SplitterError> {
+        let format = self.output.format;
+        let Some(pending) = self.series.get_mut(series) else {
+            return Ok(());
+        };
+        if pending.lines.is_empty() {
+            return Ok(());
+        }
+        let name = format!("delta_{series}_{:04}.{}", pending.chunk, format.extension());
+        let path = self.opts.output_dir.join(name);
+        check_overwrite(&path, self.opts.force)?;
+        debug!("  writing chunk {} → {}", pending.chunk, path.display());
+        let (head, tail) = format.envelope(series);
+        let mut w = BufWriter::new(fs::File::create(&path)?);
+        w.write_all(head.as_bytes())?;
+        for line in pending.lines.drain(..) {
+            w.write_all(line.as_bytes())?;
+        }
+        w.write_all(tail.as_bytes())?;
+        w.flush()?;
+        paths::set_mode(&path, self.opts.file_mode)?;
+        pending.bytes = 0;
+        pending.chunk += 1;
+        self.chunks += 1;
+        Ok(())
+    }
+
+    /// Flush both series; returns the number of chunks written.
+    fn finish(mut self) -> Result<usize, SplitterError> {
+        self.flush(REMOVED_SERIES)?;
+        self.flush(ADDED_SERIES)?;
+        Ok(self.chunks)
+    }
+}
+
+fn dump_files(pattern: &str) -> Result<Vec<PathBuf>, SplitterError> {
+    let files = expand_inputs(&[pattern.to_string()], true).map_err(SplitterError::Other)?;
+    if files.is_empty() {
+        return Err(SplitterError::Parse(format!(
+            "No input files found for '{pattern}'"
+        )));
+    }
+    Ok(files)
+}
+
+/// Hand every statement of `files` to `f`, blank nodes kept apart per file.
+fn for_each_statement(
+    files: &[PathBuf],
+    f: &mut dyn FnMut(OwnedQuad) -> Result<(), SplitterError>,
+) -> Result<(), SplitterError> {
+    for (n, path) in files.iter().enumerate() {
+        let prefix = bnodes::input_prefix(n);
+        let mut relabelled = |q: OwnedQuad| {
+            if mentions_blank_node(&q) {
+                f(bnodes::relabel(&q, &prefix))
+            } else {
+                f(q)
+            }
+        };
+        match RdfFormat::from_path(path) {
+            Some(fmt) if !fmt.is_sparql_results() => parse_quads(path, fmt, &mut relabelled)?,
+            _ => warn!("Skipping '{}': not an RDF file", path.display()),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialise::OwnedTriple;
+
+    fn q(o: &str, g: Option<&str>) -> OwnedQuad {
+        OwnedQuad {
+            triple: OwnedTriple {
+                subject: "<s>".into(),
+                predicate: "<p>".into(),
+                object: o.into(),
+            },
+            graph_name: g.map(String::from),
+        }
+    }
+
+    #[test]
+    fn renders_patch_rows() {
+        let f = DeltaFormat::Patch;
+        assert_eq!(
+            f.render(REMOVED_SERIES, &q("\"1\"", None)),
+            "D <s> <p> \"1\" .\n"
+        );
+        assert_eq!(
+            f.render(ADDED_SERIES, &q("<o>", Some("<g>"))),
+            "A <s> <p> <o> <g> .\n"
+        );
+    }
+
+    #[test]
+    fn renders_sparql_data_blocks() {
+        let f = DeltaFormat::Sparql;
+        assert_eq!(f.envelope(REMOVED_SERIES).0, "DELETE DATA {\n");
+        assert_eq!(
+            f.render(ADDED_SERIES, &q("<o>", Some("<g>"))),
+            "  GRAPH <g> { <s> <p> <o> . }\n"
+        );
+    }
+
+    #[test]
+    fn detects_blank_nodes() {
+        assert!(mentions_blank_node(&q("_:b1", None)));
+        assert!(mentions_blank_node(&q("<< _:b <p> <o> >>", None)));
+        assert!(!mentions_blank_node(&q("\"_:b\"", None)));
+    }
+}
@@ -0,0 +1,184 @@
+//! RDF 1.2 version directives in Turtle and TriG.
+//!
+//! RDF 1.2 documents may announce their version with `VERSION "1.2"` or
+//! `@version "1.2" .`, which the parser does not know.  [`VersionDirectives`]
+//! wraps the input reader and blanks these directives out before parsing;
+//! they carry no statements, and the spaces keep the parser's positions in
+//! error messages right.  SPARQL-style `PREFIX` and `BASE` need no help.
+
+use std::io::{self, BufRead, Read};
+
+/// A [`BufRead`] passing a Turtle / TriG document through with its version
+/// directives replaced by spaces.
+pub struct VersionDirectives<R> {
+    inner: R,
+    line: Vec<u8>,
+    pos: usize,
+    /// Delimiter of the long string (`"""` or `'''`) the current line
+    /// continues, where a directive cannot start.
+    long_string: Option<&'static [u8]>,
+}
+
+impl<R: BufRead> VersionDirectives<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            line: Vec::new(),
+            pos: 0,
+            long_string: None,
+        }
+    }
+}
+
+impl<R: BufRead> BufRead for VersionDirectives<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.line.len() {
+            self.line.clear();
+            self.pos = 0;
+            self.inner.read_until(b'\n', &mut self.line)?;
+            if self.long_string.is_none() {
+                blank_directive(&mut self.line);
+            }
+            self.long_string = long_string_after(&self.line, self.long_string);
+        }
+        Ok(&self.line[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.line.len());
+    }
+}
+
+impl<R: BufRead> Read for VersionDirectives<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let buf = self.fill_buf()?;
+        let n = buf.len().min(out.len());
+        out[..n].copy_from_slice(&buf[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+/// Replace a version directive at the start of `line` by spaces.
+fn blank_directive(line: &mut [u8]) {
+    let start = line
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(line.len());
+    let rest = &line[start..];
+    let (keyword, dot) = if starts_with_ignore_case(rest, b"VERSION") {
+        (b"VERSION".len(), false)
+    } else if rest.starts_with(b"@version") {
+        (b"@version".len(), true)
+    } else {
+        return;
+    };
+    let mut end = start + keyword;
+    let spaces = skip_whitespace(line, end);
+    if spaces == end {
+        return;
+    }
+    end = spaces;
+    // the version specifier: a single-line string in either quote
+    let Some(&quote @ (b'"' | b'\'')) = line.get(end) else {
+        return;
+    };
+    let Some(len) = line[end + 1..]
+        .iter()
+        .position(|&b| b == quote || b == b'\n')
+    else {
+        return;
+    };
+    if line[end + 1 + len] != quote {
+        return;
+    }
+    end += len + 2;
+    if dot {
+        end = skip_whitespace(line, end);
+        if line.get(end) != Some(&b'.') {
+            return;
+        }
+        end += 1;
+    }
+    line[start..end].fill(b' ');
+}
+
+fn starts_with_ignore_case(bytes: &[u8], keyword: &[u8]) -> bool {
+    bytes
+        .get(..keyword.len())
+        .is_some_and(|b| b.eq_ignore_ascii_case(keyword))
+}
+
+fn skip_whitespace(line: &[u8], from: usize) -> usize {
+    from + line[from..]
+        .iter()
+        .take_while(|b| b.is_ascii_whitespace() && **b != b'\n')
+        .count()
+}
+
+/// The long string still open at the end of `line`, given the one open at
+/// its start.
+fn long_string_after(line: &[u8], mut open: Option<&'static [u8]>) -> Option<&'static [u8]> {
+    let mut i = 0;
+    while i < line.len() {
+        match open {
+            Some(delim) if line[i..].starts_with(delim) => {
+                open = None;
+                i += 3;
+            }
+            Some(_) if line[i] == b'\\' => i += 2,
+            Some(_) => i += 1,
+            None if line[i..].starts_with(b"\"\"\"") => {
+                open = Some(b"\"\"\"");
+                i += 3;
+            }
+            None if line[i..].starts_with(b"'''") => {
+                open = Some(b"'''");
+                i += 3;
+            }
+            None if line[i] == b'#' => break,
+            None if line[i] == b'"' || line[i] == b'\'' || line[i] == b'<' => {
+                // skip a single-line string or an IRI, which may hold `#`
+                let close = if line[i] == b'<' { b'>' } else { line[i] };
+                i += 1;
+                while i < line.len() && line[i] != close {
+                    i += if line[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            None => i += 1,
+        }
+    }
+    open
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalise(input: &str) -> String {
+        let mut out = String::new();
+        VersionDirectives::new(input.as_bytes())
+            .read_to_string(&mut out)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn blanks_version_directives() {
+        assert_eq!(
+            normalise("VERSION \"1.2\"\n@version '1.2' .\n<s> <p> <o> .\n"),
+            format!("{}\n{}\n<s> <p> <o> .\n", " ".repeat(13), " ".repeat(16))
+        );
+        assert_eq!(
+            normalise("  version \"1.2-basic\" <s> <p> <o> .\n").trim(),
+            "<s> <p> <o> ."
+        );
+    }
+
+    #[test]
+    fn keeps_everything_else() {
+        let text = "PREFIX ex: <http://ex.org/>\nex:s ex:p \"\"\"\nVERSION \"1.2\"\n\"\"\" .\nex:versionInfo ex:p \"x\" .\n@version \"1.2\"\n";
+        assert_eq!(normalise(text), text);
+    }
+}
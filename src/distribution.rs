@@ -0,0 +1,108 @@
+//! Chunk size distribution: summary statistics and a histogram of chunk
+//! record counts and byte sizes, to spot pathologically unbalanced splits.
+
+use std::fmt;
+
+use serde_json::{json, Value};
+
+/// Summary statistics of a set of sizes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    pub count: usize,
+    pub min: u64,
+    pub median: u64,
+    pub max: u64,
+    pub mean: f64,
+    pub stddev: f64,
+}
+
+impl Distribution {
+    /// `None` for an empty set.
+    pub fn of(values: &[u64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_unstable();
+        let n = sorted.len();
+        let median = if n % 2 == 1 {
+            sorted[n / 2]
+        } else {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+        };
+        let mean = sorted.iter().sum::<u64>() as f64 / n as f64;
+        let var = sorted
+            .iter()
+            .map(|&v| (v as f64 - mean).powi(2))
+            .sum::<f64>()
+            / n as f64;
+        Some(Distribution {
+            count: n,
+            min: sorted[0],
+            median,
+            max: sorted[n - 1],
+            mean,
+            stddev: var.sqrt(),
+        })
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "min": self.min,
+            "median": self.median,
+            "max": self.max,
+            "mean": self.mean,
+            "stddev": self.stddev,
+        })
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "min {} / median {} / max {} (mean {:.1}, stddev {:.1})",
+            self.min, self.median, self.max, self.mean, self.stddev
+        )
+    }
+}
+
+/// Equal-width histogram with `buckets` bins: `(low, high, count)`, both
+/// bounds inclusive.
+pub fn histogram(values: &[u64], buckets: usize) -> Vec<(u64, u64, usize)> {
+    let (Some(&min), Some(&max)) = (values.iter().min(), values.iter().max()) else {
+        return Vec::new();
+    };
+    let buckets = buckets.max(1).min((max - min + 1) as usize);
+    let width = (max - min + 1).div_ceil(buckets as u64);
+    let mut bins: Vec<(u64, u64, usize)> = (0..buckets as u64)
+        .map(|i| (min + i * width, (min + (i + 1) * width - 1).min(max), 0))
+        .collect();
+    for &v in values {
+        bins[((v - min) / width) as usize].2 += 1;
+    }
+    bins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarises_values() {
+        let d = Distribution::of(&[10, 10, 10, 2]).unwrap();
+        assert_eq!((d.min, d.median, d.max), (2, 10, 10));
+        assert_eq!(d.mean, 8.0);
+        assert!((d.stddev - 12f64.sqrt()).abs() < 1e-9);
+        assert_eq!(Distribution::of(&[]), None);
+    }
+
+    #[test]
+    fn histogram_covers_range() {
+        assert_eq!(
+            histogram(&[1, 2, 3, 10], 3),
+            [(1, 4, 3), (5, 8, 0), (9, 10, 1)]
+        );
+        assert_eq!(histogram(&[5, 5], 4), [(5, 5, 2)]);
+    }
+}
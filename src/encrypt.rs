@@ -0,0 +1,180 @@
+//! Chunk encryption at rest (`--encrypt`).
+//!
+//! Every chunk is piped through the `age` or `gpg` command-line tool while it
+//! is written, so plaintext never reaches the output directory.  Only the
+//! public recipient is needed; decrypt with `age -d -i <key>` or
+//! `gpg -d`.  Compression, when requested, is applied before encryption.
+
+use std::{
+    io::{self, BufWriter, Write},
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+    str::FromStr,
+};
+
+/// `age:<recipient>` or `gpg:<key-id>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Encryption {
+    /// age public key (`age1…`), SSH public key or recipients file
+    Age(String),
+    /// GnuPG key id, fingerprint or user id in the local keyring
+    Gpg(String),
+}
+
+impl FromStr for Encryption {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("'{s}': expected 'age:<recipient>' or 'gpg:<key-id>'");
+        let (tool, recipient) = s.split_once(':').ok_or_else(err)?;
+        let recipient = recipient.trim();
+        if recipient.is_empty() {
+            return Err(err());
+        }
+        match tool {
+            "age" => Ok(Encryption::Age(recipient.to_owned())),
+            "gpg" => Ok(Encryption::Gpg(recipient.to_owned())),
+            _ => Err(err()),
+        }
+    }
+}
+
+impl Encryption {
+    /// Extension added to the names of encrypted chunks.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Encryption::Age(_) => "age",
+            Encryption::Gpg(_) => "gpg",
+        }
+    }
+
+    fn program(&self) -> &'static str {
+        match self {
+            Encryption::Age(_) => "age",
+            Encryption::Gpg(_) => "gpg",
+        }
+    }
+
+    /// Fail early when the encryption tool is not installed.
+    pub fn check_tool(&self) -> io::Result<()> {
+        let program = self.program();
+        let status = Command::new(program)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("'{program}' is required by --encrypt: {e}"),
+                )
+            })?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "'{program} --version' failed ({status})"
+            )));
+        }
+        Ok(())
+    }
+
+    fn command(&self, path: &Path) -> Command {
+        let mut cmd = Command::new(self.program());
+        match self {
+            Encryption::Age(recipient) if Path::new(recipient).is_file() => {
+                cmd.arg("-R").arg(recipient);
+            }
+            Encryption::Age(recipient) => {
+                cmd.arg("-r").arg(recipient);
+            }
+            Encryption::Gpg(key) => {
+                cmd.args(["--batch", "--yes", "--encrypt", "--recipient"])
+                    .arg(key);
+            }
+        }
+        cmd.arg("--output").arg(path);
+        cmd
+    }
+}
+
+/// Plaintext written here is encrypted into a file by a child process.
+pub struct Encryptor {
+    program: &'static str,
+    child: Child,
+    stdin: Option<BufWriter<ChildStdin>>,
+}
+
+impl Encryptor {
+    /// Start encrypting into `path`.
+    pub fn spawn(encryption: &Encryption, path: &Path) -> io::Result<Self> {
+        let mut child = encryption
+            .command(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().map(BufWriter::new);
+        Ok(Self {
+            program: encryption.program(),
+            child,
+            stdin,
+        })
+    }
+
+    fn stdin(&mut self) -> io::Result<&mut BufWriter<ChildStdin>> {
+        self.stdin
+            .as_mut()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+
+    /// Close the pipe and wait until the encrypted file is complete.
+    pub fn finish(mut self) -> io::Result<()> {
+        if let Some(mut stdin) = self.stdin.take() {
+            stdin.flush()?;
+        }
+        let status = self.child.wait()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "{} exited with {status}",
+                self.program
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Write for Encryptor {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stdin()?.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stdin()?.flush()
+    }
+}
+
+impl Drop for Encryptor {
+    fn drop(&mut self) {
+        // an abandoned chunk: let the tool exit before the file is removed
+        drop(self.stdin.take());
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_recipients() {
+        assert_eq!(
+            "age:age1qqqq".parse(),
+            Ok(Encryption::Age("age1qqqq".into()))
+        );
+        assert_eq!(
+            "gpg:ops@example.org".parse(),
+            Ok(Encryption::Gpg("ops@example.org".into()))
+        );
+        assert!("age:".parse::<Encryption>().is_err());
+        assert!("aes:key".parse::<Encryption>().is_err());
+        assert!("age1qqqq".parse::<Encryption>().is_err());
+    }
+}
@@ -0,0 +1,272 @@
+//! `--explain`: print the effective configuration before a run.
+
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+
+use crate::{
+    bnodes::BnodeStrategy,
+    format::{input_stem, RdfFormat},
+    graphname::GraphFromFilename,
+    paths, splitter,
+};
+
+use crate::cli::Cli;
+
+/// Render the resolved options and the per-file decisions of a split run.
+pub fn explain(cli: &Cli, files: &[PathBuf], header_statements: usize) -> String {
+    let mut out = String::new();
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+
+    let _ = writeln!(out, "Effective configuration:");
+    let _ = writeln!(out, "  chunk size      : {}", chunk_size(cli));
+    let output = match cli.dated_output {
+        true => cli.output.join("<YYYY-MM-DD>").join("<run-id>"),
+        false => cli.output.clone(),
+    };
+    let _ = writeln!(
+        out,
+        "  output directory: {} (create/overwrite: {})",
+        output.display(),
+        yes_no(cli.force)
+    );
+    let _ = writeln!(out, "  series          : {}", series(cli).join(", "));
+    if let Some(name) = &cli.merge {
+        let _ = writeln!(out, "  merge           : all inputs as one stream into {name}_NNNN");
+    }
+
+    let mut boundaries = vec!["rdf:List".to_string()];
+    if let Some(by) = cli.group_by {
+        boundaries.push(format!("statements grouped by {}", value_name(by)));
+    }
+    if cli.keep_axioms || cli.bnode_strategy == BnodeStrategy::Cluster {
+        boundaries.push("blank-node structures".into());
+    }
+    if let Some(kinds) = cli.keep_annotations {
+        boundaries.push(format!("annotations ({})", value_name(kinds)));
+    }
+    let _ = writeln!(out, "  kept together   : {}", boundaries.join(", "));
+
+    if let Some(path) = &cli.ontology_header {
+        let _ = writeln!(
+            out,
+            "  chunk header    : {} statement(s) from {}",
+            header_statements,
+            path.display()
+        );
+    }
+    let _ = writeln!(out, "  IRI encoding    : {}", value_name(cli.iri_encoding));
+    if cli.bnode_strategy == BnodeStrategy::Rename {
+        let _ = writeln!(out, "  blank nodes     : labels prefixed with the chunk name");
+    }
+    if cli.low_memory {
+        let _ = writeln!(out, "  low memory      : yes");
+    }
+    if let Some(kind) = cli.literal_index {
+        let _ = writeln!(out, "  literal index   : {}", value_name(kind));
+    }
+    if cli.line_map {
+        let _ = writeln!(out, "  line map        : <chunk>.lines.tsv");
+    }
+    if cli.dedupe {
+        let _ = writeln!(
+            out,
+            "  deduplicate     : within each input ({} MiB in memory)",
+            cli.dedupe_memory
+        );
+    }
+    if cli.dedupe_inputs {
+        let _ = writeln!(
+            out,
+            "  deduplicate     : across inputs ({} MiB in memory)",
+            cli.dedupe_memory
+        );
+    }
+    for (label, iris) in [
+        ("only predicates", &cli.include_predicate),
+        ("drop predicates", &cli.exclude_predicate),
+        ("subjects under", &cli.subject_prefix),
+        ("objects under", &cli.object_prefix),
+        ("instances of", &cli.filter_type),
+    ] {
+        if !iris.is_empty() {
+            let _ = writeln!(out, "  {label:<16}: {}", iris.join(", "));
+        }
+    }
+    if let Some(expr) = &cli.filter_expr {
+        let _ = writeln!(out, "  matching        : {expr}");
+    }
+    for spec in &cli.stratified_sample {
+        let _ = writeln!(
+            out,
+            "  sample          : {} instance(s) of {}",
+            spec.count, spec.class
+        );
+    }
+    if let Some(digest) = &cli.verify_input {
+        let _ = writeln!(out, "  verify input    : {digest:?}");
+    }
+    for (label, path) in [
+        ("link report", &cli.link_report),
+        ("load order", &cli.load_order),
+    ] {
+        if let Some(path) = path {
+            let _ = writeln!(out, "  {label:<16}: {}", path.display());
+        }
+    }
+
+    let graphs = cli
+        .graph_from_filename
+        .clone()
+        .zip(cli.graph_iri.clone())
+        .map(|(pattern, template)| GraphFromFilename::new(pattern, template));
+
+    let _ = writeln!(out, "Inputs ({}):", files.len());
+    for path in files {
+        let fmt = cli
+            .format
+            .or_else(|| RdfFormat::from_path(path))
+            .or_else(|| splitter::sniff_format(path).ok().flatten());
+        match fmt {
+            Some(fmt) => {
+                let graph = match &graphs {
+                    Some(g) if !fmt.is_sparql_results() => match g.graph_for(path) {
+                        Ok(Some(iri)) => Some(iri),
+                        Ok(None) => Some("default graph (file name does not match)".into()),
+                        Err(e) => Some(format!("error: {e}")),
+                    },
+                    _ => None,
+                };
+                let out_fmt = match cli.output_format {
+                    Some(f) if !fmt.is_sparql_results() => f,
+                    _ if graph.as_ref().is_some_and(|g| g.starts_with('<')) && !fmt.has_graphs() => {
+                        RdfFormat::NQuads
+                    }
+                    _ => fmt,
+                };
+                let _ = writeln!(
+                    out,
+                    "  {} [{}] → {}",
+                    path.display(),
+                    fmt.label(),
+                    output_template(cli, path, out_fmt).display()
+                );
+                if let Some(graph) = graph {
+                    let _ = writeln!(out, "      graph: {graph}");
+                }
+            }
+            None => {
+                let _ = writeln!(
+                    out,
+                    "  {} → skipped (unrecognised extension)",
+                    path.display()
+                );
+            }
+        }
+    }
+    out
+}
+
+/// The command-line spelling of an enum option value.
+fn value_name(v: impl ValueEnum) -> String {
+    v.to_possible_value()
+        .map(|p| p.get_name().to_string())
+        .unwrap_or_default()
+}
+
+fn chunk_size(cli: &Cli) -> String {
+    if let (Some(by), Some(n)) = (cli.partition_by, cli.partitions) {
+        return format!("{n} hash partitions by {}, not cut", value_name(by));
+    }
+    if let Some(target) = cli.adaptive {
+        let first = cli.chunk_size.unwrap_or(10_000);
+        return format!("about {target} bytes on disk, adapted per chunk (first: {first} records)");
+    }
+    match (cli.chunk_size, cli.max_bytes) {
+        (Some(n), Some(max)) => return format!("{n} records, at most {max} bytes"),
+        (None, Some(max)) => return format!("at most {max} bytes"),
+        _ => {}
+    }
+    match (cli.chunk_size, cli.file_count, cli.exact_chunks) {
+        (_, _, Some(n)) => {
+            format!("record count / {n} (exactly {n} equal chunks, counted per file)")
+        }
+        (_, Some(n), _) => match cli.chunk_size_max {
+            Some(max) => format!(
+                "record count / {n}, rounded up, at most {max} records (counted per file, \
+                 stopping after {} records)",
+                n.saturating_mul(max)
+            ),
+            None => format!("record count / {n}, rounded up (counted per file)"),
+        },
+        (Some(n), _, _) => format!("{n} records"),
+        (None, None, None) => "10000 records (default)".into(),
+    }
+}
+
+fn series(cli: &Cli) -> Vec<&'static str> {
+    let mut series = if cli.partitions.is_some() {
+        vec!["part"]
+    } else if cli.partition_predicates.is_some() {
+        vec!["hot", "cold"]
+    } else if cli.route_by_authority {
+        vec!["one per subject host", "default"]
+    } else {
+        vec!["default"]
+    };
+    if cli.separate_tbox {
+        series.push("tbox (unchunked)");
+    }
+    series
+}
+
+/// Output file name pattern for `input`, e.g. `out/data_NNNN.ttl`.
+fn output_template(cli: &Cli, input: &Path, fmt: RdfFormat) -> PathBuf {
+    let stem = match &cli.merge {
+        Some(name) => name.clone(),
+        None if cli.suffix_input_hash => {
+            format!("{}_{}", input_stem(input), splitter::input_hash(input))
+        }
+        None => input_stem(input),
+    };
+    let mut name = if cli.partitions.is_some() {
+        format!("{stem}_part_NNNN.{}", fmt.extension())
+    } else if cli.route_by_authority {
+        format!("{stem}_[<host>_]NNNN.{}", fmt.extension())
+    } else if cli.partition_predicates.is_some() {
+        format!("{stem}_{{hot,cold}}_NNNN.{}", fmt.extension())
+    } else {
+        format!("{stem}_NNNN.{}", fmt.extension())
+    };
+    if let Some(compression) = cli.compress {
+        name = format!("{name}.{}", compression.extension());
+    }
+    if let Some(encryption) = &cli.encrypt {
+        name = format!("{name}.{}", encryption.extension());
+    }
+    if cli.preserve_structure {
+        return cli.output.join(paths::mirrored_dir(input)).join(name);
+    }
+    cli.output.join(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn explains_chunking_and_outputs() {
+        let cli = Cli::parse_from(["rdfsplitter", "a.ttl", "-c", "4", "-o", "out"]);
+        let text = explain(&cli, &[PathBuf::from("a.ttl"), PathBuf::from("b.txt")], 0);
+        assert!(text.contains("record count / 4, rounded up"));
+        assert!(text.contains(&format!(
+            "a.ttl [Turtle] → {}",
+            Path::new("out").join("a_NNNN.ttl").display()
+        )));
+        assert!(text.contains("b.txt → skipped"));
+    }
+}
@@ -0,0 +1,439 @@
+//! Statement filter expressions (`--filter-expr`).
+//!
+//! A small SPARQL-like language evaluated on every statement, for filters
+//! the predicate and namespace options cannot express:
+//!
+//! ```text
+//! ?p = rdf:type && ?o = foaf:Person
+//! !(?p = rdfs:comment || isLiteral(?o))
+//! ?o = "Zürich"@de || ?o = "3"^^xsd:int
+//! ```
+//!
+//! `?s`, `?p` and `?o` are compared with `=` and `!=` to IRIs (`<…>` or
+//! prefixed names), literals and blank nodes; `isIRI`, `isLiteral` and
+//! `isBlank` test a term's kind; `&&`, `||`, `!` and parentheses combine
+//! tests.  Prefixed names use the `--prefix` declarations and the well-known
+//! `rdf`, `rdfs`, `owl`, `xsd`, `foaf`, `skos`, `dct` and `schema` prefixes.
+
+use crate::serialise::OwnedTriple;
+
+/// Prefixes known without `--prefix`.
+const WELL_KNOWN: &[(&str, &str)] = &[
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("owl", "http://www.w3.org/2002/07/owl#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+    ("foaf", "http://xmlns.com/foaf/0.1/"),
+    ("skos", "http://www.w3.org/2004/02/skos/core#"),
+    ("dct", "http://purl.org/dc/terms/"),
+    ("schema", "http://schema.org/"),
+];
+
+/// A statement position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Var {
+    Subject,
+    Predicate,
+    Object,
+}
+
+impl Var {
+    fn of(self, t: &OwnedTriple) -> &str {
+        match self {
+            Var::Subject => &t.subject,
+            Var::Predicate => &t.predicate,
+            Var::Object => &t.object,
+        }
+    }
+}
+
+/// Kind of term tested by `isIRI`, `isLiteral` and `isBlank`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Iri,
+    Literal,
+    Blank,
+}
+
+/// A compiled filter expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterExpr(Node);
+
+/// Expression tree; terms are held in N-Triples form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Or(Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Equals(Var, String),
+    Is(Kind, Var),
+}
+
+impl FilterExpr {
+    /// Compile `text`, resolving prefixed names with `prefixes` before the
+    /// well-known ones.
+    pub fn parse(text: &str, prefixes: &[(String, String)]) -> Result<Self, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            prefixes,
+        };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(Self(expr)),
+            Some(t) => Err(format!("unexpected {t:?} after the expression")),
+        }
+    }
+
+    /// True when the statement passes the expression.
+    pub fn matches(&self, t: &OwnedTriple) -> bool {
+        self.0.matches(t)
+    }
+}
+
+impl Node {
+    fn matches(&self, t: &OwnedTriple) -> bool {
+        match self {
+            Node::Or(a, b) => a.matches(t) || b.matches(t),
+            Node::And(a, b) => a.matches(t) && b.matches(t),
+            Node::Not(e) => !e.matches(t),
+            Node::Equals(var, term) => var.of(t) == term,
+            Node::Is(kind, var) => {
+                let term = var.of(t);
+                match kind {
+                    Kind::Iri => term.starts_with('<') && !term.starts_with("<<"),
+                    Kind::Literal => term.starts_with('"'),
+                    Kind::Blank => term.starts_with("_:"),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Var(Var),
+    /// A term already in N-Triples form (`<iri>`, `_:b`, a literal).
+    Term(String),
+    /// A prefixed name or function name.
+    Name(String),
+    /// A literal with a prefixed datatype, expanded by the parser.
+    TypedLiteral(String, String),
+    And,
+    Or,
+    Not,
+    Eq,
+    Ne,
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let name_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':');
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '&' if next == Some('&') => {
+                tokens.push(Token::And);
+                i += 2;
+            }
+            '|' if next == Some('|') => {
+                tokens.push(Token::Or);
+                i += 2;
+            }
+            '!' if next == Some('=') => {
+                tokens.push(Token::Ne);
+                i += 2;
+            }
+            '!' => {
+                tokens.push(Token::Not);
+                i += 1;
+            }
+            '=' => {
+                tokens.push(Token::Eq);
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                i += 1;
+            }
+            '?' => {
+                let var = match next {
+                    Some('s') => Var::Subject,
+                    Some('p') => Var::Predicate,
+                    Some('o') => Var::Object,
+                    _ => return Err("variables are ?s, ?p and ?o".into()),
+                };
+                if chars.get(i + 2).is_some_and(|&c| name_char(c)) {
+                    return Err("variables are ?s, ?p and ?o".into());
+                }
+                tokens.push(Token::Var(var));
+                i += 2;
+            }
+            '<' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == '>')
+                    .ok_or("unterminated IRI")?;
+                tokens.push(Token::Term(chars[i..=i + end].iter().collect()));
+                i += end + 1;
+            }
+            '"' => {
+                let mut j = i + 1;
+                while j < chars.len() && chars[j] != '"' {
+                    j += if chars[j] == '\\' { 2 } else { 1 };
+                }
+                if j >= chars.len() {
+                    return Err("unterminated literal".into());
+                }
+                let mut literal: String = chars[i..=j].iter().collect();
+                i = j + 1;
+                if chars.get(i) == Some(&'@') {
+                    let len = chars[i + 1..]
+                        .iter()
+                        .take_while(|c| c.is_alphanumeric() || **c == '-')
+                        .count();
+                    literal.extend(&chars[i..i + 1 + len]);
+                    i += 1 + len;
+                    tokens.push(Token::Term(literal));
+                } else if chars.get(i) == Some(&'^') && chars.get(i + 1) == Some(&'^') {
+                    i += 2;
+                    if chars.get(i) == Some(&'<') {
+                        let end = chars[i..]
+                            .iter()
+                            .position(|&c| c == '>')
+                            .ok_or("unterminated datatype IRI")?;
+                        literal.push_str("^^");
+                        literal.extend(&chars[i..=i + end]);
+                        tokens.push(Token::Term(literal));
+                        i += end + 1;
+                    } else {
+                        let len = chars[i..].iter().take_while(|&&c| name_char(c)).count();
+                        let datatype = chars[i..i + len].iter().collect();
+                        tokens.push(Token::TypedLiteral(literal, datatype));
+                        i += len;
+                    }
+                } else {
+                    tokens.push(Token::Term(literal));
+                }
+            }
+            '_' if next == Some(':') => {
+                let len = chars[i..].iter().take_while(|&&c| name_char(c)).count();
+                tokens.push(Token::Term(chars[i..i + len].iter().collect()));
+                i += len;
+            }
+            _ if name_char(c) => {
+                let len = chars[i..].iter().take_while(|&&c| name_char(c)).count();
+                tokens.push(Token::Name(chars[i..i + len].iter().collect()));
+                i += len;
+            }
+            _ => return Err(format!("unexpected character {c:?}")),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over the tokens: `||` binds loosest, then `&&`, then
+/// `!`.
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    prefixes: &'a [(String, String)],
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.tokens.get(self.pos) == Some(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), String> {
+        match self.next() {
+            Some(t) if t == token => Ok(()),
+            Some(t) => Err(format!("expected {token:?}, found {t:?}")),
+            None => Err(format!("expected {token:?} at the end")),
+        }
+    }
+
+    fn or(&mut self) -> Result<Node, String> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Node::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Node, String> {
+        let mut expr = self.unary()?;
+        while self.eat(&Token::And) {
+            expr = Node::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(Node::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                self.expect(Token::Close)?;
+                Ok(expr)
+            }
+            Some(Token::Var(var)) => {
+                let negated = match self.next() {
+                    Some(Token::Eq) => false,
+                    Some(Token::Ne) => true,
+                    _ => return Err("expected = or != after a variable".into()),
+                };
+                let term = self.term()?;
+                let test = Node::Equals(var, term);
+                Ok(if negated {
+                    Node::Not(Box::new(test))
+                } else {
+                    test
+                })
+            }
+            Some(Token::Name(name)) => {
+                let kind = match name.to_ascii_lowercase().as_str() {
+                    "isiri" | "isuri" => Kind::Iri,
+                    "isliteral" => Kind::Literal,
+                    "isblank" => Kind::Blank,
+                    _ => return Err(format!("unknown function {name}")),
+                };
+                self.expect(Token::Open)?;
+                let var = match self.next() {
+                    Some(Token::Var(var)) => var,
+                    _ => return Err(format!("{name} takes ?s, ?p or ?o")),
+                };
+                self.expect(Token::Close)?;
+                Ok(Node::Is(kind, var))
+            }
+            Some(t) => Err(format!("unexpected {t:?}")),
+            None => Err("unexpected end of the expression".into()),
+        }
+    }
+
+    /// The right-hand side of a comparison, in N-Triples form.
+    fn term(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Term(term)) => Ok(term),
+            Some(Token::Name(name)) if name == "a" => Ok(crate::vocab::RDF_TYPE.to_owned()),
+            Some(Token::Name(name)) => self.expand(&name),
+            Some(Token::TypedLiteral(literal, datatype)) => {
+                let datatype = self.expand(&datatype)?;
+                // parsers give xsd:string literals without a datatype
+                Ok(match datatype.as_str() {
+                    "<http://www.w3.org/2001/XMLSchema#string>" => literal,
+                    _ => format!("{literal}^^{datatype}"),
+                })
+            }
+            Some(t) => Err(format!("expected a term, found {t:?}")),
+            None => Err("expected a term at the end".into()),
+        }
+    }
+
+    /// `prefix:local` → `<namespace local>`.
+    fn expand(&self, name: &str) -> Result<String, String> {
+        let (prefix, local) = name
+            .split_once(':')
+            .ok_or_else(|| format!("{name} is not a prefixed name"))?;
+        let configured = self
+            .prefixes
+            .iter()
+            .map(|(p, ns)| (p.as_str(), ns.as_str()));
+        configured
+            .chain(WELL_KNOWN.iter().copied())
+            .find(|(p, _)| *p == prefix)
+            .map(|(_, ns)| format!("<{ns}{local}>"))
+            .ok_or_else(|| format!("unknown prefix {prefix}: (declare it with --prefix)"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(s: &str, p: &str, o: &str) -> OwnedTriple {
+        OwnedTriple {
+            subject: s.into(),
+            predicate: p.into(),
+            object: o.into(),
+        }
+    }
+
+    const PERSON: &str = "<http://xmlns.com/foaf/0.1/Person>";
+
+    #[test]
+    fn matches_prefixed_names() {
+        let expr = FilterExpr::parse("?p = rdf:type && ?o = foaf:Person", &[]).unwrap();
+        assert!(expr.matches(&t("<http://ex.org/a>", crate::vocab::RDF_TYPE, PERSON)));
+        assert!(!expr.matches(&t(
+            "<http://ex.org/a>",
+            crate::vocab::RDF_TYPE,
+            "<http://ex.org/C>"
+        )));
+        let short = FilterExpr::parse("?p = a", &[]).unwrap();
+        assert!(short.matches(&t("<http://ex.org/a>", crate::vocab::RDF_TYPE, PERSON)));
+    }
+
+    #[test]
+    fn precedence_and_negation() {
+        let expr =
+            FilterExpr::parse("!(?p = rdfs:comment || isLiteral(?o)) && ?s != _:b0", &[]).unwrap();
+        assert!(expr.matches(&t(
+            "<http://ex.org/a>",
+            "<http://ex.org/p>",
+            "<http://ex.org/b>"
+        )));
+        assert!(!expr.matches(&t("<http://ex.org/a>", "<http://ex.org/p>", "\"x\"")));
+        assert!(!expr.matches(&t("_:b0", "<http://ex.org/p>", "<http://ex.org/b>")));
+        let or_and = FilterExpr::parse("isBlank(?s) || isIRI(?s) && isIRI(?o)", &[]).unwrap();
+        assert!(or_and.matches(&t("_:b0", "<http://ex.org/p>", "\"x\"")));
+    }
+
+    #[test]
+    fn literals_keep_language_and_datatype() {
+        let prefixes = [("ex".to_owned(), "http://ex.org/".to_owned())];
+        let expr = FilterExpr::parse(
+            r#"?o = "Zürich"@de || ?o = "3"^^xsd:int || ?o = ex:x"#,
+            &prefixes,
+        )
+        .unwrap();
+        let p = "<http://ex.org/p>";
+        assert!(expr.matches(&t("_:a", p, "\"Zürich\"@de")));
+        assert!(expr.matches(&t(
+            "_:a",
+            p,
+            "\"3\"^^<http://www.w3.org/2001/XMLSchema#int>"
+        )));
+        assert!(expr.matches(&t("_:a", p, "<http://ex.org/x>")));
+        assert!(!expr.matches(&t("_:a", p, "\"Zürich\"")));
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert!(FilterExpr::parse("?x = rdf:type", &[]).is_err());
+        assert!(FilterExpr::parse("?p = nope:x", &[]).is_err());
+        assert!(FilterExpr::parse("?p = rdf:type &&", &[]).is_err());
+        assert!(FilterExpr::parse("(?p = rdf:type", &[]).is_err());
+    }
+}
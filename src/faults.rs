@@ -0,0 +1,116 @@
+//! Fault injection into chunk writes (`--fault-inject`, hidden).
+//!
+//! The error and cleanup paths of the chunk writers (a failing disk, a
+//! stalled network mount) are hard to reach in tests.  A fault spec makes
+//! chunk writes fail or slow down on purpose:
+//!
+//! * `fail=P`: every write fails with probability P (0 to 1);
+//! * `after=N`: a write taking a chunk past N bytes fails;
+//! * `delay=MS`: every write is delayed by MS milliseconds;
+//! * `seed=S`: seed of the `fail` draws, which are the same on every run.
+//!
+//! Items are separated by commas, e.g. `fail=0.01,delay=2,seed=7`.
+
+use std::{io, str::FromStr, thread, time::Duration};
+
+use crate::generate::SplitMix64;
+
+/// A parsed `--fault-inject` spec.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FaultInjection {
+    pub fail: f64,
+    pub after: Option<u64>,
+    pub delay: Duration,
+    pub seed: u64,
+}
+
+impl FromStr for FaultInjection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut spec = Self::default();
+        for item in s.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+            let err = || format!("'{item}': expected fail=P, after=BYTES, delay=MS or seed=N");
+            let (key, value) = item.split_once('=').ok_or_else(err)?;
+            match key {
+                "fail" => {
+                    spec.fail = value.parse().map_err(|_| err())?;
+                    if !(0.0..=1.0).contains(&spec.fail) {
+                        return Err(format!("'{item}': the probability must be 0 to 1"));
+                    }
+                }
+                "after" => spec.after = Some(value.parse().map_err(|_| err())?),
+                "delay" => spec.delay = Duration::from_millis(value.parse().map_err(|_| err())?),
+                "seed" => spec.seed = value.parse().map_err(|_| err())?,
+                _ => return Err(err()),
+            }
+        }
+        Ok(spec)
+    }
+}
+
+/// The faults of one chunk file.
+pub(crate) struct Faults {
+    spec: FaultInjection,
+    rng: SplitMix64,
+    written: u64,
+}
+
+impl Faults {
+    /// Faults of chunk number `chunk`; each chunk draws its own sequence.
+    pub(crate) fn new(spec: FaultInjection, chunk: usize) -> Self {
+        Self {
+            spec,
+            rng: SplitMix64(spec.seed ^ chunk as u64),
+            written: 0,
+        }
+    }
+
+    /// Called before writing `len` bytes: wait, then fail or let it pass.
+    pub(crate) fn before_write(&mut self, len: usize) -> io::Result<()> {
+        if !self.spec.delay.is_zero() {
+            thread::sleep(self.spec.delay);
+        }
+        self.written += len as u64;
+        let past_limit = self.spec.after.is_some_and(|after| self.written > after);
+        // 53 random bits give a uniform draw in [0, 1)
+        let draw = (self.rng.next() >> 11) as f64 / (1u64 << 53) as f64;
+        if past_limit || draw < self.spec.fail {
+            return Err(io::Error::other("injected write failure (--fault-inject)"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_specs() {
+        assert_eq!(
+            "fail=0.25, after=4096,delay=3,seed=9".parse(),
+            Ok(FaultInjection {
+                fail: 0.25,
+                after: Some(4096),
+                delay: Duration::from_millis(3),
+                seed: 9,
+            })
+        );
+        assert!("fail=2".parse::<FaultInjection>().is_err());
+        assert!("slow".parse::<FaultInjection>().is_err());
+        assert!("jitter=1".parse::<FaultInjection>().is_err());
+    }
+
+    #[test]
+    fn fails_past_the_limit_and_by_chance() {
+        let mut faults = Faults::new("after=10".parse().unwrap(), 0);
+        assert!(faults.before_write(10).is_ok());
+        assert!(faults.before_write(1).is_err());
+
+        let mut never = Faults::new(FaultInjection::default(), 0);
+        assert!((0..1000).all(|_| never.before_write(100).is_ok()));
+        let mut always = Faults::new("fail=1".parse().unwrap(), 3);
+        assert!(always.before_write(1).is_err());
+    }
+}
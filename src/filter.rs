@@ -0,0 +1,147 @@
+//! Statement filters (`--include-predicate`, `--exclude-predicate`,
+//! `--subject-prefix`, `--object-prefix`, `--filter-expr`, `--filter-type`).
+//!
+//! Statements the filter rejects are dropped as they are parsed, before
+//! they reach a chunk or count towards its size, so only the selected
+//! properties of the selected resources of a large dump are written.
+//! `--filter-type` needs to know every subject's type before the first
+//! statement is written, so [`typed_subjects`] reads the input once more
+//! beforehand.
+
+use std::{collections::HashSet, path::Path};
+
+use log::info;
+
+use crate::{
+    expr::FilterExpr,
+    format::{RdfFormat, SplitterError},
+    sample::add_blank_nodes,
+    serialise::OwnedTriple,
+    splitter::parse_quads,
+    vocab,
+};
+
+/// Which statements of an input are split; the default keeps everything.
+#[derive(Debug, Clone, Default)]
+pub struct StatementFilter {
+    /// Keep only statements with one of these predicates (N-Triples form,
+    /// `<iri>`); empty keeps every predicate.
+    pub include_predicates: HashSet<String>,
+    /// Drop statements with one of these predicates, even when included.
+    pub exclude_predicates: HashSet<String>,
+    /// Keep only statements whose subject IRI starts with one of these;
+    /// empty keeps every subject.
+    pub subject_prefixes: Vec<String>,
+    /// Keep only statements whose object is an IRI starting with one of
+    /// these; empty keeps every object.
+    pub object_prefixes: Vec<String>,
+    /// Keep only statements matching this expression.
+    pub expr: Option<FilterExpr>,
+}
+
+impl StatementFilter {
+    /// True when the filter keeps every statement.
+    pub fn is_empty(&self) -> bool {
+        self.include_predicates.is_empty()
+            && self.exclude_predicates.is_empty()
+            && self.subject_prefixes.is_empty()
+            && self.object_prefixes.is_empty()
+            && self.expr.is_none()
+    }
+
+    /// True for a statement the filter lets through.
+    pub fn keeps(&self, t: &OwnedTriple) -> bool {
+        (self.include_predicates.is_empty() || self.include_predicates.contains(&t.predicate))
+            && !self.exclude_predicates.contains(&t.predicate)
+            && under_prefix(&t.subject, &self.subject_prefixes)
+            && under_prefix(&t.object, &self.object_prefixes)
+            && self.expr.as_ref().is_none_or(|e| e.matches(t))
+    }
+}
+
+/// Subjects of `input` typed with one of `classes` (N-Triples form), plus
+/// the blank nodes reachable from them, so that their descriptions are
+/// complete.
+pub fn typed_subjects(
+    input: &Path,
+    fmt: RdfFormat,
+    classes: &[String],
+) -> Result<HashSet<String>, SplitterError> {
+    let mut subjects = HashSet::new();
+    parse_quads(input, fmt, &mut |q| {
+        let t = &q.triple;
+        if t.predicate == vocab::RDF_TYPE && classes.contains(&t.object) {
+            subjects.insert(t.subject.clone());
+        }
+        Ok(())
+    })?;
+    info!(
+        "{}: {} instance(s) of {}",
+        input.display(),
+        subjects.len(),
+        classes.join(", ")
+    );
+    add_blank_nodes(input, fmt, &mut subjects)?;
+    Ok(subjects)
+}
+
+/// True when `term` is an IRI starting with one of `prefixes`, or when there
+/// are no prefixes.
+fn under_prefix(term: &str, prefixes: &[String]) -> bool {
+    if prefixes.is_empty() {
+        return true;
+    }
+    term.strip_prefix('<')
+        .is_some_and(|iri| prefixes.iter().any(|p| iri.starts_with(p.as_str())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(p: &str) -> OwnedTriple {
+        OwnedTriple {
+            subject: "<http://ex.org/s>".into(),
+            predicate: p.into(),
+            object: "\"o\"".into(),
+        }
+    }
+
+    fn spo(s: &str, o: &str) -> OwnedTriple {
+        OwnedTriple {
+            subject: s.into(),
+            predicate: "<http://ex.org/p>".into(),
+            object: o.into(),
+        }
+    }
+
+    #[test]
+    fn includes_then_excludes() {
+        let filter = StatementFilter {
+            include_predicates: ["<http://ex.org/a>", "<http://ex.org/b>"]
+                .map(String::from)
+                .into(),
+            exclude_predicates: ["<http://ex.org/b>".to_owned()].into(),
+            ..Default::default()
+        };
+        assert!(filter.keeps(&t("<http://ex.org/a>")));
+        assert!(!filter.keeps(&t("<http://ex.org/b>")));
+        assert!(!filter.keeps(&t("<http://ex.org/c>")));
+        assert!(StatementFilter::default().keeps(&t("<http://ex.org/c>")));
+    }
+
+    #[test]
+    fn matches_subject_and_object_namespaces() {
+        let filter = StatementFilter {
+            subject_prefixes: vec!["http://ex.org/op/".into(), "http://ex.org/track/".into()],
+            object_prefixes: vec!["http://ex.org/".into()],
+            ..Default::default()
+        };
+        assert!(filter.keeps(&spo("<http://ex.org/op/1>", "<http://ex.org/c>")));
+        assert!(filter.keeps(&spo("<http://ex.org/track/9>", "<http://ex.org/c>")));
+        assert!(!filter.keeps(&spo("<http://ex.org/line/1>", "<http://ex.org/c>")));
+        assert!(!filter.keeps(&spo("_:b0", "<http://ex.org/c>")));
+        // literals are not under any namespace
+        assert!(!filter.keeps(&spo("<http://ex.org/op/1>", "\"http://ex.org/\"")));
+    }
+}
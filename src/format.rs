@@ -1,30 +1,79 @@
+//! RDF formats recognised by file extension or content, and the error type
+//! of the crate.
+
 use std::path::Path;
 use thiserror::Error;
 
+/// Format of an input or of the chunks written.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RdfFormat {
+    /// Turtle (`.ttl`).
     Turtle,
+    /// N-Triples (`.nt`).
     NTriples,
+    /// N-Quads (`.nq`).
     NQuads,
+    /// TriG (`.trig`).
     TriG,
+    /// RDF/XML (`.rdf`, `.owl`, `.xml`).
     RdfXml,
+    /// JSON-LD (`.jsonld`, `.json`).
     JsonLd,
+    /// SPARQL 1.1 Query Results JSON (not RDF, but split the same way).
+    SparqlJson,
+    /// SPARQL Query Results XML.
+    SparqlXml,
 }
 
+/// Media types that name one format: the registered types and the older
+/// aliases servers still send.
+const MEDIA_TYPES: &[(&str, RdfFormat)] = &[
+    ("text/turtle", RdfFormat::Turtle),
+    ("application/x-turtle", RdfFormat::Turtle),
+    ("application/n-triples", RdfFormat::NTriples),
+    ("application/n-quads", RdfFormat::NQuads),
+    ("text/x-nquads", RdfFormat::NQuads),
+    ("application/trig", RdfFormat::TriG),
+    ("application/x-trig", RdfFormat::TriG),
+    ("application/rdf+xml", RdfFormat::RdfXml),
+    ("application/ld+json", RdfFormat::JsonLd),
+    ("application/sparql-results+json", RdfFormat::SparqlJson),
+    ("application/sparql-results+xml", RdfFormat::SparqlXml),
+];
+
+/// Generic types, sent for any JSON or XML: an extension says more.
+const GENERIC_MEDIA_TYPES: &[(&str, RdfFormat)] = &[
+    ("application/json", RdfFormat::JsonLd),
+    ("application/xml", RdfFormat::RdfXml),
+    ("text/xml", RdfFormat::RdfXml),
+];
+
 impl RdfFormat {
+    /// Format of a file by its extension; a trailing `.gz` is looked through
+    /// (`data.nt.gz` is N-Triples).
     pub fn from_path(path: &Path) -> Option<Self> {
-        let ext = path.extension()?.to_str()?.to_lowercase();
-        match ext.as_str() {
+        let name = crate::remote::file_name(path);
+        let path = name.as_ref();
+        let path = if is_gzip(path) { Path::new(path.file_stem()?) } else { path };
+        Self::from_extension(path.extension()?.to_str()?)
+    }
+
+    /// Format for a file extension (without the dot), case-insensitive.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
             "ttl" => Some(Self::Turtle),
             "nt" => Some(Self::NTriples),
             "nq" | "nquads" => Some(Self::NQuads),
             "trig" => Some(Self::TriG),
             "rdf" | "owl" | "xml" => Some(Self::RdfXml),
             "jsonld" | "json-ld" | "json" => Some(Self::JsonLd),
+            "srj" => Some(Self::SparqlJson),
+            "srx" => Some(Self::SparqlXml),
             _ => None,
         }
     }
 
+    /// Extension of chunks written in this format.
     pub fn extension(self) -> &'static str {
         match self {
             Self::Turtle => "ttl",
@@ -33,9 +82,12 @@ impl RdfFormat {
             Self::TriG => "trig",
             Self::RdfXml => "rdf",
             Self::JsonLd => "jsonld",
+            Self::SparqlJson => "srj",
+            Self::SparqlXml => "srx",
         }
     }
 
+    /// Name of the format in messages.
     pub fn label(self) -> &'static str {
         match self {
             Self::Turtle => "Turtle",
@@ -44,8 +96,193 @@ impl RdfFormat {
             Self::TriG => "TriG",
             Self::RdfXml => "RDF/XML",
             Self::JsonLd => "JSON-LD",
+            Self::SparqlJson => "SPARQL Results JSON",
+            Self::SparqlXml => "SPARQL Results XML",
+        }
+    }
+
+    /// Format named by a `Content-Type` value, through `MEDIA_TYPES`;
+    /// parameters (`; charset=…`) are ignored.
+    pub fn from_media_type(content_type: &str) -> Option<Self> {
+        Self::lookup(MEDIA_TYPES, content_type)
+    }
+
+    /// Format a generic JSON or XML `Content-Type` stands for, through
+    /// `GENERIC_MEDIA_TYPES`, when nothing more specific is known.
+    pub fn from_generic_media_type(content_type: &str) -> Option<Self> {
+        Self::lookup(GENERIC_MEDIA_TYPES, content_type)
+    }
+
+    fn lookup(table: &[(&str, Self)], content_type: &str) -> Option<Self> {
+        let media_type = content_type.split(';').next()?.trim().to_ascii_lowercase();
+        table
+            .iter()
+            .find(|(name, _)| *name == media_type)
+            .map(|&(_, fmt)| fmt)
+    }
+
+    /// IANA media type.
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Self::Turtle => "text/turtle",
+            Self::NTriples => "application/n-triples",
+            Self::NQuads => "application/n-quads",
+            Self::TriG => "application/trig",
+            Self::RdfXml => "application/rdf+xml",
+            Self::JsonLd => "application/ld+json",
+            Self::SparqlJson => "application/sparql-results+json",
+            Self::SparqlXml => "application/sparql-results+xml",
         }
     }
+
+    /// True for formats that can carry named graphs.
+    pub fn has_graphs(self) -> bool {
+        matches!(self, Self::NQuads | Self::TriG)
+    }
+
+    /// True for SPARQL query results formats, whose records are result rows
+    /// rather than triples/quads.
+    pub fn is_sparql_results(self) -> bool {
+        matches!(self, Self::SparqlJson | Self::SparqlXml)
+    }
+}
+
+/// True for gzip-compressed files (`.gz`).
+pub fn is_gzip(path: &Path) -> bool {
+    crate::remote::file_name(path)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("gz"))
+}
+
+/// Guess the format from the first few KB of a document, for inputs whose
+/// extension says nothing (`download`, `data`, `dump.dat`).  Checks the XML
+/// and JSON envelopes first, then Turtle / TriG directives and blocks, and
+/// finally the statement lines of N-Triples and N-Quads.  A truncated last
+/// line is ignored.
+pub fn detect_format_from_content(head: &str) -> Option<RdfFormat> {
+    let text = head.trim_start_matches('\u{feff}').trim_start();
+    if text.starts_with('<') && !text.starts_with("<<") && looks_like_xml(text) {
+        return Some(if text.contains("<sparql") {
+            RdfFormat::SparqlXml
+        } else {
+            RdfFormat::RdfXml
+        });
+    }
+    if text.starts_with('{') || text.starts_with('[') {
+        let results = text.contains("\"head\"")
+            && (text.contains("\"results\"") || text.contains("\"boolean\""));
+        return Some(if results {
+            RdfFormat::SparqlJson
+        } else {
+            RdfFormat::JsonLd
+        });
+    }
+
+    let mut lines: Vec<&str> = text.lines().collect();
+    if !text.ends_with('\n') && lines.len() > 1 {
+        lines.pop();
+    }
+    let statements: Vec<&str> = lines
+        .iter()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+    if statements.is_empty() {
+        return None;
+    }
+    let trig = statements.iter().any(|l| {
+        // IRIs cannot hold `{`; only a literal or a graph block can
+        l.split('"').next().is_some_and(|code| code.contains('{'))
+            || l.get(..6).is_some_and(|k| k.eq_ignore_ascii_case("graph "))
+    });
+    let turtle = statements.iter().any(|l| {
+        l.starts_with('@')
+            || ["prefix ", "base ", "version "]
+                .iter()
+                .any(|k| l.get(..k.len()).is_some_and(|p| p.eq_ignore_ascii_case(k)))
+    });
+    if trig {
+        return Some(RdfFormat::TriG);
+    }
+    if turtle {
+        return Some(RdfFormat::Turtle);
+    }
+    let terms: Option<Vec<usize>> = statements.iter().map(|l| statement_terms(l)).collect();
+    match terms.as_deref() {
+        Some(counts) if counts.iter().all(|&n| n == 3) => Some(RdfFormat::NTriples),
+        Some(counts) if counts.iter().all(|&n| n == 3 || n == 4) => Some(RdfFormat::NQuads),
+        // prefixed names, `;` and `,` lists, `a`: Turtle without directives
+        _ if statements
+            .iter()
+            .any(|l| l.ends_with('.') || l.ends_with(';')) =>
+        {
+            Some(RdfFormat::Turtle)
+        }
+        _ => None,
+    }
+}
+
+fn looks_like_xml(text: &str) -> bool {
+    text.starts_with("<?xml")
+        || text.starts_with("<!--")
+        || text.contains("<rdf:RDF")
+        || text.contains("<sparql")
+}
+
+/// Number of terms of an N-Triples / N-Quads statement line, `None` if the
+/// line is not one.
+fn statement_terms(line: &str) -> Option<usize> {
+    let mut rest = line.strip_suffix('.')?.trim_end();
+    let mut n = 0;
+    while !rest.is_empty() {
+        let len = match rest.as_bytes()[0] {
+            b'<' => rest.find('>')? + 1,
+            b'_' if rest.starts_with("_:") => rest.find(char::is_whitespace).unwrap_or(rest.len()),
+            b'"' => literal_len(rest)?,
+            _ => return None,
+        };
+        rest = rest[len..].trim_start();
+        n += 1;
+    }
+    Some(n)
+}
+
+/// Length of the literal at the start of `s`, with its language tag or
+/// datatype.
+fn literal_len(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => break,
+            _ => i += 1,
+        }
+    }
+    if i >= bytes.len() {
+        return None;
+    }
+    let rest = &s[i + 1..];
+    let suffix = if rest.starts_with('@') {
+        rest.find(char::is_whitespace).unwrap_or(rest.len())
+    } else if let Some(dt) = rest.strip_prefix("^^<") {
+        dt.find('>')? + 4
+    } else {
+        0
+    };
+    Some(i + 1 + suffix)
+}
+
+/// File name without its format (and `.gz`) extension: `data.nt.gz` → `data`;
+/// `stdin` for standard input; the last path segment of a URL.
+pub fn input_stem(path: &Path) -> String {
+    if crate::stdio::is_stdin(path) {
+        return "stdin".into();
+    }
+    let path = crate::remote::file_name(path);
+    let path = path.as_ref();
+    let name = if is_gzip(path) { Path::new(path.file_stem().unwrap_or_default()) } else { path };
+    name.file_stem().unwrap_or_default().to_string_lossy().into_owned()
 }
 
 /// Callback error type for rio `parse_all` closures.
@@ -73,24 +310,41 @@ impl From<rio_xml::RdfXmlError> for CallbackError {
     }
 }
 
+/// Why an input could not be split, or a run not completed.
 #[derive(Debug, Error)]
 pub enum SplitterError {
-    #[allow(dead_code)]
-    #[error("Unsupported format for '{0}'; supported: .ttl .nt .nq .trig .rdf .owl .xml .jsonld")]
+    /// The format of an input is not known.
+    #[error("Unsupported format for '{0}'; supported: .ttl .nt .nq .trig .rdf .owl .xml .jsonld .srj .srx")]
     UnsupportedFormat(String),
 
+    /// The output directory is missing and `force` is not set.
     #[error("Output directory '{0}' does not exist (use --force to create it)")]
     OutputDirMissing(String),
 
+    /// A chunk would overwrite a file and `force` is not set.
     #[error("Output file '{0}' already exists (use --force to overwrite)")]
     OutputExists(String),
 
+    /// An input does not have the digest it was given (`--verify-input`).
+    #[error("Checksum mismatch for '{path}': expected sha256:{expected}, got sha256:{actual}")]
+    ChecksumMismatch {
+        /// The input.
+        path: String,
+        /// Hex digest expected.
+        expected: String,
+        /// Hex digest of the input.
+        actual: String,
+    },
+
+    /// Reading an input or writing a chunk failed.
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 
+    /// An input is not valid in its format.
     #[error("RDF parse error: {0}")]
     Parse(String),
 
+    /// Any other failure, with its message.
     #[error("{0}")]
     Other(#[from] anyhow::Error),
 }
@@ -100,6 +354,25 @@ mod tests {
     use super::*;
     use std::path::Path;
 
+    #[test]
+    fn media_types_and_aliases() {
+        assert_eq!(
+            RdfFormat::from_media_type("application/n-triples; charset=utf-8"),
+            Some(RdfFormat::NTriples)
+        );
+        assert_eq!(RdfFormat::from_media_type("Text/Turtle"), Some(RdfFormat::Turtle));
+        assert_eq!(RdfFormat::from_media_type("text/x-nquads"), Some(RdfFormat::NQuads));
+        assert_eq!(RdfFormat::from_media_type("application/json"), None);
+        assert_eq!(RdfFormat::from_media_type("application/gzip"), None);
+        assert_eq!(
+            RdfFormat::from_generic_media_type("application/json"),
+            Some(RdfFormat::JsonLd)
+        );
+        for fmt in MEDIA_TYPES.iter().map(|&(_, fmt)| fmt) {
+            assert_eq!(RdfFormat::from_media_type(fmt.media_type()), Some(fmt));
+        }
+    }
+
     #[test]
     fn detect_format_from_all_supported_extensions() {
         let cases: &[(&str, RdfFormat)] = &[
@@ -113,6 +386,8 @@ mod tests {
             ("file.xml",    RdfFormat::RdfXml),
             ("file.jsonld", RdfFormat::JsonLd),
             ("file.json",   RdfFormat::JsonLd),
+            ("file.srj",    RdfFormat::SparqlJson),
+            ("file.srx",    RdfFormat::SparqlXml),
         ];
         for (filename, expected) in cases {
             assert_eq!(
@@ -132,6 +407,13 @@ mod tests {
         assert_eq!(RdfFormat::from_path(Path::new("A.RDF")),    Some(RdfFormat::RdfXml));
     }
 
+    #[test]
+    fn detect_format_from_bare_extension() {
+        assert_eq!(RdfFormat::from_extension("NT"), Some(RdfFormat::NTriples));
+        assert_eq!(RdfFormat::from_extension("jsonld"), Some(RdfFormat::JsonLd));
+        assert_eq!(RdfFormat::from_extension("csv"), None);
+    }
+
     #[test]
     fn unknown_extension_returns_none() {
         assert_eq!(RdfFormat::from_path(Path::new("file.txt")),  None);
@@ -148,6 +430,8 @@ mod tests {
             RdfFormat::TriG,
             RdfFormat::RdfXml,
             RdfFormat::JsonLd,
+            RdfFormat::SparqlJson,
+            RdfFormat::SparqlXml,
         ];
         for fmt in formats {
             let path = std::path::PathBuf::from(format!("test.{}", fmt.extension()));
@@ -170,8 +454,69 @@ mod tests {
             RdfFormat::TriG,
             RdfFormat::RdfXml,
             RdfFormat::JsonLd,
+            RdfFormat::SparqlJson,
+            RdfFormat::SparqlXml,
         ] {
             assert!(!fmt.label().is_empty());
         }
     }
+
+    #[test]
+    fn gzip_suffix_is_looked_through() {
+        let path = Path::new("dumps/wikidata.NT.gz");
+        assert!(is_gzip(path));
+        assert_eq!(RdfFormat::from_path(path), Some(RdfFormat::NTriples));
+        assert_eq!(input_stem(path), "wikidata");
+        assert_eq!(input_stem(Path::new("data.ttl")), "data");
+        assert_eq!(input_stem(Path::new("-")), "stdin");
+        assert_eq!(RdfFormat::from_path(Path::new("archive.gz")), None);
+    }
+
+    #[test]
+    fn detects_format_from_content() {
+        let detect = detect_format_from_content;
+        assert_eq!(
+            detect("<?xml version=\"1.0\"?>\n<rdf:RDF xmlns:rdf=\"x\">"),
+            Some(RdfFormat::RdfXml)
+        );
+        assert_eq!(
+            detect("<?xml version=\"1.0\"?>\n<sparql xmlns=\"x\">"),
+            Some(RdfFormat::SparqlXml)
+        );
+        assert_eq!(
+            detect("{ \"@context\": {}, \"@id\": \"x\" }"),
+            Some(RdfFormat::JsonLd)
+        );
+        assert_eq!(
+            detect("{\"head\": {\"vars\": []}, \"results\": {}}"),
+            Some(RdfFormat::SparqlJson)
+        );
+        assert_eq!(
+            detect("@prefix ex: <http://ex.org/> .\nex:a ex:p ex:b .\n"),
+            Some(RdfFormat::Turtle)
+        );
+        assert_eq!(
+            detect("PREFIX ex: <http://ex.org/>\nex:g { ex:a ex:p ex:b . }\n"),
+            Some(RdfFormat::TriG)
+        );
+        assert_eq!(
+            detect("# dump\n<http://a> <http://p> \"x \\\" y\"@en .\n_:b <http://p> \"1\"^^<http://int> .\n"),
+            Some(RdfFormat::NTriples)
+        );
+        assert_eq!(
+            detect("<http://a> <http://p> <http://o> <http://g> .\n"),
+            Some(RdfFormat::NQuads)
+        );
+        // the cut-off last line is not held against the format
+        assert_eq!(
+            detect("<http://a> <http://p> <http://o> .\n<http://b> <htt"),
+            Some(RdfFormat::NTriples)
+        );
+        assert_eq!(
+            detect("<http://a> a <http://C> ;\n  <http://p> 1 .\n"),
+            Some(RdfFormat::Turtle)
+        );
+        assert_eq!(detect("hello world"), None);
+        assert_eq!(detect(""), None);
+    }
 }
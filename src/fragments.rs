@@ -0,0 +1,194 @@
+//! Turtle description of the chunk set (`--describe-fragments`).
+//!
+//! `fragments.ttl` in the output directory states the split as RDF, for
+//! catalogues that ingest DCAT and VoID rather than JSON manifests: every
+//! chunk is a `dcat:Distribution` with its byte size, statement count
+//! (`void:triples`), SHA-256 or BLAKE3 checksum (SPDX) and media type, derived
+//! (`prov:wasDerivedFrom`) from its input; the chunk set is a `void:Dataset`
+//! listing them, generated by one `prov:Activity`.  Chunk IRIs are relative,
+//! so the description stays valid when the directory is moved.
+
+use std::{
+    fmt::Write as _,
+    fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    format::{is_gzip, RdfFormat, SplitterError},
+    layout::utc_timestamp,
+    metadata::DatasetMetadata,
+    paths,
+    rocrate::relative_id,
+    splitter::{check_overwrite, nt_escape, ChunkInfo, SplitOptions},
+    stdio,
+};
+
+/// Name of the description in the output directory.
+pub const DESCRIPTION_FILE: &str = "fragments.ttl";
+
+const PREFIXES: &[(&str, &str)] = &[
+    ("dcat", "http://www.w3.org/ns/dcat#"),
+    ("dct", "http://purl.org/dc/terms/"),
+    ("prov", "http://www.w3.org/ns/prov#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("spdx", "http://spdx.org/rdf/terms#"),
+    ("void", "http://rdfs.org/ns/void#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+];
+
+const MEDIA_TYPES: &str = "https://www.iana.org/assignments/media-types/";
+
+/// A chunk and the inputs it came from.
+struct Fragment {
+    inputs: Vec<PathBuf>,
+    path: PathBuf,
+    records: usize,
+}
+
+/// Inputs and chunks of a run, collected for the description.
+pub struct FragmentDescription {
+    started: SystemTime,
+    metadata: DatasetMetadata,
+    fragments: Vec<Fragment>,
+}
+
+impl FragmentDescription {
+    pub fn new(started: SystemTime, metadata: DatasetMetadata) -> Self {
+        Self {
+            started,
+            metadata,
+            fragments: Vec::new(),
+        }
+    }
+
+    /// Record the chunks `inputs` were split (or merged) into.
+    pub fn add(&mut self, inputs: &[PathBuf], chunks: &[ChunkInfo]) {
+        self.fragments.extend(chunks.iter().map(|c| Fragment {
+            inputs: inputs.to_vec(),
+            path: c.path.clone(),
+            records: c.records,
+        }));
+    }
+
+    /// Write the description into `opts.output_dir`.
+    pub fn write(
+        &self,
+        opts: &SplitOptions,
+        finished: SystemTime,
+    ) -> Result<PathBuf, SplitterError> {
+        let path = opts.output_dir.join(DESCRIPTION_FILE);
+        check_overwrite(&path, opts.force)?;
+
+        let mut out = String::new();
+        for (prefix, ns) in PREFIXES {
+            let _ = writeln!(out, "@prefix {prefix}: <{ns}> .");
+        }
+        out.push('\n');
+
+        let _ = writeln!(out, "<> a void:Dataset, dcat:Dataset ;");
+        if let Some(title) = &self.metadata.title {
+            let _ = writeln!(out, "    dct:title \"{}\" ;", nt_escape(title));
+        }
+        if let Some(license) = &self.metadata.license {
+            let _ = writeln!(out, "    dct:license <{license}> ;");
+        }
+        if let Some(publisher) = &self.metadata.publisher {
+            let _ = writeln!(out, "    dct:publisher <{publisher}> ;");
+        }
+        let total: usize = self.fragments.iter().map(|f| f.records).sum();
+        let _ = writeln!(out, "    void:triples {total} ;");
+        for f in &self.fragments {
+            let id = relative_id(&opts.output_dir, &f.path);
+            let _ = writeln!(out, "    void:subset <{id}> ;");
+            let _ = writeln!(out, "    dcat:distribution <{id}> ;");
+        }
+        let _ = writeln!(out, "    prov:wasGeneratedBy <#split> .");
+        out.push('\n');
+
+        let _ = writeln!(out, "<#split> a prov:Activity ;");
+        let _ = writeln!(out, "    rdfs:label \"Split RDF input into chunks\" ;");
+        let mut inputs: Vec<String> = self
+            .fragments
+            .iter()
+            .flat_map(|f| f.inputs.iter().map(|input| input_iri(input)))
+            .collect();
+        inputs.dedup();
+        for input in &inputs {
+            let _ = writeln!(out, "    prov:used <{input}> ;");
+        }
+        let _ = writeln!(
+            out,
+            "    prov:wasAssociatedWith <{}> ;",
+            env!("CARGO_PKG_REPOSITORY")
+        );
+        let _ = writeln!(
+            out,
+            "    prov:startedAtTime \"{}\"^^xsd:dateTime ;",
+            utc_timestamp(self.started)
+        );
+        let _ = writeln!(
+            out,
+            "    prov:endedAtTime \"{}\"^^xsd:dateTime .",
+            utc_timestamp(finished)
+        );
+
+        for f in &self.fragments {
+            let id = relative_id(&opts.output_dir, &f.path);
+            let digest = opts.hash.unwrap_or_default().hex_digest(BufReader::new(fs::File::open(&f.path)?))?;
+            out.push('\n');
+            let _ = writeln!(out, "<{id}> a dcat:Distribution, void:Dataset ;");
+            let _ = writeln!(out, "    dcat:downloadURL <{id}> ;");
+            let _ = writeln!(
+                out,
+                "    dcat:byteSize \"{}\"^^xsd:nonNegativeInteger ;",
+                fs::metadata(&f.path)?.len()
+            );
+            let _ = writeln!(out, "    void:triples {} ;", f.records);
+            if let Some(fmt) = RdfFormat::from_path(&f.path) {
+                let _ = writeln!(
+                    out,
+                    "    dcat:mediaType <{MEDIA_TYPES}{}> ;",
+                    fmt.media_type()
+                );
+                if is_gzip(&f.path) {
+                    let _ = writeln!(
+                        out,
+                        "    dcat:compressFormat <{MEDIA_TYPES}application/gzip> ;"
+                    );
+                }
+            }
+            let _ = writeln!(out, "    spdx:checksum [");
+            let _ = writeln!(out, "        a spdx:Checksum ;");
+            let _ = writeln!(
+                out,
+                "        spdx:algorithm spdx:checksumAlgorithm_{} ;",
+                opts.hash.unwrap_or_default().name()
+            );
+            let _ = writeln!(
+                out,
+                "        spdx:checksumValue \"{digest}\"^^xsd:hexBinary"
+            );
+            let _ = writeln!(out, "    ] ;");
+            for input in &f.inputs {
+                let _ = writeln!(out, "    prov:wasDerivedFrom <{}> ;", input_iri(input));
+            }
+            let _ = writeln!(out, "    prov:wasGeneratedBy <#split> .");
+        }
+
+        fs::write(&path, out)?;
+        paths::set_mode(&path, opts.file_mode)?;
+        Ok(path)
+    }
+}
+
+/// IRI of an input: its `file:` IRI, or `#stdin`.
+fn input_iri(input: &Path) -> String {
+    if stdio::is_stdin(input) {
+        "#stdin".to_owned()
+    } else {
+        paths::path_to_file_iri(input)
+    }
+}
@@ -0,0 +1,147 @@
+//! Synthetic test data (`rdfsplitter generate`).
+//!
+//! Produces RDF of a given size and shape from a seed, so large inputs for
+//! trying chunk settings, benchmarks and tests can be made on the spot
+//! instead of being shipped.  The same shape and seed always give the same
+//! statements, in the same order, on every platform and release.
+//!
+//! Every resource `<http://example.org/gen/r{i}>` is an instance of one of
+//! the `Class{k}` classes and has a label, an integer value and a link to an
+//! earlier resource; with graphs, each class has its own named graph.
+
+use std::io::{self, Write};
+
+use crate::{
+    format::RdfFormat,
+    serialise::{ChunkWriter, OwnedQuad, OwnedTriple},
+    vocab::{RDFS_NS, RDF_TYPE},
+};
+
+/// Namespace of generated resources, classes and properties.
+pub const NS: &str = "http://example.org/gen/";
+
+const XSD_NS: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Statements per resource.
+const PER_RESOURCE: usize = 4;
+
+/// Size and shape of the generated data.
+#[derive(Debug, Clone, Copy)]
+pub struct Shape {
+    /// Number of statements.
+    pub triples: usize,
+    /// Number of classes the resources are spread over (at least 1).
+    pub classes: usize,
+    /// Put the statements of each class into its own named graph.
+    pub graphs: bool,
+    pub seed: u64,
+}
+
+/// The statements of `shape`, generated lazily.
+pub fn statements(shape: Shape) -> impl Iterator<Item = OwnedQuad> {
+    let mut rng = SplitMix64(shape.seed);
+    let mut class = 0;
+    (0..shape.triples).map(move |n| {
+        let (resource, step) = (n / PER_RESOURCE, n % PER_RESOURCE);
+        if step == 0 {
+            class = (rng.next() % shape.classes.max(1) as u64) as usize;
+        }
+        let subject = format!("<{NS}r{resource}>");
+        let (predicate, object) = match step {
+            0 => (RDF_TYPE.to_owned(), format!("<{NS}Class{class}>")),
+            1 => (
+                format!("<{RDFS_NS}label>"),
+                format!("\"Resource {resource}\"@en"),
+            ),
+            2 => (
+                format!("<{NS}value>"),
+                format!("\"{}\"^^<{XSD_NS}integer>", rng.next() % 1_000_000),
+            ),
+            _ => {
+                let target = rng.next() % (resource as u64 + 1);
+                (format!("<{NS}linksTo>"), format!("<{NS}r{target}>"))
+            }
+        };
+        OwnedQuad {
+            triple: OwnedTriple {
+                subject,
+                predicate,
+                object,
+            },
+            graph_name: shape.graphs.then(|| format!("<{NS}graph/Class{class}>")),
+        }
+    })
+}
+
+/// Write the statements of `shape` to `w` as `fmt`.
+pub fn write<W: Write>(shape: Shape, fmt: RdfFormat, w: W) -> io::Result<W> {
+    let mut writer = ChunkWriter::new(w, fmt)?;
+    writer.declare_prefix("gen", NS)?;
+    writer.declare_prefix("rdfs", RDFS_NS)?;
+    writer.declare_prefix("xsd", XSD_NS)?;
+    for q in statements(shape) {
+        writer.write(&q)?;
+    }
+    writer.finish()
+}
+
+/// SplitMix64, a small generator with a fixed, documented output sequence.
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn shape(triples: usize, seed: u64) -> Shape {
+        Shape {
+            triples,
+            classes: 3,
+            graphs: true,
+            seed,
+        }
+    }
+
+    #[test]
+    fn generator_sequence_is_fixed() {
+        // reference output of SplitMix64 seeded with 0
+        let mut rng = SplitMix64(0);
+        assert_eq!(rng.next(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next(), 0x6e78_9e6a_a1b9_65f4);
+    }
+
+    #[test]
+    fn same_shape_gives_same_statements() {
+        let a: Vec<String> = statements(shape(101, 7))
+            .map(|q| format!("{:?}", q))
+            .collect();
+        let b: Vec<String> = statements(shape(101, 7))
+            .map(|q| format!("{:?}", q))
+            .collect();
+        assert_eq!(a.len(), 101);
+        assert_eq!(a, b);
+        let c: Vec<String> = statements(shape(101, 8))
+            .map(|q| format!("{:?}", q))
+            .collect();
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn resources_spread_over_classes() {
+        let classes: HashSet<String> = statements(shape(400, 1))
+            .filter(|q| q.triple.predicate == RDF_TYPE)
+            .map(|q| q.triple.object)
+            .collect();
+        assert_eq!(classes.len(), 3);
+    }
+}
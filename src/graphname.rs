@@ -0,0 +1,78 @@
+//! Named graphs derived from input file names (`--graph-from-filename`).
+//!
+//! The pattern is matched against the file name of each input; its capture
+//! groups fill an IRI template (`$1`, `${name}`).  Statements in the default
+//! graph are moved into the resulting graph, so a directory of per-source
+//! triple files becomes one organised quad dataset.
+
+use std::path::Path;
+
+use oxiri::Iri;
+use regex::Regex;
+
+use crate::format::SplitterError;
+
+/// File name pattern and graph IRI template.
+#[derive(Debug, Clone)]
+pub struct GraphFromFilename {
+    pattern: Regex,
+    template: String,
+}
+
+impl GraphFromFilename {
+    /// Name the graph of an input whose file name matches `pattern` by
+    /// `template`, in which `$1` or `${name}` stand for its capture groups.
+    pub fn new(pattern: Regex, template: impl Into<String>) -> Self {
+        Self {
+            pattern,
+            template: template.into(),
+        }
+    }
+
+    /// Graph name (N-Triples form) for `input`, or `None` when its file name
+    /// does not match the pattern.
+    pub fn graph_for(&self, input: &Path) -> Result<Option<String>, SplitterError> {
+        let name = input.file_name().unwrap_or_default().to_string_lossy();
+        let Some(caps) = self.pattern.captures(&name) else {
+            return Ok(None);
+        };
+        let mut iri = String::new();
+        caps.expand(&self.template, &mut iri);
+        Iri::parse(iri.as_str()).map_err(|e| {
+            SplitterError::Parse(format!(
+                "graph IRI '{iri}' derived from '{name}' is not a valid IRI: {e}"
+            ))
+        })?;
+        Ok(Some(format!("<{iri}>")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn g(pattern: &str, template: &str) -> GraphFromFilename {
+        GraphFromFilename::new(Regex::new(pattern).unwrap(), template)
+    }
+
+    #[test]
+    fn expands_capture_groups() {
+        let by_source = g(
+            r"^(?<source>[a-z]+)_(\d{4})\.nt$",
+            "http://example.org/graph/${source}/$2",
+        );
+        assert_eq!(
+            by_source
+                .graph_for(Path::new("dumps/infra_2026.nt"))
+                .unwrap(),
+            Some("<http://example.org/graph/infra/2026>".into())
+        );
+        assert_eq!(by_source.graph_for(Path::new("README.nt")).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_invalid_iris() {
+        let relative = g(r"^(.*)\.nt$", "graphs/$1");
+        assert!(relative.graph_for(Path::new("a.nt")).is_err());
+    }
+}
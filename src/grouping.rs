@@ -0,0 +1,492 @@
+//! Chunk boundary guards.
+//!
+//! A guard watches the statements appended to a chunk and vetoes cutting the
+//! chunk while a multi-statement structure (e.g. an `rdf:List`) is still
+//! open.  The chunk then grows past `--chunk-size` until the structure is
+//! complete, so no structure is ever split across two files.
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{
+    serialise::{OwnedQuad, OwnedTriple},
+    vocab,
+};
+
+/// Statement grouping for `--group-by`: a group is never split across chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    /// All statements sharing a subject
+    Subject,
+    /// All statements of a named graph (quad formats); the default graph is
+    /// one group
+    Graph,
+    /// Weakly connected components: subjects linked through blank nodes or
+    /// through IRI objects of the --connect-predicate predicates
+    Connected,
+}
+
+/// Gather `quads` into groups, in order of each group's first statement.
+/// Statements keep their relative order within a group.  `links` are the
+/// predicates whose IRI objects join a component (`--connect-predicate`).
+pub fn group_statements(
+    quads: Vec<OwnedQuad>,
+    by: GroupBy,
+    links: &HashSet<String>,
+) -> Vec<Vec<OwnedQuad>> {
+    let mut components = Components::default();
+    if by == GroupBy::Connected {
+        for q in &quads {
+            let t = &q.triple;
+            if t.object.starts_with("_:")
+                || (t.object.starts_with('<') && links.contains(&t.predicate))
+            {
+                components.union(&t.subject, &t.object);
+            }
+        }
+    }
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut groups: Vec<Vec<OwnedQuad>> = Vec::new();
+    for q in quads {
+        let key = match by {
+            GroupBy::Subject => Cow::Borrowed(q.triple.subject.as_str()),
+            GroupBy::Graph => Cow::Borrowed(q.graph_name.as_deref().unwrap_or("")),
+            GroupBy::Connected => Cow::Owned(components.root(&q.triple.subject)),
+        };
+        let i = match index.get(key.as_ref()) {
+            Some(&i) => i,
+            None => {
+                index.insert(key.into_owned(), groups.len());
+                groups.push(Vec::new());
+                groups.len() - 1
+            }
+        };
+        groups[i].push(q);
+    }
+    groups
+}
+
+/// Union-find over terms, for [`GroupBy::Connected`].
+#[derive(Default)]
+struct Components {
+    index: HashMap<String, usize>,
+    terms: Vec<String>,
+    parent: Vec<usize>,
+}
+
+impl Components {
+    fn id(&mut self, term: &str) -> usize {
+        if let Some(&id) = self.index.get(term) {
+            return id;
+        }
+        let id = self.parent.len();
+        self.index.insert(term.to_owned(), id);
+        self.terms.push(term.to_owned());
+        self.parent.push(id);
+        id
+    }
+
+    fn find(&mut self, mut id: usize) -> usize {
+        while self.parent[id] != id {
+            // path halving
+            self.parent[id] = self.parent[self.parent[id]];
+            id = self.parent[id];
+        }
+        id
+    }
+
+    fn union(&mut self, a: &str, b: &str) {
+        let (a, b) = (self.id(a), self.id(b));
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+
+    /// The term standing for the component of `term`.
+    fn root(&mut self, term: &str) -> String {
+        match self.index.get(term) {
+            Some(&id) => {
+                let root = self.find(id);
+                self.terms[root].clone()
+            }
+            None => term.to_owned(),
+        }
+    }
+}
+
+/// Decides where a chunk may end so multi-statement structures stay together.
+pub trait BoundaryGuard {
+    /// May the current chunk end right before `next`?
+    fn can_cut_before(&self, next: &OwnedTriple) -> bool;
+    /// Record a statement appended to the current chunk.
+    fn observe(&mut self, t: &OwnedTriple);
+    /// The current chunk was written; forget per-chunk state.
+    fn chunk_closed(&mut self);
+}
+
+/// Keeps `rdf:first`/`rdf:rest` chains — and the statement owning the list —
+/// in one chunk.
+///
+/// The Turtle parser emits a collection's nodes head to tail (nested lists
+/// first) and the statement referencing the head last; N-Triples dumps often
+/// have the owner first.  A cut is only safe when no chain is awaiting its
+/// `rdf:rest`, the next statement does not point at a list node of the
+/// current chunk, and the next statement does not start a list at a blank
+/// node the chunk already refers to.
+#[derive(Default)]
+pub struct ListGuard {
+    /// List nodes still waiting for their `rdf:first`/`rdf:rest` statements.
+    open: HashSet<String>,
+    /// Every list node seen in the current chunk.
+    nodes: HashSet<String>,
+    /// Blank-node objects of the current chunk not seen as a list node
+    /// (yet): the head of a list whose owner came first.
+    referenced: HashSet<String>,
+}
+
+impl BoundaryGuard for ListGuard {
+    fn can_cut_before(&self, next: &OwnedTriple) -> bool {
+        let is_list_statement =
+            next.predicate == vocab::RDF_FIRST || next.predicate == vocab::RDF_REST;
+        self.open.is_empty()
+            && !self.nodes.contains(&next.object)
+            && !(is_list_statement && self.referenced.contains(&next.subject))
+    }
+
+    fn observe(&mut self, t: &OwnedTriple) {
+        if is_blank(&t.object) && t.predicate != vocab::RDF_REST && !self.nodes.contains(&t.object)
+        {
+            self.referenced.insert(t.object.clone());
+        }
+        if t.predicate == vocab::RDF_FIRST || t.predicate == vocab::RDF_REST {
+            self.referenced.remove(&t.subject);
+        }
+        if t.predicate == vocab::RDF_FIRST {
+            if !self.nodes.contains(&t.subject) {
+                self.open.insert(t.subject.clone());
+            }
+            self.nodes.insert(t.subject.clone());
+        } else if t.predicate == vocab::RDF_REST {
+            self.open.remove(&t.subject);
+            self.nodes.insert(t.subject.clone());
+            if t.object != vocab::RDF_NIL && !self.nodes.contains(&t.object) {
+                self.open.insert(t.object.clone());
+                self.nodes.insert(t.object.clone());
+            }
+        }
+    }
+
+    fn chunk_closed(&mut self) {
+        self.nodes.clear();
+        self.referenced.clear();
+        // an unterminated chain cannot be kept together forever
+        self.open.clear();
+    }
+}
+
+/// Keeps blank-node structures together: OWL restrictions, class expressions,
+/// axiom annotations and anything else built from `[ … ]` nodes.
+///
+/// A cut is vetoed when the next statement continues a blank node already
+/// present in the chunk (as subject or object), or references as its object
+/// a blank node the chunk has described — the latter being the owning
+/// statement, which parsers emit after the nested description.
+#[derive(Default)]
+pub struct BlankNodeGuard {
+    /// Blank nodes mentioned anywhere in the current chunk.
+    mentioned: HashSet<String>,
+    /// Blank nodes described (used as subject) in the current chunk.
+    described: HashSet<String>,
+}
+
+fn is_blank(term: &str) -> bool {
+    term.starts_with("_:")
+}
+
+impl BoundaryGuard for BlankNodeGuard {
+    fn can_cut_before(&self, next: &OwnedTriple) -> bool {
+        !self.mentioned.contains(&next.subject) && !self.described.contains(&next.object)
+    }
+
+    fn observe(&mut self, t: &OwnedTriple) {
+        if is_blank(&t.subject) {
+            self.mentioned.insert(t.subject.clone());
+            self.described.insert(t.subject.clone());
+        }
+        if is_blank(&t.object) {
+            self.mentioned.insert(t.object.clone());
+        }
+    }
+
+    fn chunk_closed(&mut self) {
+        self.mentioned.clear();
+        self.described.clear();
+    }
+}
+
+/// Which statement-about-statement patterns the annotation guard keeps next
+/// to the statement they describe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum AnnotationKinds {
+    /// `rdf:Statement` reification nodes
+    Reification,
+    /// RDF-star quoted-triple annotations
+    RdfStar,
+    /// Both reification and RDF-star
+    #[value(name = "all")]
+    Both,
+}
+
+/// Keeps reification nodes and RDF-star annotations in the chunk of the
+/// statement they describe.
+///
+/// Heuristic: annotations follow the annotated statement (as Turtle-star
+/// `{| … |}` blocks and most reification exporters produce them), so a cut
+/// is vetoed before any statement that describes a reifier
+/// (`rdf:subject`/`rdf:predicate`/`rdf:object`, `a rdf:Statement`, or a known
+/// reifier as subject) and before any statement about a quoted triple that is
+/// asserted or already annotated in the current chunk.
+pub struct AnnotationGuard {
+    reification: bool,
+    star: bool,
+    /// Reifier nodes described in the current chunk.
+    reifiers: HashSet<String>,
+    /// `s p o` of statements asserted in the current chunk (RDF-star only).
+    asserted: HashSet<String>,
+    /// Quoted triples (`<< s p o >>`) used as subject in the current chunk.
+    quoted: HashSet<String>,
+}
+
+impl AnnotationGuard {
+    pub fn new(kinds: AnnotationKinds) -> Self {
+        Self {
+            reification: kinds != AnnotationKinds::RdfStar,
+            star: kinds != AnnotationKinds::Reification,
+            reifiers: HashSet::new(),
+            asserted: HashSet::new(),
+            quoted: HashSet::new(),
+        }
+    }
+
+    fn is_reification(t: &OwnedTriple) -> bool {
+        matches!(
+            t.predicate.as_str(),
+            vocab::RDF_SUBJECT | vocab::RDF_PREDICATE | vocab::RDF_OBJECT
+        ) || (t.predicate == vocab::RDF_TYPE && t.object == vocab::RDF_STATEMENT)
+    }
+
+    /// `<< s p o >>` → `s p o`
+    fn quoted_inner(term: &str) -> Option<&str> {
+        term.strip_prefix("<< ")?.strip_suffix(" >>")
+    }
+}
+
+impl BoundaryGuard for AnnotationGuard {
+    fn can_cut_before(&self, next: &OwnedTriple) -> bool {
+        if self.reification
+            && (Self::is_reification(next) || self.reifiers.contains(&next.subject))
+        {
+            return false;
+        }
+        if self.star {
+            if let Some(inner) = Self::quoted_inner(&next.subject) {
+                return !self.asserted.contains(inner) && !self.quoted.contains(&next.subject);
+            }
+        }
+        true
+    }
+
+    fn observe(&mut self, t: &OwnedTriple) {
+        if self.reification && Self::is_reification(t) {
+            self.reifiers.insert(t.subject.clone());
+        }
+        if self.star {
+            if t.subject.starts_with("<< ") {
+                self.quoted.insert(t.subject.clone());
+            }
+            self.asserted
+                .insert(format!("{} {} {}", t.subject, t.predicate, t.object));
+        }
+    }
+
+    fn chunk_closed(&mut self) {
+        self.reifiers.clear();
+        self.asserted.clear();
+        self.quoted.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(s: &str, p: &str, o: &str) -> OwnedTriple {
+        OwnedTriple {
+            subject: s.into(),
+            predicate: p.into(),
+            object: o.into(),
+        }
+    }
+
+    fn feed(guard: &mut impl BoundaryGuard, triples: &[OwnedTriple]) -> Vec<bool> {
+        triples
+            .iter()
+            .map(|tr| {
+                let ok = guard.can_cut_before(tr);
+                guard.observe(tr);
+                ok
+            })
+            .collect()
+    }
+
+    #[test]
+    fn list_guard_blocks_cuts_inside_a_collection() {
+        let mut g = ListGuard::default();
+        let cuts = feed(
+            &mut g,
+            &[
+                t("_:l1", vocab::RDF_FIRST, "<a>"),
+                t("_:l1", vocab::RDF_REST, "_:l2"),
+                t("_:l2", vocab::RDF_FIRST, "<b>"),
+                t("_:l2", vocab::RDF_REST, vocab::RDF_NIL),
+                t("<s>", "<p>", "_:l1"),
+                t("<s2>", "<p>", "<o>"),
+            ],
+        );
+        assert_eq!(cuts, [true, false, false, false, false, true]);
+    }
+
+    #[test]
+    fn list_guard_keeps_an_owner_first_list_with_its_owner() {
+        let mut g = ListGuard::default();
+        let cuts = feed(
+            &mut g,
+            &[
+                t("<s>", "<p>", "_:l1"),
+                t("_:l1", vocab::RDF_FIRST, "_:m1"),
+                t("_:l1", vocab::RDF_REST, vocab::RDF_NIL),
+                t("_:m1", vocab::RDF_FIRST, "<a>"),
+                t("_:m1", vocab::RDF_REST, vocab::RDF_NIL),
+                t("<s2>", "<p>", "_:b"),
+                t("_:b", "<q>", "<o>"),
+            ],
+        );
+        // a blank node that is not a list does not hold the cut back
+        assert_eq!(cuts, [true, false, false, false, false, true, true]);
+    }
+
+    #[test]
+    fn blank_node_guard_keeps_restriction_with_its_owner() {
+        let mut g = BlankNodeGuard::default();
+        let cuts = feed(
+            &mut g,
+            &[
+                t("_:r", vocab::RDF_TYPE, "<owl#Restriction>"),
+                t("_:r", "<owl#onProperty>", "<p>"),
+                t("_:r", "<owl#someValuesFrom>", "<D>"),
+                t("<C>", "<owl#equivalentClass>", "_:r"),
+                t("<C>", vocab::RDF_TYPE, "<owl#Class>"),
+            ],
+        );
+        assert_eq!(cuts, [true, false, false, false, true]);
+    }
+
+    #[test]
+    fn annotation_guard_keeps_reification_with_statement() {
+        let mut g = AnnotationGuard::new(AnnotationKinds::Reification);
+        let cuts = feed(
+            &mut g,
+            &[
+                t("<a>", "<b>", "<c>"),
+                t("_:r", vocab::RDF_TYPE, vocab::RDF_STATEMENT),
+                t("_:r", vocab::RDF_SUBJECT, "<a>"),
+                t("_:r", "<source>", "<x>"),
+                t("<d>", "<b>", "<c>"),
+            ],
+        );
+        assert_eq!(cuts, [true, false, false, false, true]);
+    }
+
+    #[test]
+    fn annotation_guard_keeps_rdf_star_annotations_with_statement() {
+        let mut g = AnnotationGuard::new(AnnotationKinds::RdfStar);
+        let cuts = feed(
+            &mut g,
+            &[
+                t("<a>", "<b>", "<c>"),
+                t("<< <a> <b> <c> >>", "<src>", "<x>"),
+                t("<< <a> <b> <c> >>", "<date>", "\"2020\""),
+                t("<< <a> <b> <d> >>", "<src>", "<y>"),
+            ],
+        );
+        assert_eq!(cuts, [true, false, false, true]);
+    }
+
+    #[test]
+    fn list_guard_resets_when_chunk_closes() {
+        let mut g = ListGuard::default();
+        g.observe(&t("_:l1", vocab::RDF_FIRST, "<a>"));
+        assert!(!g.can_cut_before(&t("<s>", "<p>", "<o>")));
+        g.chunk_closed();
+        assert!(g.can_cut_before(&t("_:l1", vocab::RDF_REST, vocab::RDF_NIL)));
+    }
+
+    #[test]
+    fn groups_by_subject_in_first_appearance_order() {
+        let quads = [t("<b>", "<p>", "1"), t("<a>", "<p>", "2"), t("<b>", "<p>", "3")]
+            .into_iter()
+            .map(OwnedQuad::from_triple)
+            .collect();
+        let groups = group_statements(quads, GroupBy::Subject, &HashSet::new());
+        let objects: Vec<Vec<&str>> = groups
+            .iter()
+            .map(|g| g.iter().map(|q| q.triple.object.as_str()).collect())
+            .collect();
+        assert_eq!(objects, [vec!["1", "3"], vec!["2"]]);
+    }
+
+    #[test]
+    fn groups_by_graph_with_default_graph_as_one_group() {
+        let quad = |s: &str, g: Option<&str>| OwnedQuad {
+            graph_name: g.map(String::from),
+            ..OwnedQuad::from_triple(t(s, "<p>", "<o>"))
+        };
+        let quads = vec![
+            quad("<a>", Some("<g1>")),
+            quad("<b>", None),
+            quad("<c>", Some("<g2>")),
+            quad("<d>", Some("<g1>")),
+            quad("<e>", None),
+        ];
+        let sizes: Vec<usize> = group_statements(quads, GroupBy::Graph, &HashSet::new())
+            .iter()
+            .map(Vec::len)
+            .collect();
+        assert_eq!(sizes, [2, 2, 1]);
+    }
+
+    #[test]
+    fn groups_connected_components_through_blank_nodes_and_links() {
+        let quads = [
+            t("<a>", "<name>", "\"A\""),
+            t("<b>", "<name>", "\"B\""),
+            t("_:addr", "<street>", "\"S\""),
+            t("<a>", "<address>", "_:addr"),
+            t("<c>", "<partOf>", "<a>"),
+            t("<b>", "<seeAlso>", "<a>"),
+        ]
+        .into_iter()
+        .map(OwnedQuad::from_triple)
+        .collect();
+        let links = HashSet::from(["<partOf>".to_owned()]);
+        let groups = group_statements(quads, GroupBy::Connected, &links);
+        let subjects: Vec<Vec<&str>> = groups
+            .iter()
+            .map(|g| g.iter().map(|q| q.triple.subject.as_str()).collect())
+            .collect();
+        assert_eq!(subjects, [vec!["<a>", "_:addr", "<a>", "<c>"], vec!["<b>", "<b>"]]);
+    }
+}
@@ -0,0 +1,171 @@
+//! Streamed inputs that go quiet (`--chunk-idle-timeout`).
+//!
+//! A pipeline may trickle statements into standard input with long pauses
+//! between bursts.  [`IdleFeed`] reads the input on its own thread and hands
+//! it over in blocks through a bounded channel: at most [`BUFFERED_BLOCKS`]
+//! blocks of [`BLOCK_BYTES`] wait to be split, after which the thread stops
+//! reading and a producer bursting faster than the chunks are written blocks
+//! on its pipe.  The splitter in turn learns when nothing has arrived for
+//! the timeout, and closes its open chunks instead of holding them until the
+//! next burst.
+
+use std::{
+    io::{self, Read},
+    mem,
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+/// Bytes of input handed over at a time.
+pub const BLOCK_BYTES: usize = 64 * 1024;
+
+/// Blocks read ahead of the splitter.
+pub const BUFFERED_BLOCKS: usize = 16;
+
+/// What waiting on an [`IdleFeed`] gives.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Fed {
+    /// Complete lines, each with its newline.
+    Lines(Vec<u8>),
+    /// Nothing arrived within the timeout.
+    Idle,
+    /// The input ended; the bytes after its last newline, if any.
+    End(Vec<u8>),
+}
+
+/// A line-based input read ahead on a thread, waited on with a timeout.
+pub struct IdleFeed {
+    blocks: Receiver<io::Result<Vec<u8>>>,
+    timeout: Duration,
+    max_line_bytes: usize,
+    /// Bytes after the last newline handed over, waiting for the rest of
+    /// their line.
+    partial: Vec<u8>,
+}
+
+impl IdleFeed {
+    /// Start reading `input`; a line longer than `max_line_bytes` fails
+    /// the feed, as [`LineLimit`](crate::linelimit::LineLimit) does.
+    pub fn spawn(
+        mut input: impl Read + Send + 'static,
+        timeout: Duration,
+        max_line_bytes: usize,
+    ) -> Self {
+        let (sender, blocks) = mpsc::sync_channel(BUFFERED_BLOCKS);
+        thread::spawn(move || loop {
+            let mut block = vec![0; BLOCK_BYTES];
+            let read = match input.read(&mut block) {
+                Ok(0) => break,
+                Ok(n) => {
+                    block.truncate(n);
+                    Ok(block)
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => Err(e),
+            };
+            let failed = read.is_err();
+            // the splitter stopped, or the error is passed on: either way done
+            if sender.send(read).is_err() || failed {
+                break;
+            }
+        });
+        Self {
+            blocks,
+            timeout,
+            max_line_bytes,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Wait for the next complete lines, at most the timeout.
+    pub fn wait(&mut self) -> io::Result<Fed> {
+        loop {
+            let block = match self.blocks.recv_timeout(self.timeout) {
+                Ok(block) => block?,
+                Err(RecvTimeoutError::Timeout) => return Ok(Fed::Idle),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Ok(Fed::End(mem::take(&mut self.partial)))
+                }
+            };
+            self.partial.extend_from_slice(&block);
+            let lines = match self.partial.iter().rposition(|&b| b == b'\n') {
+                Some(end) => {
+                    let rest = self.partial.split_off(end + 1);
+                    Some(mem::replace(&mut self.partial, rest))
+                }
+                None => None,
+            };
+            if self.partial.len() > self.max_line_bytes {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "a line is longer than {} bytes (raise --max-line-bytes to accept it)",
+                        self.max_line_bytes
+                    ),
+                ));
+            }
+            if let Some(lines) = lines {
+                return Ok(Fed::Lines(lines));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// Gives its parts one per read, sleeping before each.
+    struct Trickle(Vec<(u64, &'static [u8])>);
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Ok(0);
+            }
+            let (millis, part) = self.0.remove(0);
+            thread::sleep(Duration::from_millis(millis));
+            buf[..part.len()].copy_from_slice(part);
+            Ok(part.len())
+        }
+    }
+
+    #[test]
+    fn hands_over_whole_lines_and_reports_quiet_spells() {
+        let input = Trickle(vec![
+            (0, b"<a> <p> <b> .\n<a> <p> "),
+            (0, b"<c> .\n"),
+            (300, b"<d"),
+        ]);
+        let mut feed = IdleFeed::spawn(input, Duration::from_millis(100), 1024);
+        assert_eq!(
+            feed.wait().unwrap(),
+            Fed::Lines(b"<a> <p> <b> .\n".to_vec())
+        );
+        assert_eq!(
+            feed.wait().unwrap(),
+            Fed::Lines(b"<a> <p> <c> .\n".to_vec())
+        );
+        let started = Instant::now();
+        assert_eq!(feed.wait().unwrap(), Fed::Idle);
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        let mut next = feed.wait().unwrap();
+        while next == Fed::Idle {
+            next = feed.wait().unwrap();
+        }
+        assert_eq!(next, Fed::End(b"<d".to_vec()));
+    }
+
+    #[test]
+    fn fails_on_a_line_over_the_limit() {
+        let input = Trickle(vec![
+            (0, b"<a> <p> \"a long literal"),
+            (0, b" going on\" .\n"),
+        ]);
+        let mut feed = IdleFeed::spawn(input, Duration::from_secs(5), 16);
+        let err = feed.wait().unwrap_err();
+        assert!(err.to_string().contains("longer than 16 bytes"));
+    }
+}
@@ -1,23 +1,103 @@
-use std::path::PathBuf;
+//! Expansion of input arguments (files, directories, glob patterns) into
+//! the list of files to split.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Context;
 use glob::glob;
-use log::warn;
+use log::{info, warn};
+use serde_json::{json, Value};
+
+use crate::{format::RdfFormat, remote, splitter, stdio};
+
+/// Why an input was left out of a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// Neither the extension nor the content names an RDF format.
+    UnknownExtension,
+    /// The file or directory could not be read.
+    ReadError,
+    /// A file of a walked directory without an RDF extension.
+    FilteredOut,
+}
+
+impl SkipReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SkipReason::UnknownExtension => "unknown extension",
+            SkipReason::ReadError => "read error",
+            SkipReason::FilteredOut => "filtered out",
+        }
+    }
+}
+
+/// An input left out of a run, listed in the run report.
+#[derive(Debug, Clone)]
+pub struct SkippedInput {
+    pub path: PathBuf,
+    pub reason: SkipReason,
+    /// The underlying error of a [`SkipReason::ReadError`].
+    pub detail: Option<String>,
+}
 
-use crate::format::RdfFormat;
+impl SkippedInput {
+    pub fn new(path: impl Into<PathBuf>, reason: SkipReason) -> Self {
+        Self {
+            path: path.into(),
+            reason,
+            detail: None,
+        }
+    }
+
+    fn read_error(path: impl Into<PathBuf>, e: impl std::fmt::Display) -> Self {
+        Self {
+            detail: Some(e.to_string()),
+            ..Self::new(path, SkipReason::ReadError)
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut doc = json!({
+            "path": self.path.display().to_string(),
+            "reason": self.reason.as_str(),
+        });
+        if let Some(detail) = &self.detail {
+            doc["detail"] = json!(detail);
+        }
+        doc
+    }
+}
 
 /// Expand a list of input patterns (may contain globs) into concrete file
 /// paths.  If `recursive` is true and a pattern is a bare directory, walk it
-/// for known RDF extensions.
+/// for known RDF extensions.  `-` (standard input) and URLs are kept as is.
 pub fn expand_inputs(patterns: &[String], recursive: bool) -> anyhow::Result<Vec<PathBuf>> {
+    expand_inputs_skipping(patterns, recursive, &mut Vec::new())
+}
+
+/// [`expand_inputs`], adding the paths left out on the way to `skipped`.
+pub fn expand_inputs_skipping(
+    patterns: &[String],
+    recursive: bool,
+    skipped: &mut Vec<SkippedInput>,
+) -> anyhow::Result<Vec<PathBuf>> {
     let mut paths: Vec<PathBuf> = Vec::new();
 
     for pattern in patterns {
         let p = std::path::Path::new(pattern);
 
+        // `-` is standard input and URLs are downloaded, read as is
+        if stdio::is_stdin(p) || remote::is_remote(p) {
+            paths.push(p.to_path_buf());
+            continue;
+        }
+
         // bare existing directory → walk
         if p.is_dir() {
-            let dir_files = walk_dir(p, recursive);
+            let dir_files = walk_dir(p, recursive, skipped);
             if dir_files.is_empty() {
                 warn!("No RDF files found in directory '{}'", pattern);
             }
@@ -32,6 +112,7 @@ pub fn expand_inputs(patterns: &[String], recursive: bool) -> anyhow::Result<Vec
                 Ok(p) => Some(p),
                 Err(e) => {
                     warn!("Glob error: {e}");
+                    skipped.push(SkippedInput::read_error(e.path(), e.error()));
                     None
                 }
             })
@@ -40,12 +121,16 @@ pub fn expand_inputs(patterns: &[String], recursive: bool) -> anyhow::Result<Vec
 
         if matches.is_empty() {
             warn!("No files matched pattern '{pattern}'");
+            // a plain path rather than a pattern names a missing input
+            if !pattern.contains(['*', '?', '[']) {
+                skipped.push(SkippedInput::read_error(p, "no such file or directory"));
+            }
         }
 
         // If recursive flag and we matched directories, walk them
         for m in matches {
             if m.is_dir() {
-                paths.extend(walk_dir(&m, recursive));
+                paths.extend(walk_dir(&m, recursive, skipped));
             } else {
                 paths.push(m);
             }
@@ -53,19 +138,87 @@ pub fn expand_inputs(patterns: &[String], recursive: bool) -> anyhow::Result<Vec
     }
 
     // de-duplicate while preserving order
-    let mut seen = std::collections::HashSet::new();
+    let mut seen = HashSet::new();
     paths.retain(|p| seen.insert(p.clone()));
 
     Ok(paths)
 }
 
-fn walk_dir(dir: &std::path::Path, recursive: bool) -> Vec<PathBuf> {
+/// Format of an input: `forced` (`--format`), else the file extension, else
+/// a guess from the first few KB of content.  URL inputs go by the server's
+/// `Content-Type` first, see [`remote_format`].
+pub fn input_format(
+    path: &Path,
+    forced: Option<RdfFormat>,
+    trust_extension: bool,
+) -> Option<RdfFormat> {
+    if let Some(fmt) = forced {
+        return Some(fmt);
+    }
+    if remote::is_remote(path) {
+        return remote_format(path, trust_extension);
+    }
+    if let Some(fmt) = RdfFormat::from_path(path) {
+        return Some(fmt);
+    }
+    match splitter::sniff_format(path) {
+        Ok(Some(fmt)) => {
+            info!("{}: no known extension; content looks like {}", path.display(), fmt.label());
+            Some(fmt)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            warn!("{}: {e}", path.display());
+            None
+        }
+    }
+}
+
+/// Format of a URL input: the `Content-Type` of a HEAD request when it
+/// names a format, since the server knows what it sends and URLs often
+/// carry no or a misleading extension; else the extension, else a generic
+/// JSON or XML type.  With `trust_extension` (`--trust-extension`) a known
+/// extension is used without asking the server.
+fn remote_format(path: &Path, trust_extension: bool) -> Option<RdfFormat> {
+    let by_extension = RdfFormat::from_path(path);
+    if trust_extension && by_extension.is_some() {
+        return by_extension;
+    }
+    let content_type = match remote::content_type(&path.to_string_lossy()) {
+        Ok(content_type) => content_type,
+        Err(e) => {
+            warn!("{}: {e}", path.display());
+            return by_extension;
+        }
+    };
+    match RdfFormat::from_media_type(&content_type) {
+        Some(fmt) => {
+            if by_extension.is_some_and(|ext| ext != fmt) {
+                info!(
+                    "{}: served as {content_type}, read as {} despite its extension \
+                     (--trust-extension to go by the extension)",
+                    path.display(),
+                    fmt.label()
+                );
+            }
+            Some(fmt)
+        }
+        None => by_extension.or_else(|| RdfFormat::from_generic_media_type(&content_type)),
+    }
+}
+
+fn walk_dir(
+    dir: &std::path::Path,
+    recursive: bool,
+    skipped: &mut Vec<SkippedInput>,
+) -> Vec<PathBuf> {
     let mut results = Vec::new();
 
     let read = match std::fs::read_dir(dir) {
         Ok(r) => r,
         Err(e) => {
             warn!("Cannot read directory '{}': {e}", dir.display());
+            skipped.push(SkippedInput::read_error(dir, e));
             return results;
         }
     };
@@ -73,11 +226,29 @@ fn walk_dir(dir: &std::path::Path, recursive: bool) -> Vec<PathBuf> {
     for entry in read.flatten() {
         let path = entry.path();
         if path.is_dir() && recursive {
-            results.extend(walk_dir(&path, recursive));
+            results.extend(walk_dir(&path, recursive, skipped));
         } else if path.is_file() && RdfFormat::from_path(&path).is_some() {
             results.push(path);
+        } else if path.is_file() {
+            skipped.push(SkippedInput::new(path, SkipReason::FilteredOut));
         }
     }
 
     results
 }
+
+/// Read a list of IRIs, one per line, into their N-Triples form (`<iri>`).
+/// Blank lines and `#` comments are ignored; angle brackets are optional.
+pub fn read_iri_list(path: &std::path::Path) -> anyhow::Result<HashSet<String>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Cannot read IRI list '{}'", path.display()))?;
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| {
+            let iri = l.trim_start_matches('<').trim_end_matches('>');
+            format!("<{iri}>")
+        })
+        .collect())
+}
@@ -0,0 +1,62 @@
+//! Co-process job intake (`--stdin-list`).
+//!
+//! Input paths are read from standard input, one per line, as they arrive.
+//! Each is split with the options of the command line as soon as its line
+//! is complete, and the same JSON result lines as `--json` are written to
+//! standard output, one per file (a directory line gives one per file in
+//! it), so another tool can feed paths and follow the outcome of each.  The
+//! intake ends at the end of standard input.
+
+use std::{
+    io::{self, BufRead, Write},
+    path::Path,
+    time::Instant,
+};
+
+use log::info;
+
+use crate::{
+    app::run,
+    cli::Cli,
+    report::InputReport,
+};
+
+/// Split every path read from standard input.  Returns the number of
+/// inputs that failed.
+pub fn run_intake(cli: &Cli) -> anyhow::Result<usize> {
+    let mut failed = 0usize;
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let input = line.trim();
+        if input.is_empty() {
+            continue;
+        }
+        info!("Intake {input}");
+        let started = Instant::now();
+        let mut job = cli.clone();
+        job.stdin_list = false;
+        job.inputs = vec![input.to_owned()];
+        let reports = match run(job) {
+            Ok(totals) if totals.inputs.is_empty() => {
+                // nothing to split: the file was skipped
+                let reason = totals.skipped.first().map_or("no input", |s| s.reason.as_str());
+                vec![InputReport::failed(Path::new(input), &reason, started)]
+            }
+            Ok(totals) => totals.inputs,
+            Err(e) => vec![InputReport::failed(Path::new(input), &e, started)],
+        };
+        if reports.iter().any(|r| !r.is_done()) {
+            failed += 1;
+        }
+        for report in &reports {
+            writeln!(stdout, "{}", report.to_json())?;
+        }
+        stdout.flush()?;
+        if cli.fail_fast && failed > 0 {
+            info!("Stopped at the first failed input (--fail-fast)");
+            break;
+        }
+    }
+    Ok(failed)
+}
@@ -0,0 +1,196 @@
+//! IRI character encoding policy (`--iri-encoding`).
+//!
+//! Stores disagree on whether non-ASCII IRI characters should be written as
+//! UTF-8 or percent-encoded.  The policy is applied to every IRI of a
+//! statement (including datatypes and IRIs inside RDF-star quoted triples)
+//! before it is written; literal values are never touched.
+
+use std::borrow::Cow;
+
+use crate::serialise::{OwnedQuad, OwnedTriple};
+
+/// How non-ASCII characters in IRIs are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum IriEncoding {
+    /// Write IRIs exactly as parsed
+    #[default]
+    Preserve,
+    /// Percent-encode the UTF-8 bytes of non-ASCII characters
+    PercentEncode,
+    /// Decode percent-encoded sequences that form non-ASCII UTF-8 characters
+    Decode,
+}
+
+/// Apply `enc` to every IRI in `quad`.
+pub fn apply(quad: OwnedQuad, enc: IriEncoding) -> OwnedQuad {
+    if enc == IriEncoding::Preserve {
+        return quad;
+    }
+    let t = quad.triple;
+    OwnedQuad {
+        triple: OwnedTriple {
+            subject: encode_term(&t.subject, enc).into_owned(),
+            predicate: encode_term(&t.predicate, enc).into_owned(),
+            object: encode_term(&t.object, enc).into_owned(),
+        },
+        graph_name: quad.graph_name.map(|g| encode_term(&g, enc).into_owned()),
+    }
+}
+
+/// Rewrite the IRIs of an N-Triples term, skipping literal lexical forms.
+pub fn encode_term(term: &str, enc: IriEncoding) -> Cow<'_, str> {
+    if enc == IriEncoding::Preserve || !term.contains('<') {
+        return Cow::Borrowed(term);
+    }
+    let mut out = String::with_capacity(term.len());
+    let mut rest = term;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '"' => {
+                let end = literal_end(rest);
+                out.push_str(&rest[..end]);
+                rest = &rest[end..];
+            }
+            '<' if rest.starts_with("<<") => {
+                out.push_str("<<");
+                rest = &rest[2..];
+            }
+            '<' => match rest.find('>') {
+                Some(end) => {
+                    out.push('<');
+                    out.push_str(&encode_iri(&rest[1..end], enc));
+                    out.push('>');
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    out.push_str(rest);
+                    rest = "";
+                }
+            },
+            c => {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Byte length of the quoted lexical form at the start of `s` (both quotes).
+pub(crate) fn literal_end(s: &str) -> usize {
+    let mut escaped = false;
+    for (i, b) in s.bytes().enumerate().skip(1) {
+        match b {
+            _ if escaped => escaped = false,
+            b'\\' => escaped = true,
+            b'"' => return i + 1,
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+fn encode_iri(iri: &str, enc: IriEncoding) -> Cow<'_, str> {
+    match enc {
+        IriEncoding::Preserve => Cow::Borrowed(iri),
+        IriEncoding::PercentEncode if iri.is_ascii() => Cow::Borrowed(iri),
+        IriEncoding::PercentEncode => {
+            let mut out = String::with_capacity(iri.len() * 2);
+            for c in iri.chars() {
+                if c.is_ascii() {
+                    out.push(c);
+                } else {
+                    let mut buf = [0u8; 4];
+                    for b in c.encode_utf8(&mut buf).bytes() {
+                        out.push_str(&format!("%{b:02X}"));
+                    }
+                }
+            }
+            Cow::Owned(out)
+        }
+        IriEncoding::Decode if !iri.contains('%') => Cow::Borrowed(iri),
+        IriEncoding::Decode => Cow::Owned(decode_non_ascii(iri)),
+    }
+}
+
+/// Decode `%XX` escapes of bytes >= 0x80.  ASCII escapes such as `%2F` are
+/// kept because decoding them would change the meaning of the IRI; invalid
+/// UTF-8 sequences are left encoded.
+fn decode_non_ascii(iri: &str) -> String {
+    let mut out = String::with_capacity(iri.len());
+    let mut rest = iri;
+    while !rest.is_empty() {
+        // collect a run of high-byte escapes
+        let mut bytes = Vec::new();
+        let mut run = rest;
+        while let Some(b) = high_escape(run) {
+            bytes.push(b);
+            run = &run[3..];
+        }
+        if !bytes.is_empty() {
+            match String::from_utf8(bytes) {
+                Ok(s) => out.push_str(&s),
+                Err(_) => out.push_str(&rest[..rest.len() - run.len()]),
+            }
+            rest = run;
+            continue;
+        }
+        let c = rest.chars().next().unwrap_or_default();
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+fn high_escape(s: &str) -> Option<u8> {
+    let hex = s.strip_prefix('%')?.get(..2)?;
+    let b = u8::from_str_radix(hex, 16).ok()?;
+    (b >= 0x80).then_some(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_encodes_non_ascii() {
+        assert_eq!(
+            encode_term("<http://ex.org/Zürich>", IriEncoding::PercentEncode),
+            "<http://ex.org/Z%C3%BCrich>"
+        );
+    }
+
+    #[test]
+    fn decodes_non_ascii_only() {
+        assert_eq!(
+            encode_term("<http://ex.org/Z%C3%BCrich%2Fx>", IriEncoding::Decode),
+            "<http://ex.org/Zürich%2Fx>"
+        );
+        assert_eq!(
+            encode_term("<http://ex.org/%FF>", IriEncoding::Decode),
+            "<http://ex.org/%FF>"
+        );
+    }
+
+    #[test]
+    fn leaves_literals_alone() {
+        assert_eq!(
+            encode_term(
+                "\"Zürich <x>\"^^<http://ex.org/ü>",
+                IriEncoding::PercentEncode
+            ),
+            "\"Zürich <x>\"^^<http://ex.org/%C3%BC>"
+        );
+    }
+
+    #[test]
+    fn encodes_quoted_triples() {
+        assert_eq!(
+            encode_term(
+                "<< <http://ex.org/ä> <p> \"ä\" >>",
+                IriEncoding::PercentEncode
+            ),
+            "<< <http://ex.org/%C3%A4> <p> \"ä\" >>"
+        );
+    }
+}
@@ -0,0 +1,458 @@
+//! JSON-LD to N-Quads, and compaction of JSON-LD chunks.
+//!
+//! JSON-LD inputs are turned into statements by the [`json_ld`] crate, a
+//! complete JSON-LD 1.1 processor run against the W3C test suite, rather
+//! than by a partial expansion of our own.  Remote contexts are fetched with
+//! `curl`, as remote inputs are; a document that is not valid JSON-LD or
+//! whose context cannot be loaded fails with the processor's error.  Blank
+//! nodes are relabelled `_:b0`, `_:b1`, ….
+//!
+//! [`Compaction`] goes the other way for written chunks, compacting them
+//! with the same processor against a context given with `--jsonld-context`.
+
+use std::{collections::BTreeMap, io};
+
+use json_ld::{
+    compaction::CompactFragment,
+    rdf_types::{self, vocabulary::no_vocabulary_mut},
+    syntax::{Parse, TryFromJson},
+    Iri as JsonLdIri, IriBuf, JsonLdProcessor, LoadError, Loader, Process, RemoteDocument,
+};
+use serde_json::{json, Map, Value};
+
+use crate::remote;
+
+/// Convert a JSON-LD document to N-Quads; relative IRIs resolve against
+/// `base`, the document's own IRI.
+pub fn to_nquads(raw: &str, base: Option<&str>) -> Result<String, String> {
+    let (value, _) = json_ld::syntax::Value::parse_str(raw).map_err(|e| e.to_string())?;
+    let base = base
+        .map(|b| IriBuf::new(b.to_owned()).map_err(|e| format!("invalid base IRI {}", e.0)))
+        .transpose()?;
+    let doc = RemoteDocument::new(base, None, value);
+    let mut generator = rdf_types::generator::Blank::new_with_prefix("b".into());
+    let mut rdf = pollster::block_on(doc.to_rdf(&mut generator, &CurlLoader))
+        .map_err(|e| e.to_string())?;
+    let mut out = String::new();
+    for quad in rdf.cloned_quads() {
+        out.push_str(&format!("{quad} .\n"));
+    }
+    Ok(out)
+}
+
+/// Loads remote contexts over HTTP(S) with `curl`.
+struct CurlLoader;
+
+impl Loader for CurlLoader {
+    async fn load(&self, url: &JsonLdIri) -> Result<RemoteDocument<IriBuf>, LoadError> {
+        let fail = |e: io::Error| LoadError::new(url.to_owned(), e);
+        if !matches!(url.scheme().as_str(), "http" | "https") {
+            return Err(fail(io::Error::other("only http(s) contexts can be loaded")));
+        }
+        let body = remote::fetch(url.as_str(), "application/ld+json, application/json")
+            .map_err(fail)?;
+        let (value, _) = json_ld::syntax::Value::parse_str(&body)
+            .map_err(|e| fail(io::Error::new(io::ErrorKind::InvalidData, e.to_string())))?;
+        Ok(RemoteDocument::new(Some(url.to_owned()), None, value))
+    }
+}
+
+/// Compaction of JSON-LD chunks against a context (`--jsonld-context`).
+///
+/// The context is processed once; every node is then built in expanded
+/// form and compacted by the [`json_ld`] crate, so properties, types and
+/// values take the shortest form the context's terms, containers and
+/// coercions allow, and every compacted node expands back to the
+/// statements it was made of.
+pub struct Compaction {
+    /// The `@context` written at the top of every chunk.
+    context: Value,
+    active: json_ld::Context,
+}
+
+impl Compaction {
+    /// Compaction against a context document (its `@context` member) or a
+    /// bare context.  Remote contexts it names are fetched.
+    pub fn new(doc: &Value) -> Result<Self, String> {
+        let context = doc.get("@context").unwrap_or(doc).clone();
+        let (json, _) =
+            json_ld::syntax::Value::parse_str(&context.to_string()).map_err(|e| e.to_string())?;
+        let local = json_ld::syntax::context::Context::try_from_json(json)
+            .map_err(|e| format!("invalid JSON-LD context: {e}"))?;
+        let processed = pollster::block_on(local.process(no_vocabulary_mut(), &CurlLoader, None))
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            context,
+            active: processed.into_processed(),
+        })
+    }
+
+    /// The context to declare in every chunk.
+    pub fn context(&self) -> &Value {
+        &self.context
+    }
+
+    /// Node object of `subject` with its objects (N-Triples terms) by
+    /// predicate.
+    pub fn node(
+        &self,
+        subject: &str,
+        by_pred: &BTreeMap<String, Vec<String>>,
+    ) -> Result<Value, String> {
+        let expanded = expanded_node(subject, by_pred).to_string();
+        let (json, _) = json_ld::syntax::Value::parse_str(&expanded).map_err(|e| e.to_string())?;
+        let doc = RemoteDocument::new(None, None, json);
+        let compacted = pollster::block_on(async {
+            let expanded = doc.expand(&CurlLoader).await.map_err(|e| e.to_string())?;
+            expanded
+                .objects()
+                .compact_fragment_full(
+                    no_vocabulary_mut(),
+                    &self.active,
+                    &self.active,
+                    None,
+                    &CurlLoader,
+                    json_ld::compaction::Options::default(),
+                )
+                .await
+                .map_err(|e| e.to_string())
+        })?;
+        serde_json::from_str(&compacted.to_string()).map_err(|e| e.to_string())
+    }
+}
+
+/// Node object of `subject` in expanded form; IRI objects of `rdf:type`
+/// become its `@type`.
+fn expanded_node(subject: &str, by_pred: &BTreeMap<String, Vec<String>>) -> Value {
+    let mut node = Map::new();
+    node.insert("@id".into(), strip_angles(subject).into());
+    for (pred, objects) in by_pred {
+        let pred = strip_angles(pred);
+        if pred == RDF_TYPE && objects.iter().all(|o| o.starts_with('<')) {
+            let types = objects.iter().map(|o| Value::from(strip_angles(o))).collect();
+            node.insert("@type".into(), Value::Array(types));
+            continue;
+        }
+        let values = objects.iter().map(|o| expanded_value(o)).collect();
+        node.insert(pred.into(), Value::Array(values));
+    }
+    Value::Object(node)
+}
+
+/// An object as an expanded JSON-LD value: `{"@id": …}` for IRIs and blank
+/// nodes, `{"@value": …}` with the unescaped lexical form for literals.
+pub(crate) fn expanded_value(object: &str) -> Value {
+    match parse_literal(object) {
+        None => json!({ "@id": strip_angles(object) }),
+        Some((lexical, Some(lang), _)) => json!({ "@value": lexical, "@language": lang }),
+        Some((lexical, None, Some(datatype))) => json!({ "@value": lexical, "@type": datatype }),
+        Some((lexical, None, None)) => json!({ "@value": lexical }),
+    }
+}
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+fn strip_angles(term: &str) -> &str {
+    term.strip_prefix('<')
+        .and_then(|t| t.strip_suffix('>'))
+        .unwrap_or(term)
+}
+
+/// Lexical form, language and datatype of an N-Triples literal; `None` for
+/// IRIs and blank nodes.
+pub(crate) fn parse_literal(term: &str) -> Option<(String, Option<String>, Option<String>)> {
+    let body = term.strip_prefix('"')?;
+    let mut lexical = String::new();
+    let mut chars = body.char_indices();
+    let end = loop {
+        let (i, c) = chars.next()?;
+        match c {
+            '"' => break i,
+            '\\' => {
+                let (_, e) = chars.next()?;
+                match e {
+                    't' => lexical.push('\t'),
+                    'n' => lexical.push('\n'),
+                    'r' => lexical.push('\r'),
+                    'b' => lexical.push('\u{8}'),
+                    'f' => lexical.push('\u{c}'),
+                    'u' | 'U' => {
+                        let len = if e == 'u' { 4 } else { 8 };
+                        let hex: String = (0..len)
+                            .filter_map(|_| chars.next().map(|(_, h)| h))
+                            .collect();
+                        lexical.extend(u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32));
+                    }
+                    other => lexical.push(other),
+                }
+            }
+            c => lexical.push(c),
+        }
+    };
+    let rest = &body[end + 1..];
+    if let Some(lang) = rest.strip_prefix('@') {
+        Some((lexical, Some(lang.to_owned()), None))
+    } else {
+        let datatype = rest
+            .strip_prefix("^^")
+            .map(|dt| strip_angles(dt).to_owned());
+        Some((lexical, None, datatype))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+    fn quads(doc: Value) -> Vec<String> {
+        let mut lines: Vec<String> = to_nquads(&doc.to_string(), Some("file:///data/doc.jsonld"))
+            .unwrap()
+            .lines()
+            .map(str::to_owned)
+            .collect();
+        lines.sort();
+        lines
+    }
+
+    #[test]
+    fn expands_terms_compact_iris_and_vocab() {
+        let doc = json!({
+            "@context": {
+                "@vocab": "http://schema.org/",
+                "ex": "http://ex.org/",
+                "id": "@id",
+                "knows": { "@id": "ex:knows", "@type": "@id" },
+                "age": { "@id": "ex:age", "@type": "http://www.w3.org/2001/XMLSchema#int" },
+                "nick": { "@id": "ex:nick", "@language": "en" },
+                "skip": null
+            },
+            "id": "ex:alice",
+            "@type": "Person",
+            "name": "Alice",
+            "knows": "ex:bob",
+            "age": 42,
+            "nick": "Al",
+            "skip": "dropped",
+            "ex:height": 1.5
+        });
+        assert_eq!(
+            quads(doc),
+            [
+                "<http://ex.org/alice> <http://ex.org/age> \"42\"^^<http://www.w3.org/2001/XMLSchema#int> .",
+                "<http://ex.org/alice> <http://ex.org/height> \"1.5E0\"^^<http://www.w3.org/2001/XMLSchema#double> .",
+                "<http://ex.org/alice> <http://ex.org/knows> <http://ex.org/bob> .",
+                "<http://ex.org/alice> <http://ex.org/nick> \"Al\"@en .",
+                "<http://ex.org/alice> <http://schema.org/name> \"Alice\" .",
+                "<http://ex.org/alice> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Person> .",
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_properties_without_an_iri() {
+        let doc = json!({ "@id": "http://ex.org/s", "name": "x", "label": "y" });
+        assert!(quads(doc).is_empty());
+    }
+
+    #[test]
+    fn resolves_relative_iris_against_the_base() {
+        let doc = json!({
+            "@context": { "p": { "@id": "http://ex.org/p", "@type": "@id" } },
+            "@id": "#s",
+            "p": "other.jsonld#o"
+        });
+        assert_eq!(
+            quads(doc),
+            ["<file:///data/doc.jsonld#s> <http://ex.org/p> <file:///data/other.jsonld#o> ."]
+        );
+    }
+
+    #[test]
+    fn nested_nodes_lists_and_graphs() {
+        let doc = json!({
+            "@context": {
+                "ex": "http://ex.org/",
+                "steps": { "@id": "ex:steps", "@container": "@list" },
+                "owner": { "@id": "ex:owner", "@context": { "name": "http://ex.org/name" } }
+            },
+            "@id": "ex:g",
+            "@graph": [{
+                "@id": "ex:route",
+                "steps": ["a", "b"],
+                "owner": { "name": "Infrabel" }
+            }]
+        });
+        let g = "<http://ex.org/g>";
+        assert_eq!(
+            quads(doc),
+            [
+                format!("<http://ex.org/route> <http://ex.org/owner> _:b0 {g} ."),
+                format!("<http://ex.org/route> <http://ex.org/steps> _:b1 {g} ."),
+                format!("_:b0 <http://ex.org/name> \"Infrabel\" {g} ."),
+                format!("_:b1 <{RDF}first> \"a\" {g} ."),
+                format!("_:b1 <{RDF}rest> _:b2 {g} ."),
+                format!("_:b2 <{RDF}first> \"b\" {g} ."),
+                format!("_:b2 <{RDF}rest> <{RDF}nil> {g} ."),
+            ]
+        );
+    }
+
+    #[test]
+    fn reverse_nest_and_included() {
+        let doc = json!({
+            "@context": {
+                "@vocab": "http://ex.org/",
+                "parentOf": { "@reverse": "http://ex.org/childOf" },
+                "meta": "@nest"
+            },
+            "@id": "http://ex.org/anna",
+            "parentOf": { "@id": "http://ex.org/ben" },
+            "meta": { "name": "Anna" },
+            "@included": [{ "@id": "http://ex.org/ben", "name": "Ben" }]
+        });
+        assert_eq!(
+            quads(doc),
+            [
+                "<http://ex.org/anna> <http://ex.org/name> \"Anna\" .",
+                "<http://ex.org/ben> <http://ex.org/childOf> <http://ex.org/anna> .",
+                "<http://ex.org/ben> <http://ex.org/name> \"Ben\" .",
+            ]
+        );
+    }
+
+    #[test]
+    fn aliases_containers_and_type_scoped_contexts() {
+        let doc = json!({
+            "@context": {
+                "@version": 1.1,
+                "ex": "http://ex.org/",
+                "id": "@id",
+                "type": "@type",
+                "label": { "@id": "ex:label", "@container": "@language" },
+                "parts": { "@id": "ex:part", "@container": "@index" },
+                "Track": {
+                    "@id": "ex:Track",
+                    "@context": { "gauge": { "@id": "ex:gauge", "@type": "ex:mm" } }
+                }
+            },
+            "id": "ex:t1",
+            "type": "Track",
+            "label": { "en": "track", "nl": "spoor" },
+            "parts": { "first": { "id": "ex:p1" } },
+            "gauge": "1435"
+        });
+        assert_eq!(
+            quads(doc),
+            [
+                "<http://ex.org/t1> <http://ex.org/gauge> \"1435\"^^<http://ex.org/mm> .",
+                "<http://ex.org/t1> <http://ex.org/label> \"spoor\"@nl .",
+                "<http://ex.org/t1> <http://ex.org/label> \"track\"@en .",
+                "<http://ex.org/t1> <http://ex.org/part> <http://ex.org/p1> .",
+                &format!("<http://ex.org/t1> <{RDF}type> <http://ex.org/Track> ."),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_json_ld_is_an_error() {
+        let doc = json!({ "@context": { "p": { "@id": 5 } }, "p": "x" });
+        assert!(to_nquads(&doc.to_string(), None).is_err());
+        assert!(to_nquads("{ not json", None).is_err());
+    }
+
+    fn compaction() -> Compaction {
+        Compaction::new(&json!({
+            "@context": {
+                "@vocab": "http://schema.org/",
+                "ex": "http://ex.org/",
+                "knows": { "@id": "ex:knows", "@type": "@id" },
+                "age": { "@id": "ex:age", "@type": "http://www.w3.org/2001/XMLSchema#int" }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn compacts_nodes_against_the_context() {
+        let by_pred = BTreeMap::from([
+            (
+                "<http://www.w3.org/1999/02/22-rdf-syntax-ns#type>".to_owned(),
+                vec!["<http://schema.org/Person>".to_owned()],
+            ),
+            (
+                "<http://ex.org/knows>".to_owned(),
+                vec!["<http://ex.org/bob>".to_owned()],
+            ),
+            (
+                "<http://ex.org/age>".to_owned(),
+                vec!["\"42\"^^<http://www.w3.org/2001/XMLSchema#int>".to_owned()],
+            ),
+            (
+                "<http://schema.org/name>".to_owned(),
+                vec!["\"Alice\"".to_owned(), "\"Alicia\"@es".to_owned()],
+            ),
+            (
+                "<http://other.org/p>".to_owned(),
+                vec!["\"x\"".to_owned()],
+            ),
+        ]);
+        assert_eq!(
+            compaction().node("<http://ex.org/alice>", &by_pred).unwrap(),
+            json!({
+                "@id": "ex:alice",
+                "@type": "Person",
+                "knows": "ex:bob",
+                "age": "42",
+                "name": ["Alice", { "@value": "Alicia", "@language": "es" }],
+                "http://other.org/p": "x"
+            })
+        );
+    }
+
+    #[test]
+    fn compacted_nodes_expand_to_the_same_statements() {
+        let compaction = compaction();
+        let by_pred = BTreeMap::from([
+            (
+                "<http://ex.org/knows>".to_owned(),
+                vec!["\"not an IRI\"".to_owned(), "_:b1".to_owned()],
+            ),
+            (
+                "<http://ex.org/age>".to_owned(),
+                vec!["\"42\"^^<http://www.w3.org/2001/XMLSchema#integer>".to_owned()],
+            ),
+        ]);
+        let doc = json!({
+            "@context": compaction.context(),
+            "@graph": [compaction.node("_:b0", &by_pred).unwrap()]
+        });
+        assert_eq!(
+            quads(doc),
+            [
+                "_:b0 <http://ex.org/age> \"42\"^^<http://www.w3.org/2001/XMLSchema#integer> .",
+                "_:b0 <http://ex.org/knows> \"not an IRI\" .",
+                "_:b0 <http://ex.org/knows> _:b1 .",
+            ]
+        );
+    }
+
+    #[test]
+    fn list_terms_are_not_used_for_node_references() {
+        let compaction = Compaction::new(&json!({
+            "@vocab": "http://ex.org/",
+            "items": { "@id": "http://ex.org/items", "@container": "@list" }
+        }))
+        .unwrap();
+        let by_pred = BTreeMap::from([(
+            "<http://ex.org/items>".to_owned(),
+            vec!["_:b1".to_owned()],
+        )]);
+        let node = compaction.node("<http://ex.org/a>", &by_pred).unwrap();
+        assert!(node.get("items").is_none(), "{node}");
+        let doc = json!({ "@context": compaction.context(), "@graph": [node] });
+        assert_eq!(quads(doc), ["<http://ex.org/a> <http://ex.org/items> _:b0 ."]);
+    }
+}
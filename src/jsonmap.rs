@@ -0,0 +1,246 @@
+//! Lifting plain JSON into statements (`--json-pointer`, `--json-map`).
+//!
+//! `.json` inputs are read as JSON-LD, which gives no statements for plain
+//! JSON.  A mapping selects an array of the document by JSON pointer and
+//! writes the N-Triples template once per element, with `{key}` replaced by
+//! the element's `key` member, `{/a/b}` by the value at that pointer within
+//! the element and `{#}` by the element's index:
+//!
+//! ```text
+//! <http://ex.org/op/{id}> <http://ex.org/name> "{name}" .
+//! ```
+//!
+//! Values are percent-encoded inside `<…>` and escaped inside `"…"`.  A
+//! statement of the template is left out for an element lacking one of its
+//! values (missing, `null`, an array or an object).
+
+use rio_api::parser::TriplesParser;
+use rio_turtle::NTriplesParser;
+use serde_json::Value;
+
+use crate::splitter::nt_escape;
+
+/// Where a placeholder stands in a statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Context {
+    Iri,
+    Literal,
+}
+
+#[derive(Debug, Clone)]
+enum Piece {
+    Text(String),
+    Value(String, Context),
+}
+
+/// A parsed `--json-map` template with the array it applies to.
+#[derive(Debug, Clone)]
+pub struct JsonMapping {
+    pointer: String,
+    statements: Vec<Vec<Piece>>,
+}
+
+impl JsonMapping {
+    /// Parse `template`, a sequence of N-Triples statements with
+    /// placeholders, for the array at `pointer` (`""` for the document).
+    pub fn new(pointer: &str, template: &str) -> Result<Self, String> {
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            return Err(format!("'{pointer}' is not a JSON pointer (expected /a/b)"));
+        }
+        let mapping = Self {
+            pointer: pointer.to_owned(),
+            statements: parse_template(template)?,
+        };
+        // a sample element must give valid N-Triples
+        let sample = mapping.render(0, &|_| Some("x".into()));
+        NTriplesParser::new(sample.as_bytes())
+            .parse_all(&mut |_| Ok::<_, rio_turtle::TurtleError>(()))
+            .map_err(|e| format!("template is not N-Triples: {e}"))?;
+        Ok(mapping)
+    }
+
+    /// N-Triples of the statements `doc` is mapped to.
+    pub fn to_ntriples(&self, doc: &Value) -> Result<String, String> {
+        let target = doc
+            .pointer(&self.pointer)
+            .ok_or_else(|| format!("JSON pointer '{}' selects nothing", self.pointer))?;
+        let elements = match target {
+            Value::Array(items) => items.iter().collect(),
+            other => vec![other],
+        };
+        let mut out = String::new();
+        for (i, element) in elements.into_iter().enumerate() {
+            out.push_str(&self.render(i, &|key| lookup(element, key)));
+        }
+        Ok(out)
+    }
+
+    /// One element's statements, with placeholder values from `value`.
+    fn render(&self, index: usize, value: &dyn Fn(&str) -> Option<String>) -> String {
+        let mut out = String::new();
+        'statements: for statement in &self.statements {
+            let mut line = String::new();
+            for piece in statement {
+                match piece {
+                    Piece::Text(text) => line.push_str(text),
+                    Piece::Value(key, context) => {
+                        let v = match key.as_str() {
+                            "#" => Some(index.to_string()),
+                            key => value(key),
+                        };
+                        let Some(v) = v else {
+                            continue 'statements;
+                        };
+                        match context {
+                            Context::Iri => line.push_str(&iri_escape(&v)),
+                            Context::Literal => line.push_str(&nt_escape(&v)),
+                        }
+                    }
+                }
+            }
+            out.push_str(line.trim());
+            out.push_str(" .\n");
+        }
+        out
+    }
+}
+
+/// Value of placeholder `key` in `element`.
+fn lookup(element: &Value, key: &str) -> Option<String> {
+    let v = if key.starts_with('/') {
+        element.pointer(key)?
+    } else {
+        element.get(key)?
+    };
+    match v {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Split `template` into statements (at `.` outside IRIs and literals) of
+/// text and placeholders.
+fn parse_template(template: &str) -> Result<Vec<Vec<Piece>>, String> {
+    let mut statements = Vec::new();
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut context = None;
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match (c, context) {
+            ('{', Some(ctx)) => {
+                let key: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                if key.is_empty() {
+                    return Err("empty placeholder {}".into());
+                }
+                pieces.push(Piece::Text(std::mem::take(&mut text)));
+                pieces.push(Piece::Value(key, ctx));
+            }
+            ('{', None) => {
+                return Err("placeholders must stand inside <…> or \"…\"".into());
+            }
+            ('<', None) => {
+                context = Some(Context::Iri);
+                text.push(c);
+            }
+            ('>', Some(Context::Iri)) => {
+                context = None;
+                text.push(c);
+            }
+            ('"', None) => {
+                context = Some(Context::Literal);
+                text.push(c);
+            }
+            ('"', Some(Context::Literal)) => {
+                context = None;
+                text.push(c);
+            }
+            ('\\', Some(Context::Literal)) => {
+                text.push(c);
+                text.extend(chars.next());
+            }
+            ('.', None) => {
+                pieces.push(Piece::Text(std::mem::take(&mut text)));
+                statements.push(std::mem::take(&mut pieces));
+            }
+            _ => text.push(c),
+        }
+    }
+    if !text.trim().is_empty() || context.is_some() {
+        return Err("every statement must end with ' .'".into());
+    }
+    if statements.is_empty() {
+        return Err("the template has no statement".into());
+    }
+    Ok(statements)
+}
+
+/// Percent-encode the characters IRIs cannot hold.
+fn iri_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_ascii_control() || " <>\"{}|\\^`".contains(c) {
+            out.push_str(&format!("%{:02X}", c as u32));
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// True if `doc` uses a JSON-LD keyword (`@context`, `@id`, `@graph`, …)
+/// anywhere; plain JSON gives no statements when read as JSON-LD.
+pub fn has_jsonld_keywords(doc: &Value) -> bool {
+    match doc {
+        Value::Object(members) => members
+            .iter()
+            .any(|(key, v)| key.starts_with('@') || has_jsonld_keywords(v)),
+        Value::Array(items) => items.iter().any(has_jsonld_keywords),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn lifts_array_elements() {
+        let mapping = JsonMapping::new(
+            "/items",
+            "<http://ex.org/op/{id}> <http://ex.org/name> \"{name}\" . \
+             <http://ex.org/op/{id}> <http://ex.org/line> \"{/line/no}\" .",
+        )
+        .unwrap();
+        let doc = json!({ "items": [
+            { "id": "A 1", "name": "Brussel \"Zuid\"", "line": { "no": 50 } },
+            { "id": 2, "name": null },
+        ]});
+        assert_eq!(
+            mapping.to_ntriples(&doc).unwrap(),
+            "<http://ex.org/op/A%201> <http://ex.org/name> \"Brussel \\\"Zuid\\\"\" .\n\
+             <http://ex.org/op/A%201> <http://ex.org/line> \"50\" .\n"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_templates() {
+        assert!(JsonMapping::new("", "<http://ex.org/{id}> <http://ex.org/p> {v} .").is_err());
+        assert!(JsonMapping::new("", "<http://ex.org/{id}> <http://ex.org/p> \"{v}\"").is_err());
+        assert!(JsonMapping::new("", "<{id}> <http://ex.org/p> \"v\" .").is_err());
+        assert!(
+            JsonMapping::new("items", "<http://ex.org/{#}> <http://ex.org/p> \"v\" .").is_err()
+        );
+    }
+
+    #[test]
+    fn finds_jsonld_keywords() {
+        assert!(has_jsonld_keywords(
+            &json!([{ "x": { "@id": "http://ex.org/a" } }])
+        ));
+        assert!(!has_jsonld_keywords(&json!({ "items": [{ "id": 1 }] })));
+    }
+}
@@ -0,0 +1,100 @@
+//! Output directory layout (`--dated-output`).
+//!
+//! Nightly jobs keep their runs side by side under
+//! `<output>/<YYYY-MM-DD>/<run-id>/`, where the run id is the UTC start time
+//! (`HHMMSS`), suffixed with `-2`, `-3`, … if that directory already exists.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::paths;
+
+/// Create and return a fresh dated run directory below `base`; created
+/// directories get `dir_mode` (see [`paths::set_mode`]).
+pub fn dated_run_dir(base: &Path, now: SystemTime, dir_mode: Option<u32>) -> io::Result<PathBuf> {
+    let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (y, m, d) = civil_from_days((secs / 86_400) as i64);
+    let tod = secs % 86_400;
+    let day_dir = base.join(format!("{y:04}-{m:02}-{d:02}"));
+    if !day_dir.exists() {
+        fs::create_dir_all(&day_dir)?;
+        paths::set_mode(&day_dir, dir_mode)?;
+    }
+
+    let run_id = format!("{:02}{:02}{:02}", tod / 3600, tod / 60 % 60, tod % 60);
+    let mut n = 1;
+    loop {
+        let dir = match n {
+            1 => day_dir.join(&run_id),
+            n => day_dir.join(format!("{run_id}-{n}")),
+        };
+        match fs::create_dir(&dir) {
+            Ok(()) => {
+                paths::set_mode(&dir, dir_mode)?;
+                return Ok(dir);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// `now` as an ISO 8601 UTC timestamp, e.g. `2026-10-15T03:15:00Z`.
+pub fn utc_timestamp(now: SystemTime) -> String {
+    let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (y, m, d) = civil_from_days((secs / 86_400) as i64);
+    let tod = secs % 86_400;
+    format!(
+        "{y:04}-{m:02}-{d:02}T{:02}:{:02}:{:02}Z",
+        tod / 3600,
+        tod / 60 % 60,
+        tod % 60
+    )
+}
+
+/// Days since 1970-01-01 → (year, month, day), proleptic Gregorian.
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn converts_days_to_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20_741), (2026, 10, 15));
+    }
+
+    #[test]
+    fn creates_unique_run_dirs() {
+        let base = tempfile::TempDir::new().unwrap();
+        // 2026-10-15 03:15:00 UTC
+        let t = UNIX_EPOCH + Duration::from_secs(20_741 * 86_400 + 3 * 3600 + 15 * 60);
+        let first = dated_run_dir(base.path(), t, None).unwrap();
+        let second = dated_run_dir(base.path(), t, None).unwrap();
+        assert_eq!(first, base.path().join("2026-10-15").join("031500"));
+        assert_eq!(second, base.path().join("2026-10-15").join("031500-2"));
+    }
+
+    #[test]
+    fn formats_utc_timestamps() {
+        let t = UNIX_EPOCH + Duration::from_secs(20_741 * 86_400 + 3 * 3600 + 15 * 60 + 7);
+        assert_eq!(utc_timestamp(t), "2026-10-15T03:15:07Z");
+    }
+}
@@ -0,0 +1,121 @@
+//! Split large RDF files into smaller chunks.
+//!
+//! This is the library behind the `rdfsplitter` command.  [`split_file`]
+//! streams one input (Turtle, N-Triples, N-Quads, TriG, RDF/XML, JSON-LD or
+//! SPARQL query results, optionally gzip-compressed) into numbered chunk
+//! files below [`SplitOptions::output_dir`]; every command-line option of
+//! the split run maps to a field of [`SplitOptions`].
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! use rdfsplitter::{split_file, RdfFormat, SplitOptions};
+//!
+//! let input = Path::new("data.ttl");
+//! let fmt = RdfFormat::from_path(input).expect("known extension");
+//! let mut opts = SplitOptions::new("out", 10_000);
+//! opts.force = true;
+//! let summary = split_file(input, fmt, &opts)?;
+//! println!("{} triples in {} chunks", summary.records, summary.chunks.len());
+//! # Ok::<(), rdfsplitter::SplitterError>(())
+//! ```
+//!
+//! [`run_convert`] writes inputs whole in another format, and the serialisers
+//! in [`serialise`] write statements in any of the output formats without
+//! splitting, e.g. to convert a stream of [`OwnedQuad`]s.  The types of the
+//! [`SplitOptions`] fields are exported here too; the options holding run
+//! state (deduplication across inputs, checkpoints) are set with its `with_`
+//! methods.  The rest of the crate is the command itself, run by
+//! [`run_cli`].
+
+#![warn(missing_docs)]
+
+mod adaptive;
+mod app;
+mod authority;
+mod bnodes;
+mod checks;
+mod checkpoint;
+mod checksum;
+mod chunksize;
+mod cli;
+mod compress;
+mod convert;
+mod count;
+mod dedupe;
+mod delta;
+mod directives;
+mod distribution;
+mod encrypt;
+mod explain;
+mod expr;
+mod faults;
+mod filter;
+mod format;
+mod fragments;
+mod generate;
+mod graphname;
+mod grouping;
+mod idle;
+mod inputs;
+mod intake;
+mod iri;
+mod jsonld;
+mod jsonmap;
+mod layout;
+mod linelimit;
+mod linemap;
+mod links;
+mod literals;
+mod manifest;
+mod metadata;
+mod partition;
+mod paths;
+mod profile;
+mod progress;
+mod queue;
+mod rejects;
+mod remote;
+mod repack;
+mod report;
+mod results;
+mod rocrate;
+mod sample;
+pub mod serialise;
+mod space;
+mod splitter;
+mod stale;
+mod stats;
+mod stdio;
+mod tail;
+mod vocab;
+
+pub use bnodes::BnodeStrategy;
+pub use checksum::HashAlgorithm;
+pub use compress::{Compression, CompressionTuning};
+pub use convert::{run_convert, ConvertSummary};
+pub use dedupe::DedupeKey;
+pub use encrypt::Encryption;
+pub use filter::StatementFilter;
+pub use format::{RdfFormat, SplitterError};
+pub use graphname::GraphFromFilename;
+pub use grouping::{AnnotationKinds, GroupBy};
+pub use iri::IriEncoding;
+pub use jsonld::Compaction;
+pub use jsonmap::JsonMapping;
+pub use links::ChunkLinks;
+pub use literals::LiteralIndex;
+pub use partition::{PartitionBy, Partitioning};
+pub use sample::StratifiedSample;
+pub use serialise::{ChunkWriter, OwnedQuad, OwnedTriple};
+pub use splitter::{
+    count_records, count_records_up_to, load_statements, merge_files, split_file, ChunkInfo,
+    SplitOptions, SplitSummary,
+};
+pub use stdio::StdoutMode;
+
+/// Run the `rdfsplitter` command with the arguments of the process; all of
+/// the binary's `main`.
+pub fn run_cli() {
+    app::main()
+}
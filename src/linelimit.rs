@@ -0,0 +1,96 @@
+//! Line length guard for line-based inputs (`--max-line-bytes`).
+//!
+//! A single N-Triples line with a literal of hundreds of MB makes the parser
+//! buffer the whole line.  [`LineLimit`] wraps the input reader and fails
+//! with a diagnostic naming the line once it grows beyond the limit, so the
+//! run stops cleanly instead of exhausting memory.
+
+use std::io::{self, BufRead, Read};
+
+/// Default for `--max-line-bytes`: 256 MiB.
+pub const DEFAULT_MAX_LINE_BYTES: usize = 256 * 1024 * 1024;
+
+/// A [`BufRead`] that errors once a line exceeds `max` bytes.
+pub struct LineLimit<R> {
+    inner: R,
+    max: usize,
+    /// Bytes consumed since the last newline.
+    line_len: usize,
+    /// 1-based number of the current line.
+    line: usize,
+}
+
+impl<R: BufRead> LineLimit<R> {
+    pub fn new(inner: R, max: usize) -> Self {
+        Self {
+            inner,
+            max,
+            line_len: 0,
+            line: 1,
+        }
+    }
+}
+
+impl<R: BufRead> BufRead for LineLimit<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.line_len > self.max {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "line {} is longer than {} bytes (raise --max-line-bytes to accept it)",
+                    self.line, self.max
+                ),
+            ));
+        }
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Ok(buf) = self.inner.fill_buf() {
+            for &b in &buf[..amt.min(buf.len())] {
+                if b == b'\n' {
+                    self.line += 1;
+                    self.line_len = 0;
+                } else {
+                    self.line_len += 1;
+                }
+            }
+        }
+        self.inner.consume(amt);
+    }
+}
+
+impl<R: BufRead> Read for LineLimit<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let buf = self.fill_buf()?;
+        let n = buf.len().min(out.len());
+        out[..n].copy_from_slice(&buf[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn read_all(input: &str, max: usize) -> io::Result<String> {
+        // a tiny buffer so the limit is checked between refills
+        let mut r = LineLimit::new(BufReader::with_capacity(4, input.as_bytes()), max);
+        let mut out = String::new();
+        r.read_to_string(&mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn passes_short_lines() {
+        assert_eq!(read_all("abc\ndef\n", 3).unwrap(), "abc\ndef\n");
+    }
+
+    #[test]
+    fn rejects_long_line_with_its_number() {
+        let err = read_all("ok\n0123456789abcdef\n", 8).unwrap_err();
+        assert!(err.to_string().starts_with("line 2 is longer than 8 bytes"));
+    }
+}
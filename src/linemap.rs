@@ -0,0 +1,99 @@
+//! Source positions of chunk records (`--line-map`).
+//!
+//! For N-Triples and N-Quads inputs a sidecar next to each chunk lists, for
+//! every record, the input line and byte range it was read from, so a
+//! statement rejected by a store can be found in the source without
+//! searching it.  Positions of `.gz` inputs count decompressed bytes.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::format::is_gzip;
+
+/// Where a record was read: 1-based line, byte offset and length of the line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceSpan {
+    pub line: u64,
+    pub offset: u64,
+    pub len: u64,
+}
+
+/// `data_0000.nt` (or `data_0000.nt.gz`) → `data_0000.lines.tsv`.
+pub fn sidecar_path(chunk: &Path) -> PathBuf {
+    let chunk = if is_gzip(chunk) {
+        chunk.with_extension("")
+    } else {
+        chunk.to_path_buf()
+    };
+    chunk.with_extension("lines.tsv")
+}
+
+/// Writes the line map of one chunk: a header, then one tab-separated
+/// `record line offset bytes` row per record.
+pub struct LineMapWriter {
+    path: PathBuf,
+    /// `None` while suspended.
+    w: Option<BufWriter<fs::File>>,
+    records: u64,
+}
+
+impl LineMapWriter {
+    pub fn create(chunk: &Path) -> std::io::Result<Self> {
+        let path = sidecar_path(chunk);
+        let mut w = BufWriter::new(fs::File::create(&path)?);
+        writeln!(w, "record\tline\toffset\tbytes")?;
+        Ok(Self {
+            path,
+            w: Some(w),
+            records: 0,
+        })
+    }
+
+    /// Close the sidecar until the next record, which reopens it to append.
+    pub fn suspend(&mut self) -> std::io::Result<()> {
+        match self.w.take() {
+            Some(mut w) => w.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Record the position of the chunk's next record.
+    pub fn add(&mut self, span: SourceSpan) -> std::io::Result<()> {
+        self.records += 1;
+        if self.w.is_none() {
+            let file = OpenOptions::new().append(true).open(&self.path)?;
+            self.w = Some(BufWriter::new(file));
+        }
+        writeln!(
+            self.w.as_mut().expect("opened above"),
+            "{}\t{}\t{}\t{}",
+            self.records, span.line, span.offset, span.len
+        )
+    }
+
+    /// Flush the sidecar and return its path.
+    pub fn finish(mut self) -> std::io::Result<PathBuf> {
+        self.suspend()?;
+        Ok(self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sidecar_replaces_the_format_extension() {
+        assert_eq!(
+            sidecar_path(Path::new("out/data_0003.nt")),
+            PathBuf::from("out/data_0003.lines.tsv")
+        );
+        assert_eq!(
+            sidecar_path(Path::new("out/data_0003.nq.gz")),
+            PathBuf::from("out/data_0003.lines.tsv")
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Cross-chunk reference report (`--link-report`).
+//!
+//! Lists, per ordered pair of chunks, how many object IRIs used in the first
+//! chunk are described (used as subject) only in the second.  Consumers use it
+//! to decide which chunks must be loaded together, and in which order
+//! (`--load-order`).
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// Subjects and IRI objects of one chunk.
+#[derive(Debug, Default)]
+pub struct ChunkLinks {
+    /// Subjects of the chunk's statements, in N-Triples form.
+    pub subjects: HashSet<String>,
+    /// IRI objects of the chunk's statements, in N-Triples form.
+    pub objects: HashSet<String>,
+}
+
+/// References from `source` chunk to subjects described in `target`.
+#[derive(Debug)]
+pub struct CrossReference {
+    pub source: usize,
+    pub target: usize,
+    pub count: usize,
+    /// A few of the referenced IRIs, for orientation.
+    pub examples: Vec<String>,
+}
+
+const MAX_EXAMPLES: usize = 3;
+
+/// Collects the links of every chunk produced in a run.
+#[derive(Debug, Default)]
+pub struct LinkIndex {
+    chunks: Vec<(PathBuf, ChunkLinks)>,
+}
+
+impl LinkIndex {
+    pub fn add(&mut self, path: PathBuf, links: ChunkLinks) {
+        self.chunks.push((path, links));
+    }
+
+    pub fn chunk_path(&self, i: usize) -> &Path {
+        &self.chunks[i].0
+    }
+
+    /// All chunk pairs with at least one dangling reference, most references first.
+    pub fn cross_references(&self) -> Vec<CrossReference> {
+        let mut described_in: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, (_, links)) in self.chunks.iter().enumerate() {
+            for s in &links.subjects {
+                described_in.entry(s.as_str()).or_default().push(i);
+            }
+        }
+
+        let mut pairs: BTreeMap<(usize, usize), CrossReference> = BTreeMap::new();
+        for (source, (_, links)) in self.chunks.iter().enumerate() {
+            for o in &links.objects {
+                if links.subjects.contains(o) {
+                    continue;
+                }
+                for &target in described_in.get(o.as_str()).into_iter().flatten() {
+                    let xref = pairs.entry((source, target)).or_insert(CrossReference {
+                        source,
+                        target,
+                        count: 0,
+                        examples: Vec::new(),
+                    });
+                    xref.count += 1;
+                    if xref.examples.len() < MAX_EXAMPLES {
+                        xref.examples.push(o.clone());
+                    }
+                }
+            }
+        }
+
+        let mut out: Vec<_> = pairs.into_values().collect();
+        out.sort_by_key(|x| std::cmp::Reverse(x.count));
+        out
+    }
+
+    /// Chunks ordered so that the chunks describing referenced entities come
+    /// before the chunks referencing them.  Reference cycles are broken by
+    /// taking the chunk with the fewest unmet dependencies, then input order.
+    pub fn load_order(&self) -> Vec<&Path> {
+        let n = self.chunks.len();
+        let mut deps: Vec<HashSet<usize>> = vec![HashSet::new(); n];
+        for x in self.cross_references() {
+            if x.source != x.target {
+                deps[x.source].insert(x.target);
+            }
+        }
+
+        let mut placed = vec![false; n];
+        let mut order = Vec::with_capacity(n);
+        while order.len() < n {
+            let next = (0..n)
+                .filter(|&i| !placed[i])
+                .min_by_key(|&i| deps[i].iter().filter(|&&d| !placed[d]).count())
+                .expect("at least one chunk left");
+            placed[next] = true;
+            order.push(self.chunk_path(next));
+        }
+        order
+    }
+
+    /// Write [`load_order`](Self::load_order) to `path`, one chunk per line.
+    pub fn write_load_order(&self, path: &Path) -> std::io::Result<()> {
+        let mut w = BufWriter::new(fs::File::create(path)?);
+        for chunk in self.load_order() {
+            writeln!(w, "{}", chunk.display())?;
+        }
+        w.flush()
+    }
+
+    /// Write the report as CSV: `source_chunk,target_chunk,references,examples`.
+    pub fn write_csv(&self, path: &Path) -> std::io::Result<usize> {
+        let xrefs = self.cross_references();
+        let mut w = BufWriter::new(fs::File::create(path)?);
+        writeln!(w, "source_chunk,target_chunk,references,examples")?;
+        for x in &xrefs {
+            writeln!(
+                w,
+                "{},{},{},\"{}\"",
+                self.chunk_path(x.source).display(),
+                self.chunk_path(x.target).display(),
+                x.count,
+                x.examples.join(" ").replace('"', "\"\"")
+            )?;
+        }
+        w.flush()?;
+        Ok(xrefs.iter().map(|x| x.count).sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(subjects: &[&str], objects: &[&str]) -> ChunkLinks {
+        ChunkLinks {
+            subjects: subjects.iter().map(|s| s.to_string()).collect(),
+            objects: objects.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn counts_references_described_in_other_chunks() {
+        let mut index = LinkIndex::default();
+        index.add("a".into(), links(&["<s1>"], &["<s2>", "<s3>", "<s1>", "<ext>"]));
+        index.add("b".into(), links(&["<s2>", "<s3>"], &["<s1>"]));
+        let xrefs = index.cross_references();
+        assert_eq!(xrefs.len(), 2);
+        assert_eq!((xrefs[0].source, xrefs[0].target, xrefs[0].count), (0, 1, 2));
+        assert_eq!((xrefs[1].source, xrefs[1].target, xrefs[1].count), (1, 0, 1));
+    }
+
+    #[test]
+    fn load_order_puts_referenced_chunks_first() {
+        let mut index = LinkIndex::default();
+        index.add("a".into(), links(&["<a>"], &["<b>"]));
+        index.add("b".into(), links(&["<b>"], &["<c>"]));
+        index.add("c".into(), links(&["<c>"], &[]));
+        let order: Vec<_> = index.load_order().iter().map(|p| p.display().to_string()).collect();
+        assert_eq!(order, ["c", "b", "a"]);
+    }
+
+    #[test]
+    fn load_order_breaks_cycles() {
+        let mut index = LinkIndex::default();
+        index.add("a".into(), links(&["<a>"], &["<b>"]));
+        index.add("b".into(), links(&["<b>"], &["<a>"]));
+        assert_eq!(index.load_order().len(), 2);
+    }
+}
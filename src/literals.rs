@@ -0,0 +1,227 @@
+//! Per-chunk string literal extraction (`--literal-index`).
+//!
+//! Next to each chunk a sidecar lists `(subject, predicate, literal)` for
+//! every string literal (plain, language-tagged or `xsd:string`), so search
+//! indexers can be fed without parsing the chunk again.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use serde_json::json;
+
+use crate::{format::is_gzip, serialise::OwnedQuad};
+
+const XSD_STRING: &str = "<http://www.w3.org/2001/XMLSchema#string>";
+
+/// Sidecar layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LiteralIndex {
+    /// One JSON object per line: `subject`, `predicate`, `value`, `lang`
+    Jsonl,
+    /// Tab-separated `subject predicate value` lines
+    Text,
+}
+
+impl LiteralIndex {
+    fn extension(self) -> &'static str {
+        match self {
+            LiteralIndex::Jsonl => "literals.jsonl",
+            LiteralIndex::Text => "literals.tsv",
+        }
+    }
+}
+
+/// `data_0000.ttl` (or `data_0000.ttl.gz`) → `data_0000.literals.jsonl`.
+pub fn sidecar_path(chunk: &Path, kind: LiteralIndex) -> PathBuf {
+    let chunk = if is_gzip(chunk) { chunk.with_extension("") } else { chunk.to_path_buf() };
+    chunk.with_extension(kind.extension())
+}
+
+/// A string literal split into its value and optional language tag.
+#[derive(Debug, PartialEq, Eq)]
+pub struct StringLiteral {
+    pub value: String,
+    pub lang: Option<String>,
+}
+
+/// Parse an N-Triples term, returning it if it is a string literal.
+pub fn string_literal(term: &str) -> Option<StringLiteral> {
+    let body = term.strip_prefix('"')?;
+    let close = closing_quote(body)?;
+    let suffix = &body[close + 1..];
+    let lang = if suffix.is_empty() || suffix == format!("^^{XSD_STRING}") {
+        None
+    } else if let Some(tag) = suffix.strip_prefix('@') {
+        Some(tag.to_string())
+    } else {
+        return None;
+    };
+    Some(StringLiteral {
+        value: unescape(&body[..close]),
+        lang,
+    })
+}
+
+fn closing_quote(body: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, b) in body.bytes().enumerate() {
+        match b {
+            _ if escaped => escaped = false,
+            b'\\' => escaped = true,
+            b'"' => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Undo N-Triples string escapes (`\n`, `\"`, `\uXXXX`, …).
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('b') => out.push('\u{8}'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('f') => out.push('\u{c}'),
+            Some(e @ ('u' | 'U')) => {
+                let len = if e == 'u' { 4 } else { 8 };
+                let hex: String = chars.by_ref().take(len).collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(ch) => out.push(ch),
+                    None => {
+                        out.push('\\');
+                        out.push(e);
+                        out.push_str(&hex);
+                    }
+                }
+            }
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// `<iri>` → `iri`; blank nodes stay as `_:b`.
+fn bare(term: &str) -> &str {
+    term.strip_prefix('<')
+        .and_then(|t| t.strip_suffix('>'))
+        .unwrap_or(term)
+}
+
+/// Writes the literal sidecar of one chunk as its statements are written.
+pub struct SidecarWriter {
+    path: PathBuf,
+    kind: LiteralIndex,
+    /// `None` while suspended.
+    w: Option<BufWriter<fs::File>>,
+}
+
+impl SidecarWriter {
+    pub fn create(chunk: &Path, kind: LiteralIndex) -> std::io::Result<Self> {
+        let path = sidecar_path(chunk, kind);
+        let w = Some(BufWriter::new(fs::File::create(&path)?));
+        Ok(Self { path, kind, w })
+    }
+
+    /// Close the sidecar until the next record, which reopens it to append.
+    pub fn suspend(&mut self) -> std::io::Result<()> {
+        match self.w.take() {
+            Some(mut w) => w.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn file(&mut self) -> std::io::Result<&mut BufWriter<fs::File>> {
+        if self.w.is_none() {
+            let file = OpenOptions::new().append(true).open(&self.path)?;
+            self.w = Some(BufWriter::new(file));
+        }
+        Ok(self.w.as_mut().expect("opened above"))
+    }
+
+    /// Record the object of `q` if it is a string literal.
+    pub fn add(&mut self, q: &OwnedQuad) -> std::io::Result<()> {
+        let t = &q.triple;
+        let Some(lit) = string_literal(&t.object) else {
+            return Ok(());
+        };
+        match self.kind {
+            LiteralIndex::Jsonl => {
+                let rec = json!({
+                    "subject": bare(&t.subject),
+                    "predicate": bare(&t.predicate),
+                    "value": lit.value,
+                    "lang": lit.lang,
+                });
+                writeln!(self.file()?, "{rec}")
+            }
+            LiteralIndex::Text => {
+                let value = lit.value.replace(['\t', '\n', '\r'], " ");
+                let (s, p) = (bare(&t.subject), bare(&t.predicate));
+                writeln!(self.file()?, "{s}\t{p}\t{value}")
+            }
+        }
+    }
+
+    /// Flush the sidecar and return its path.
+    pub fn finish(mut self) -> std::io::Result<PathBuf> {
+        self.suspend()?;
+        Ok(self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_string_literals() {
+        assert_eq!(
+            string_literal("\"a \\\"b\\\"\\n\"@en"),
+            Some(StringLiteral {
+                value: "a \"b\"\n".into(),
+                lang: Some("en".into())
+            })
+        );
+        assert_eq!(
+            string_literal(&format!("\"x\"^^{XSD_STRING}"))
+                .unwrap()
+                .value,
+            "x"
+        );
+        assert_eq!(string_literal("\"\\u00FC\"").unwrap().value, "ü");
+    }
+
+    #[test]
+    fn skips_other_terms() {
+        assert_eq!(
+            string_literal("\"1\"^^<http://www.w3.org/2001/XMLSchema#int>"),
+            None
+        );
+        assert_eq!(string_literal("<http://ex.org/a>"), None);
+        assert_eq!(string_literal("_:b0"), None);
+    }
+
+    #[test]
+    fn sidecar_replaces_chunk_extension() {
+        assert_eq!(
+            sidecar_path(Path::new("out/data_0000.ttl"), LiteralIndex::Jsonl),
+            PathBuf::from("out/data_0000.literals.jsonl")
+        );
+        assert_eq!(
+            sidecar_path(Path::new("out/data_0000.nt.gz"), LiteralIndex::Text),
+            PathBuf::from("out/data_0000.literals.tsv")
+        );
+    }
+}
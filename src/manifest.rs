@@ -0,0 +1,169 @@
+//! JSON manifest of the chunk set (`--manifest FILE`).
+//!
+//! Load orchestrators verify and schedule chunks from the manifest rather
+//! than from a directory listing: every chunk is listed with its path
+//! relative to the output directory, the inputs it came from, its record
+//! count, byte size and checksum.  The checksum is named after its digest
+//! (`"sha256"`, or `"blake3"` / `"xxh3"` with `--hash`), which the top-level
+//! `checksum` key states for the whole manifest.
+//!
+//! `rdfsplitter check DIR --manifest FILE` ([`run_check`]) verifies an
+//! existing chunk set against its manifest before it is loaded again.
+
+use std::{
+    fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use clap::ValueEnum;
+use log::{error, info, warn};
+use serde_json::{json, Value};
+
+use crate::{
+    checksum::HashAlgorithm,
+    format::{RdfFormat, SplitterError},
+    layout::utc_timestamp,
+    paths,
+    splitter::{check_overwrite, count_records, ChunkInfo, SplitOptions},
+};
+
+/// A chunk and the inputs it came from.
+struct Entry {
+    inputs: Vec<PathBuf>,
+    path: PathBuf,
+    records: usize,
+}
+
+/// Chunks of a run, collected for the manifest.
+pub struct Manifest {
+    started: SystemTime,
+    entries: Vec<Entry>,
+}
+
+impl Manifest {
+    pub fn new(started: SystemTime) -> Self {
+        Self {
+            started,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Record the chunks `inputs` were split (or merged) into.
+    pub fn add(&mut self, inputs: &[PathBuf], chunks: &[ChunkInfo]) {
+        self.entries.extend(chunks.iter().map(|c| Entry {
+            inputs: inputs.to_vec(),
+            path: c.path.clone(),
+            records: c.records,
+        }));
+    }
+
+    /// Write the manifest to `path`; sizes and checksums are taken from the
+    /// chunk files as they are on disk.
+    pub fn write(
+        &self,
+        path: &Path,
+        opts: &SplitOptions,
+        finished: SystemTime,
+    ) -> Result<(), SplitterError> {
+        check_overwrite(path, opts.force)?;
+        let hash = opts.hash.unwrap_or_default();
+
+        let mut chunks = Vec::new();
+        let mut total_bytes = 0;
+        for entry in &self.entries {
+            let bytes = fs::metadata(&entry.path)?.len();
+            total_bytes += bytes;
+            let digest = hash.hex_digest(BufReader::new(fs::File::open(&entry.path)?))?;
+            let inputs: Vec<String> = entry
+                .inputs
+                .iter()
+                .map(|i| i.display().to_string())
+                .collect();
+            let mut chunk = json!({
+                "path": entry.path.strip_prefix(&opts.output_dir).unwrap_or(&entry.path).display().to_string(),
+                "inputs": inputs,
+                "records": entry.records,
+                "bytes": bytes,
+            });
+            chunk[hash.name()] = json!(digest);
+            chunks.push(chunk);
+        }
+
+        let doc = json!({
+            "generator": format!("rdfsplitter {}", env!("CARGO_PKG_VERSION")),
+            "started": utc_timestamp(self.started),
+            "finished": utc_timestamp(finished),
+            "output": opts.output_dir.display().to_string(),
+            "checksum": hash.name(),
+            "records": self.entries.iter().map(|e| e.records).sum::<usize>(),
+            "bytes": total_bytes,
+            "chunks": chunks,
+        });
+        fs::write(path, format!("{doc:#}\n"))?;
+        paths::set_mode(path, opts.file_mode)?;
+        Ok(())
+    }
+}
+
+/// Verify the chunks listed in `manifest` below `dir`: that each exists,
+/// has the recorded size and checksum, and parses.  Every difference is
+/// logged; returns the number of chunks that differ.
+pub fn run_check(dir: &Path, manifest: &Path) -> Result<usize, SplitterError> {
+    let raw = fs::read_to_string(manifest)?;
+    let doc: Value = serde_json::from_str(&raw)
+        .map_err(|e| SplitterError::Parse(format!("{}: {e}", manifest.display())))?;
+    let name = doc["checksum"].as_str().unwrap_or("sha256");
+    let hash = HashAlgorithm::from_str(name, true).map_err(|_| {
+        SplitterError::Parse(format!("{}: unknown checksum '{name}'", manifest.display()))
+    })?;
+    let chunks = doc["chunks"].as_array().ok_or_else(|| {
+        SplitterError::Parse(format!("{}: no 'chunks' list", manifest.display()))
+    })?;
+
+    let mut failed = 0usize;
+    for chunk in chunks {
+        let Some(rel) = chunk["path"].as_str() else {
+            return Err(SplitterError::Parse(format!(
+                "{}: chunk without 'path'",
+                manifest.display()
+            )));
+        };
+        let path = dir.join(rel);
+        if let Err(drift) = check_chunk(&path, chunk, hash) {
+            error!("{}: {drift}", path.display());
+            failed += 1;
+        }
+    }
+    info!(
+        "{} of {} chunk(s) match {}",
+        chunks.len() - failed,
+        chunks.len(),
+        manifest.display()
+    );
+    Ok(failed)
+}
+
+/// How `path` differs from its manifest `entry`, if at all.
+fn check_chunk(path: &Path, entry: &Value, hash: HashAlgorithm) -> Result<(), String> {
+    let meta = fs::metadata(path).map_err(|e| format!("missing ({e})"))?;
+    if let Some(bytes) = entry["bytes"].as_u64().filter(|&b| b != meta.len()) {
+        return Err(format!("{} byte(s), the manifest says {bytes}", meta.len()));
+    }
+    if let Some(expected) = entry[hash.name()].as_str() {
+        let file = fs::File::open(path).map_err(|e| e.to_string())?;
+        let digest = hash.hex_digest(BufReader::new(file)).map_err(|e| e.to_string())?;
+        if !digest.eq_ignore_ascii_case(expected) {
+            return Err(format!("{} checksum {digest}, the manifest says {expected}", hash.name()));
+        }
+    }
+    match RdfFormat::from_path(path) {
+        Some(fmt) => {
+            count_records(path, fmt).map_err(|e| format!("does not parse: {e}"))?;
+        }
+        // encrypted chunks cannot be read back
+        None => warn!("{}: format unknown, not parsed", path.display()),
+    }
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! Dataset metadata (`--title`, `--license`, `--publisher`).
+//!
+//! The metadata is recorded in the RO-Crate descriptor and, with
+//! `--metadata-triples`, stated in every chunk with Dublin Core terms on a
+//! `dcat:Dataset` node, so a fragment published on its own still names its
+//! licence and publisher.
+
+use crate::{
+    serialise::{OwnedQuad, OwnedTriple},
+    splitter::nt_escape,
+    vocab,
+};
+
+const DCAT_DATASET: &str = "<http://www.w3.org/ns/dcat#Dataset>";
+const DCT_TITLE: &str = "<http://purl.org/dc/terms/title>";
+const DCT_LICENSE: &str = "<http://purl.org/dc/terms/license>";
+const DCT_PUBLISHER: &str = "<http://purl.org/dc/terms/publisher>";
+
+/// Blank node the per-chunk metadata statements describe.
+const DATASET_NODE: &str = "_:rdfsplitterDataset";
+
+/// Descriptive metadata of the chunk set; IRIs are given without `<>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatasetMetadata {
+    pub title: Option<String>,
+    pub license: Option<String>,
+    pub publisher: Option<String>,
+}
+
+impl DatasetMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.license.is_none() && self.publisher.is_none()
+    }
+
+    /// The metadata as default-graph statements about one dataset node.
+    pub fn statements(&self) -> Vec<OwnedQuad> {
+        let mut objects = vec![(vocab::RDF_TYPE, DCAT_DATASET.to_owned())];
+        if let Some(title) = &self.title {
+            objects.push((DCT_TITLE, format!("\"{}\"", nt_escape(title))));
+        }
+        if let Some(license) = &self.license {
+            objects.push((DCT_LICENSE, format!("<{license}>")));
+        }
+        if let Some(publisher) = &self.publisher {
+            objects.push((DCT_PUBLISHER, format!("<{publisher}>")));
+        }
+        objects
+            .into_iter()
+            .map(|(predicate, object)| {
+                OwnedQuad::from_triple(OwnedTriple {
+                    subject: DATASET_NODE.to_owned(),
+                    predicate: predicate.to_owned(),
+                    object,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn states_only_given_fields() {
+        let meta = DatasetMetadata {
+            title: Some("Infra \"2026\"".into()),
+            license: Some("https://creativecommons.org/licenses/by/4.0/".into()),
+            publisher: None,
+        };
+        let objects: Vec<String> = meta
+            .statements()
+            .into_iter()
+            .map(|q| q.triple.object)
+            .collect();
+        assert_eq!(
+            objects,
+            [
+                DCAT_DATASET,
+                "\"Infra \\\"2026\\\"\"",
+                "<https://creativecommons.org/licenses/by/4.0/>"
+            ]
+        );
+        assert!(DatasetMetadata::default().is_empty());
+    }
+}
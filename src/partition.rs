@@ -0,0 +1,141 @@
+//! Hash partitioning (`--partition-by`, `--partitions`).
+//!
+//! Instead of cutting sequential chunks, every statement is routed to one of
+//! N partition files (`<stem>_part_NNNN.<ext>`) by a hash of its key.  The
+//! hash is FNV-1a over the key's N-Triples form, or the digest chosen with
+//! `--hash`, fixed across platforms and releases, so a resource lands in the
+//! same partition on every run and a re-split of an updated dump only
+//! changes the partitions it touches.
+//!
+//! Blank-node labels are made up by the parser, so a blank node is not
+//! placed by its own label: the statements about a blank node (an RDF
+//! list, a nested description) go to the partition of the resource that
+//! names it as object (see [`BlankOwners`](crate::bnodes::BlankOwners)).
+
+use crate::{checksum::HashAlgorithm, serialise::OwnedQuad};
+
+/// The statement key partitions are chosen by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PartitionBy {
+    /// The subject, so a resource's description stays in one partition
+    Subject,
+}
+
+/// Route statements to `count` partitions by `by`.
+#[derive(Debug, Clone, Copy)]
+pub struct Partitioning {
+    /// What a statement is placed by.
+    pub by: PartitionBy,
+    /// Number of partitions.
+    pub count: usize,
+    /// Digest of the key instead of FNV-1a (`--hash`).
+    pub hash: Option<HashAlgorithm>,
+}
+
+impl Partitioning {
+    /// Index (`0..count`) of the partition receiving `q`.
+    pub fn of(&self, q: &OwnedQuad) -> usize {
+        let key = match self.by {
+            PartitionBy::Subject => &q.triple.subject,
+        };
+        let h = match self.hash {
+            Some(alg) => {
+                let digest = alg.digest(key.as_bytes());
+                u64::from_be_bytes(digest[..8].try_into().expect("8 bytes"))
+            }
+            None => fnv1a(key.as_bytes()),
+        };
+        (h % self.count as u64) as usize
+    }
+}
+
+/// 64-bit FNV-1a.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    bytes
+        .iter()
+        .fold(OFFSET, |h, &b| (h ^ u64::from(b)).wrapping_mul(PRIME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bnodes::BlankOwners, serialise::OwnedTriple};
+
+    fn q(s: &str, o: &str) -> OwnedQuad {
+        OwnedQuad::from_triple(OwnedTriple {
+            subject: s.into(),
+            predicate: "<http://ex.org/p>".into(),
+            object: o.into(),
+        })
+    }
+
+    fn routed(quads: Vec<OwnedQuad>) -> Vec<(usize, String)> {
+        let parts = Partitioning {
+            by: PartitionBy::Subject,
+            count: 64,
+            hash: None,
+        };
+        let mut owners = BlankOwners::default();
+        let mut ready = Vec::new();
+        for q in quads {
+            owners.route(q, |q| parts.of(q), &mut ready);
+        }
+        owners.finish(|q| parts.of(q), &mut ready);
+        ready.into_iter().map(|(part, q)| (part, q.triple.subject)).collect()
+    }
+
+    #[test]
+    fn blank_nodes_follow_the_resource_naming_them() {
+        // a Turtle list arrives before the statement that names it
+        let nil = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#nil>";
+        let routed = routed(vec![
+            q("_:l1", "\"1\""),
+            q("_:l1", "_:l2"),
+            q("_:l2", "\"2\""),
+            q("_:l2", nil),
+            q("<http://ex.org/a>", "_:l1"),
+        ]);
+        assert_eq!(routed.len(), 5);
+        assert_eq!(routed[0].1, "<http://ex.org/a>");
+        assert!(routed.iter().all(|(part, _)| *part == routed[0].0));
+        // relabelling the list does not move it
+        let relabelled = self::routed(vec![q("<http://ex.org/a>", "_:x"), q("_:x", "\"1\"")]);
+        assert_eq!(relabelled[1].0, routed[0].0);
+    }
+
+    #[test]
+    fn unnamed_blank_nodes_are_written_at_the_end() {
+        let routed = routed(vec![
+            q("_:b", "_:c"),
+            q("_:c", "\"1\""),
+            q("<http://ex.org/a>", "\"2\""),
+        ]);
+        let subjects: Vec<&str> = routed.iter().map(|(_, s)| s.as_str()).collect();
+        assert_eq!(subjects, ["<http://ex.org/a>", "_:b", "_:c"]);
+        assert_eq!(routed[1].0, routed[2].0);
+    }
+
+    #[test]
+    fn hash_is_fixed() {
+        // reference values of the FNV-1a specification
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn statements_of_a_subject_share_a_partition() {
+        for hash in [None, Some(HashAlgorithm::Blake3), Some(HashAlgorithm::Xxh3)] {
+            let parts = Partitioning {
+                by: PartitionBy::Subject,
+                count: 16,
+                hash,
+            };
+            let p = parts.of(&q("<http://ex.org/s>", "\"1\""));
+            assert!(p < 16);
+            assert_eq!(parts.of(&q("<http://ex.org/s>", "<http://ex.org/o>")), p);
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Platform path handling: `file:` IRIs for base resolution, Windows
+//! long-path output names and Unix permission modes of created outputs.
+//!
+//! The IRI and long-path helpers work on the textual form of a path so the Windows rules are
+//! exercised by the tests on every platform.
+
+use std::{
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+/// Windows `MAX_PATH`; longer paths need the `\\?\` verbatim prefix.
+const WINDOWS_MAX_PATH: usize = 260;
+
+/// Convert an absolute path to a `file:` IRI.
+///
+/// Handles Unix paths, drive letters (`C:\x` → `file:///C:/x`), UNC shares
+/// (`\\server\share\x` → `file://server/share/x`) and their verbatim forms
+/// (`\\?\C:\x`, `\\?\UNC\server\share\x`) as returned by `canonicalize` on
+/// Windows.  Characters not allowed in IRIs are percent-encoded.
+pub fn file_iri(path: &str) -> String {
+    let (authority, rest) = if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        split_unc(unc)
+    } else if let Some(local) = path.strip_prefix(r"\\?\") {
+        ("", local)
+    } else if let Some(unc) = path.strip_prefix(r"\\") {
+        split_unc(unc)
+    } else {
+        ("", path)
+    };
+
+    let rest = rest.replace('\\', "/");
+    let rest = rest.strip_prefix('/').unwrap_or(&rest);
+    format!("file://{}/{}", encode(authority), encode(rest))
+}
+
+/// `server\share\x` → (`server`, `share\x`).
+fn split_unc(unc: &str) -> (&str, &str) {
+    unc.split_once('\\').unwrap_or((unc, ""))
+}
+
+/// Percent-encode characters that may not appear in an IRI path.
+pub(crate) fn encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            ' ' | '"' | '<' | '>' | '\\' | '^' | '`' | '{' | '|' | '}' | '%' | '#' | '?' => {
+                out.push_str(&format!("%{:02X}", c as u32))
+            }
+            c if c.is_control() => {
+                let mut buf = [0u8; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    out.push_str(&format!("%{b:02X}"));
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Prefix an absolute Windows path that exceeds `MAX_PATH` with `\\?\`
+/// (`\\?\UNC\` for shares); other paths are returned unchanged.
+pub fn long_path(path: &str) -> String {
+    if path.len() < WINDOWS_MAX_PATH || path.starts_with(r"\\?\") {
+        return path.to_string();
+    }
+    if let Some(unc) = path.strip_prefix(r"\\") {
+        return format!(r"\\?\UNC\{unc}");
+    }
+    let bytes = path.as_bytes();
+    let has_drive = bytes.len() > 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic();
+    if has_drive && (bytes[2] == b'\\' || bytes[2] == b'/') {
+        return format!(r"\\?\{}", path.replace('/', "\\"));
+    }
+    path.to_string()
+}
+
+/// Make an output path usable on Windows even beyond `MAX_PATH`.
+pub fn output_path(path: PathBuf) -> PathBuf {
+    if cfg!(windows) {
+        if let Ok(abs) = std::path::absolute(&path) {
+            let abs = abs.display().to_string();
+            let long = long_path(&abs);
+            if long != abs {
+                return PathBuf::from(long);
+            }
+        }
+    }
+    path
+}
+
+/// Parse an octal permission mode such as `0644` or `755`.
+pub fn parse_mode(s: &str) -> Result<u32, String> {
+    let digits = s.strip_prefix("0o").unwrap_or(s);
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err(format!("'{s}' is not an octal permission mode (e.g. 0644)")),
+    }
+}
+
+/// Apply a Unix permission mode to a created file or directory.  A no-op
+/// when `mode` is `None` and on platforms without Unix permissions.
+pub fn set_mode(path: &Path, mode: Option<u32>) -> io::Result<()> {
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (path, mode);
+    Ok(())
+}
+
+/// Directory of `input` mirrored below the output directory
+/// (`--preserve-structure`): its parent directory, relative to the working
+/// directory when below it, without root and `.` components and with `..`
+/// resolved lexically; a `..` above the start is dropped, so the mirror
+/// stays below the output directory.
+pub fn mirrored_dir(input: &Path) -> PathBuf {
+    let parent = input.parent().unwrap_or(Path::new(""));
+    let cwd = std::env::current_dir().ok();
+    let parent = cwd
+        .as_deref()
+        .and_then(|cwd| parent.strip_prefix(cwd).ok())
+        .unwrap_or(parent);
+    let mut mirrored = PathBuf::new();
+    for c in parent.components() {
+        match c {
+            Component::Normal(part) => mirrored.push(part),
+            Component::ParentDir => {
+                mirrored.pop();
+            }
+            _ => {}
+        }
+    }
+    mirrored
+}
+
+/// `file:` IRI of `path`, made absolute first where possible; a URL input
+/// is its own IRI.
+pub fn path_to_file_iri(path: &Path) -> String {
+    if crate::remote::is_remote(path) {
+        return path.display().to_string();
+    }
+    let abs = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    file_iri(&abs.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_octal_modes() {
+        assert_eq!(parse_mode("0644"), Ok(0o644));
+        assert_eq!(parse_mode("755"), Ok(0o755));
+        assert!(parse_mode("0899").is_err());
+        assert!(parse_mode("77777").is_err());
+    }
+
+    #[test]
+    fn mirrors_the_input_directory() {
+        assert_eq!(mirrored_dir(Path::new("data/a/x.nt")), PathBuf::from("data/a"));
+        assert_eq!(mirrored_dir(Path::new("./data/../b/x.nt")), PathBuf::from("b"));
+        assert_eq!(mirrored_dir(Path::new("../../a/x.nt")), PathBuf::from("a"));
+        assert_eq!(mirrored_dir(Path::new("x.nt")), PathBuf::new());
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(mirrored_dir(&cwd.join("data").join("x.nt")), PathBuf::from("data"));
+    }
+
+    #[test]
+    fn unix_paths() {
+        assert_eq!(file_iri("/data/my file.ttl"), "file:///data/my%20file.ttl");
+    }
+
+    #[test]
+    fn drive_letter_paths() {
+        assert_eq!(file_iri(r"C:\data\x.ttl"), "file:///C:/data/x.ttl");
+        assert_eq!(
+            file_iri(r"\\?\C:\data\x#1.ttl"),
+            "file:///C:/data/x%231.ttl"
+        );
+    }
+
+    #[test]
+    fn unc_paths() {
+        assert_eq!(
+            file_iri(r"\\server\share\x.ttl"),
+            "file://server/share/x.ttl"
+        );
+        assert_eq!(
+            file_iri(r"\\?\UNC\server\share\x.ttl"),
+            "file://server/share/x.ttl"
+        );
+    }
+
+    #[test]
+    fn long_paths_get_verbatim_prefix() {
+        let long = format!(r"C:\{}\x.nt", "d".repeat(300));
+        assert_eq!(long_path(&long), format!(r"\\?\{long}"));
+        let unc = format!(r"\\server\share\{}", "d".repeat(300));
+        assert_eq!(
+            long_path(&unc),
+            format!(r"\\?\UNC\server\share\{}", "d".repeat(300))
+        );
+        assert_eq!(long_path(r"C:\short.nt"), r"C:\short.nt");
+        assert_eq!(long_path(&format!(r"\\?\{long}")), format!(r"\\?\{long}"));
+    }
+}
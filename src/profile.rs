@@ -0,0 +1,163 @@
+//! Named run profiles (`rdfsplitter run --profile NAME`).
+//!
+//! A profile file is TOML with one table per profile.  Keys are the long
+//! option names (`chunk-size` or `chunk_size`), `inputs` lists the input
+//! patterns:
+//!
+//! ```toml
+//! [era-infra]
+//! inputs = ["data/era/*.ttl"]
+//! chunk-size = 50000
+//! output = "out/era"
+//! force = true
+//! ```
+//!
+//! A profile is turned into ordinary command-line arguments, so it accepts
+//! exactly the options the command line does and is validated the same way.
+
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, bail, Context};
+use toml::Value;
+
+/// Load profile `name` from `path` and return it as command-line arguments
+/// (without the program name).
+pub fn profile_args(path: &Path, name: &str) -> anyhow::Result<Vec<String>> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("cannot read profile file '{}'", path.display()))?;
+    let doc: toml::Table = raw
+        .parse()
+        .with_context(|| format!("invalid profile file '{}'", path.display()))?;
+    let profile = match doc.get(name) {
+        Some(Value::Table(t)) => t,
+        Some(_) => bail!("profile '{name}' is not a table"),
+        None => {
+            let known: Vec<&str> = doc.keys().map(String::as_str).collect();
+            bail!(
+                "no profile '{name}' in '{}' (available: {})",
+                path.display(),
+                known.join(", ")
+            )
+        }
+    };
+    table_to_args(profile).with_context(|| format!("profile '{name}'"))
+}
+
+/// Load a standalone job descriptor (TOML, or JSON with the same keys) and
+/// return it as command-line arguments, led by `convert` for a convert job.
+pub fn job_args(path: &Path) -> anyhow::Result<Vec<String>> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("cannot read job '{}'", path.display()))?;
+    let mut table: toml::Table = if path.extension().is_some_and(|e| e == "json") {
+        serde_json::from_str(&raw)?
+    } else {
+        raw.parse()?
+    };
+    let command = match table.remove("command") {
+        None => None,
+        Some(Value::String(c)) if c == "split" => None,
+        Some(Value::String(c)) if c == "convert" => Some(c),
+        Some(v) => bail!(
+            "job '{}': unknown command {v} (a job runs split or convert)",
+            path.display()
+        ),
+    };
+    let args = table_to_args(&table).with_context(|| format!("job '{}'", path.display()))?;
+    Ok(command.into_iter().chain(args).collect())
+}
+
+fn table_to_args(table: &toml::Table) -> anyhow::Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut inputs = Vec::new();
+    for (key, value) in table {
+        if key == "inputs" {
+            match value {
+                Value::Array(items) => {
+                    for v in items {
+                        inputs.push(scalar(key, v)?);
+                    }
+                }
+                v => inputs.push(scalar(key, v)?),
+            }
+            continue;
+        }
+        let flag = format!("--{}", key.replace('_', "-"));
+        match value {
+            Value::Boolean(true) => args.push(flag),
+            Value::Boolean(false) => {}
+            Value::Array(items) => {
+                for v in items {
+                    args.push(flag.clone());
+                    args.push(scalar(key, v)?);
+                }
+            }
+            v => {
+                args.push(flag);
+                args.push(scalar(key, v)?);
+            }
+        }
+    }
+    // positionals after `--` so input names never read as options
+    args.push("--".into());
+    args.extend(inputs);
+    Ok(args)
+}
+
+fn scalar(key: &str, v: &Value) -> anyhow::Result<String> {
+    match v {
+        Value::String(s) => Ok(s.clone()),
+        Value::Integer(i) => Ok(i.to_string()),
+        Value::Float(f) => Ok(f.to_string()),
+        Value::Boolean(b) => Ok(b.to_string()),
+        _ => Err(anyhow!("unsupported value for '{key}'")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(src: &str) -> Vec<String> {
+        table_to_args(&src.parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn converts_keys_to_long_options() {
+        assert_eq!(
+            args(
+                "inputs = [\"a.ttl\", \"b.ttl\"]\nchunk_size = 5\nforce = true\nrecursive = false"
+            ),
+            ["--chunk-size", "5", "--force", "--", "a.ttl", "b.ttl"]
+        );
+    }
+
+    #[test]
+    fn reads_json_jobs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("job.json");
+        fs::write(&path, r#"{"inputs": ["a.nt"], "chunk-size": 10}"#).unwrap();
+        assert_eq!(
+            job_args(&path).unwrap(),
+            ["--chunk-size", "10", "--", "a.nt"]
+        );
+    }
+
+    #[test]
+    fn jobs_name_their_command() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("job.toml");
+        fs::write(&path, "command = \"convert\"\nto = \"nt\"\ninputs = \"a.ttl\"").unwrap();
+        assert_eq!(job_args(&path).unwrap(), ["convert", "--to", "nt", "--", "a.ttl"]);
+        fs::write(&path, "command = \"upload\"\ninputs = \"a.ttl\"").unwrap();
+        let err = job_args(&path).unwrap_err().to_string();
+        assert!(err.contains("job.toml") && err.contains("unknown command \"upload\""), "{err}");
+    }
+
+    #[test]
+    fn repeats_array_options() {
+        assert_eq!(
+            args("exclude = [\"x\", \"y\"]"),
+            ["--exclude", "x", "--exclude", "y", "--"]
+        );
+    }
+}
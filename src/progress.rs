@@ -0,0 +1,123 @@
+//! Progress display on standard error.
+//!
+//! Every parse of an input shows a bar with the bytes read against the
+//! size of the file (the compressed size for `.gz` inputs), the records
+//! parsed per second and an estimate of the time left; inputs of unknown
+//! size (standard input, URLs) show a spinner instead.  Nothing is drawn
+//! when standard error is not a terminal or after [`disable`]
+//! (`--no-progress`).  Log lines are written through [`suspend`], so they
+//! appear above the bar instead of through it.
+
+use std::{
+    io::{self, Read},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+/// Records between two updates of the record count and rate.
+const UPDATE_INTERVAL: usize = 10_000;
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+fn bars() -> &'static MultiProgress {
+    static BARS: OnceLock<MultiProgress> = OnceLock::new();
+    BARS.get_or_init(|| MultiProgress::with_draw_target(ProgressDrawTarget::stderr()))
+}
+
+/// Draw no progress for the rest of the process (`--no-progress`).
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+    bars().set_draw_target(ProgressDrawTarget::hidden());
+}
+
+/// Run `f` with the bars cleared from the terminal, e.g. to write a log line.
+pub fn suspend<R>(f: impl FnOnce() -> R) -> R {
+    bars().suspend(f)
+}
+
+/// Standard error, writing between progress redraws; the log target.
+pub struct Stderr;
+
+impl io::Write for Stderr {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        suspend(|| io::stderr().write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+/// The progress of parsing one input; cleared when dropped.
+pub struct InputProgress {
+    bar: ProgressBar,
+    started: Instant,
+    records: usize,
+}
+
+impl InputProgress {
+    /// Progress over `len` bytes, or of unknown length.
+    pub fn start(len: Option<u64>) -> Self {
+        if DISABLED.load(Ordering::Relaxed) {
+            return Self::hidden();
+        }
+        let bar = match len {
+            Some(len) => ProgressBar::new(len).with_style(
+                ProgressStyle::with_template(
+                    "  [{bar:30}] {bytes}/{total_bytes} {msg} (ETA {eta})",
+                )
+                .expect("valid template")
+                .progress_chars("=> "),
+            ),
+            None => ProgressBar::new_spinner().with_style(
+                ProgressStyle::with_template("  {spinner} {bytes} {msg}")
+                    .expect("valid template"),
+            ),
+        };
+        let bar = bars().add(bar);
+        bar.enable_steady_tick(Duration::from_millis(200));
+        Self {
+            bar,
+            started: Instant::now(),
+            records: 0,
+        }
+    }
+
+    fn hidden() -> Self {
+        Self {
+            bar: ProgressBar::hidden(),
+            started: Instant::now(),
+            records: 0,
+        }
+    }
+
+    /// `r`, advancing the bar by the bytes read from it.
+    pub fn wrap<R: Read>(&self, r: R) -> impl Read {
+        self.bar.wrap_read(r)
+    }
+
+    /// Count a parsed record.
+    pub fn record(&mut self) {
+        self.records += 1;
+        if self.records.is_multiple_of(UPDATE_INTERVAL) && !self.bar.is_hidden() {
+            let secs = self.started.elapsed().as_secs_f64().max(1e-3);
+            self.bar.set_message(format!(
+                "{} records, {:.0}/s",
+                self.records,
+                self.records as f64 / secs
+            ));
+        }
+    }
+}
+
+impl Drop for InputProgress {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+        bars().remove(&self.bar);
+    }
+}
@@ -0,0 +1,117 @@
+//! Job queue mode (`rdfsplitter queue DIR`).
+//!
+//! Every `*.toml` / `*.json` file directly inside DIR is a job descriptor with
+//! the same keys as a run profile, plus an optional `command`: `split` (the
+//! default) or `convert`.  Jobs run one after another in file-name order;
+//! afterwards the descriptor is moved to `DIR/done/` or `DIR/failed/`
+//! next to a `<descriptor>.report.json` describing the outcome.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use clap::Parser;
+use log::{info, warn};
+use serde_json::json;
+
+use crate::{
+    app::{run, run_convert_command},
+    cli::Cli,
+    profile,
+    report::RunTotals,
+};
+
+const DONE_DIR: &str = "done";
+const FAILED_DIR: &str = "failed";
+
+/// Run all queued jobs.  Returns the number of failed jobs.
+pub fn run_queue(dir: &Path) -> anyhow::Result<usize> {
+    let jobs = queued_jobs(dir)?;
+    info!("{} job(s) queued in {}", jobs.len(), dir.display());
+
+    let mut failed = 0usize;
+    for job in &jobs {
+        info!("Job {}", job.display());
+        let report = job_report(run_job(job));
+        let ok = report["status"] == "done";
+        if !ok {
+            warn!("Job {} failed", job.display());
+            failed += 1;
+        }
+        file_job(dir, job, if ok { DONE_DIR } else { FAILED_DIR }, &report)?;
+    }
+
+    info!("Queue done. {} job(s), {} failed.", jobs.len(), failed);
+    Ok(failed)
+}
+
+/// The outcome of a job: `done` when every input was split, `failed`
+/// otherwise.
+pub fn job_report(result: anyhow::Result<RunTotals>) -> serde_json::Value {
+    match result {
+        Ok(totals) if totals.errors == 0 => json!({
+            "status": "done",
+            "files": totals.files,
+            "records": totals.records,
+            "chunks": totals.chunks_json(),
+            "skipped": totals.skipped_json(),
+        }),
+        Ok(totals) => json!({
+            "status": "failed",
+            "files": totals.files,
+            "records": totals.records,
+            "errors": totals.errors,
+            "chunks": totals.chunks_json(),
+            "skipped": totals.skipped_json(),
+        }),
+        Err(e) => json!({ "status": "failed", "error": format!("{e:#}") }),
+    }
+}
+
+fn queued_jobs(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut jobs = Vec::new();
+    for entry in fs::read_dir(dir)
+        .with_context(|| format!("cannot read queue directory '{}'", dir.display()))?
+    {
+        let path = entry?.path();
+        let is_job = path.extension().is_some_and(|e| e == "toml" || e == "json");
+        if path.is_file() && is_job {
+            jobs.push(path);
+        }
+    }
+    jobs.sort();
+    Ok(jobs)
+}
+
+fn run_job(job: &Path) -> anyhow::Result<RunTotals> {
+    let args = profile::job_args(job)?;
+    let cli = Cli::try_parse_from(std::iter::once("rdfsplitter".into()).chain(args))
+        .with_context(|| format!("job '{}'", job.display()))?;
+    if let Some(converted) = run_convert_command(&cli) {
+        return Ok(converted?.into());
+    }
+    match cli.command {
+        None => Ok(run(cli)?),
+        Some(_) => bail!("job '{}': a job runs split or convert", job.display()),
+    }
+}
+
+/// Move `job` into `dir/<target>/` and write its report alongside.
+fn file_job(
+    dir: &Path,
+    job: &Path,
+    target: &str,
+    report: &serde_json::Value,
+) -> anyhow::Result<()> {
+    let target = dir.join(target);
+    fs::create_dir_all(&target)?;
+    let name = job.file_name().expect("job descriptors are files");
+    let report_path = target.join(format!("{}.report.json", name.to_string_lossy()));
+    let mut report = report.clone();
+    report["job"] = json!(name.to_string_lossy());
+    fs::write(&report_path, serde_json::to_string_pretty(&report)? + "\n")?;
+    fs::rename(job, target.join(name))?;
+    Ok(())
+}
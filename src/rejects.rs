@@ -0,0 +1,123 @@
+//! Lenient N-Triples / N-Quads parsing (`--skip-bad-records`).
+//!
+//! Real-world dumps are rarely perfectly clean: one line with an unescaped
+//! quote or a space in an IRI should not abort the split of the other
+//! hundred million.  With `--skip-bad-records` line-based inputs are parsed
+//! line by line and a line that does not parse is logged with its input
+//! and line number and passed over.  `--rejects FILE` collects those lines,
+//! each after a comment saying where it came from and why it was rejected,
+//! so they can be fixed and loaded on their own.  A limit `N` still fails
+//! an input with more than `N` bad lines: that many is more likely a wrong
+//! format than a dirty dump.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{format::SplitterError, paths, splitter::check_overwrite};
+
+/// The bad lines of a run and where they are collected.
+pub struct BadRecords {
+    limit: Option<usize>,
+    rejects: Option<(PathBuf, BufWriter<File>)>,
+    /// Lines passed over so far, in all inputs.
+    pub skipped: usize,
+}
+
+impl BadRecords {
+    /// Pass over at most `limit` bad lines per input (any number for
+    /// `None`), collecting them in `rejects` if given.
+    pub fn new(
+        limit: Option<usize>,
+        rejects: Option<&Path>,
+        force: bool,
+        file_mode: Option<u32>,
+    ) -> Result<Self, SplitterError> {
+        let rejects = match rejects {
+            Some(path) => {
+                check_overwrite(path, force)?;
+                let file = File::create(path)?;
+                paths::set_mode(path, file_mode)?;
+                Some((path.to_path_buf(), BufWriter::new(file)))
+            }
+            None => None,
+        };
+        Ok(Self {
+            limit,
+            rejects,
+            skipped: 0,
+        })
+    }
+
+    /// Pass over `line`, line `number` of `input`, which does not parse
+    /// because of `error`; `in_input` is the count of bad lines of the input
+    /// so far, this one included.  Fails once it exceeds the limit.
+    pub fn reject(
+        &mut self,
+        input: &Path,
+        number: u64,
+        line: &[u8],
+        error: &str,
+        in_input: usize,
+    ) -> Result<(), String> {
+        if let Some(limit) = self.limit.filter(|&limit| in_input > limit) {
+            return Err(format!(
+                "line {number}: {error}; more than {limit} bad record(s) (--skip-bad-records)"
+            ));
+        }
+        let error = error.trim().replace(['\r', '\n'], " ");
+        log::warn!("{}: line {number} skipped: {error}", input.display());
+        self.skipped += 1;
+        if let Some((path, w)) = &mut self.rejects {
+            let written = writeln!(w, "# {} line {number}: {error}", input.display())
+                .and_then(|()| w.write_all(line))
+                .and_then(|()| match line.last() {
+                    Some(b'\n') => Ok(()),
+                    _ => w.write_all(b"\n"),
+                });
+            written.map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Flush the rejects file, if any.
+    pub fn finish(&mut self) -> io::Result<()> {
+        match &mut self.rejects {
+            Some((_, w)) => w.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Where the bad lines are collected.
+    pub fn rejects_path(&self) -> Option<&Path> {
+        self.rejects.as_ref().map(|(path, _)| path.as_path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_bad_lines_up_to_the_limit() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("rejects.nt");
+        let input = Path::new("data.nt");
+        let mut bad = BadRecords::new(Some(1), Some(&path), false, None).unwrap();
+        bad.reject(input, 3, b"<a> <b> .\n", "expected object\n", 1)
+            .unwrap();
+        let err = bad
+            .reject(input, 7, b"<a> <b>", "expected object", 2)
+            .unwrap_err();
+        assert!(err.contains("more than 1 bad record(s)"), "{err}");
+        bad.finish().unwrap();
+        assert_eq!(bad.skipped, 1);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "# data.nt line 3: expected object\n<a> <b> .\n"
+        );
+        assert!(BadRecords::new(None, Some(&path), false, None).is_err());
+    }
+}
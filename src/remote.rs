@@ -0,0 +1,184 @@
+//! Remote inputs (`http://…`, `https://…`).
+//!
+//! An input argument that is an HTTP(S) URL is streamed through the parser
+//! while it downloads, so a public dump is split without being saved first.
+//! The download is done by the `curl` command-line tool, as `--encrypt`
+//! leaves encryption to `age` or `gpg`.  The format comes from `--format`,
+//! else from the `Content-Type` the server answers a HEAD request with,
+//! which names what it actually sends, else from the extension of the last
+//! path segment of the URL (`dump.nt.gz`); `--trust-extension` skips the
+//! request when the extension is known.  A remote input is
+//! read once: options needing a second pass are refused, as for standard
+//! input.
+
+use std::{
+    borrow::Cow,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+    process::{Child, ChildStdout, Command, Stdio},
+};
+
+const PROGRAM: &str = "curl";
+
+/// True for an `http://` or `https://` input.
+pub fn is_remote(input: &Path) -> bool {
+    input
+        .to_str()
+        .is_some_and(|s| s.starts_with("http://") || s.starts_with("https://"))
+}
+
+/// The name the input goes by for format detection and chunk names: the
+/// last path segment of a URL, without query or fragment; other inputs as
+/// they are.
+pub fn file_name(input: &Path) -> Cow<'_, Path> {
+    let Some(url) = input.to_str().filter(|_| is_remote(input)) else {
+        return Cow::Borrowed(input);
+    };
+    let (_, rest) = url.split_once("://").unwrap_or_default();
+    let path = rest.split(['?', '#']).next().unwrap_or_default();
+    let segment = match path.split_once('/') {
+        Some((_, path)) => path.rsplit('/').next().unwrap_or_default(),
+        None => "",
+    };
+    Cow::Owned(Path::new(if segment.is_empty() { "index" } else { segment }).to_path_buf())
+}
+
+/// Fail early when `curl` is not installed.
+pub fn check_tool() -> io::Result<()> {
+    let status = Command::new(PROGRAM)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("'{PROGRAM}' is required by URL inputs: {e}"),
+            )
+        })?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "'{PROGRAM} --version' failed ({status})"
+        )));
+    }
+    Ok(())
+}
+
+/// The `Content-Type` of `url`, from a HEAD request; empty when the server
+/// sends none.
+pub fn content_type(url: &str) -> io::Result<String> {
+    let output = Command::new(PROGRAM)
+        .args(["--silent", "--show-error", "--fail", "--location", "--head"])
+        .args(["--output", if cfg!(windows) { "NUL" } else { "/dev/null" }])
+        .args(["--write-out", "%{content_type}", "--", url])
+        .stderr(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "HEAD {url} failed ({})",
+            output.status
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The whole response body of `url`, for small documents such as JSON-LD
+/// contexts; `accept` is sent as the `Accept` header.
+pub fn fetch(url: &str, accept: &str) -> io::Result<String> {
+    let output = Command::new(PROGRAM)
+        .args(["--silent", "--show-error", "--fail", "--location", "--compressed"])
+        .args(["--header", &format!("Accept: {accept}"), "--", url])
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("cannot run '{PROGRAM}': {e}")))?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "download of {url} failed ({})",
+            output.status
+        )));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{url}: {e}")))
+}
+
+/// The response body of `url`, streamed from a `curl` child process.  A
+/// failed download (HTTP error, broken connection) ends the stream with an
+/// error instead of a silently truncated input.
+pub fn open(url: &str) -> io::Result<Box<dyn BufRead>> {
+    let mut child = Command::new(PROGRAM)
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--location",
+            "--compressed",
+        ])
+        .args(["--", url])
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("cannot run '{PROGRAM}': {e}")))?;
+    let stdout = child.stdout.take().expect("piped stdout");
+    Ok(Box::new(BufReader::new(Download {
+        url: url.to_owned(),
+        child,
+        stdout,
+        done: false,
+    })))
+}
+
+/// The body of a download in progress; its end checks how `curl` exited.
+struct Download {
+    url: String,
+    child: Child,
+    stdout: ChildStdout,
+    done: bool,
+}
+
+impl Read for Download {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done {
+            return Ok(0);
+        }
+        let n = self.stdout.read(buf)?;
+        if n == 0 && !buf.is_empty() {
+            self.done = true;
+            let status = self.child.wait()?;
+            if !status.success() {
+                return Err(io::Error::other(format!(
+                    "download of {} failed ({status})",
+                    self.url
+                )));
+            }
+        }
+        Ok(n)
+    }
+}
+
+impl Drop for Download {
+    fn drop(&mut self) {
+        // an input abandoned early (parse error, sniffing) stops the download
+        if !self.done {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_inputs_by_the_last_url_segment() {
+        let name = |s: &str| file_name(Path::new(s)).into_owned();
+        assert!(is_remote(Path::new("https://data.europa.eu/dump.nt")));
+        assert!(!is_remote(Path::new("data/http/dump.nt")));
+        assert_eq!(
+            name("https://data.europa.eu/949/ops.nt.gz?download=1#x"),
+            Path::new("ops.nt.gz")
+        );
+        assert_eq!(name("http://example.org"), Path::new("index"));
+        assert_eq!(name("http://example.org/data/"), Path::new("index"));
+        assert_eq!(name("local/ops.ttl"), Path::new("local/ops.ttl"));
+    }
+}
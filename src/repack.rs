@@ -0,0 +1,155 @@
+//! Re-chunking an existing chunk set (`rdfsplitter repack`).
+//!
+//! The chunks in a directory are grouped into their series, by the name
+//! before `_NNNN`, their format and whether they are gzipped.  Every series
+//! is read back as one stream, in chunk order, and written again as
+//! `<series>_NNNN.<ext>` chunks of at most `--max-bytes`: small chunks are
+//! merged and oversized ones cut, without going back to the original
+//! dumps.  Encrypted chunks cannot be read and are left out.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{error, info, warn};
+use regex::Regex;
+
+use crate::{
+    compress::Compression,
+    format::{is_gzip, RdfFormat, SplitterError},
+    splitter::{merge_series, SplitOptions},
+};
+
+/// A series of chunks: name, format extension and compression.
+type Series = (String, &'static str, bool);
+
+/// Repack the chunks in `dir` into `opts.output_dir`, in chunks of at most
+/// `opts.max_bytes`.  Returns the number of series that failed.
+pub fn run_repack(dir: &Path, opts: SplitOptions) -> Result<usize, SplitterError> {
+    if same_dir(dir, &opts.output_dir) {
+        return Err(SplitterError::Other(anyhow::anyhow!(
+            "repacking into {} would overwrite the chunks being read; choose another -o",
+            dir.display()
+        )));
+    }
+    let series = chunk_series(dir)?;
+    if series.is_empty() {
+        return Err(SplitterError::Parse(format!(
+            "no chunks found in {}",
+            dir.display()
+        )));
+    }
+    let mut opts = SplitOptions {
+        chunk_size: usize::MAX,
+        ..opts
+    };
+    let mut failed = 0usize;
+    for ((name, _, gzipped), chunks) in &series {
+        opts.compress = gzipped.then_some(Compression::Gzip);
+        match merge_series(chunks, name, &opts) {
+            Ok(summary) => info!(
+                "{name}: {} chunk(s) → {} chunk(s), {} record(s)",
+                chunks.len(),
+                summary.chunks.len(),
+                summary.records
+            ),
+            Err(e) => {
+                error!("{name}: {e}");
+                failed += 1;
+            }
+        }
+    }
+    Ok(failed)
+}
+
+/// The chunk files in `dir` by series, each in chunk order.
+fn chunk_series(dir: &Path) -> Result<BTreeMap<Series, Vec<(PathBuf, RdfFormat)>>, SplitterError> {
+    let re = Regex::new(r"^(.+)_(\d{4,})\.[^.]+(?:\.[A-Za-z0-9]+)*$").expect("valid pattern");
+    let mut series: BTreeMap<Series, Vec<(u64, PathBuf, RdfFormat)>> = BTreeMap::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(caps) = re.captures(&name) else {
+            continue;
+        };
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        match RdfFormat::from_path(&path) {
+            Some(fmt) if !fmt.is_sparql_results() => {
+                let key = (caps[1].to_owned(), fmt.extension(), is_gzip(&path));
+                let index = caps[2].parse().unwrap_or(u64::MAX);
+                series.entry(key).or_default().push((index, path, fmt));
+            }
+            // sidecars (`.lines.tsv`, `.sha256`) and result sets are not chunks
+            Some(_) => {}
+            None if name.ends_with(".age") || name.ends_with(".gpg") => {
+                warn!("{}: encrypted, left out", path.display());
+            }
+            None => {}
+        }
+    }
+    // numbered past 9999, `data_10000` sorts after `data_9999`
+    Ok(series
+        .into_iter()
+        .map(|(key, mut chunks)| {
+            chunks.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+            (
+                key,
+                chunks
+                    .into_iter()
+                    .map(|(_, path, fmt)| (path, fmt))
+                    .collect(),
+            )
+        })
+        .collect())
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_chunks_by_series_format_and_compression() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for name in [
+            "data_10000.nt",
+            "data_0001.nt",
+            "data_0000.nt",
+            "data_hot_0000.nt",
+            "data_0000.nt.gz",
+            "data_0000.ttl",
+            "data_0000.lines.tsv",
+            "notes.txt",
+        ] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        let series = chunk_series(dir.path()).unwrap();
+        let keys: Vec<_> = series
+            .keys()
+            .map(|(n, ext, gz)| (n.as_str(), *ext, *gz))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                ("data", "nt", false),
+                ("data", "nt", true),
+                ("data", "ttl", false),
+                ("data_hot", "nt", false),
+            ]
+        );
+        let plain = &series[&("data".to_owned(), "nt", false)];
+        let names: Vec<_> = plain.iter().map(|(p, _)| p.file_name().unwrap()).collect();
+        assert_eq!(names, ["data_0000.nt", "data_0001.nt", "data_10000.nt"]);
+    }
+}
@@ -0,0 +1,379 @@
+//! What a split run reports: a line of JSON per input (`--json`,
+//! `--stdin-list`), the totals, and the reports and log lines written once
+//! every input is split.
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime},
+};
+
+use log::{error, info};
+
+use crate::{
+    cli::Cli,
+    convert::ConvertSummary,
+    distribution::{histogram, Distribution},
+    fragments::FragmentDescription,
+    inputs::SkippedInput,
+    links::LinkIndex,
+    manifest::Manifest,
+    metadata::DatasetMetadata,
+    paths,
+    rocrate::RoCrate,
+    splitter::SplitSummary,
+    SplitOptions, SplitterError,
+};
+
+/// The outcome of one input (or `--merge` group), printed as a line of JSON
+/// by `--json` and `--stdin-list`.
+pub struct InputReport {
+    input: PathBuf,
+    /// The inputs of a `--merge` group.
+    merged: Vec<PathBuf>,
+    records: usize,
+    /// Path, records and bytes of every chunk written.
+    chunks: Vec<(PathBuf, usize, u64)>,
+    /// Why the input failed.
+    error: Option<String>,
+    seconds: f64,
+}
+
+impl InputReport {
+    fn done(input: &Path, merged: Vec<PathBuf>, summary: &SplitSummary, started: Instant) -> Self {
+        let chunks = summary.chunks.iter().map(|c| (c.path.clone(), c.records, c.bytes));
+        Self {
+            input: input.to_path_buf(),
+            merged,
+            records: summary.records,
+            chunks: chunks.collect(),
+            error: None,
+            seconds: started.elapsed().as_secs_f64(),
+        }
+    }
+
+    /// An input (or `--merge` group) that failed with `error`.
+    pub fn failed(input: &Path, error: &dyn Display, started: Instant) -> Self {
+        Self {
+            input: input.to_path_buf(),
+            merged: Vec::new(),
+            records: 0,
+            chunks: Vec::new(),
+            error: Some(error.to_string()),
+            seconds: started.elapsed().as_secs_f64(),
+        }
+    }
+
+    /// True when the input was split.
+    pub fn is_done(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// `"type": "input"`, the `input`, `status` `done` with the `records`,
+    /// the `chunks` written and the `inputs` of a `--merge` group, or
+    /// `failed` with the `error`, and the `seconds` it took.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut line = serde_json::json!({
+            "type": "input",
+            "input": self.input.display().to_string(),
+        });
+        if let Some(error) = &self.error {
+            line["status"] = "failed".into();
+            line["error"] = error.as_str().into();
+        } else {
+            line["status"] = "done".into();
+            line["records"] = self.records.into();
+            line["chunks"] = self
+                .chunks
+                .iter()
+                .map(|(path, records, bytes)| {
+                    serde_json::json!({
+                        "path": path.display().to_string(),
+                        "records": records,
+                        "bytes": bytes,
+                    })
+                })
+                .collect();
+            if !self.merged.is_empty() {
+                line["inputs"] = self.merged.iter().map(|s| s.display().to_string()).collect();
+            }
+        }
+        line["seconds"] = self.seconds.into();
+        line
+    }
+}
+
+/// What a run over all inputs processed.
+pub struct RunTotals {
+    pub files: usize,
+    pub records: usize,
+    pub errors: usize,
+    /// Record count and byte size of every chunk written.
+    pub chunk_records: Vec<u64>,
+    pub chunk_bytes: Vec<u64>,
+    /// Inputs left out of the run.
+    pub skipped: Vec<SkippedInput>,
+    /// The outcome of every input split or failed.
+    pub inputs: Vec<InputReport>,
+}
+
+impl RunTotals {
+    /// Chunk size distribution for reports.
+    pub fn chunks_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "count": self.chunk_records.len(),
+            "records": Distribution::of(&self.chunk_records).map(|d| d.to_json()),
+            "bytes": Distribution::of(&self.chunk_bytes).map(|d| d.to_json()),
+        })
+    }
+
+    /// The skipped inputs for reports, with path and reason.
+    pub fn skipped_json(&self) -> serde_json::Value {
+        self.skipped.iter().map(SkippedInput::to_json).collect()
+    }
+}
+
+impl From<ConvertSummary> for RunTotals {
+    fn from(summary: ConvertSummary) -> Self {
+        Self {
+            files: summary.converted.len() + summary.failed,
+            records: summary.converted.iter().map(|c| c.records).sum(),
+            errors: summary.failed,
+            chunk_records: summary.converted.iter().map(|c| c.records as u64).collect(),
+            chunk_bytes: summary.converted.iter().map(|c| c.bytes).collect(),
+            skipped: Vec::new(),
+            inputs: Vec::new(),
+        }
+    }
+}
+
+/// The outcome of a split run so far, collected for the reports written
+/// by [`RunReport::finish`].
+pub struct RunReport {
+    json: bool,
+    merge: bool,
+    dry_run: bool,
+    link_index: LinkIndex,
+    fragments: Option<FragmentDescription>,
+    ro_crate: Option<RoCrate>,
+    manifest: Option<Manifest>,
+    // chunk-named files found before each input was split, and those written
+    preexisting: BTreeSet<PathBuf>,
+    written: HashSet<PathBuf>,
+    totals: RunTotals,
+}
+
+impl RunReport {
+    /// A run started at `started` with the inputs left out in `skipped`,
+    /// which are errors under `--strict`.
+    pub fn new(
+        cli: &Cli,
+        started: SystemTime,
+        metadata: DatasetMetadata,
+        skipped: Vec<SkippedInput>,
+    ) -> Self {
+        let mut errors = 0;
+        if cli.strict {
+            for input in &skipped {
+                error!("{}: {} (--strict)", input.path.display(), input.reason.as_str());
+            }
+            errors = skipped.len();
+        }
+        Self {
+            json: cli.json,
+            merge: cli.merge.is_some(),
+            dry_run: cli.dry_run,
+            link_index: LinkIndex::default(),
+            fragments: cli
+                .describe_fragments
+                .then(|| FragmentDescription::new(started, metadata.clone())),
+            ro_crate: cli.ro_crate.then(|| RoCrate::new(started, metadata)),
+            manifest: cli.manifest.is_some().then(|| Manifest::new(started)),
+            preexisting: BTreeSet::new(),
+            written: HashSet::new(),
+            totals: RunTotals {
+                files: 0,
+                records: 0,
+                errors,
+                chunk_records: Vec::new(),
+                chunk_bytes: Vec::new(),
+                skipped,
+                inputs: Vec::new(),
+            },
+        }
+    }
+
+    /// Errors so far.
+    pub fn errors(&self) -> usize {
+        self.totals.errors
+    }
+
+    /// An input left out of the run.
+    pub fn skipped(&mut self, input: SkippedInput) {
+        self.totals.skipped.push(input);
+    }
+
+    /// `input` (or a `--merge` group) failed with `error`.
+    pub fn failed(&mut self, input: &Path, error: &dyn Display, started: Instant) {
+        error!("{}: {error}", input.display());
+        self.totals.errors += 1;
+        self.push(InputReport::failed(input, error, started));
+    }
+
+    /// `input` (or a `--merge` group of `sources`) was split into the
+    /// chunks of `summary`; `existing` are the files named like them found
+    /// before.
+    pub fn done(
+        &mut self,
+        input: &Path,
+        sources: Vec<PathBuf>,
+        existing: Vec<PathBuf>,
+        summary: SplitSummary,
+        started: Instant,
+    ) {
+        self.totals.records += summary.records;
+        self.totals.files += sources.len();
+        self.preexisting.extend(existing.into_iter().map(paths::output_path));
+        if let Some(ro_crate) = &mut self.ro_crate {
+            // the chunks are listed once, with the first input
+            for (i, source) in sources.iter().enumerate() {
+                ro_crate.add(source, if i == 0 { &summary.chunks } else { &[] });
+            }
+        }
+        if let Some(fragments) = &mut self.fragments {
+            fragments.add(&sources, &summary.chunks);
+        }
+        if let Some(manifest) = &mut self.manifest {
+            manifest.add(&sources, &summary.chunks);
+        }
+        let merged = if self.merge { sources } else { Vec::new() };
+        self.push(InputReport::done(input, merged, &summary, started));
+        for chunk in summary.chunks {
+            log::debug!(
+                "  {}: {} record(s), {} byte(s)",
+                chunk.path.display(),
+                chunk.records,
+                chunk.bytes
+            );
+            if self.dry_run {
+                println!("{}\t{}\t{}", chunk.path.display(), chunk.records, chunk.bytes);
+            }
+            self.totals.chunk_records.push(chunk.records as u64);
+            self.totals.chunk_bytes.push(chunk.bytes);
+            self.written.insert(chunk.path.clone());
+            if let Some(links) = chunk.links {
+                self.link_index.add(chunk.path, links);
+            }
+        }
+    }
+
+    fn push(&mut self, line: InputReport) {
+        if self.json {
+            println!("{}", line.to_json());
+        }
+        self.totals.inputs.push(line);
+    }
+
+    /// Deal with stale chunk files, write the reports asked for and log
+    /// what the run did.
+    pub fn finish(self, cli: &Cli, opts: &SplitOptions) -> Result<RunTotals, SplitterError> {
+        let stale: Vec<PathBuf> =
+            self.preexisting.into_iter().filter(|p| !self.written.contains(p)).collect();
+        if !stale.is_empty() {
+            if cli.clean_stale && !cli.dry_run {
+                for path in &stale {
+                    log::debug!("  removing stale {}", path.display());
+                    fs::remove_file(path)?;
+                }
+                info!("Removed {} stale chunk file(s) of earlier runs", stale.len());
+            } else {
+                for path in &stale {
+                    log::warn!("  {}", path.display());
+                }
+                log::warn!(
+                    "{} file(s) named like this run's chunks were not written by it; the chunk \
+                     set is mixed with stale files (--clean-stale deletes them)",
+                    stale.len()
+                );
+            }
+        }
+
+        if let Some(report) = &cli.link_report {
+            let dangling = self.link_index.write_csv(report)?;
+            if dangling > 0 {
+                log::warn!(
+                    "{} object reference(s) point into other chunks; see {}",
+                    dangling,
+                    report.display()
+                );
+            }
+        }
+
+        if let Some(path) = &cli.load_order {
+            self.link_index.write_load_order(path)?;
+        }
+
+        if let Some(ro_crate) = &self.ro_crate {
+            let path = ro_crate.write(opts, SystemTime::now())?;
+            info!("RO-Crate metadata written to {}", path.display());
+        }
+
+        if let Some(fragments) = &self.fragments {
+            let path = fragments.write(opts, SystemTime::now())?;
+            info!("Fragment description written to {}", path.display());
+        }
+
+        if let Some((manifest, path)) = self.manifest.as_ref().zip(cli.manifest.as_ref()) {
+            manifest.write(path, opts, SystemTime::now())?;
+            info!("Manifest written to {}", path.display());
+        }
+
+        if let Some(seen) = &opts.seen {
+            info!("{} duplicate statement(s) dropped", seen.borrow().duplicates);
+        }
+
+        if let Some(bad) = &opts.bad_records {
+            let mut bad = bad.borrow_mut();
+            bad.finish()?;
+            match bad.rejects_path() {
+                Some(path) => {
+                    info!("{} bad record(s) skipped; see {}", bad.skipped, path.display())
+                }
+                None => info!("{} bad record(s) skipped", bad.skipped),
+            }
+        }
+
+        let totals = self.totals;
+        log_chunk_distribution(&totals.chunk_records, &totals.chunk_bytes);
+
+        info!(
+            "Done. {} file(s) processed, {} triple/quad(s) total, {} error(s).",
+            totals.files, totals.records, totals.errors
+        );
+        if !totals.skipped.is_empty() {
+            info!("{} input(s) skipped.", totals.skipped.len());
+        }
+        if cli.dry_run {
+            info!("Dry run: {} chunk(s) planned, no file written.", totals.chunk_records.len());
+        }
+        Ok(totals)
+    }
+}
+
+/// Summarise how balanced the chunks of this run are.
+fn log_chunk_distribution(records: &[u64], bytes: &[u64]) {
+    let (Some(r), Some(b)) = (Distribution::of(records), Distribution::of(bytes)) else {
+        return;
+    };
+    info!("Chunk records: {r}");
+    info!("Chunk bytes:   {b}");
+    if r.count > 1 {
+        let widest = histogram(records, 8).iter().map(|b| b.2).max().unwrap_or(1);
+        for (lo, hi, n) in histogram(records, 8) {
+            let bar = "#".repeat((n * 40).div_ceil(widest));
+            info!("  {lo:>10} – {hi:<10} {n:>6} {bar}");
+        }
+    }
+}
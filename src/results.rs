@@ -0,0 +1,553 @@
+//! SPARQL Query Results (JSON `.srj` / XML `.srx`) splitting.
+//!
+//! Result files are not RDF, but they are produced by the same pipelines and
+//! grow just as large.  Each chunk repeats the `head` (variable list) and
+//! carries a slice of the `bindings` / `<result>` elements, so every chunk is
+//! a valid results document of the same format.  Both formats are read row
+//! by row, so a results file is split without holding it in memory.
+
+use std::{
+    fmt, fs,
+    io::{self, BufRead, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use log::debug;
+use quick_xml::events::Event;
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::{json, Value};
+
+use crate::{
+    compress::ChunkFile,
+    format::{RdfFormat, SplitterError},
+    splitter::{
+        chunk_path, create_chunk_file, finish_chunk_file, open_input, SplitOptions, SplitSummary,
+    },
+};
+
+/// Count the result rows (bindings) in a SPARQL results file.
+pub fn count_results(input: &Path, fmt: RdfFormat) -> Result<usize, SplitterError> {
+    let reader = open_input(input)?;
+    let mut n = 0usize;
+    match fmt {
+        RdfFormat::SparqlJson => read_json(reader, &mut |_, _| {
+            n += 1;
+            Ok(())
+        })?,
+        RdfFormat::SparqlXml => read_xml(reader, &mut |_, _| {
+            n += 1;
+            Ok(())
+        })?,
+        _ => unreachable!(),
+    }
+    Ok(n)
+}
+
+/// Split a SPARQL results file into chunks of `opts.chunk_size` rows.
+pub fn split_results(
+    input: &Path,
+    fmt: RdfFormat,
+    opts: &SplitOptions,
+) -> Result<SplitSummary, SplitterError> {
+    let reader = open_input(input)?;
+    let mut chunks = RowChunks::new(input, fmt, opts);
+    let read = match fmt {
+        RdfFormat::SparqlJson => {
+            let mut frame = None;
+            read_json(reader, &mut |head, row| {
+                let frame = frame.get_or_insert_with(|| json_frame(head));
+                chunks.push(frame, row.to_string().as_bytes())
+            })
+        }
+        RdfFormat::SparqlXml => read_xml(reader, &mut |frame, row| chunks.push(frame, row)),
+        _ => unreachable!(),
+    };
+    match read {
+        Ok(()) => chunks.finish(),
+        Err(e) => {
+            chunks.abandon();
+            Err(e)
+        }
+    }
+}
+
+/// What a results chunk holds besides its rows.
+struct Frame {
+    /// Everything before the first row.
+    open: Vec<u8>,
+    /// Written before the first row.
+    first_lead: &'static str,
+    /// Written before every other row.
+    lead: &'static str,
+    /// Everything after the last row.
+    close: Vec<u8>,
+}
+
+/// Result chunks being written, cut after `chunk_size` rows and, with
+/// `max_bytes`, before the rows would exceed that many bytes.
+struct RowChunks<'a> {
+    input: &'a Path,
+    fmt: RdfFormat,
+    opts: &'a SplitOptions,
+    open: Option<OpenRows>,
+    summary: SplitSummary,
+}
+
+/// The chunk being written, with the rows and bytes of rows it holds.
+struct OpenRows {
+    file: ChunkFile,
+    path: PathBuf,
+    close: Vec<u8>,
+    rows: usize,
+    bytes: u64,
+}
+
+impl<'a> RowChunks<'a> {
+    fn new(input: &'a Path, fmt: RdfFormat, opts: &'a SplitOptions) -> Self {
+        Self {
+            input,
+            fmt,
+            opts,
+            open: None,
+            summary: SplitSummary { records: 0, chunks: Vec::new() },
+        }
+    }
+
+    /// Append the raw text of a row, opening a chunk with `frame` first.
+    fn push(&mut self, frame: &Frame, row: &[u8]) -> Result<(), SplitterError> {
+        let n = row.len() as u64;
+        if let Some(open) = &self.open {
+            if is_full(open.rows, open.bytes, n, self.opts) {
+                self.close()?;
+            }
+        }
+        if self.open.is_none() {
+            self.open = Some(self.start(frame)?);
+        }
+        let open = self.open.as_mut().expect("a chunk is open");
+        let lead = if open.rows == 0 { frame.first_lead } else { frame.lead };
+        open.file.write_all(lead.as_bytes())?;
+        open.file.write_all(row)?;
+        open.rows += 1;
+        open.bytes += n;
+        self.summary.records += 1;
+        Ok(())
+    }
+
+    fn start(&self, frame: &Frame) -> Result<OpenRows, SplitterError> {
+        let chunk = self.summary.chunks.len();
+        let path = chunk_path(self.input, self.fmt, "", chunk, self.opts);
+        debug!("  writing chunk {} → {}", chunk, path.display());
+        let mut file = create_chunk_file(self.input, &path, "", chunk, self.opts)?;
+        file.write_all(&frame.open)?;
+        Ok(OpenRows { file, path, close: frame.close.clone(), rows: 0, bytes: 0 })
+    }
+
+    fn close(&mut self) -> Result<(), SplitterError> {
+        if let Some(mut open) = self.open.take() {
+            open.file.write_all(&open.close)?;
+            let info = finish_chunk_file(open.file, open.path, open.rows, self.opts)?;
+            self.summary.chunks.push(info);
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<SplitSummary, SplitterError> {
+        self.close()?;
+        Ok(self.summary)
+    }
+
+    /// Remove the chunk still open after a failed read.  Completed chunks
+    /// are kept, as for RDF inputs.
+    fn abandon(self) {
+        if let Some(open) = self.open {
+            drop(open.file);
+            if self.opts.stdout.is_none() && !self.opts.dry_run {
+                let _ = fs::remove_file(&open.path);
+            }
+        }
+    }
+}
+
+/// True when a chunk holding `rows` rows of `bytes` bytes must be closed
+/// before a row of `next` bytes.
+fn is_full(rows: usize, bytes: u64, next: u64, opts: &SplitOptions) -> bool {
+    rows > 0 && (rows >= opts.chunk_size || opts.max_bytes.is_some_and(|max| bytes + next > max))
+}
+
+// ─── JSON ────────────────────────────────────────────────────────────────────
+
+/// Receives each binding of a JSON document with its `head`.
+type JsonRow<'r> = dyn FnMut(&Value, Value) -> Result<(), SplitterError> + 'r;
+
+/// Receives the raw text of each `<result>` with the chunk frame.
+type XmlRow<'r> = dyn FnMut(&Frame, &[u8]) -> Result<(), SplitterError> + 'r;
+
+/// Chunks of a JSON document: its `head` and the `bindings` array.
+fn json_frame(head: &Value) -> Frame {
+    Frame {
+        open: format!(r#"{{"head":{head},"results":{{"bindings":["#).into_bytes(),
+        first_lead: "",
+        lead: ",",
+        close: b"]}}\n".to_vec(),
+    }
+}
+
+/// Read a SPARQL JSON results document binding by binding, passing each to
+/// `row` with the document's `head`.  Bindings read before the head (a
+/// document listing `results` first) wait for it.
+fn read_json(reader: impl BufRead, row: &mut JsonRow) -> Result<(), SplitterError> {
+    let mut doc = JsonDocument {
+        row,
+        head: None,
+        waiting: Vec::new(),
+        bindings: false,
+        boolean: false,
+        failed: None,
+    };
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let read = (&mut doc).deserialize(&mut de).and_then(|()| de.end());
+    if let Some(e) = doc.failed.take() {
+        return Err(e);
+    }
+    read.map_err(|e| SplitterError::Parse(e.to_string()))?;
+    if doc.boolean {
+        return Err(SplitterError::Parse(
+            "ASK results have no bindings to split".into(),
+        ));
+    }
+    if !doc.bindings {
+        return Err(SplitterError::Parse(
+            "missing 'results.bindings' array".into(),
+        ));
+    }
+    let head = doc.head.take().unwrap_or_else(|| json!({}));
+    for binding in std::mem::take(&mut doc.waiting) {
+        (doc.row)(&head, binding)?;
+    }
+    Ok(())
+}
+
+/// What [`read_json`] has read so far.
+struct JsonDocument<'r> {
+    row: &'r mut JsonRow<'r>,
+    head: Option<Value>,
+    /// Bindings read before the head.
+    waiting: Vec<Value>,
+    bindings: bool,
+    /// Whether the document is an ASK result.
+    boolean: bool,
+    /// The error of `row` that stopped reading.
+    failed: Option<SplitterError>,
+}
+
+impl JsonDocument<'_> {
+    fn binding<E: de::Error>(&mut self, binding: Value) -> Result<(), E> {
+        let Some(head) = &self.head else {
+            self.waiting.push(binding);
+            return Ok(());
+        };
+        (self.row)(head, binding).map_err(|e| {
+            self.failed = Some(e);
+            E::custom("stopped")
+        })
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for &mut JsonDocument<'_> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, d: D) -> Result<(), D::Error> {
+        d.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for &mut JsonDocument<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a SPARQL results object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "head" => {
+                    self.head = Some(map.next_value()?);
+                    for binding in std::mem::take(&mut self.waiting) {
+                        self.binding(binding)?;
+                    }
+                }
+                "results" => map.next_value_seed(JsonResults(&mut *self))?,
+                "boolean" => {
+                    self.boolean = true;
+                    map.next_value::<IgnoredAny>()?;
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `results` object, whose `bindings` are read one at a time.
+struct JsonResults<'a, 'r>(&'a mut JsonDocument<'r>);
+
+impl<'de> DeserializeSeed<'de> for JsonResults<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, d: D) -> Result<(), D::Error> {
+        d.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for JsonResults<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a 'results' object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "bindings" {
+                self.0.bindings = true;
+                map.next_value_seed(JsonBindings(&mut *self.0))?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `bindings` array.
+struct JsonBindings<'a, 'r>(&'a mut JsonDocument<'r>);
+
+impl<'de> DeserializeSeed<'de> for JsonBindings<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, d: D) -> Result<(), D::Error> {
+        d.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for JsonBindings<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a 'bindings' array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(binding) = seq.next_element::<Value>()? {
+            self.0.binding(binding)?;
+        }
+        Ok(())
+    }
+}
+
+// ─── XML ─────────────────────────────────────────────────────────────────────
+
+/// Read a SPARQL XML results document `<result>` by `<result>`, passing the
+/// raw text of each to `row` with the frame of the chunks: the document up to
+/// and including the `<results>` start tag, and end tags for the elements
+/// open there, named and indented as in the document.
+fn read_xml(reader: impl BufRead, row: &mut XmlRow) -> Result<(), SplitterError> {
+    let mut reader = quick_xml::Reader::from_reader(Recorder::new(reader));
+    let mut buf = Vec::new();
+    // the elements open before `<results>`, with the indentation of their
+    // start tags
+    let mut open: Vec<(String, String)> = Vec::new();
+    let mut indent = String::new();
+    let mut frame: Option<Frame> = None;
+    let mut result_start: Option<u64> = None;
+
+    loop {
+        let before = reader.buffer_position();
+        buf.clear();
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| SplitterError::Parse(e.to_string()))?;
+        let after = reader.buffer_position();
+        match (&frame, event) {
+            (None, Event::Text(text)) => {
+                let text = text.rsplit(|&b| b == b'\n').next().unwrap_or_default();
+                indent = if text.iter().all(u8::is_ascii_whitespace) {
+                    String::from_utf8_lossy(text).into_owned()
+                } else {
+                    String::new()
+                };
+            }
+            (None, Event::Start(e)) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+                open.push((name, std::mem::take(&mut indent)));
+                if e.local_name().as_ref() == b"results" {
+                    let prologue = reader.get_mut().take(0, after);
+                    frame = Some(xml_frame(prologue, &open));
+                }
+            }
+            (None, Event::End(_)) => {
+                open.pop();
+                indent.clear();
+            }
+            (None, Event::Empty(e)) if e.local_name().as_ref() == b"results" => {
+                // `<results/>`: no rows at all
+                return Ok(());
+            }
+            (Some(_), Event::Start(e)) if e.local_name().as_ref() == b"result" => {
+                result_start = Some(before);
+            }
+            (Some(frame), Event::End(e)) if e.local_name().as_ref() == b"result" => {
+                if let Some(start) = result_start.take() {
+                    row(frame, &reader.get_mut().take(start, after))?;
+                }
+            }
+            (Some(frame), Event::Empty(e)) if e.local_name().as_ref() == b"result" => {
+                row(frame, &reader.get_mut().take(before, after))?;
+            }
+            (_, Event::Eof) => break,
+            _ => {}
+        }
+        if frame.is_some() && result_start.is_none() {
+            reader.get_mut().forget(after);
+        }
+    }
+
+    match frame {
+        Some(_) => Ok(()),
+        None => Err(SplitterError::Parse(
+            "missing <results> element (ASK results cannot be split)".into(),
+        )),
+    }
+}
+
+/// Chunks of an XML document: its `prologue` and end tags for the `open`
+/// elements, innermost first.
+fn xml_frame(prologue: Vec<u8>, open: &[(String, String)]) -> Frame {
+    let mut close = String::new();
+    for (name, indent) in open.iter().rev() {
+        close.push_str(&format!("\n{indent}</{name}>"));
+    }
+    close.push('\n');
+    Frame {
+        open: prologue,
+        first_lead: "\n    ",
+        lead: "\n    ",
+        close: close.into_bytes(),
+    }
+}
+
+/// A reader keeping the bytes read through it, from the offset `start` of
+/// the input on, so the raw text between two reader positions can be taken.
+struct Recorder<R> {
+    inner: R,
+    kept: Vec<u8>,
+    start: u64,
+}
+
+impl<R: BufRead> Recorder<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, kept: Vec::new(), start: 0 }
+    }
+
+    /// The bytes from offset `from` to `to`; those before `to` are dropped.
+    fn take(&mut self, from: u64, to: u64) -> Vec<u8> {
+        let text = self.kept[(from - self.start) as usize..(to - self.start) as usize].to_vec();
+        self.forget(to);
+        text
+    }
+
+    /// Drop the bytes before offset `to`.
+    fn forget(&mut self, to: u64) {
+        self.kept.drain(..(to - self.start) as usize);
+        self.start = to;
+    }
+}
+
+impl<R: BufRead> Read for Recorder<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(out.len());
+        out[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Recorder<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, n: usize) {
+        if n > 0 {
+            if let Ok(available) = self.inner.fill_buf() {
+                self.kept.extend_from_slice(&available[..n]);
+            }
+        }
+        self.inner.consume(n);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cuts_by_rows_and_bytes() {
+        let opts = SplitOptions {
+            chunk_size: 2,
+            max_bytes: Some(6),
+            ..Default::default()
+        };
+        assert!(!is_full(0, 0, 10, &opts));
+        assert!(!is_full(1, 4, 2, &opts));
+        assert!(is_full(2, 6, 1, &opts));
+        assert!(is_full(1, 2, 6, &opts));
+    }
+
+    #[test]
+    fn json_bindings_before_the_head_wait_for_it() {
+        let doc = r#"{"results": {"bindings": [{"a": 1}, {"a": 2}]}, "head": {"vars": ["a"]}}"#;
+        let mut rows = Vec::new();
+        read_json(doc.as_bytes(), &mut |head, row| {
+            rows.push((head["vars"][0].clone(), row["a"].clone()));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(rows, [(json!("a"), json!(1)), (json!("a"), json!(2))]);
+        let ask = r#"{"head": {}, "boolean": true}"#;
+        assert!(read_json(ask.as_bytes(), &mut |_, _| Ok(())).is_err());
+    }
+
+    #[test]
+    fn xml_end_tags_follow_the_document() {
+        let doc = "<?xml version=\"1.0\"?>\n\
+                   <res:sparql xmlns:res=\"http://www.w3.org/2005/sparql-results#\">\n \
+                   <res:head/>\n \
+                   <res:results>\n  \
+                   <res:result><res:binding name=\"s\"/></res:result>\n  \
+                   <res:result/>\n \
+                   </res:results>\n\
+                   </res:sparql>\n";
+        let mut rows = Vec::new();
+        let mut close = Vec::new();
+        read_xml(doc.as_bytes(), &mut |frame, row| {
+            close = frame.close.clone();
+            rows.push(String::from_utf8(row.to_vec()).unwrap());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            rows,
+            [
+                "<res:result><res:binding name=\"s\"/></res:result>",
+                "<res:result/>"
+            ]
+        );
+        assert_eq!(close, b"\n </res:results>\n</res:sparql>\n");
+    }
+}
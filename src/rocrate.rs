@@ -0,0 +1,226 @@
+//! RO-Crate packaging of the chunk set (`--ro-crate`).
+//!
+//! `ro-crate-metadata.json` in the output directory describes the chunks as
+//! an RO-Crate 1.1 dataset: every chunk is a `File` part with its media
+//! type, size and SHA-256 digest, the split itself is a `CreateAction`
+//! linking the input files to the chunks it produced, and the dataset
+//! metadata (`--title`, `--license`, `--publisher`) applies to the whole set.
+
+use std::{
+    fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde_json::json;
+
+use crate::{
+    format::{is_gzip, RdfFormat, SplitterError},
+    layout::utc_timestamp,
+    metadata::DatasetMetadata,
+    paths,
+    splitter::{check_overwrite, ChunkInfo, SplitOptions},
+    stdio,
+};
+
+/// Name of the metadata descriptor at the crate root.
+pub const METADATA_FILE: &str = "ro-crate-metadata.json";
+
+const CONTEXT: &str = "https://w3id.org/ro/crate/1.1/context";
+const PROFILE: &str = "https://w3id.org/ro/crate/1.1";
+
+/// Inputs and chunks of a run, collected for the crate metadata.
+pub struct RoCrate {
+    started: SystemTime,
+    metadata: DatasetMetadata,
+    inputs: Vec<PathBuf>,
+    chunks: Vec<PathBuf>,
+}
+
+impl RoCrate {
+    pub fn new(started: SystemTime, metadata: DatasetMetadata) -> Self {
+        Self {
+            started,
+            metadata,
+            inputs: Vec::new(),
+            chunks: Vec::new(),
+        }
+    }
+
+    /// Record an input and the chunks it was split into.
+    pub fn add(&mut self, input: &Path, chunks: &[ChunkInfo]) {
+        self.inputs.push(input.to_path_buf());
+        self.chunks.extend(chunks.iter().map(|c| c.path.clone()));
+    }
+
+    /// Write the metadata descriptor into `opts.output_dir`, the crate root.
+    pub fn write(
+        &self,
+        opts: &SplitOptions,
+        finished: SystemTime,
+    ) -> Result<PathBuf, SplitterError> {
+        let path = opts.output_dir.join(METADATA_FILE);
+        check_overwrite(&path, opts.force)?;
+
+        let mut parts = Vec::new();
+        let mut entities = Vec::new();
+        for chunk in &self.chunks {
+            let id = relative_id(&opts.output_dir, chunk);
+            let mut file = json!({
+                "@id": id,
+                "@type": "File",
+                "name": chunk.file_name().unwrap_or_default().to_string_lossy(),
+                "contentSize": fs::metadata(chunk)?.len().to_string(),
+            });
+            let digest = opts.hash.unwrap_or_default().hex_digest(BufReader::new(fs::File::open(chunk)?))?;
+            file[opts.hash.unwrap_or_default().name()] = json!(digest);
+            if let Some(media_type) = encoding_format(chunk) {
+                file["encodingFormat"] = json!(media_type);
+            }
+            parts.push(json!({ "@id": id }));
+            entities.push(file);
+        }
+
+        let mut sources = Vec::new();
+        for input in &self.inputs {
+            let (id, name) = if stdio::is_stdin(input) {
+                ("#stdin".to_owned(), "standard input".to_owned())
+            } else {
+                let name = input.file_name().unwrap_or_default().to_string_lossy();
+                (paths::path_to_file_iri(input), name.into_owned())
+            };
+            let mut file = json!({ "@id": id, "@type": "File", "name": name });
+            if let Ok(meta) = fs::metadata(input) {
+                file["contentSize"] = json!(meta.len().to_string());
+            }
+            if let Some(media_type) = encoding_format(input) {
+                file["encodingFormat"] = json!(media_type);
+            }
+            sources.push(json!({ "@id": id }));
+            entities.push(file);
+        }
+
+        let names: Vec<String> = self
+            .inputs
+            .iter()
+            .map(|i| {
+                i.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        let name = match &self.metadata.title {
+            Some(title) => title.clone(),
+            None => format!("Chunks of {}", names.join(", ")),
+        };
+        let mut dataset = json!({
+            "@id": "./",
+            "@type": "Dataset",
+            "name": name,
+            "description": format!(
+                "{} chunk(s) split from {} input file(s) by rdfsplitter",
+                self.chunks.len(),
+                self.inputs.len()
+            ),
+            "datePublished": utc_timestamp(finished),
+            "hasPart": parts,
+        });
+        if let Some(license) = &self.metadata.license {
+            dataset["license"] = json!({ "@id": license });
+            entities.push(json!({ "@id": license, "@type": "CreativeWork", "name": license }));
+        }
+        if let Some(publisher) = &self.metadata.publisher {
+            dataset["publisher"] = json!({ "@id": publisher });
+            entities.push(json!({ "@id": publisher, "@type": "Organization", "name": publisher }));
+        }
+
+        let mut graph = vec![
+            json!({
+                "@id": METADATA_FILE,
+                "@type": "CreativeWork",
+                "conformsTo": { "@id": PROFILE },
+                "about": { "@id": "./" },
+            }),
+            dataset,
+            json!({
+                "@id": "#split",
+                "@type": "CreateAction",
+                "name": "Split RDF input into chunks",
+                "instrument": { "@id": "#rdfsplitter" },
+                "object": sources,
+                "result": parts,
+                "startTime": utc_timestamp(self.started),
+                "endTime": utc_timestamp(finished),
+            }),
+            json!({
+                "@id": "#rdfsplitter",
+                "@type": "SoftwareApplication",
+                "name": "rdfsplitter",
+                "version": env!("CARGO_PKG_VERSION"),
+                "url": env!("CARGO_PKG_REPOSITORY"),
+            }),
+        ];
+        graph.extend(entities);
+
+        let doc = json!({ "@context": CONTEXT, "@graph": graph });
+        fs::write(
+            &path,
+            serde_json::to_string_pretty(&doc).map_err(std::io::Error::from)? + "\n",
+        )?;
+        paths::set_mode(&path, opts.file_mode)?;
+        Ok(path)
+    }
+}
+
+/// `@id` of a file below the crate root: its relative path as an IRI reference.
+pub(crate) fn relative_id(root: &Path, file: &Path) -> String {
+    let rel = file.strip_prefix(root).unwrap_or(file);
+    let parts: Vec<String> = rel
+        .components()
+        .map(|c| paths::encode(&c.as_os_str().to_string_lossy()))
+        .collect();
+    parts.join("/")
+}
+
+/// Media type of an input or chunk, when known.
+fn encoding_format(path: &Path) -> Option<&'static str> {
+    let fmt = RdfFormat::from_path(path)?;
+    Some(if is_gzip(path) {
+        "application/gzip"
+    } else {
+        fmt.media_type()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_relative_to_the_root() {
+        let root = Path::new("out");
+        assert_eq!(
+            relative_id(root, Path::new("out/data_0000.nt")),
+            "data_0000.nt"
+        );
+        assert_eq!(
+            relative_id(root, Path::new("out/my data_0000.nt")),
+            "my%20data_0000.nt"
+        );
+    }
+
+    #[test]
+    fn media_types_follow_the_extension() {
+        assert_eq!(
+            encoding_format(Path::new("a_0000.ttl")),
+            Some("text/turtle")
+        );
+        assert_eq!(
+            encoding_format(Path::new("a_0000.nt.gz")),
+            Some("application/gzip")
+        );
+        assert_eq!(encoding_format(Path::new("a_0000.nt.age")), None);
+    }
+}
@@ -0,0 +1,230 @@
+//! Class-stratified sampling (`--stratified-sample`).
+//!
+//! A first pass picks up to N instances of every requested class.  Further
+//! passes add the blank nodes the picked subjects reference, transitively,
+//! so every sampled resource keeps its complete description.  Instances are
+//! chosen by the smallest hash of their term, which spreads the sample over
+//! the whole input yet yields the same sample on every run.
+
+use std::{
+    collections::{BinaryHeap, HashSet},
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+    str::FromStr,
+};
+
+use log::{debug, info, warn};
+
+use crate::{
+    format::{RdfFormat, SplitterError},
+    splitter::parse_quads,
+    vocab,
+};
+
+/// `type=<ClassIRI>:N` — sample N instances of a class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StratifiedSample {
+    /// Class in N-Triples form (`<iri>`).
+    pub class: String,
+    /// Instances of the class to keep.
+    pub count: usize,
+}
+
+impl FromStr for StratifiedSample {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("'{s}': expected 'type=<ClassIRI>:N'");
+        let spec = s.strip_prefix("type=").ok_or_else(err)?;
+        // the class IRI contains colons itself, so the count is the last field
+        let (class, count) = spec.rsplit_once(':').ok_or_else(err)?;
+        let count = count.parse().map_err(|_| err())?;
+        let class = class.trim();
+        if class.is_empty() {
+            return Err(err());
+        }
+        let class = if class.starts_with('<') {
+            class.to_owned()
+        } else {
+            format!("<{class}>")
+        };
+        Ok(Self { class, count })
+    }
+}
+
+/// Instances picked for one class: the `count` smallest subject hashes.
+struct Picks {
+    heap: BinaryHeap<(u64, String)>,
+    members: HashSet<String>,
+}
+
+impl Picks {
+    fn offer(&mut self, subject: &str, count: usize) {
+        if count == 0 || self.members.contains(subject) {
+            return;
+        }
+        let key = (term_hash(subject), subject.to_owned());
+        if self.heap.len() < count {
+            self.members.insert(key.1.clone());
+            self.heap.push(key);
+        } else if self.heap.peek().is_some_and(|top| key < *top) {
+            if let Some((_, evicted)) = self.heap.pop() {
+                self.members.remove(&evicted);
+            }
+            self.members.insert(key.1.clone());
+            self.heap.push(key);
+        }
+    }
+}
+
+fn term_hash(term: &str) -> u64 {
+    let mut h = DefaultHasher::new();
+    term.hash(&mut h);
+    h.finish()
+}
+
+/// Subjects whose statements make up the sample of `input`: the picked
+/// class instances plus the blank nodes reachable from them.
+pub fn select_subjects(
+    input: &Path,
+    fmt: RdfFormat,
+    specs: &[StratifiedSample],
+) -> Result<HashSet<String>, SplitterError> {
+    let mut picks: Vec<Picks> = specs
+        .iter()
+        .map(|_| Picks {
+            heap: BinaryHeap::new(),
+            members: HashSet::new(),
+        })
+        .collect();
+    parse_quads(input, fmt, &mut |q| {
+        if q.triple.predicate == vocab::RDF_TYPE {
+            for (spec, picks) in specs.iter().zip(&mut picks) {
+                if q.triple.object == spec.class {
+                    picks.offer(&q.triple.subject, spec.count);
+                }
+            }
+        }
+        Ok(())
+    })?;
+
+    let mut keep = HashSet::new();
+    for (spec, picks) in specs.iter().zip(picks) {
+        let n = picks.members.len();
+        if n < spec.count {
+            warn!(
+                "{}: only {} instance(s) of {} available for a sample of {}",
+                input.display(),
+                n,
+                spec.class,
+                spec.count
+            );
+        } else {
+            info!(
+                "{}: sampled {} instance(s) of {}",
+                input.display(),
+                n,
+                spec.class
+            );
+        }
+        keep.extend(picks.members);
+    }
+    add_blank_nodes(input, fmt, &mut keep)?;
+    Ok(keep)
+}
+
+/// Add the blank nodes reachable from the subjects in `keep` to it, with
+/// one more pass over `input` per level of blank-node nesting.
+pub(crate) fn add_blank_nodes(
+    input: &Path,
+    fmt: RdfFormat,
+    keep: &mut HashSet<String>,
+) -> Result<(), SplitterError> {
+    loop {
+        let mut reached = HashSet::new();
+        parse_quads(input, fmt, &mut |q| {
+            let o = &q.triple.object;
+            if o.starts_with("_:") && keep.contains(&q.triple.subject) && !keep.contains(o) {
+                reached.insert(o.clone());
+            }
+            Ok(())
+        })?;
+        if reached.is_empty() {
+            break;
+        }
+        debug!("  blank-node closure: {} blank node(s) added", reached.len());
+        keep.extend(reached);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_specs() {
+        assert_eq!(
+            "type=<http://ex.org/C>:1000".parse(),
+            Ok(StratifiedSample {
+                class: "<http://ex.org/C>".into(),
+                count: 1000
+            })
+        );
+        assert_eq!(
+            "type=http://ex.org/C:5"
+                .parse::<StratifiedSample>()
+                .unwrap()
+                .class,
+            "<http://ex.org/C>"
+        );
+        assert!("<http://ex.org/C>:5".parse::<StratifiedSample>().is_err());
+        assert!("type=<http://ex.org/C>"
+            .parse::<StratifiedSample>()
+            .is_err());
+        assert!("type=:5".parse::<StratifiedSample>().is_err());
+    }
+
+    #[test]
+    fn picks_are_stable_and_bounded() {
+        let pick = |subjects: &[&str]| {
+            let mut p = Picks {
+                heap: BinaryHeap::new(),
+                members: HashSet::new(),
+            };
+            for s in subjects {
+                p.offer(s, 2);
+            }
+            let mut picked: Vec<String> = p.members.into_iter().collect();
+            picked.sort();
+            picked
+        };
+        let forward = pick(&["<a>", "<b>", "<c>", "<d>", "<a>"]);
+        let backward = pick(&["<d>", "<c>", "<b>", "<a>"]);
+        assert_eq!(forward.len(), 2);
+        assert_eq!(forward, backward);
+    }
+
+    #[test]
+    fn sample_includes_blank_node_closure() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("data.nt");
+        let ty = vocab::RDF_TYPE;
+        std::fs::write(
+            &path,
+            format!(
+                "_:inner <http://ex.org/v> \"1\" .\n\
+                 <http://ex.org/x> {ty} <http://ex.org/C> .\n\
+                 <http://ex.org/x> <http://ex.org/has> _:outer .\n\
+                 _:outer <http://ex.org/has> _:inner .\n\
+                 <http://ex.org/y> {ty} <http://ex.org/D> .\n"
+            ),
+        )
+        .unwrap();
+        let specs = ["type=<http://ex.org/C>:10".parse().unwrap()];
+        let keep = select_subjects(&path, RdfFormat::NTriples, &specs).unwrap();
+        let mut keep: Vec<&str> = keep.iter().map(String::as_str).collect();
+        keep.sort();
+        assert_eq!(keep, ["<http://ex.org/x>", "_:inner", "_:outer"]);
+    }
+}
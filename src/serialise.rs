@@ -1,23 +1,42 @@
-use std::io::Write;
+//! Owned statements and the writers that serialise them in every output
+//! format.  [`ChunkWriter`] writes one chunk statement by statement.
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    io::{self, Write},
+    sync::Arc,
+};
 
 use rio_api::model::{Quad, Triple};
 
+use crate::{
+    format::RdfFormat,
+    jsonld::{expanded_value, parse_literal, Compaction},
+};
+
 /// A lightweight serialisable triple (owned strings).
 #[derive(Debug, Clone)]
 pub struct OwnedTriple {
+    /// The subject in N-Triples form (`<iri>` or `_:label`).
     pub subject: String,
+    /// The predicate in N-Triples form.
     pub predicate: String,
+    /// The object in N-Triples form, literals included.
     pub object: String,
 }
 
 /// A lightweight serialisable quad (triple + optional graph name).
 #[derive(Debug, Clone)]
 pub struct OwnedQuad {
+    /// Subject, predicate and object.
     pub triple: OwnedTriple,
+    /// The graph in N-Triples form; `None` for the default graph.
     pub graph_name: Option<String>,
 }
 
 impl OwnedTriple {
+    /// Copy a triple read by a rio parser.
     pub fn from_rio(t: &Triple<'_>) -> Self {
         Self {
             subject: t.subject.to_string(),
@@ -28,6 +47,20 @@ impl OwnedTriple {
 }
 
 impl OwnedQuad {
+    /// Length of the statement as an N-Quads line; a cheap estimate of its
+    /// serialised size in the other formats.
+    pub fn nquads_len(&self) -> usize {
+        let t = &self.triple;
+        let graph = self.graph_name.as_ref().map_or(0, |g| g.len() + 1);
+        t.subject.len() + t.predicate.len() + t.object.len() + graph + 5
+    }
+
+    /// A quad in the default graph.
+    pub fn from_triple(triple: OwnedTriple) -> Self {
+        Self { triple, graph_name: None }
+    }
+
+    /// Copy a quad read by a rio parser.
     pub fn from_rio(q: &Quad<'_>) -> Self {
         Self {
             triple: OwnedTriple {
@@ -42,9 +75,10 @@ impl OwnedQuad {
 
 // ─── Writers ───────────────────────────────────────────────────────────────
 
-pub fn write_ntriples<W: Write>(
+/// Write `triples` as N-Triples lines.
+pub fn write_ntriples<'a, W: Write>(
     w: &mut W,
-    triples: &[OwnedTriple],
+    triples: impl IntoIterator<Item = &'a OwnedTriple>,
 ) -> std::io::Result<()> {
     for t in triples {
         writeln!(w, "{} {} {} .", t.subject, t.predicate, t.object)?;
@@ -52,9 +86,10 @@ pub fn write_ntriples<W: Write>(
     Ok(())
 }
 
-pub fn write_nquads<W: Write>(
+/// Write `quads` as N-Quads lines.
+pub fn write_nquads<'a, W: Write>(
     w: &mut W,
-    quads: &[OwnedQuad],
+    quads: impl IntoIterator<Item = &'a OwnedQuad>,
 ) -> std::io::Result<()> {
     for q in quads {
         if let Some(g) = &q.graph_name {
@@ -74,31 +109,171 @@ pub fn write_nquads<W: Write>(
     Ok(())
 }
 
-/// Write a minimal valid Turtle chunk.
-/// We serialise as N-Triples inside a .ttl file since N-Triples is a
-/// valid subset of Turtle, keeping the output parse-able with any Turtle
-/// parser while avoiding the complexity of prefix round-tripping.
-pub fn write_turtle<W: Write>(
+/// Write triples as Turtle, with `;` and `,` for consecutive triples
+/// sharing their subject (and predicate).
+pub fn write_turtle<'a, W: Write>(
     w: &mut W,
-    triples: &[OwnedTriple],
+    triples: impl IntoIterator<Item = &'a OwnedTriple>,
 ) -> std::io::Result<()> {
-    // N-Triples syntax is valid Turtle
-    write_ntriples(w, triples)
+    let mut turtle = TurtleState::new(false);
+    for t in triples {
+        turtle.write(w, &t.subject, &t.predicate, &t.object, None)?;
+    }
+    turtle.end(w)
 }
 
-/// Write a minimal valid TriG chunk (N-Quads is valid TriG).
-pub fn write_trig<W: Write>(
+/// Write quads as TriG: default-graph statements as plain triples, runs of
+/// named-graph statements in `<g> { … }` blocks.
+pub fn write_trig<'a, W: Write>(
     w: &mut W,
-    quads: &[OwnedQuad],
+    quads: impl IntoIterator<Item = &'a OwnedQuad>,
 ) -> std::io::Result<()> {
-    write_nquads(w, quads)
+    let mut turtle = TurtleState::new(true);
+    for q in quads {
+        let t = &q.triple;
+        turtle.write(w, &t.subject, &t.predicate, &t.object, q.graph_name.as_deref())?;
+    }
+    turtle.end(w)
 }
 
-/// Write RDF/XML for a chunk of triples.
-pub fn write_rdfxml<W: Write>(
-    w: &mut W,
-    triples: &[OwnedTriple],
-) -> std::io::Result<()> {
+const RDF_TYPE: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#type>";
+
+/// Prefix declarations of a chunk, `(prefix, namespace IRI)`.
+type Prefixes = [(String, String)];
+
+/// Streaming Turtle / TriG writer state: the statement still open for more
+/// predicates or objects, the open graph block, and the declared prefixes.
+struct TurtleState {
+    /// Write graph blocks (TriG); otherwise graph names are dropped.
+    graphs: bool,
+    prefixes: Vec<(String, String)>,
+    /// Subject and predicate of the statement not yet ended with ` .`.
+    statement: Option<(String, String)>,
+    /// Graph of the open `{ … }` block.
+    graph: Option<String>,
+}
+
+impl TurtleState {
+    fn new(graphs: bool) -> Self {
+        Self {
+            graphs,
+            prefixes: Vec::new(),
+            statement: None,
+            graph: None,
+        }
+    }
+
+    fn declare_prefix<W: Write>(&mut self, w: &mut W, prefix: &str, ns: &str) -> io::Result<()> {
+        // directives are not allowed inside a statement or a graph block
+        self.end(w)?;
+        writeln!(w, "@prefix {prefix}: <{ns}> .")?;
+        self.prefixes.retain(|(p, _)| p != prefix);
+        self.prefixes.push((prefix.to_owned(), ns.to_owned()));
+        Ok(())
+    }
+
+    fn write<W: Write>(
+        &mut self,
+        w: &mut W,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+        graph: Option<&str>,
+    ) -> io::Result<()> {
+        let graph = graph.filter(|_| self.graphs);
+        let indent = if self.graph.is_some() { "    " } else { "" };
+        let o = turtle_term(object, &self.prefixes);
+        if self.graph.as_deref() == graph {
+            match &self.statement {
+                Some((s, p)) if s == subject && p == predicate => {
+                    return write!(w, ",\n{indent}        {o}");
+                }
+                Some((s, _)) if s == subject => {
+                    write!(w, " ;\n{indent}    {} {o}", self.predicate(predicate))?;
+                    self.statement = Some((subject.to_owned(), predicate.to_owned()));
+                    return Ok(());
+                }
+                _ => self.end_statement(w)?,
+            }
+        } else {
+            self.end(w)?;
+            if let Some(g) = graph {
+                writeln!(w, "{} {{", turtle_term(g, &self.prefixes))?;
+                self.graph = Some(g.to_owned());
+            }
+        }
+        let indent = if self.graph.is_some() { "    " } else { "" };
+        let s = turtle_term(subject, &self.prefixes);
+        write!(w, "{indent}{s} {} {o}", self.predicate(predicate))?;
+        self.statement = Some((subject.to_owned(), predicate.to_owned()));
+        Ok(())
+    }
+
+    fn predicate<'a>(&self, predicate: &'a str) -> Cow<'a, str> {
+        if predicate == RDF_TYPE {
+            Cow::Borrowed("a")
+        } else {
+            turtle_term(predicate, &self.prefixes)
+        }
+    }
+
+    fn end_statement<W: Write>(&mut self, w: &mut W) -> io::Result<()> {
+        if self.statement.take().is_some() {
+            writeln!(w, " .")?;
+        }
+        Ok(())
+    }
+
+    /// End the open statement and graph block.
+    fn end<W: Write>(&mut self, w: &mut W) -> io::Result<()> {
+        self.end_statement(w)?;
+        if self.graph.take().is_some() {
+            writeln!(w, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+/// `term` (N-Triples form) with its IRI, or its literal's datatype IRI,
+/// shortened to a prefixed name.
+fn turtle_term<'a>(term: &'a str, prefixes: &Prefixes) -> Cow<'a, str> {
+    if prefixes.is_empty() || term.starts_with("<<") {
+        return Cow::Borrowed(term);
+    }
+    if let Some(iri) = try_strip_angles(term) {
+        return compact_iri(iri, prefixes).map_or(Cow::Borrowed(term), Cow::Owned);
+    }
+    if let Some((lexical, datatype)) = term.rsplit_once("^^<").filter(|_| term.starts_with('"')) {
+        if let Some(name) = compact_iri(datatype.trim_end_matches('>'), prefixes) {
+            return Cow::Owned(format!("{lexical}^^{name}"));
+        }
+    }
+    Cow::Borrowed(term)
+}
+
+/// `prefix:local` for `iri` under the longest matching namespace, if the
+/// local part is a safe `PN_LOCAL`.
+fn compact_iri(iri: &str, prefixes: &Prefixes) -> Option<String> {
+    prefixes
+        .iter()
+        .filter(|(_, ns)| !ns.is_empty() && iri.starts_with(ns.as_str()))
+        .max_by_key(|(_, ns)| ns.len())
+        .and_then(|(prefix, ns)| {
+            let local = &iri[ns.len()..];
+            is_plain_local(local).then(|| format!("{prefix}:{local}"))
+        })
+}
+
+/// Conservative `PN_LOCAL` test: ASCII letters, digits, `_`, `-` and
+/// inner `.`, not starting with `-` or `.`.
+fn is_plain_local(local: &str) -> bool {
+    let ok = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.');
+    local.chars().all(ok)
+        && !local.starts_with(['-', '.'])
+        && !local.ends_with('.')
+}
+
+fn write_rdfxml_prologue<W: Write>(w: &mut W) -> std::io::Result<()> {
     writeln!(
         w,
         r#"<?xml version="1.0" encoding="utf-8"?>"#
@@ -107,107 +282,289 @@ pub fn write_rdfxml<W: Write>(
         w,
         r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">"#
     )?;
-    for t in triples {
-        // subject
-        let subj = strip_angles(&t.subject);
-        let pred = strip_angles(&t.predicate);
-        writeln!(
+    Ok(())
+}
+
+/// One `rdf:Description` element per triple.  The predicate's namespace
+/// is declared on its property element; blank nodes are written as
+/// `rdf:nodeID`.
+fn write_rdfxml_description<W: Write>(w: &mut W, t: &OwnedTriple) -> std::io::Result<()> {
+    let (ns, local) = split_predicate(strip_angles(&t.predicate)).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("predicate {} cannot be written as RDF/XML", t.predicate),
+        )
+    })?;
+    let pred = format!("ns:{local}");
+    writeln!(w, "  <rdf:Description {}>", rdfxml_node(&t.subject, "about")?)?;
+    let open = format!(r#"{pred} xmlns:ns="{}""#, xml_escape(ns));
+    match parse_literal(&t.object) {
+        None => writeln!(w, "    <{open} {}/>", rdfxml_node(&t.object, "resource")?)?,
+        Some((lexical, Some(lang), _)) => writeln!(
             w,
-            r#"  <rdf:Description rdf:about="{}">"#,
-            xml_escape(subj)
-        )?;
-        if let Some(obj_iri) = try_strip_angles(&t.object) {
-            writeln!(
-                w,
-                r#"    <{} rdf:resource="{}"/>"#,
-                pred,
-                xml_escape(obj_iri)
-            )?;
-        } else if let Some((lit, lang)) = try_lang_literal(&t.object) {
-            writeln!(
-                w,
-                r#"    <{} xml:lang="{}">{}</{}>"#,
-                pred,
-                lang,
-                xml_escape(lit),
-                pred
-            )?;
-        } else if let Some((lit, dt)) = try_typed_literal(&t.object) {
+            r#"    <{open} xml:lang="{}">{}</{pred}>"#,
+            xml_escape(&lang),
+            xml_escape(&lexical)
+        )?,
+        Some((lexical, None, Some(datatype))) => writeln!(
+            w,
+            r#"    <{open} rdf:datatype="{}">{}</{pred}>"#,
+            xml_escape(&datatype),
+            xml_escape(&lexical)
+        )?,
+        Some((lexical, None, None)) => {
+            writeln!(w, "    <{open}>{}</{pred}>", xml_escape(&lexical))?
+        }
+    }
+    writeln!(w, "  </rdf:Description>")
+}
+
+/// `rdf:<attr>="iri"` for an IRI, `rdf:nodeID="label"` for a blank node.
+/// A label that is no XML name gets a leading `_`.
+fn rdfxml_node(term: &str, attr: &str) -> io::Result<String> {
+    if let Some(iri) = try_strip_angles(term) {
+        return Ok(format!(r#"rdf:{attr}="{}""#, xml_escape(iri)));
+    }
+    match term.strip_prefix("_:") {
+        Some(label) if label.starts_with(is_name_start) => {
+            Ok(format!(r#"rdf:nodeID="{}""#, xml_escape(label)))
+        }
+        Some(label) => Ok(format!(r#"rdf:nodeID="_{}""#, xml_escape(label))),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{term} cannot be written as RDF/XML"),
+        )),
+    }
+}
+
+/// Namespace and local name of a predicate IRI: the local name is the
+/// longest suffix that is an XML name.  `None` if there is none.
+fn split_predicate(iri: &str) -> Option<(&str, &str)> {
+    let tail = iri
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_name_char(c))
+        .last()
+        .map(|(i, _)| i)?;
+    let start = tail + iri[tail..].find(is_name_start)?;
+    Some((&iri[..start], &iri[start..])).filter(|(ns, _)| !ns.is_empty())
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '\u{b7}')
+}
+
+/// Objects of one JSON-LD node object, by predicate.
+type JsonLdNode = BTreeMap<String, Vec<String>>;
+
+fn add_jsonld_value(node: &mut JsonLdNode, t: &OwnedTriple) {
+    node.entry(t.predicate.clone())
+        .or_default()
+        .push(t.object.clone());
+}
+
+/// Write one node object; the separating comma goes before every node but
+/// the first, so the writer need not know which node is last.
+fn write_jsonld_node<W: Write>(
+    w: &mut W,
+    subj: &str,
+    by_pred: &JsonLdNode,
+    first: bool,
+) -> std::io::Result<()> {
+    if !first {
+        writeln!(w, ",")?;
+    }
+    writeln!(w, "  {{")?;
+    writeln!(w, r#"    "@id": {},"#, json_string(strip_angles(subj)))?;
+    for (pi, (pred, values)) in by_pred.iter().enumerate() {
+        let pred_str = json_string(strip_angles(pred));
+        let trailing = if pi + 1 < by_pred.len() { "," } else { "" };
+        if values.len() == 1 {
             writeln!(
                 w,
-                r#"    <{} rdf:datatype="{}">{}</{}>"#,
-                pred,
-                xml_escape(dt),
-                xml_escape(lit),
-                pred
+                r#"    {}: [{}]{}"#,
+                pred_str,
+                expanded_value(&values[0]),
+                trailing
             )?;
         } else {
-            // plain literal
-            let lit = plain_literal(&t.object);
-            writeln!(w, r#"    <{}>{}</{}>"#, pred, xml_escape(lit), pred)?;
+            writeln!(w, r#"    {}: ["#, pred_str)?;
+            for (vi, v) in values.iter().enumerate() {
+                let comma = if vi + 1 < values.len() { "," } else { "" };
+                writeln!(w, "      {}{}", expanded_value(v), comma)?;
+            }
+            writeln!(w, r#"    ]{}"#, trailing)?;
         }
-        writeln!(w, r#"  </rdf:Description>"#)?;
     }
-    writeln!(w, r#"</rdf:RDF>"#)?;
-    Ok(())
+    write!(w, "  }}")
 }
 
-/// Write JSON-LD for a chunk of triples (expanded form, no context).
-pub fn write_jsonld<W: Write>(
+fn write_jsonld_end<W: Write>(w: &mut W, nodes: usize) -> std::io::Result<()> {
+    if nodes > 0 {
+        writeln!(w)?;
+    }
+    writeln!(w, "]")
+}
+
+/// `{"@context": …, "@graph": [` opening a compacted chunk.
+fn write_compacted_prologue<W: Write>(w: &mut W, compaction: &Compaction) -> io::Result<()> {
+    let context = serde_json::to_string_pretty(compaction.context())?;
+    writeln!(w, "{{")?;
+    writeln!(w, r#"  "@context": {},"#, context.replace('\n', "\n  "))?;
+    writeln!(w, r#"  "@graph": ["#)
+}
+
+/// One compacted node object, indented into the `@graph` array.
+fn write_compacted_node<W: Write>(
     w: &mut W,
-    triples: &[OwnedTriple],
-) -> std::io::Result<()> {
-    // Group by subject for a cleaner output
-    use std::collections::BTreeMap;
-    let mut map: BTreeMap<String, Vec<(&OwnedTriple, &str)>> = BTreeMap::new();
-    for t in triples {
-        map.entry(t.subject.clone())
-            .or_default()
-            .push((t, &t.predicate));
-    }
-
-    writeln!(w, "[")?;
-    let subjects: Vec<_> = map.keys().cloned().collect();
-    for (si, subj) in subjects.iter().enumerate() {
-        let entries = &map[subj];
-        let subj_iri = try_strip_angles(subj).unwrap_or(subj.as_str());
-        writeln!(w, "  {{")?;
-        writeln!(w, r#"    "@id": "{}","#, json_escape(subj_iri))?;
-        // group by predicate
-        let mut by_pred: BTreeMap<String, Vec<String>> = BTreeMap::new();
-        for (t, _) in entries {
-            by_pred
-                .entry(t.predicate.clone())
-                .or_default()
-                .push(object_to_jsonld_value(&t.object));
+    compaction: &Compaction,
+    subj: &str,
+    by_pred: &JsonLdNode,
+    first: bool,
+) -> io::Result<()> {
+    if !first {
+        writeln!(w, ",")?;
+    }
+    let node = compaction.node(subj, by_pred).map_err(io::Error::other)?;
+    let node = serde_json::to_string_pretty(&node)?;
+    write!(w, "    {}", node.replace('\n', "\n    "))
+}
+
+fn write_compacted_end<W: Write>(w: &mut W, nodes: usize) -> io::Result<()> {
+    if nodes > 0 {
+        writeln!(w)?;
+    }
+    writeln!(w, "  ]")?;
+    writeln!(w, "}}")
+}
+
+// ─── Streaming writer ───────────────────────────────────────────────────────
+
+/// Writes one chunk statement by statement, so a chunk never has to be held
+/// in memory.  JSON-LD node objects are built from runs of consecutive
+/// statements about the same subject; a subject that appears again later in
+/// the chunk gets a second node object, which JSON-LD merges on load.
+pub struct ChunkWriter<W: Write> {
+    w: W,
+    fmt: RdfFormat,
+    /// JSON-LD node object still being collected, with its subject.
+    node: Option<(String, JsonLdNode)>,
+    /// JSON-LD node objects written so far.
+    nodes: usize,
+    /// Context JSON-LD nodes are compacted against (`--jsonld-context`).
+    compaction: Option<Arc<Compaction>>,
+    /// Statement and graph block still open in Turtle / TriG.
+    turtle: TurtleState,
+}
+
+impl<W: Write> ChunkWriter<W> {
+    /// Start a chunk in `fmt` by writing the format's prologue.
+    pub fn new(w: W, fmt: RdfFormat) -> io::Result<Self> {
+        Self::compacted(w, fmt, None)
+    }
+
+    /// Start a chunk whose JSON-LD node objects are compacted against a
+    /// context, declared at its top.
+    pub fn compacted(
+        mut w: W,
+        fmt: RdfFormat,
+        compaction: Option<Arc<Compaction>>,
+    ) -> io::Result<Self> {
+        let compaction = compaction.filter(|_| fmt == RdfFormat::JsonLd);
+        match (fmt, &compaction) {
+            (RdfFormat::RdfXml, _) => write_rdfxml_prologue(&mut w)?,
+            (RdfFormat::JsonLd, Some(compaction)) => write_compacted_prologue(&mut w, compaction)?,
+            (RdfFormat::JsonLd, None) => writeln!(w, "[")?,
+            _ => {}
+        }
+        Ok(Self {
+            w,
+            fmt,
+            node: None,
+            nodes: 0,
+            compaction,
+            turtle: TurtleState::new(fmt == RdfFormat::TriG),
+        })
+    }
+
+    /// Declare a prefix (`@prefix p: <ns> .`) for the statements that
+    /// follow; a no-op for formats without prefixes.
+    pub fn declare_prefix(&mut self, prefix: &str, namespace: &str) -> io::Result<()> {
+        if !matches!(self.fmt, RdfFormat::Turtle | RdfFormat::TriG) {
+            return Ok(());
         }
-        let preds: Vec<_> = by_pred.keys().cloned().collect();
-        for (pi, pred) in preds.iter().enumerate() {
-            let pred_str = try_strip_angles(pred).unwrap_or(pred.as_str());
-            let values = &by_pred[pred];
-            let trailing = if pi + 1 < preds.len() { "," } else { "" };
-            if values.len() == 1 {
-                writeln!(
-                    w,
-                    r#"    "{}": [{}]{}"#,
-                    json_escape(pred_str),
-                    values[0],
-                    trailing
-                )?;
-            } else {
-                writeln!(w, r#"    "{}": ["#, json_escape(pred_str))?;
-                for (vi, v) in values.iter().enumerate() {
-                    let comma = if vi + 1 < values.len() { "," } else { "" };
-                    writeln!(w, "      {}{}", v, comma)?;
+        self.turtle.declare_prefix(&mut self.w, prefix, namespace)
+    }
+
+    /// Append one statement; triple formats drop the graph name.
+    pub fn write(&mut self, q: &OwnedQuad) -> io::Result<()> {
+        let t = &q.triple;
+        match self.fmt {
+            RdfFormat::NTriples => write_ntriples(&mut self.w, [t]),
+            RdfFormat::NQuads => write_nquads(&mut self.w, [q]),
+            RdfFormat::Turtle | RdfFormat::TriG => {
+                let graph = q.graph_name.as_deref();
+                self.turtle.write(&mut self.w, &t.subject, &t.predicate, &t.object, graph)
+            }
+            RdfFormat::RdfXml => write_rdfxml_description(&mut self.w, t),
+            RdfFormat::JsonLd => {
+                if self.node.as_ref().is_some_and(|(s, _)| *s != t.subject) {
+                    self.end_node()?;
                 }
-                writeln!(w, r#"    ]{}"#, trailing)?;
+                let (_, node) = self
+                    .node
+                    .get_or_insert_with(|| (t.subject.clone(), JsonLdNode::new()));
+                add_jsonld_value(node, t);
+                Ok(())
+            }
+            RdfFormat::SparqlJson | RdfFormat::SparqlXml => {
+                unreachable!("SPARQL results are not written as statements")
             }
         }
-        let comma = if si + 1 < subjects.len() { "," } else { "" };
-        writeln!(w, "  }}{}", comma)?;
     }
-    writeln!(w, "]")?;
-    Ok(())
+
+    /// The underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.w
+    }
+
+    /// The underlying writer, mutably.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.w
+    }
+
+    fn end_node(&mut self) -> io::Result<()> {
+        if let Some((subj, node)) = self.node.take() {
+            let first = self.nodes == 0;
+            match &self.compaction {
+                Some(c) => write_compacted_node(&mut self.w, c, &subj, &node, first)?,
+                None => write_jsonld_node(&mut self.w, &subj, &node, first)?,
+            }
+            self.nodes += 1;
+        }
+        Ok(())
+    }
+
+    /// Write the epilogue and hand back the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.fmt {
+            RdfFormat::RdfXml => writeln!(self.w, r#"</rdf:RDF>"#)?,
+            RdfFormat::JsonLd => {
+                self.end_node()?;
+                match self.compaction {
+                    Some(_) => write_compacted_end(&mut self.w, self.nodes)?,
+                    None => write_jsonld_end(&mut self.w, self.nodes)?,
+                }
+            }
+            RdfFormat::Turtle | RdfFormat::TriG => self.turtle.end(&mut self.w)?,
+            _ => {}
+        }
+        Ok(self.w)
+    }
 }
 
 // ─── helpers ────────────────────────────────────────────────────────────────
@@ -224,70 +581,177 @@ fn try_strip_angles(s: &str) -> Option<&str> {
     }
 }
 
-/// `"foo"@en` → Some(("foo", "en"))
-fn try_lang_literal(s: &str) -> Option<(&str, &str)> {
-    if let Some(pos) = s.rfind("\"@") {
-        let lang = &s[pos + 2..];
-        let lit = s.trim_start_matches('"');
-        let lit = &lit[..lit.rfind('"').unwrap_or(lit.len())];
-        Some((lit, lang))
-    } else {
-        None
-    }
-}
-
-/// `"foo"^^<dt>` → Some(("foo", "dt-iri"))
-fn try_typed_literal(s: &str) -> Option<(&str, &str)> {
-    if let Some(pos) = s.find("\"^^<") {
-        let lit = s.trim_start_matches('"');
-        let lit = &lit[..lit.find('"').unwrap_or(lit.len())];
-        let dt = &s[pos + 4..s.len() - 1];
-        Some((lit, dt))
-    } else {
-        None
-    }
-}
-
-fn plain_literal(s: &str) -> &str {
-    let s = s.trim_start_matches('"');
-    if let Some(p) = s.rfind('"') {
-        &s[..p]
-    } else {
-        s
-    }
-}
-
 fn xml_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
         .replace('"', "&quot;")
+        .replace('\r', "&#13;")
 }
 
-fn json_escape(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
+/// `s` as a JSON string, quotes included.
+fn json_string(s: &str) -> String {
+    serde_json::Value::from(s).to_string()
 }
 
-fn object_to_jsonld_value(obj: &str) -> String {
-    if let Some(iri) = try_strip_angles(obj) {
-        format!(r#"{{"@id": "{}"}}"#, json_escape(iri))
-    } else if let Some((lit, lang)) = try_lang_literal(obj) {
-        format!(
-            r#"{{"@value": "{}", "@language": "{}"}}"#,
-            json_escape(lit),
-            lang
-        )
-    } else if let Some((lit, dt)) = try_typed_literal(obj) {
-        format!(
-            r#"{{"@value": "{}", "@type": "{}"}}"#,
-            json_escape(lit),
-            json_escape(dt)
-        )
-    } else {
-        format!(r#"{{"@value": "{}"}}"#, json_escape(plain_literal(obj)))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q(s: &str, o: &str) -> OwnedQuad {
+        OwnedQuad::from_triple(OwnedTriple {
+            subject: s.into(),
+            predicate: "<http://ex.org/p>".into(),
+            object: o.into(),
+        })
+    }
+
+    fn streamed(fmt: RdfFormat, quads: &[OwnedQuad]) -> String {
+        let mut w = ChunkWriter::new(Vec::new(), fmt).unwrap();
+        for q in quads {
+            w.write(q).unwrap();
+        }
+        String::from_utf8(w.finish().unwrap()).unwrap()
+    }
+
+    /// Statements with a blank-node object and a literal needing escapes.
+    fn awkward() -> [OwnedQuad; 3] {
+        [
+            q("<http://ex.org/a>", "_:b1"),
+            q("_:b1", "\"say \\\"hi\\\" \\\\ bye\"@en"),
+            q("_:b1", "\"1\"^^<http://www.w3.org/2001/XMLSchema#int>"),
+        ]
+    }
+
+    fn written() -> Vec<String> {
+        let t = awkward().map(|q| q.triple);
+        t.iter().map(|t| format!("{} {} {} .", t.subject, t.predicate, t.object)).collect()
+    }
+
+    /// The statements as N-Triples lines, blank nodes as `_:`.
+    fn lines(quads: impl IntoIterator<Item = String>) -> Vec<String> {
+        let mut lines: Vec<String> = quads
+            .into_iter()
+            .map(|l| regex::Regex::new(r"_:\w+").unwrap().replace_all(&l, "_:").into_owned())
+            .collect();
+        lines.sort();
+        lines
+    }
+
+    #[test]
+    fn streams_rdfxml_descriptions() {
+        let out = streamed(RdfFormat::RdfXml, &[q("<http://ex.org/a>", "\"1\"@en")]);
+        assert!(out.starts_with("<?xml"));
+        assert!(out.contains(r#"<rdf:Description rdf:about="http://ex.org/a">"#));
+        assert!(out.contains(r#"<ns:p xmlns:ns="http://ex.org/" xml:lang="en">1</ns:p>"#));
+        assert!(out.ends_with("</rdf:RDF>\n"));
+    }
+
+    #[test]
+    fn rdfxml_reads_back_as_written() {
+        use rio_api::parser::TriplesParser;
+        let out = streamed(RdfFormat::RdfXml, &awkward());
+        assert!(out.contains(r#"rdf:nodeID="b1""#));
+        let mut parsed = Vec::new();
+        rio_xml::RdfXmlParser::new(out.as_bytes(), None)
+            .parse_all(&mut |t| {
+                parsed.push(format!("{t} ."));
+                Ok::<_, rio_xml::RdfXmlError>(())
+            })
+            .unwrap();
+        assert_eq!(lines(parsed), lines(written()));
+    }
+
+    #[test]
+    fn splits_predicates_at_the_last_xml_name() {
+        assert_eq!(
+            split_predicate("http://ex.org/ns#label"),
+            Some(("http://ex.org/ns#", "label"))
+        );
+        assert_eq!(split_predicate("http://ex.org/v2.1"), Some(("http://ex.org/", "v2.1")));
+        assert_eq!(split_predicate("http://ex.org/123"), None);
+    }
+
+    #[test]
+    fn jsonld_reads_back_as_written() {
+        let out = streamed(RdfFormat::JsonLd, &awkward());
+        assert!(out.contains(r#"{"@id":"_:b1"}"#));
+        let parsed = crate::jsonld::to_nquads(&out, None).unwrap();
+        assert_eq!(lines(parsed.lines().map(String::from)), lines(written()));
+    }
+
+    #[test]
+    fn groups_consecutive_jsonld_statements_per_subject() {
+        let quads = [
+            q("<http://ex.org/a>", "\"1\""),
+            q("<http://ex.org/a>", "\"2\""),
+            q("<http://ex.org/b>", "\"3\""),
+        ];
+        let v: serde_json::Value =
+            serde_json::from_str(&streamed(RdfFormat::JsonLd, &quads)).unwrap();
+        assert_eq!(v[0]["http://ex.org/p"].as_array().unwrap().len(), 2);
+        assert_eq!(v[1]["@id"], "http://ex.org/b");
+        assert_eq!(streamed(RdfFormat::JsonLd, &[]), "[\n]\n");
+    }
+
+    #[test]
+    fn turtle_uses_declared_prefixes() {
+        let mut w = ChunkWriter::new(Vec::new(), RdfFormat::Turtle).unwrap();
+        w.declare_prefix("ex", "http://ex.org/").unwrap();
+        w.declare_prefix("xsd", "http://www.w3.org/2001/XMLSchema#").unwrap();
+        w.write(&q("<http://ex.org/a>", "\"1\"^^<http://www.w3.org/2001/XMLSchema#int>"))
+            .unwrap();
+        w.write(&q("<http://ex.org/a/b?c>", "<http://other.org/x>")).unwrap();
+        let out = String::from_utf8(w.finish().unwrap()).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "@prefix ex: <http://ex.org/> .");
+        assert_eq!(lines[2], "ex:a ex:p \"1\"^^xsd:int .");
+        assert_eq!(lines[3], "<http://ex.org/a/b?c> ex:p <http://other.org/x> .");
+    }
+
+    #[test]
+    fn trig_writes_graph_blocks() {
+        let mut quad = q("<http://ex.org/a>", "\"1\"");
+        quad.graph_name = Some("<http://ex.org/g>".into());
+        let out = streamed(RdfFormat::TriG, &[quad, q("<http://ex.org/b>", "\"2\"")]);
+        assert_eq!(
+            out,
+            "<http://ex.org/g> {\n    <http://ex.org/a> <http://ex.org/p> \"1\" .\n}\n\
+             <http://ex.org/b> <http://ex.org/p> \"2\" .\n"
+        );
+    }
+
+    #[test]
+    fn turtle_groups_statements_by_subject_and_predicate() {
+        let mut w = ChunkWriter::new(Vec::new(), RdfFormat::Turtle).unwrap();
+        w.declare_prefix("ex", "http://ex.org/").unwrap();
+        w.write(&q("<http://ex.org/a>", "\"1\"")).unwrap();
+        w.write(&q("<http://ex.org/a>", "\"2\"")).unwrap();
+        let mut typed = q("<http://ex.org/a>", "<http://ex.org/C>");
+        typed.triple.predicate = RDF_TYPE.into();
+        w.write(&typed).unwrap();
+        // a prefix declared mid-chunk ends the open statement
+        w.declare_prefix("o", "http://other.org/").unwrap();
+        w.write(&q("<http://ex.org/a>", "<http://other.org/x>")).unwrap();
+        let out = String::from_utf8(w.finish().unwrap()).unwrap();
+        assert_eq!(
+            out,
+            "@prefix ex: <http://ex.org/> .\n\
+             ex:a ex:p \"1\",\n        \"2\" ;\n    a ex:C .\n\
+             @prefix o: <http://other.org/> .\n\
+             ex:a ex:p o:x .\n"
+        );
+    }
+
+    #[test]
+    fn streamed_jsonld_is_valid_when_subjects_recur() {
+        let quads = [
+            q("<http://ex.org/a>", "\"1\""),
+            q("<http://ex.org/b>", "\"2\""),
+            q("<http://ex.org/a>", "\"3\""),
+        ];
+        let v: serde_json::Value =
+            serde_json::from_str(&streamed(RdfFormat::JsonLd, &quads)).unwrap();
+        assert_eq!(v.as_array().unwrap().len(), 3);
     }
 }
@@ -0,0 +1,146 @@
+//! Free-space preflight check (`--min-free-space`).
+//!
+//! Before the first chunk is written, the size of the output is estimated
+//! from the size of the inputs, the verbosity of their format relative to
+//! the output format and the effect of compression, and compared with the
+//! space available on the file system of the output directory.  The
+//! factors are rough averages over typical dumps, so the estimate errs on
+//! the large side; it is meant to fail a run that cannot fit within seconds
+//! instead of an hour later with "No space left on device".
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    format::{is_gzip, RdfFormat, SplitterError},
+    remote,
+    splitter::SplitOptions,
+    stdio,
+};
+
+/// Uncompressed size of a gzip input relative to the compressed file.
+const GZIP_EXPANSION: f64 = 8.0;
+
+/// Size of a gzip chunk relative to the uncompressed chunk.
+const GZIP_RATIO: f64 = 0.15;
+
+/// Prefixes, headers and prologues repeated in every chunk.
+const CHUNK_OVERHEAD: f64 = 1.05;
+
+/// Bytes per statement relative to N-Triples: Turtle and TriG abbreviate
+/// with prefixes and `;` / `,` lists, RDF/XML and JSON-LD add markup.
+fn verbosity(fmt: RdfFormat) -> f64 {
+    match fmt {
+        RdfFormat::Turtle => 0.45,
+        RdfFormat::TriG => 0.5,
+        RdfFormat::NTriples | RdfFormat::SparqlJson | RdfFormat::SparqlXml => 1.0,
+        RdfFormat::NQuads => 1.15,
+        RdfFormat::JsonLd => 1.3,
+        RdfFormat::RdfXml => 1.4,
+    }
+}
+
+/// Estimated bytes of the chunks of `input`; `None` when its size is not
+/// known up front (standard input, URLs).
+pub fn estimate(input: &Path, fmt: RdfFormat, opts: &SplitOptions) -> Option<u64> {
+    if stdio::is_stdin(input) || remote::is_remote(input) {
+        return None;
+    }
+    let mut bytes = fs::metadata(input).ok()?.len() as f64;
+    if is_gzip(input) {
+        bytes *= GZIP_EXPANSION;
+    }
+    let out_fmt = match opts.output_format {
+        Some(out) if !fmt.is_sparql_results() => out,
+        _ => fmt,
+    };
+    bytes *= verbosity(out_fmt) / verbosity(fmt) * CHUNK_OVERHEAD;
+    if opts.compress.is_some() {
+        bytes *= GZIP_RATIO;
+    }
+    Some(bytes.ceil() as u64)
+}
+
+/// Bytes available to unprivileged users on the file system holding `dir`,
+/// or the closest existing directory above it.
+pub fn available(dir: &Path) -> io::Result<u64> {
+    let existing = dir
+        .ancestors()
+        .find(|d| d.is_dir())
+        .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+    fs4::available_space(&existing).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("cannot read the free space of {}: {e}", existing.display()),
+        )
+    })
+}
+
+/// Fail unless the estimated output of `inputs` fits on the file system of
+/// the output directory with `keep_free` bytes to spare.
+pub fn check(
+    inputs: &[(PathBuf, RdfFormat)],
+    keep_free: u64,
+    opts: &SplitOptions,
+) -> Result<(), SplitterError> {
+    let mut needed = 0u64;
+    for (input, fmt) in inputs {
+        match estimate(input, *fmt, opts) {
+            Some(bytes) => needed = needed.saturating_add(bytes),
+            None => log::warn!(
+                "{}: size not known up front, left out of the free-space estimate",
+                input.display()
+            ),
+        }
+    }
+    let free = available(&opts.output_dir)?;
+    log::debug!(
+        "About {} byte(s) of chunks estimated, {} byte(s) free in {}",
+        needed,
+        free,
+        opts.output_dir.display()
+    );
+    if needed.saturating_add(keep_free) > free {
+        return Err(SplitterError::Other(anyhow::anyhow!(
+            "not enough free space in {}: about {} byte(s) of chunks estimated and {} to keep \
+             free (--min-free-space), but {} available",
+            opts.output_dir.display(),
+            needed,
+            keep_free,
+            free
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_available_space_of_the_closest_existing_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let missing = dir.path().join("not/yet/created");
+        assert!(available(&missing).unwrap() > 0);
+    }
+
+    #[test]
+    fn estimates_conversion_and_compression() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let input = dir.path().join("data.ttl");
+        fs::write(&input, vec![b'#'; 1000]).unwrap();
+        let opts = SplitOptions {
+            output_format: Some(RdfFormat::NTriples),
+            ..Default::default()
+        };
+        let as_nt = estimate(&input, RdfFormat::Turtle, &opts).unwrap();
+        assert!(as_nt > 2000 && as_nt < 3000, "{as_nt}");
+        let gzipped = SplitOptions {
+            compress: Some(crate::compress::Compression::Gzip),
+            ..opts
+        };
+        assert!(estimate(&input, RdfFormat::Turtle, &gzipped).unwrap() < as_nt / 4);
+    }
+}
@@ -1,533 +1,1727 @@
+//! Parsing inputs and splitting them into chunk files ([`split_file`]).
+
 use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, HashMap, HashSet},
     fs,
-    io::{BufReader, BufWriter},
+    io::{BufRead, BufReader, Read, Write},
+    ops::{ControlFlow, Range},
     path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
 };
 
-use log::{debug, info};
+use log::{debug, info, warn};
+use flate2::read::MultiGzDecoder;
 use oxiri::Iri;
 use rio_api::parser::{QuadsParser, TriplesParser};
 use rio_turtle::{NQuadsParser, NTriplesParser, TriGParser, TurtleParser};
 use rio_xml::RdfXmlParser;
 
 use crate::{
-    format::{CallbackError, RdfFormat, SplitterError},
+    adaptive::AdaptiveSize,
+    bnodes::{self, BlankOwners, BnodeStrategy},
+    authority,
+    checkpoint::Checkpoint,
+    checksum::{sha256_hex, HashAlgorithm},
+    compress::{ChunkFile, Compression, CompressionTuning},
+    encrypt::Encryption,
+    faults::FaultInjection,
+    filter::{self, StatementFilter},
+    dedupe::{DedupeKey, SeenSet},
+    directives::VersionDirectives,
+    format::{
+        detect_format_from_content, input_stem, is_gzip, CallbackError, RdfFormat, SplitterError,
+    },
+    graphname::GraphFromFilename,
+    grouping::{
+        group_statements, AnnotationGuard, AnnotationKinds, BlankNodeGuard, BoundaryGuard,
+        GroupBy, ListGuard,
+    },
+    idle::{Fed, IdleFeed},
+    iri::{self, IriEncoding},
+    jsonld,
+    jsonld::Compaction,
+    jsonmap::{self, JsonMapping},
+    linelimit::{LineLimit, DEFAULT_MAX_LINE_BYTES},
+    linemap::{LineMapWriter, SourceSpan},
+    links::ChunkLinks,
+    literals::{LiteralIndex, SidecarWriter},
+    partition::Partitioning,
+    paths,
+    progress::InputProgress,
+    rejects::BadRecords,
+    remote,
+    results,
+    sample::{self, StratifiedSample},
+    stdio::{self, StdoutMode},
+    tail::{self, TailState},
+    vocab,
     serialise::{
-        write_jsonld, write_nquads, write_ntriples, write_rdfxml, write_trig, write_turtle,
-        OwnedQuad, OwnedTriple,
+        ChunkWriter, OwnedQuad, OwnedTriple,
     },
 };
 
-/// Print an in-place progress counter to stderr every [`PROGRESS_INTERVAL`] records.
-const PROGRESS_INTERVAL: usize = 100_000;
+/// How inputs are split; every option of the split command has a field or,
+/// for those holding run state, a `with_` method.  Start from
+/// [`SplitOptions::new`] and set the fields wanted.
+#[derive(Default)]
+pub struct SplitOptions {
+    /// Directory the chunks are written to.
+    pub output_dir: PathBuf,
+    /// Records per chunk; `usize::MAX` to cut by `max_bytes` only.
+    pub chunk_size: usize,
+    /// Start a new chunk before one would grow beyond this many bytes
+    /// (serialised, before compression).
+    pub max_bytes: Option<u64>,
+    /// Re-estimate the record count of every chunk so it comes out at about
+    /// this many bytes on disk (`--adaptive`); `chunk_size` is the first
+    /// chunk's count.
+    pub adaptive: Option<u64>,
+    /// Create a missing output directory and overwrite existing chunks.
+    pub force: bool,
+    /// Predicates (N-Triples form, `<iri>`) routed to the `hot` chunk series;
+    /// everything else goes to `cold`.  `None` disables partitioning.
+    pub hot_predicates: Option<HashSet<String>>,
+    /// Divert schema statements into a single, unchunked `tbox` series.
+    pub separate_tbox: bool,
+    /// Statements prepended to every chunk (`--ontology-header`); not counted
+    /// towards the chunk size.
+    pub header: Vec<OwnedQuad>,
+    /// Text written verbatim at the start of every chunk (`--chunk-header`).
+    pub chunk_header: Option<String>,
+    /// Text written verbatim at the end of every chunk (`--chunk-footer`).
+    pub chunk_footer: Option<String>,
+    /// Keep blank-node structures (OWL restrictions, axiom annotations,
+    /// class expressions) in one chunk.
+    pub keep_axioms: bool,
+    /// Keep blank-node clusters together or relabel blank nodes per chunk
+    /// (`--bnode-strategy`).
+    pub bnode_strategy: BnodeStrategy,
+    /// Keep reification / RDF-star annotations with the annotated statement.
+    pub keep_annotations: Option<AnnotationKinds>,
+    /// Collect per-chunk subjects and objects (link report, load order).
+    pub collect_links: bool,
+    /// How non-ASCII characters in IRIs are written.
+    pub iri_encoding: IriEncoding,
+    /// Write a string-literal sidecar next to every chunk.
+    pub literal_index: Option<LiteralIndex>,
+    /// Write the source line of every record next to each chunk of an
+    /// N-Triples / N-Quads input (`--line-map`).
+    pub line_map: bool,
+    /// Statements to split; the others are dropped while parsing.
+    pub filter: StatementFilter,
+    /// Drop repeated statements within each input, keeping up to this many
+    /// MiB of fingerprints in memory (`--dedupe`).
+    pub dedupe: Option<usize>,
+    /// Whether the graph is part of a statement's identity when
+    /// deduplicating (`--dedupe-key`).
+    pub dedupe_key: DedupeKey,
+    /// Statements seen earlier in the run; repeats are dropped when set
+    /// (see [`SplitOptions::with_dedupe_inputs`]).
+    pub(crate) seen: Option<RefCell<SeenSet>>,
+    /// Progress recorded for `--resume`; chunks an earlier run completed
+    /// are not written again (see [`SplitOptions::with_checkpoint`]).
+    pub(crate) checkpoint: Option<RefCell<Checkpoint>>,
+    /// N-Triples / N-Quads lines that do not parse are passed over when set
+    /// (`--skip-bad-records`, see [`SplitOptions::with_bad_records`]).
+    pub(crate) bad_records: Option<RefCell<BadRecords>>,
+    /// Longest accepted N-Triples / N-Quads line; `None` for no limit.
+    pub max_line_bytes: Option<usize>,
+    /// Close the open chunks once standard input has been quiet this long
+    /// (`--chunk-idle-timeout`).
+    pub chunk_idle_timeout: Option<Duration>,
+    /// Write chunks in this format instead of the input's.
+    pub output_format: Option<RdfFormat>,
+    /// Context JSON-LD chunks are compacted against (`--jsonld-context`).
+    pub jsonld_context: Option<Arc<Compaction>>,
+    /// Prefixes declared in every Turtle / TriG chunk (`--prefix`); they
+    /// take precedence over input prefixes of the same name.
+    pub prefixes: Vec<(String, String)>,
+    /// Keep every group of statements in a single chunk.
+    pub group_by: Option<GroupBy>,
+    /// Predicates whose IRI objects join a `--group-by connected` component,
+    /// in N-Triples form (`--connect-predicate`).
+    pub connect_predicates: HashSet<String>,
+    /// Digest of manifest checksums (SHA-256 when `None`) and of statement
+    /// fingerprints when deduplicating (`--hash`).
+    pub hash: Option<HashAlgorithm>,
+    /// Write hash partitions instead of sequential chunks (`--partitions`).
+    pub partition: Option<Partitioning>,
+    /// Give every subject IRI authority its own chunk series
+    /// (`--route-by-authority`).
+    pub route_by_authority: bool,
+    /// Append to existing partition files instead of creating them
+    /// (`--append`).
+    pub append: bool,
+    /// Files the open chunks of all series may hold at once; beyond it the
+    /// least recently written chunk is closed and reopened to append when
+    /// its series is written again (`--max-open-files`).
+    pub max_open_files: Option<usize>,
+    /// Name the chunk `<stem>.<ext>` instead of numbering it, for runs that
+    /// write a single chunk per input (`convert`).
+    pub unnumbered: bool,
+    /// Unix permission mode for created files (`--mode`).
+    pub file_mode: Option<u32>,
+    /// Unix permission mode for created directories (`--dir-mode`).
+    pub dir_mode: Option<u32>,
+    /// Put default-graph statements into a graph named after the input file.
+    pub graph_from_filename: Option<GraphFromFilename>,
+    /// Compress chunk files (`--compress`).
+    pub compress: Option<Compression>,
+    /// Level and threads of the compressor (`--compress-level`,
+    /// `--compress-threads`).
+    pub compress_tuning: CompressionTuning,
+    /// Encrypt chunk files for a recipient (`--encrypt`).
+    pub encrypt: Option<Encryption>,
+    /// Write chunks to standard output instead of files (`--stdout`).
+    pub stdout: Option<StdoutMode>,
+    /// Write the chunks of an input to a hidden staging directory and move
+    /// them into the output directory only once the input is complete.
+    pub staged: bool,
+    /// Fail unless an input splits into this many chunks of `chunk_size`
+    /// records (`--exact-chunks`); its chunks are staged until they do.
+    pub exact_chunks: Option<usize>,
+    /// Mirror the directory of each input below `output_dir`
+    /// (`--preserve-structure`).
+    pub preserve_structure: bool,
+    /// Split only what was appended to each input since the last run with
+    /// `tail`, continuing its chunk numbers (`--tail`).
+    pub tail: bool,
+    /// Suffix chunk names with a hash of the input path, so inputs sharing a
+    /// stem get distinct chunks (`--suffix-input-hash`).
+    pub suffix_input_hash: bool,
+    /// Go through the split without writing anything (`--dry-run`): chunks
+    /// are counted, their sizes are the serialised bytes before compression,
+    /// and no staging directory or sidecar is created.
+    pub dry_run: bool,
+    /// Lift JSON inputs into statements with this template instead of
+    /// reading them as JSON-LD.
+    pub json_mapping: Option<JsonMapping>,
+    /// Make chunk writes fail or slow down, to test error handling
+    /// (`--fault-inject`, see [`SplitOptions::with_fault_injection`]).
+    pub(crate) fault_injection: Option<FaultInjection>,
+    /// Write only a class-stratified sample of each input.
+    pub stratified_sample: Vec<StratifiedSample>,
+    /// Write only the descriptions of instances of these classes
+    /// (N-Triples form, `--filter-type`); read in a first pass.
+    pub filter_types: Vec<String>,
+}
+
+impl SplitOptions {
+    /// Split into chunks of `chunk_size` records in `output_dir`, with the
+    /// defaults of the command for everything else.
+    pub fn new(output_dir: impl Into<PathBuf>, chunk_size: usize) -> Self {
+        Self {
+            output_dir: output_dir.into(),
+            chunk_size,
+            ..Default::default()
+        }
+    }
+
+    /// Drop statements repeated anywhere in the inputs split with these
+    /// options (`--dedupe-inputs`), keeping up to `memory_mib` MiB of
+    /// fingerprints in memory.  Uses the `dedupe_key` and `hash` set before.
+    pub fn with_dedupe_inputs(mut self, memory_mib: usize) -> Self {
+        let seen = SeenSet::new(memory_mib, self.dedupe_key, self.hash);
+        self.seen = Some(RefCell::new(seen));
+        self
+    }
+
+    /// Record the chunks completed in `output_dir` (`--resume`), so a run
+    /// interrupted and started again with the same `options` (any text
+    /// identifying them) does not write them twice.
+    pub fn with_checkpoint(mut self, options: &str) -> Result<Self, SplitterError> {
+        let checkpoint = Checkpoint::open(&self.output_dir, options, self.file_mode)?;
+        self.checkpoint = Some(RefCell::new(checkpoint));
+        Ok(self)
+    }
 
-fn show_progress(n: usize) {
-    use std::io::Write;
-    eprint!("\r  {:>12} records...", n);
-    let _ = std::io::stderr().flush();
+    /// Pass over N-Triples / N-Quads lines that do not parse, at most
+    /// `limit` per input (any number for `None`), writing them to `rejects`
+    /// if given (`--skip-bad-records`, `--rejects`).
+    pub fn with_bad_records(
+        mut self,
+        limit: Option<usize>,
+        rejects: Option<&Path>,
+    ) -> Result<Self, SplitterError> {
+        let bad = BadRecords::new(limit, rejects, self.force, self.file_mode)?;
+        self.bad_records = Some(RefCell::new(bad));
+        Ok(self)
+    }
+
+    /// Make chunk writes fail or slow down as `spec` says (`--fault-inject`,
+    /// e.g. `fail=0.01,seed=7`), to test how a caller handles errors.
+    pub fn with_fault_injection(mut self, spec: &str) -> Result<Self, String> {
+        self.fault_injection = Some(spec.parse()?);
+        Ok(self)
+    }
 }
 
-/// Erase the progress line so subsequent log output starts on a clean line.
-fn clear_progress() {
-    eprint!("\r{:40}\r", "");
+/// A chunk file written by [`split_file`].
+#[derive(Debug)]
+pub struct ChunkInfo {
+    /// Where the chunk was written.
+    pub path: PathBuf,
+    /// Statements (or result rows) in the chunk, header excluded.
+    pub records: usize,
+    /// Size of the chunk file, after compression and encryption; with
+    /// `dry_run` the serialised bytes.
+    pub bytes: u64,
+    /// Subjects and IRI objects, collected when `SplitOptions::collect_links` is set.
+    pub links: Option<ChunkLinks>,
 }
 
-pub struct SplitOptions {
-    pub output_dir: PathBuf,
-    pub chunk_size: usize,
-    pub force: bool,
+/// Outcome of splitting one input file.
+#[derive(Debug, Default)]
+pub struct SplitSummary {
+    /// Triples/quads (or result rows) processed.
+    pub records: usize,
+    /// The chunks written, in order.
+    pub chunks: Vec<ChunkInfo>,
 }
 
 /// Count the total number of triples/quads in a file without storing them.
 /// Used by `--file-count` to compute the required chunk size.
 pub fn count_records(input: &Path, fmt: RdfFormat) -> Result<usize, SplitterError> {
-    let file = fs::File::open(input)?;
-    let reader = BufReader::new(file);
-    let base_str = file_base_iri(input);
-    let mut n = 0usize;
+    count_records_up_to(input, fmt, &StatementFilter::default(), usize::MAX)
+}
 
-    match fmt {
-        RdfFormat::NTriples => {
-            let mut p = NTriplesParser::new(reader);
-            p.parse_all(&mut |_: rio_api::model::Triple<'_>| -> Result<(), CallbackError> {
-                n += 1;
-                if n % PROGRESS_INTERVAL == 0 { show_progress(n); }
-                Ok(())
-            })
-            .map_err(|e| SplitterError::Parse(e.to_string()))?;
-        }
-        RdfFormat::Turtle => {
-            let base = Iri::parse(base_str).map_err(|e| SplitterError::Parse(e.to_string()))?;
-            let mut p = TurtleParser::new(reader, Some(base));
-            p.parse_all(&mut |_: rio_api::model::Triple<'_>| -> Result<(), CallbackError> {
-                n += 1;
-                if n % PROGRESS_INTERVAL == 0 { show_progress(n); }
-                Ok(())
-            })
-            .map_err(|e| SplitterError::Parse(e.to_string()))?;
-        }
-        RdfFormat::RdfXml => {
-            let base = Iri::parse(base_str).map_err(|e| SplitterError::Parse(e.to_string()))?;
-            let mut p = RdfXmlParser::new(reader, Some(base));
-            p.parse_all(&mut |_: rio_api::model::Triple<'_>| -> Result<(), CallbackError> {
-                n += 1;
-                if n % PROGRESS_INTERVAL == 0 { show_progress(n); }
-                Ok(())
-            })
-            .map_err(|e| SplitterError::Parse(e.to_string()))?;
-        }
-        RdfFormat::NQuads => {
-            let mut p = NQuadsParser::new(reader);
-            p.parse_all(&mut |_: rio_api::model::Quad<'_>| -> Result<(), CallbackError> {
-                n += 1;
-                if n % PROGRESS_INTERVAL == 0 { show_progress(n); }
-                Ok(())
-            })
-            .map_err(|e| SplitterError::Parse(e.to_string()))?;
+/// [`count_records`] of the statements `filter` keeps, stopping as soon as
+/// the count exceeds `limit`; a result above `limit` only means "more than
+/// `limit`".  Used by `--chunk-size-max`, whose chunk size is settled at
+/// that point.
+pub fn count_records_up_to(
+    input: &Path,
+    fmt: RdfFormat,
+    filter: &StatementFilter,
+    limit: usize,
+) -> Result<usize, SplitterError> {
+    if fmt.is_sparql_results() {
+        return results::count_results(input, fmt);
+    }
+    let mut n = 0usize;
+    parse_quads_until(input, fmt, &mut |q| {
+        if filter.keeps(&q.triple) {
+            n += 1;
         }
-        RdfFormat::TriG => {
-            let base = Iri::parse(base_str).map_err(|e| SplitterError::Parse(e.to_string()))?;
-            let mut p = TriGParser::new(reader, Some(base));
-            p.parse_all(&mut |_: rio_api::model::Quad<'_>| -> Result<(), CallbackError> {
-                n += 1;
-                if n % PROGRESS_INTERVAL == 0 { show_progress(n); }
-                Ok(())
-            })
-            .map_err(|e| SplitterError::Parse(e.to_string()))?;
+        Ok(if n > limit { ControlFlow::Break(()) } else { ControlFlow::Continue(()) })
+    })
+    .map(|_| n)
+}
+
+/// [`count_records`] of the statements `filter` keeps and none of `seen`
+/// (the `--dedupe` / `--dedupe-inputs` sets) has seen before; the sets
+/// remember them.  Used by `--exact-chunks`, whose chunk size must follow
+/// the statements actually written.
+pub fn count_distinct_records(
+    input: &Path,
+    fmt: RdfFormat,
+    filter: &StatementFilter,
+    seen: &mut [&mut SeenSet],
+) -> Result<usize, SplitterError> {
+    if fmt.is_sparql_results() {
+        return results::count_results(input, fmt);
+    }
+    let mut n = 0usize;
+    parse_quads(input, fmt, &mut |q| {
+        if !filter.keeps(&q.triple) {
+            return Ok(());
         }
-        RdfFormat::JsonLd => {
-            let raw = fs::read_to_string(input)?;
-            let nt = jsonld_to_ntriples(&raw)?;
-            n = nt.lines().filter(|l| !l.trim().is_empty()).count();
+        for set in seen.iter_mut() {
+            if !set.insert(&q)? {
+                return Ok(());
+            }
         }
-    }
-    clear_progress();
-
+        n += 1;
+        Ok(())
+    })?;
     Ok(n)
 }
 
-/// Split a single file into chunks.  Returns the number of triples/quads processed.
+/// Parse a whole (small) RDF file into memory, e.g. an ontology header.
+pub fn load_statements(path: &Path) -> Result<Vec<OwnedQuad>, SplitterError> {
+    let fmt = RdfFormat::from_path(path)
+        .filter(|f| !f.is_sparql_results())
+        .ok_or_else(|| SplitterError::UnsupportedFormat(path.display().to_string()))?;
+    let mut quads = Vec::new();
+    parse_quads(path, fmt, &mut |q| {
+        quads.push(q);
+        Ok(())
+    })?;
+    Ok(quads)
+}
+
+/// Split a single file into chunks.
 pub fn split_file(
     input: &Path,
     fmt: RdfFormat,
     opts: &SplitOptions,
-) -> Result<usize, SplitterError> {
-    prepare_output_dir(&opts.output_dir, opts.force)?;
+) -> Result<SplitSummary, SplitterError> {
+    if opts.stdout.is_none() {
+        prepare_output_dir(opts)?;
+        prepare_input_dir(input, opts)?;
+    }
     info!("Splitting {} [{}]", input.display(), fmt.label());
+    if opts.tail {
+        return split_tail(input, fmt, opts);
+    }
+    if fmt.is_sparql_results() {
+        // result chunks are not written through the chunker, so not recorded
+        return staged(input, opts, || split_chunks(input, fmt, opts, None));
+    }
+    checkpointed(input, opts, || staged(input, opts, || split_chunks(input, fmt, opts, None)))
+}
 
-    match fmt {
-        RdfFormat::NTriples | RdfFormat::Turtle | RdfFormat::RdfXml => {
-            split_triples(input, fmt, opts)
+/// Run `split`, with `--resume` recording the chunks it completes; an input
+/// an earlier run split completely is passed over.
+fn checkpointed(
+    input: &Path,
+    opts: &SplitOptions,
+    split: impl FnOnce() -> Result<SplitSummary, SplitterError>,
+) -> Result<SplitSummary, SplitterError> {
+    let Some(checkpoint) = &opts.checkpoint else {
+        return split();
+    };
+    // links and duplicates across inputs are only known by reading them again
+    let may_skip = !opts.collect_links && opts.seen.is_none();
+    let resumed = checkpoint.borrow_mut().begin(input, may_skip)?;
+    if resumed.complete {
+        info!("{}: split by an earlier run, passed over", input.display());
+        return Ok(SplitSummary {
+            records: resumed.chunks.iter().map(|c| c.records).sum(),
+            chunks: resumed.chunks,
+        });
+    }
+    if !resumed.chunks.is_empty() {
+        info!(
+            "{}: {} chunk(s) of an earlier run kept",
+            input.display(),
+            resumed.chunks.len()
+        );
+    }
+    match split() {
+        Ok(summary) => {
+            checkpoint.borrow_mut().input_done(input)?;
+            Ok(summary)
+        }
+        Err(e) => {
+            // the chunks completed before the error are kept by the next run
+            if let Err(save) = checkpoint.borrow_mut().save() {
+                warn!("{save}");
+            }
+            Err(e)
         }
-        RdfFormat::NQuads | RdfFormat::TriG => split_quads(input, fmt, opts),
-        RdfFormat::JsonLd => split_jsonld_file(input, opts),
     }
 }
 
-// ─── triple-based formats ───────────────────────────────────────────────────
-
-fn split_triples(
+/// Split what was appended to `input` since the last `--tail` run and
+/// record how far it got.
+fn split_tail(
     input: &Path,
     fmt: RdfFormat,
     opts: &SplitOptions,
-) -> Result<usize, SplitterError> {
-    let base_str = file_base_iri(input);
-
-    let mut triples: Vec<OwnedTriple> = Vec::with_capacity(opts.chunk_size);
-    let mut chunk = 0usize;
-    let mut total = 0usize;
-    let mut flush_err: Option<SplitterError> = None;
-
+) -> Result<SplitSummary, SplitterError> {
+    if !matches!(fmt, RdfFormat::NTriples | RdfFormat::NQuads)
+        || is_gzip(input)
+        || stdio::is_stdin(input)
+        || remote::is_remote(input)
     {
-        let file = fs::File::open(input)?;
-        let reader = BufReader::new(file);
-
-        let flush = |triples: &mut Vec<OwnedTriple>,
-                     chunk: &mut usize,
-                     total: &mut usize,
-                     flush_err: &mut Option<SplitterError>| {
-            if triples.is_empty() {
-                return;
-            }
-            match write_triple_chunk(input, fmt, triples, *chunk, opts) {
-                Ok(()) => {
-                    *chunk += 1;
-                    *total += triples.len();
-                    triples.clear();
-                }
-                Err(e) => {
-                    *flush_err = Some(e);
-                }
-            }
+        return Err(SplitterError::Other(anyhow::anyhow!(
+            "--tail needs an uncompressed N-Triples or N-Quads file"
+        )));
+    }
+    let state_path = tail::state_path(&input_output_dir(input, opts), &chunk_stem(input, opts));
+    let state = TailState::load(&state_path)?;
+    let range = tail::pending_range(input, state.offset)?;
+    if range.start < state.offset {
+        warn!("{}: shorter than at the last --tail run; reading it from the start", input.display());
+    }
+    debug!("  resuming at byte {}, chunk {}", range.start, state.next_chunk);
+    let resume = Some((&range, state.next_chunk));
+    let summary = staged(input, opts, || split_chunks(input, fmt, opts, resume))?;
+    if !opts.dry_run {
+        let state = TailState {
+            offset: range.end,
+            next_chunk: state.next_chunk + summary.chunks.len(),
         };
+        state.save(&state_path, input)?;
+        paths::set_mode(&state_path, opts.file_mode)?;
+    }
+    Ok(summary)
+}
 
-        let mut parsed = 0usize;
-        let mut on_triple = |t: rio_api::model::Triple<'_>| -> Result<(), CallbackError> {
-            triples.push(OwnedTriple::from_rio(&t));
-            parsed += 1;
-            if parsed % PROGRESS_INTERVAL == 0 { show_progress(parsed); }
-            if triples.len() >= opts.chunk_size {
-                flush(&mut triples, &mut chunk, &mut total, &mut flush_err);
-            }
-            Ok(())
-        };
+/// Split `inputs` as one stream into chunks named `<name>_NNNN.<ext>`
+/// (`--merge`), e.g. to consolidate many small files into a few large ones.
+/// Without an output format, inputs of one format keep it; mixed inputs are
+/// written as N-Quads if any of them has graphs, as N-Triples otherwise.
+pub fn merge_files(
+    inputs: &[(PathBuf, RdfFormat)],
+    name: &str,
+    opts: &SplitOptions,
+) -> Result<SplitSummary, SplitterError> {
+    if opts.stdout.is_none() {
+        prepare_output_dir(opts)?;
+    }
+    info!("Merging {} input(s) into {name}", inputs.len());
+    let merged = Path::new(name);
+    staged(merged, opts, || merge_chunks(inputs, merged, opts, true))
+}
 
-        match fmt {
-            RdfFormat::NTriples => {
-                let mut parser = NTriplesParser::new(reader);
-                parser
-                    .parse_all(&mut on_triple)
-                    .map_err(|e| SplitterError::Parse(e.to_string()))?;
-            }
-            RdfFormat::Turtle => {
-                let base = Iri::parse(base_str)
-                    .map_err(|e| SplitterError::Parse(e.to_string()))?;
-                let mut parser = TurtleParser::new(reader, Some(base));
-                parser
-                    .parse_all(&mut on_triple)
-                    .map_err(|e| SplitterError::Parse(e.to_string()))?;
-            }
-            RdfFormat::RdfXml => {
-                let base = Iri::parse(base_str)
-                    .map_err(|e| SplitterError::Parse(e.to_string()))?;
-                let mut parser = RdfXmlParser::new(reader, Some(base));
-                parser
-                    .parse_all(&mut on_triple)
-                    .map_err(|e| SplitterError::Parse(e.to_string()))?;
-            }
-            _ => unreachable!(),
+/// [`merge_files`] for chunks of one source (`repack`): their blank-node
+/// labels are shared across chunks and kept as they are.
+pub(crate) fn merge_series(
+    chunks: &[(PathBuf, RdfFormat)],
+    name: &str,
+    opts: &SplitOptions,
+) -> Result<SplitSummary, SplitterError> {
+    prepare_output_dir(opts)?;
+    let merged = Path::new(name);
+    staged(merged, opts, || merge_chunks(chunks, merged, opts, false))
+}
+
+/// Run `split`, with `--staged` inside the staging directory of `input`
+/// whose chunks are moved into place once it succeeded.
+fn staged(
+    input: &Path,
+    opts: &SplitOptions,
+    split: impl FnOnce() -> Result<SplitSummary, SplitterError>,
+) -> Result<SplitSummary, SplitterError> {
+    let Some(staging) = staging_dir(input, opts) else {
+        return split().and_then(|summary| check_exact_chunks(summary, opts));
+    };
+    if staging.exists() {
+        // left behind by an interrupted run
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir(&staging)?;
+    let result = split()
+        .and_then(|summary| check_exact_chunks(summary, opts))
+        .and_then(|summary| commit_staged(input, &staging, summary, opts));
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    result
+}
+
+/// Hidden directory receiving the chunks of `input` with `--staged`, or
+/// until they pass `--exact-chunks`.
+fn staging_dir(input: &Path, opts: &SplitOptions) -> Option<PathBuf> {
+    let staged = opts.staged || opts.exact_chunks.is_some() && opts.stdout.is_none();
+    (staged && !opts.dry_run).then(|| {
+        input_output_dir(input, opts).join(format!(".{}.rdfsplitter-staging", chunk_stem(input, opts)))
+    })
+}
+
+/// `--exact-chunks`: fail unless `summary` has the requested number of
+/// chunks of `chunk_size` records each, which structures kept whole (lists,
+/// blank nodes) can prevent.
+fn check_exact_chunks(
+    summary: SplitSummary,
+    opts: &SplitOptions,
+) -> Result<SplitSummary, SplitterError> {
+    let Some(chunks) = opts.exact_chunks else {
+        return Ok(summary);
+    };
+    let size = opts.chunk_size;
+    if summary.chunks.len() == chunks && summary.chunks.iter().all(|c| c.records == size) {
+        return Ok(summary);
+    }
+    let sizes: Vec<String> = summary.chunks.iter().map(|c| c.records.to_string()).collect();
+    Err(SplitterError::Other(anyhow::anyhow!(
+        "--exact-chunks {chunks} wrote chunks of {} record(s) instead of {chunks} × {size}; \
+         statements kept together (lists, blank nodes) made them uneven",
+        sizes.join(", ")
+    )))
+}
+
+/// Move the staged chunks (and their sidecars) into the output directory.
+fn commit_staged(
+    input: &Path,
+    staging: &Path,
+    mut summary: SplitSummary,
+    opts: &SplitOptions,
+) -> Result<SplitSummary, SplitterError> {
+    let dir = input_output_dir(input, opts);
+    for entry in fs::read_dir(staging)? {
+        let staged = entry?.path();
+        let target = dir.join(staged.file_name().unwrap_or_default());
+        fs::rename(&staged, target)?;
+    }
+    fs::remove_dir(staging)?;
+    for chunk in &mut summary.chunks {
+        chunk.path = dir.join(chunk.path.file_name().unwrap_or_default());
+    }
+    debug!("  {} chunk(s) moved into {}", summary.chunks.len(), dir.display());
+    Ok(summary)
+}
+
+/// Split `input` into chunks; with `--staged` they land in the staging
+/// directory.  With `--tail` only the byte range of `resume` is read, its
+/// chunks numbered from the given one.
+fn split_chunks(
+    input: &Path,
+    fmt: RdfFormat,
+    opts: &SplitOptions,
+    resume: Option<(&Range<u64>, usize)>,
+) -> Result<SplitSummary, SplitterError> {
+    if fmt.is_sparql_results() {
+        if opts.output_format.is_some_and(|f| f != fmt) {
+            return Err(SplitterError::Parse(format!(
+                "{} cannot be converted to another format",
+                fmt.label()
+            )));
         }
+        return results::split_results(input, fmt, opts);
     }
 
-    clear_progress();
-    if let Some(e) = flush_err {
+    let file_graph = file_graph(input, opts)?;
+    // statements moved into a graph need a quad format unless told otherwise
+    let default_fmt = match file_graph {
+        Some(_) if !fmt.has_graphs() => RdfFormat::NQuads,
+        _ => fmt,
+    };
+    let out_fmt = output_format(default_fmt, opts)?;
+    let mut chunker = Chunker::new(input, out_fmt, opts);
+    if let Some((_, first)) = resume {
+        chunker.first_chunk = first;
+    }
+    let range = resume.map(|(range, _)| range);
+    let mut within = opts.dedupe.map(|mb| SeenSet::new(mb, opts.dedupe_key, opts.hash));
+    if let Err(e) = feed(&mut chunker, input, fmt, file_graph, range, &mut within, None) {
+        chunker.abandon();
         return Err(e);
     }
+    if let Some(seen) = within {
+        info!(
+            "{}: {} duplicate statement(s) dropped",
+            input.display(),
+            seen.duplicates
+        );
+    }
+    chunker.finish()
+}
 
-    // flush remainder
-    if !triples.is_empty() {
-        write_triple_chunk(input, fmt, &triples, chunk, opts)?;
-        total += triples.len();
+/// Split `inputs` as one stream into the chunks of `merged`; with
+/// `relabel` the blank nodes of every input are kept apart.
+fn merge_chunks(
+    inputs: &[(PathBuf, RdfFormat)],
+    merged: &Path,
+    opts: &SplitOptions,
+    relabel: bool,
+) -> Result<SplitSummary, SplitterError> {
+    if let Some((input, fmt)) = inputs.iter().find(|(_, f)| f.is_sparql_results()) {
+        return Err(SplitterError::Parse(format!(
+            "{}: {} cannot be merged with RDF inputs",
+            input.display(),
+            fmt.label()
+        )));
+    }
+    let graphs = opts.graph_from_filename.is_some() || inputs.iter().any(|(_, f)| f.has_graphs());
+    let default_fmt = match inputs.first() {
+        Some((_, fmt))
+            if inputs.iter().all(|(_, f)| f == fmt)
+                && (fmt.has_graphs() || opts.graph_from_filename.is_none()) =>
+        {
+            *fmt
+        }
+        _ if graphs => RdfFormat::NQuads,
+        _ => RdfFormat::NTriples,
+    };
+    let out_fmt = output_format(default_fmt, opts)?;
+    let mut chunker = Chunker::new(merged, out_fmt, opts);
+    let mut within = opts.dedupe.map(|mb| SeenSet::new(mb, opts.dedupe_key, opts.hash));
+    let relabel = relabel && inputs.len() > 1;
+    for (n, (input, fmt)) in inputs.iter().enumerate() {
+        debug!("  {} [{}]", input.display(), fmt.label());
+        let prefix = relabel.then(|| bnodes::input_prefix(n));
+        let fed = file_graph(input, opts).and_then(|graph| {
+            feed(&mut chunker, input, *fmt, graph, None, &mut within, prefix.as_deref())
+        });
+        if let Err(e) = fed {
+            chunker.abandon();
+            return Err(SplitterError::Parse(format!("{}: {e}", input.display())));
+        }
     }
+    if let Some(seen) = within {
+        info!(
+            "{}: {} duplicate statement(s) dropped",
+            merged.display(),
+            seen.duplicates
+        );
+    }
+    chunker.finish()
+}
 
-    Ok(total)
+/// Graph for the default-graph statements of `input` (`--graph-from-filename`).
+fn file_graph(input: &Path, opts: &SplitOptions) -> Result<Option<String>, SplitterError> {
+    let Some(g) = &opts.graph_from_filename else {
+        return Ok(None);
+    };
+    let graph = g.graph_for(input)?;
+    if graph.is_none() {
+        warn!(
+            "{}: file name does not match --graph-from-filename; statements stay in the default graph",
+            input.display()
+        );
+    }
+    Ok(graph)
 }
 
-fn write_triple_chunk(
+/// `--output-format`, or `default_fmt`; result formats cannot be written.
+fn output_format(default_fmt: RdfFormat, opts: &SplitOptions) -> Result<RdfFormat, SplitterError> {
+    let out_fmt = opts.output_format.unwrap_or(default_fmt);
+    if out_fmt.is_sparql_results() {
+        return Err(SplitterError::Parse(format!(
+            "cannot write {} input as {}",
+            default_fmt.label(),
+            out_fmt.label()
+        )));
+    }
+    Ok(out_fmt)
+}
+
+/// Parse `input` and push its statements to `chunker`, applying the
+/// filters, graph naming, IRI encoding and deduplication of `chunker`'s
+/// options.  `within` is the `--dedupe` set of the chunk stream;
+/// `bnode_prefix` keeps the blank nodes of a merged input apart.
+fn feed(
+    chunker: &mut Chunker,
     input: &Path,
     fmt: RdfFormat,
-    triples: &[OwnedTriple],
-    chunk: usize,
-    opts: &SplitOptions,
+    file_graph: Option<String>,
+    range: Option<&Range<u64>>,
+    within: &mut Option<SeenSet>,
+    bnode_prefix: Option<&str>,
 ) -> Result<(), SplitterError> {
-    let out_path = chunk_path(input, fmt, chunk, opts);
-    check_overwrite(&out_path, opts.force)?;
-    debug!("  writing chunk {} → {}", chunk, out_path.display());
-    let file = fs::File::create(&out_path)?;
-    let mut w = BufWriter::new(file);
-    match fmt {
-        RdfFormat::NTriples => write_ntriples(&mut w, triples)?,
-        RdfFormat::Turtle => write_turtle(&mut w, triples)?,
-        RdfFormat::RdfXml => write_rdfxml(&mut w, triples)?,
-        _ => unreachable!(),
+    let opts = chunker.opts;
+    let out_fmt = chunker.fmt;
+    if opts.group_by == Some(GroupBy::Graph) && !fmt.has_graphs() {
+        warn!(
+            "{}: {} has no named graphs; --group-by graph keeps the whole input in one chunk",
+            input.display(),
+            fmt.label()
+        );
+    }
+    let sample = match opts.stratified_sample.as_slice() {
+        [] => None,
+        specs => Some(sample::select_subjects(input, fmt, specs)?),
+    };
+    let typed = match opts.filter_types.as_slice() {
+        [] => None,
+        classes => Some(filter::typed_subjects(input, fmt, classes)?),
+    };
+    let line_based = matches!(fmt, RdfFormat::NTriples | RdfFormat::NQuads);
+    if opts.line_map && !line_based {
+        warn!(
+            "{}: --line-map needs N-Triples or N-Quads input; no line map for {}",
+            input.display(),
+            fmt.label()
+        );
+    }
+    if opts.bad_records.is_some() && !line_based {
+        warn!(
+            "{}: --skip-bad-records needs N-Triples or N-Quads input; {} is parsed strictly",
+            input.display(),
+            fmt.label()
+        );
+    }
+    let tracking = ParseTracking {
+        // the input's prefixes are kept when writing a format that has them
+        prefixes: matches!(out_fmt, RdfFormat::Turtle | RdfFormat::TriG)
+            .then(|| RefCell::new(Vec::new())),
+        span: (opts.line_map && line_based).then(Cell::default),
+        json_mapping: opts.json_mapping.as_ref(),
+        range: range.cloned(),
+        bad_records: opts.bad_records.as_ref().filter(|_| line_based),
+        idle_timeout: opts
+            .chunk_idle_timeout
+            .filter(|_| line_based && stdio::is_stdin(input)),
+    };
+    chunker.input_prefixes = 0;
+    let seen_prefix = opts
+        .seen
+        .as_ref()
+        .map(|seen| bnodes::input_prefix(seen.borrow_mut().start_input()));
+    let mut dropped_graphs = false;
+    let mut grouped = Vec::new();
+    let max_line_bytes = opts.max_line_bytes.unwrap_or(usize::MAX);
+    let shared = RefCell::new(chunker);
+    let mut on_idle = || {
+        let closed = shared.borrow_mut().close_open()?;
+        if let Some(timeout) = opts.chunk_idle_timeout.filter(|_| closed > 0) {
+            info!("{}: nothing for {timeout:?}, {closed} chunk(s) closed", input.display());
+        }
+        Ok(())
+    };
+    let on_quad = &mut |mut q: OwnedQuad| {
+        let mut chunker = shared.borrow_mut();
+        if let Some(prefixes) = &tracking.prefixes {
+            chunker.declare_prefixes(&prefixes.borrow())?;
+        }
+        chunker.source = tracking.span.as_ref().map(Cell::get);
+        if !opts.filter.keeps(&q.triple) {
+            return Ok(());
+        }
+        let subject = &q.triple.subject;
+        if [&sample, &typed].into_iter().flatten().any(|keep| !keep.contains(subject)) {
+            return Ok(());
+        }
+        if q.graph_name.is_none() {
+            q.graph_name.clone_from(&file_graph);
+        }
+        let mut q = iri::apply(q, opts.iri_encoding);
+        let blank = bnodes::mentions_blank_node(&q);
+        if let Some(prefix) = bnode_prefix.filter(|_| blank) {
+            q = bnodes::relabel(&q, prefix);
+        }
+        dropped_graphs |= q.graph_name.is_some() && !out_fmt.has_graphs();
+        if let Some(seen) = within {
+            if !seen.insert(&q)? {
+                return Ok(());
+            }
+        }
+        if let Some(seen) = &opts.seen {
+            let new = match seen_prefix.as_deref().filter(|_| blank) {
+                Some(prefix) => seen.borrow_mut().insert(&bnodes::relabel(&q, prefix))?,
+                None => seen.borrow_mut().insert(&q)?,
+            };
+            if !new {
+                return Ok(());
+            }
+        }
+        match opts.group_by {
+            // grouping needs the whole input before the first cut
+            Some(_) => grouped.push(q),
+            None => chunker.push(q)?,
+        }
+        Ok(())
+    };
+    let on_quad = &mut |q| on_quad(q).map(ControlFlow::Continue);
+    parse_quads_limited(input, fmt, max_line_bytes, &tracking, on_quad, &mut on_idle).map(drop)?;
+    let chunker = shared.into_inner();
+    if let Some(by) = opts.group_by {
+        if let Some(prefixes) = &tracking.prefixes {
+            chunker.declare_prefixes(&prefixes.borrow())?;
+        }
+        for group in group_statements(grouped, by, &opts.connect_predicates) {
+            chunker.push_group(group)?;
+        }
+    }
+    if dropped_graphs {
+        warn!(
+            "{}: named graphs are not kept in {} output",
+            input.display(),
+            out_fmt.label()
+        );
     }
     Ok(())
 }
 
-// ─── quad-based formats ─────────────────────────────────────────────────────
+// ─── parsing ────────────────────────────────────────────────────────────────
 
-fn split_quads(
+/// Parse `input` and hand every statement to `on_quad`.  Triple formats yield
+/// quads in the default graph.  The first error returned by `on_quad` aborts
+/// the parse and is passed through unchanged.
+pub(crate) fn parse_quads(
     input: &Path,
     fmt: RdfFormat,
-    opts: &SplitOptions,
-) -> Result<usize, SplitterError> {
-    let base_str = file_base_iri(input);
+    on_quad: &mut dyn FnMut(OwnedQuad) -> Result<(), SplitterError>,
+) -> Result<(), SplitterError> {
+    parse_quads_until(input, fmt, &mut |q| on_quad(q).map(ControlFlow::Continue)).map(drop)
+}
 
-    let mut quads: Vec<OwnedQuad> = Vec::with_capacity(opts.chunk_size);
-    let mut chunk = 0usize;
-    let mut total = 0usize;
-    let mut flush_err: Option<SplitterError> = None;
+/// [`parse_quads`] that stops early, without an error, when `on_quad`
+/// returns [`ControlFlow::Break`]; the result tells whether it did.
+pub(crate) fn parse_quads_until(
+    input: &Path,
+    fmt: RdfFormat,
+    on_quad: &mut dyn FnMut(OwnedQuad) -> Result<ControlFlow<()>, SplitterError>,
+) -> Result<ControlFlow<()>, SplitterError> {
+    let tracking = ParseTracking::default();
+    parse_quads_limited(input, fmt, DEFAULT_MAX_LINE_BYTES, &tracking, on_quad, &mut || Ok(()))
+}
 
-    {
-        let file = fs::File::open(input)?;
-        let reader = BufReader::new(file);
-
-        let flush = |quads: &mut Vec<OwnedQuad>,
-                     chunk: &mut usize,
-                     total: &mut usize,
-                     flush_err: &mut Option<SplitterError>| {
-            if quads.is_empty() {
-                return;
+/// What [`split_file`] follows of a parse besides the statements, and how
+/// it reads plain JSON.
+#[derive(Default)]
+struct ParseTracking<'a> {
+    /// Prefixes a Turtle / TriG input has declared so far.
+    prefixes: Option<RefCell<Vec<(String, String)>>>,
+    /// Position of the N-Triples / N-Quads line being parsed; reading line
+    /// by line is slower, so only when asked for.
+    span: Option<Cell<SourceSpan>>,
+    /// Statements to lift plain JSON inputs into (`--json-map`).
+    json_mapping: Option<&'a JsonMapping>,
+    /// Bytes of an N-Triples / N-Quads input to read (`--tail`).
+    range: Option<Range<u64>>,
+    /// N-Triples / N-Quads lines that do not parse are passed over.
+    bad_records: Option<&'a RefCell<BadRecords>>,
+    /// Standard input is waited on this long before the parse reports it
+    /// idle (`--chunk-idle-timeout`).
+    idle_timeout: Option<Duration>,
+}
+
+/// [`parse_quads`] with an explicit line length limit for N-Triples and
+/// N-Quads inputs (`usize::MAX` for none).  `on_idle` is called whenever
+/// the input has been quiet for the idle timeout of `tracking`.
+fn parse_quads_limited(
+    input: &Path,
+    fmt: RdfFormat,
+    max_line_bytes: usize,
+    tracking: &ParseTracking,
+    on_quad: &mut dyn FnMut(OwnedQuad) -> Result<ControlFlow<()>, SplitterError>,
+    on_idle: &mut dyn FnMut() -> Result<(), SplitterError>,
+) -> Result<ControlFlow<()>, SplitterError> {
+    let prefixes = tracking.prefixes.as_ref();
+    let base_str = paths::path_to_file_iri(input);
+    // a --tail range is read from the middle of the file, not tracked in bytes
+    let len = match &tracking.range {
+        Some(_) => None,
+        None => input_len(input),
+    };
+    let progress = RefCell::new(InputProgress::start(len));
+    let open_input = |input: &Path| open_tracked(input, Some(&progress.borrow()));
+    let mut cb_err: Option<SplitterError> = None;
+    let mut idle_err: Option<SplitterError> = None;
+    // a failed or stopping callback is not a bad record
+    let cb_failed = Cell::new(false);
+    let mut stopped = false;
+
+    let mut emit = |q: OwnedQuad| -> Result<(), CallbackError> {
+        progress.borrow_mut().record();
+        match on_quad(q) {
+            Ok(ControlFlow::Continue(())) => Ok(()),
+            Ok(ControlFlow::Break(())) => {
+                cb_failed.set(true);
+                stopped = true;
+                Err(CallbackError("stopped".into()))
+            }
+            Err(e) => {
+                let msg = e.to_string();
+                cb_failed.set(true);
+                cb_err = Some(e);
+                Err(CallbackError(msg))
             }
-            match write_quad_chunk(input, fmt, quads, *chunk, opts) {
-                Ok(()) => {
-                    *chunk += 1;
-                    *total += quads.len();
-                    quads.clear();
+        }
+    };
+    let mut on_triple = |t: rio_api::model::Triple<'_>| -> Result<(), CallbackError> {
+        emit(OwnedQuad::from_triple(OwnedTriple::from_rio(&t)))
+    };
+
+    let result = match fmt {
+        RdfFormat::NTriples | RdfFormat::NQuads if tracking.idle_timeout.is_some() => {
+            let timeout = tracking.idle_timeout.expect("checked by the guard");
+            let mut feed = IdleFeed::spawn(std::io::stdin(), timeout, max_line_bytes);
+            // whole lines only, so every block parses on its own
+            let mut parse = |lines: &[u8]| {
+                if fmt == RdfFormat::NTriples {
+                    NTriplesParser::new(lines).parse_all(&mut |t: rio_api::model::Triple<'_>| {
+                        emit(OwnedQuad::from_triple(OwnedTriple::from_rio(&t)))
+                    })
+                } else {
+                    NQuadsParser::new(lines).parse_all(&mut |q: rio_api::model::Quad<'_>| {
+                        emit(OwnedQuad::from_rio(&q))
+                    })
                 }
-                Err(e) => {
-                    *flush_err = Some(e);
+                .map_err(|e| e.to_string())
+            };
+            loop {
+                match feed.wait() {
+                    Ok(Fed::Lines(lines)) => {
+                        if let Err(e) = parse(&lines) {
+                            break Err(e);
+                        }
+                    }
+                    Ok(Fed::Idle) => {
+                        if let Err(e) = on_idle() {
+                            let msg = e.to_string();
+                            idle_err = Some(e);
+                            break Err(msg);
+                        }
+                    }
+                    Ok(Fed::End(rest)) => break parse(&rest),
+                    Err(e) => break Err(e.to_string()),
                 }
             }
-        };
-
-        let mut parsed = 0usize;
-        let mut on_quad = |q: rio_api::model::Quad<'_>| -> Result<(), CallbackError> {
-            quads.push(OwnedQuad::from_rio(&q));
-            parsed += 1;
-            if parsed % PROGRESS_INTERVAL == 0 { show_progress(parsed); }
-            if quads.len() >= opts.chunk_size {
-                flush(&mut quads, &mut chunk, &mut total, &mut flush_err);
+        }
+        RdfFormat::NTriples | RdfFormat::NQuads
+            if tracking.span.is_some() || tracking.bad_records.is_some() =>
+        {
+            let raw = match &tracking.range {
+                Some(range) => tail::open_range(input, range)?,
+                None => open_input(input)?,
+            };
+            let mut reader = LineLimit::new(raw, max_line_bytes);
+            let mut line = Vec::new();
+            let mut pos = SourceSpan::default();
+            let mut bad = 0usize;
+            loop {
+                line.clear();
+                let n = match reader.read_until(b'\n', &mut line) {
+                    Ok(0) => break Ok(()),
+                    Ok(n) => n as u64,
+                    Err(e) => break Err(e.to_string()),
+                };
+                pos = SourceSpan {
+                    line: pos.line + 1,
+                    offset: pos.offset + pos.len,
+                    len: n,
+                };
+                if let Some(span) = &tracking.span {
+                    span.set(pos);
+                }
+                let parsed = if fmt == RdfFormat::NTriples {
+                    NTriplesParser::new(&line[..]).parse_all(&mut |t: rio_api::model::Triple<'_>| {
+                        emit(OwnedQuad::from_triple(OwnedTriple::from_rio(&t)))
+                    })
+                } else {
+                    NQuadsParser::new(&line[..])
+                        .parse_all(&mut |q: rio_api::model::Quad<'_>| emit(OwnedQuad::from_rio(&q)))
+                };
+                match (parsed, tracking.bad_records) {
+                    (Ok(()), _) => {}
+                    (Err(e), Some(bad_records)) if !cb_failed.get() => {
+                        bad += 1;
+                        let error = e.to_string();
+                        let rejected =
+                            bad_records.borrow_mut().reject(input, pos.line, &line, &error, bad);
+                        if let Err(msg) = rejected {
+                            break Err(msg);
+                        }
+                    }
+                    (Err(e), _) => break Err(format!("line {}: {e}", pos.line)),
+                }
             }
-            Ok(())
-        };
-
-        match fmt {
-            RdfFormat::NQuads => {
-                let mut parser = NQuadsParser::new(reader);
-                parser
-                    .parse_all(&mut on_quad)
-                    .map_err(|e| SplitterError::Parse(e.to_string()))?;
+        }
+        RdfFormat::NTriples | RdfFormat::NQuads if tracking.range.is_some() => {
+            let range = tracking.range.as_ref().expect("checked by the guard");
+            let reader = LineLimit::new(tail::open_range(input, range)?, max_line_bytes);
+            if fmt == RdfFormat::NTriples {
+                NTriplesParser::new(reader).parse_all(&mut on_triple)
+            } else {
+                NQuadsParser::new(reader)
+                    .parse_all(&mut |q: rio_api::model::Quad<'_>| emit(OwnedQuad::from_rio(&q)))
+            }
+            .map_err(|e| e.to_string())
+        }
+        RdfFormat::NTriples => {
+            let reader = LineLimit::new(open_input(input)?, max_line_bytes);
+            let mut parser = NTriplesParser::new(reader);
+            parser.parse_all(&mut on_triple).map_err(|e| e.to_string())
+        }
+        RdfFormat::Turtle => {
+            let base = Iri::parse(base_str)
+                .map_err(|e| SplitterError::Parse(e.to_string()))?;
+            let mut parser = TurtleParser::new(VersionDirectives::new(open_input(input)?), Some(base));
+            // one statement or directive per step, so new prefixes are
+            // passed on before the statements using them
+            let mut stepped = Ok(());
+            while stepped.is_ok() && !parser.is_end() {
+                stepped = parser.parse_step(&mut on_triple);
+                sync_prefixes(parser.prefixes(), prefixes);
             }
-            RdfFormat::TriG => {
-                let base = Iri::parse(base_str)
-                    .map_err(|e| SplitterError::Parse(e.to_string()))?;
-                let mut parser = TriGParser::new(reader, Some(base));
-                parser
-                    .parse_all(&mut on_quad)
-                    .map_err(|e| SplitterError::Parse(e.to_string()))?;
+            stepped.map_err(|e| e.to_string())
+        }
+        RdfFormat::RdfXml => {
+            let base = Iri::parse(base_str)
+                .map_err(|e| SplitterError::Parse(e.to_string()))?;
+            let mut parser = RdfXmlParser::new(open_input(input)?, Some(base));
+            parser.parse_all(&mut on_triple).map_err(|e| e.to_string())
+        }
+        RdfFormat::NQuads => {
+            let reader = LineLimit::new(open_input(input)?, max_line_bytes);
+            let mut parser = NQuadsParser::new(reader);
+            parser
+                .parse_all(&mut |q: rio_api::model::Quad<'_>| emit(OwnedQuad::from_rio(&q)))
+                .map_err(|e| e.to_string())
+        }
+        RdfFormat::TriG => {
+            let base = Iri::parse(base_str)
+                .map_err(|e| SplitterError::Parse(e.to_string()))?;
+            let mut parser = TriGParser::new(VersionDirectives::new(open_input(input)?), Some(base));
+            let mut on_quad = |q: rio_api::model::Quad<'_>| emit(OwnedQuad::from_rio(&q));
+            let mut stepped = Ok(());
+            while stepped.is_ok() && !parser.is_end() {
+                stepped = parser.parse_step(&mut on_quad);
+                sync_prefixes(parser.prefixes(), prefixes);
             }
-            _ => unreachable!(),
+            stepped.map_err(|e| e.to_string())
         }
-    }
+        RdfFormat::JsonLd => {
+            info!("  loading and converting JSON-LD...");
+            let mut raw = String::new();
+            open_input(input)?.read_to_string(&mut raw)?;
+            let doc: serde_json::Value =
+                serde_json::from_str(&raw).map_err(|e| SplitterError::Parse(e.to_string()))?;
+            let nq_string = match tracking.json_mapping {
+                Some(mapping) => mapping.to_ntriples(&doc).map_err(SplitterError::Parse)?,
+                None => {
+                    if !jsonmap::has_jsonld_keywords(&doc) {
+                        warn!(
+                            "{}: no JSON-LD keywords (@context, @id, @graph); plain JSON gives \
+                             no statements unless lifted with --json-map",
+                            input.display()
+                        );
+                    }
+                    jsonld::to_nquads(&raw, Some(&base_str)).map_err(SplitterError::Parse)?
+                }
+            };
+            // graph-scoped nodes produce quads, so read the conversion back as N-Quads
+            let mut parser = NQuadsParser::new(BufReader::new(nq_string.as_bytes()));
+            parser
+                .parse_all(&mut |q: rio_api::model::Quad<'_>| emit(OwnedQuad::from_rio(&q)))
+                .map_err(|e| e.to_string())
+        }
+        RdfFormat::SparqlJson | RdfFormat::SparqlXml => unreachable!(),
+    };
+    // clear the bar before the caller logs the outcome
+    drop(progress);
 
-    clear_progress();
-    if let Some(e) = flush_err {
-        return Err(e);
+    match (result, cb_err.or(idle_err)) {
+        (_, Some(e)) => Err(e),
+        (Err(_), None) if stopped => Ok(ControlFlow::Break(())),
+        (Err(msg), None) => Err(SplitterError::Parse(msg)),
+        (Ok(()), None) => Ok(ControlFlow::Continue(())),
     }
+}
 
-    if !quads.is_empty() {
-        write_quad_chunk(input, fmt, &quads, chunk, opts)?;
-        total += quads.len();
+/// Append the prefixes a Turtle / TriG parser has declared since the last
+/// call to `known`, in name order.  A redeclared prefix keeps its first
+/// namespace: the names written with it stay correct, just less compact.
+fn sync_prefixes(
+    declared: &HashMap<String, String>,
+    known: Option<&RefCell<Vec<(String, String)>>>,
+) {
+    let Some(known) = known else {
+        return;
+    };
+    let mut known = known.borrow_mut();
+    if declared.len() == known.len() {
+        return;
     }
+    let mut new: Vec<(String, String)> = declared
+        .iter()
+        .filter(|(prefix, _)| !known.iter().any(|(p, _)| p == *prefix))
+        .map(|(prefix, ns)| (prefix.clone(), ns.clone()))
+        .collect();
+    new.sort();
+    known.extend(new);
+}
 
-    Ok(total)
+/// Open an input for reading, decompressing `.gz` files on the fly; `-`
+/// reads standard input, a URL is downloaded while it is read.
+pub(crate) fn open_input(input: &Path) -> Result<Box<dyn BufRead>, SplitterError> {
+    open_tracked(input, None)
 }
 
-fn write_quad_chunk(
+/// [`open_input`], advancing `progress` by the bytes read before
+/// decompression.
+fn open_tracked(
     input: &Path,
-    fmt: RdfFormat,
-    quads: &[OwnedQuad],
-    chunk: usize,
-    opts: &SplitOptions,
-) -> Result<(), SplitterError> {
-    let out_path = chunk_path(input, fmt, chunk, opts);
-    check_overwrite(&out_path, opts.force)?;
-    debug!("  writing chunk {} → {}", chunk, out_path.display());
-    let file = fs::File::create(&out_path)?;
-    let mut w = BufWriter::new(file);
-    match fmt {
-        RdfFormat::NQuads => write_nquads(&mut w, quads)?,
-        RdfFormat::TriG => write_trig(&mut w, quads)?,
-        _ => unreachable!(),
+    progress: Option<&InputProgress>,
+) -> Result<Box<dyn BufRead>, SplitterError> {
+    let raw: Box<dyn Read> = if stdio::is_stdin(input) {
+        Box::new(std::io::stdin().lock())
+    } else if remote::is_remote(input) {
+        remote::open(&input.to_string_lossy())?
+    } else {
+        Box::new(fs::File::open(input)?)
+    };
+    let raw: Box<dyn Read> = match progress {
+        Some(progress) => Box::new(progress.wrap(raw)),
+        None => raw,
+    };
+    Ok(if is_gzip(input) {
+        Box::new(BufReader::new(MultiGzDecoder::new(raw)))
+    } else {
+        Box::new(BufReader::new(raw))
+    })
+}
+
+/// Size of a local input file, as read (compressed for `.gz` files).
+fn input_len(input: &Path) -> Option<u64> {
+    if stdio::is_stdin(input) || remote::is_remote(input) {
+        return None;
     }
-    Ok(())
+    fs::metadata(input).ok().map(|m| m.len())
 }
 
-// ─── JSON-LD ─────────────────────────────────────────────────────────────────
-
-fn split_jsonld_file(input: &Path, opts: &SplitOptions) -> Result<usize, SplitterError> {
-    info!("  loading and converting JSON-LD...");
-    let raw = fs::read_to_string(input)?;
-    let nt_string = jsonld_to_ntriples(&raw)?;
-
-    let cursor = std::io::Cursor::new(nt_string.as_bytes());
-    let reader = BufReader::new(cursor);
-
-    let mut triples: Vec<OwnedTriple> = Vec::with_capacity(opts.chunk_size);
-    let mut chunk = 0usize;
-    let mut total = 0usize;
-    let mut flush_err: Option<SplitterError> = None;
-
-    let flush = |triples: &mut Vec<OwnedTriple>,
-                 chunk: &mut usize,
-                 total: &mut usize,
-                 flush_err: &mut Option<SplitterError>| {
-        if triples.is_empty() {
-            return;
-        }
-        let out_path = chunk_path(input, RdfFormat::JsonLd, *chunk, opts);
-        let result = (|| -> Result<(), SplitterError> {
-            check_overwrite(&out_path, opts.force)?;
-            debug!("  writing chunk {} → {}", chunk, out_path.display());
-            let file = fs::File::create(&out_path)?;
-            let mut w = BufWriter::new(file);
-            write_jsonld(&mut w, triples)?;
-            Ok(())
-        })();
-        match result {
-            Ok(()) => {
-                *chunk += 1;
-                *total += triples.len();
-                triples.clear();
-            }
-            Err(e) => *flush_err = Some(e),
-        }
-    };
+/// Bytes read from an input to guess its format.
+const SNIFF_BYTES: u64 = 8 * 1024;
 
-    let mut parsed = 0usize;
-    let mut parser = NTriplesParser::new(reader);
-    parser
-        .parse_all(&mut |t: rio_api::model::Triple<'_>| -> Result<(), CallbackError> {
-            triples.push(OwnedTriple::from_rio(&t));
-            parsed += 1;
-            if parsed % PROGRESS_INTERVAL == 0 { show_progress(parsed); }
-            if triples.len() >= opts.chunk_size {
-                flush(&mut triples, &mut chunk, &mut total, &mut flush_err);
-            }
-            Ok(())
-        })
-        .map_err(|e| SplitterError::Parse(e.to_string()))?;
-    clear_progress();
+/// Guess the format of `input` from its first few KB (see
+/// [`detect_format_from_content`]); `.gz` files are looked into.  Standard
+/// input is never sniffed, as that would consume it, and neither are URLs,
+/// which would be downloaded twice.
+pub fn sniff_format(input: &Path) -> Result<Option<RdfFormat>, SplitterError> {
+    if stdio::is_stdin(input) || remote::is_remote(input) {
+        return Ok(None);
+    }
+    let mut head = Vec::new();
+    open_input(input)?.take(SNIFF_BYTES).read_to_end(&mut head)?;
+    Ok(detect_format_from_content(&String::from_utf8_lossy(&head)))
+}
 
-    if let Some(e) = flush_err {
-        return Err(e);
+// ─── chunking ───────────────────────────────────────────────────────────────
+
+/// Name of the default output series, whose chunks are `<stem>_NNNN.<ext>`.
+const DEFAULT_SERIES: &str = "";
+
+/// Series receiving schema statements under `--separate-tbox`.
+const TBOX_SERIES: &str = "tbox";
+
+/// Series of the `--partitions` files; the chunk number is the partition.
+const PARTITION_SERIES: &str = "part";
+
+/// A series name and, for partitions, the partition index.
+type SeriesKey = (Cow<'static, str>, usize);
+
+/// One sequence of chunks (`<stem>_<series>_NNNN.<ext>`) with its own counter.
+struct Series {
+    /// The chunk being written; opened by its first statement.
+    open: Option<OpenChunk>,
+    /// When the open chunk was last written, while it holds its files open.
+    resident: Option<u64>,
+    chunk: usize,
+    /// Records per chunk, re-estimated by `adaptive` after every chunk.
+    limit: usize,
+    adaptive: Option<AdaptiveSize>,
+    guards: Vec<Box<dyn BoundaryGuard>>,
+}
+
+impl Series {
+    /// A series whose first chunk is numbered `chunk`.
+    fn new(chunk: usize, opts: &SplitOptions) -> Self {
+        let mut guards: Vec<Box<dyn BoundaryGuard>> = vec![Box::new(ListGuard::default())];
+        if opts.keep_axioms || opts.bnode_strategy == BnodeStrategy::Cluster {
+            guards.push(Box::new(BlankNodeGuard::default()));
+        }
+        if let Some(kinds) = opts.keep_annotations {
+            guards.push(Box::new(AnnotationGuard::new(kinds)));
+        }
+        Self {
+            open: None,
+            resident: None,
+            chunk,
+            limit: opts.chunk_size,
+            adaptive: opts.adaptive.map(AdaptiveSize::new),
+            guards,
+        }
+    }
+
+    /// True when the open chunk is full, by count or by size with `next`.
+    fn is_full(&self, next: &OwnedQuad, opts: &SplitOptions) -> bool {
+        let Some(chunk) = &self.open else {
+            return false;
+        };
+        chunk.records >= self.limit
+            || opts.max_bytes.is_some_and(|max| {
+                chunk.writer.get_ref().written() + next.nquads_len() as u64 > max
+            })
     }
 
-    if !triples.is_empty() {
-        let out_path = chunk_path(input, RdfFormat::JsonLd, chunk, opts);
-        check_overwrite(&out_path, opts.force)?;
-        debug!("  writing chunk {} → {}", chunk, out_path.display());
-        let file = fs::File::create(&out_path)?;
-        let mut w = BufWriter::new(file);
-        write_jsonld(&mut w, &triples)?;
-        total += triples.len();
+    fn can_cut_before(&self, next: &OwnedTriple) -> bool {
+        self.guards.iter().all(|g| g.can_cut_before(next))
     }
+}
 
-    Ok(total)
+/// Routes statements to output series and streams them into each series'
+/// open chunk, starting a new chunk once a series reaches `chunk_size` (or
+/// `max_bytes`) and its boundary guards allow a cut.
+pub(crate) struct Chunker<'a> {
+    input: &'a Path,
+    fmt: RdfFormat,
+    opts: &'a SplitOptions,
+    series: HashMap<SeriesKey, Series>,
+    /// Prefixes declared so far, repeated at the top of every new chunk.
+    prefixes: Vec<(String, String)>,
+    /// How many prefixes of the current input were taken up.
+    input_prefixes: usize,
+    /// Source position of the statements pushed next (`--line-map`).
+    source: Option<SourceSpan>,
+    /// Number of the first chunk of the default series (`--tail`).
+    first_chunk: usize,
+    /// Series whose open chunk holds its files, by when it was last
+    /// written; the first is closed when `--max-open-files` is reached.
+    resident: BTreeMap<u64, SeriesKey>,
+    /// Files held by the resident chunks.
+    open_files: usize,
+    clock: u64,
+    /// Partition of the blank nodes seen so far (`--partitions`).
+    partition_owners: BlankOwners<usize>,
+    /// Whether the blank nodes seen so far belong to the TBox
+    /// (`--separate-tbox`).
+    tbox_owners: BlankOwners<bool>,
+    summary: SplitSummary,
 }
 
-/// Convert JSON-LD string to N-Triples via serde_json structural walk.
-fn jsonld_to_ntriples(raw: &str) -> Result<String, SplitterError> {
-    use serde_json::Value;
-    let v: Value =
-        serde_json::from_str(raw).map_err(|e| SplitterError::Parse(e.to_string()))?;
+impl<'a> Chunker<'a> {
+    /// Chunks are named after `input` and written as `fmt`.
+    pub(crate) fn new(input: &'a Path, fmt: RdfFormat, opts: &'a SplitOptions) -> Self {
+        Self {
+            input,
+            fmt,
+            opts,
+            series: HashMap::new(),
+            prefixes: Vec::new(),
+            input_prefixes: 0,
+            source: None,
+            first_chunk: 0,
+            resident: BTreeMap::new(),
+            open_files: 0,
+            clock: 0,
+            partition_owners: BlankOwners::default(),
+            tbox_owners: BlankOwners::default(),
+            summary: SplitSummary::default(),
+        }
+    }
 
-    let mut out = String::new();
-    match &v {
-        Value::Array(arr) => {
-            for node in arr {
-                extract_node(node, None, &mut out);
+    /// Files an open chunk holds: the chunk and its sidecars.
+    fn files_per_chunk(&self) -> usize {
+        1 + usize::from(self.opts.literal_index.is_some()) + usize::from(self.opts.line_map)
+    }
+
+    /// Mark the keyed series' chunk as written now, first closing the least
+    /// recently written chunks if opening or reopening its files would pass
+    /// `--max-open-files`.
+    fn make_resident(&mut self, key: &SeriesKey) -> Result<(), SplitterError> {
+        let Some(limit) = self.opts.max_open_files else {
+            return Ok(());
+        };
+        self.clock += 1;
+        let now = self.clock;
+        let series = self.series.get_mut(key).expect("series exists");
+        if let Some(then) = series.resident.replace(now) {
+            self.resident.remove(&then);
+            self.resident.insert(now, key.clone());
+            return Ok(());
+        }
+        let files = self.files_per_chunk();
+        while self.open_files + files > limit {
+            let Some((_, victim)) = self.resident.pop_first() else {
+                break;
+            };
+            let series = self.series.get_mut(&victim).expect("resident series exists");
+            series.resident = None;
+            let chunk = series.open.as_mut().expect("resident series has a chunk");
+            if !chunk.writer.get_ref().is_suspendable() {
+                return Err(SplitterError::Other(anyhow::anyhow!(
+                    "more chunks are open at once than --max-open-files {limit} allows, and \
+                     encrypted chunks cannot be closed and reopened; raise the limit \
+                     (`ulimit -n`) or write fewer series"
+                )));
             }
+            debug!("  suspending {}", chunk.path.display());
+            chunk.suspend()?;
+            self.open_files -= files;
         }
-        Value::Object(_) => {
-            extract_node(&v, None, &mut out);
+        self.resident.insert(now, key.clone());
+        self.open_files += files;
+        Ok(())
+    }
+
+    /// Forget the keyed series' chunk in the pool once it is closed.
+    fn release(&mut self, key: &SeriesKey) {
+        if let Some(then) = self.series.get_mut(key).and_then(|s| s.resident.take()) {
+            self.resident.remove(&then);
+            self.open_files -= self.files_per_chunk();
         }
-        _ => {}
     }
-    Ok(out)
-}
 
-fn expand_iri(s: &str) -> String {
-    if s.starts_with("http://") || s.starts_with("https://") || s.starts_with("urn:") {
-        format!("<{s}>")
-    } else if s.starts_with("_:") {
-        s.to_owned()
-    } else {
-        format!("<{s}>")
+    /// Take up the prefixes of the current input's `known` not seen before,
+    /// declaring them in the chunks already open; `known` only ever grows.
+    /// A name an earlier input declared keeps its first namespace.
+    fn declare_prefixes(&mut self, known: &[(String, String)]) -> Result<(), SplitterError> {
+        let Some(new) = known.get(self.input_prefixes..).filter(|new| !new.is_empty()) else {
+            return Ok(());
+        };
+        self.input_prefixes = known.len();
+        let new: Vec<(String, String)> = new
+            .iter()
+            .filter(|(p, _)| !self.prefixes.iter().any(|(known, _)| known == p))
+            .cloned()
+            .collect();
+        let mut open: Vec<SeriesKey> = self
+            .series
+            .iter()
+            .filter(|(_, s)| s.open.is_some())
+            .map(|(key, _)| key.clone())
+            .collect();
+        open.sort();
+        for key in &open {
+            self.make_resident(key)?;
+            let chunk = self.series.get_mut(key).and_then(|s| s.open.as_mut());
+            let chunk = chunk.expect("series has an open chunk");
+            for (prefix, ns) in new.iter().filter(|(p, _)| !is_configured(p, self.opts)) {
+                chunk.writer.declare_prefix(prefix, ns)?;
+            }
+        }
+        self.prefixes.extend(new);
+        Ok(())
     }
-}
 
-fn extract_node(node: &serde_json::Value, graph: Option<&str>, out: &mut String) {
-    use serde_json::Value;
-    let obj = match node.as_object() {
-        Some(o) => o,
-        None => return,
-    };
+    /// The series a statement belongs to.
+    fn route(&self, q: &OwnedQuad) -> SeriesKey {
+        if self.opts.separate_tbox && is_tbox(q) {
+            return (TBOX_SERIES.into(), 0);
+        }
+        if self.opts.route_by_authority {
+            if let Some(host) = authority::series_name(&q.triple.subject) {
+                return (host.into(), 0);
+            }
+        }
+        let name = match &self.opts.hot_predicates {
+            Some(hot) if hot.contains(&q.triple.predicate) => "hot",
+            Some(_) => "cold",
+            None => DEFAULT_SERIES,
+        };
+        (name.into(), 0)
+    }
 
-    if let Some(Value::Array(graph_nodes)) = obj.get("@graph") {
-        let g = obj
-            .get("@id")
-            .and_then(|v| v.as_str())
-            .map(|s| expand_iri(s));
-        for n in graph_nodes {
-            extract_node(n, g.as_deref(), out);
+    fn push(&mut self, q: OwnedQuad) -> Result<(), SplitterError> {
+        match self.opts.partition {
+            Some(parts) => {
+                let mut ready = Vec::new();
+                self.partition_owners.route(q, |q| parts.of(q), &mut ready);
+                self.push_partitioned(ready)
+            }
+            None if self.opts.separate_tbox => {
+                let mut ready = Vec::new();
+                self.tbox_owners.route(q, is_tbox, &mut ready);
+                self.push_separated(ready)
+            }
+            None => self.push_to(self.route(&q), q, true),
         }
-        return;
     }
 
-    let subject = match obj.get("@id").and_then(|v| v.as_str()) {
-        Some(id) => expand_iri(id),
-        None => return,
-    };
+    /// Append statements to the TBox series when they or the resource
+    /// naming their blank subject (a class expression, the cells of an
+    /// `owl:unionOf` list) belong to it, and to their series otherwise.
+    fn push_separated(&mut self, ready: Vec<(bool, OwnedQuad)>) -> Result<(), SplitterError> {
+        for (tbox, q) in ready {
+            self.push_to(self.tbox_key(tbox, &q), q, true)?;
+        }
+        Ok(())
+    }
 
-    for (key, values) in obj {
-        if key == "@id" || key == "@context" {
-            continue;
+    /// The series of `q`, given whether it belongs to the TBox.
+    fn tbox_key(&self, tbox: bool, q: &OwnedQuad) -> SeriesKey {
+        if tbox {
+            (TBOX_SERIES.into(), 0)
+        } else {
+            self.route(q)
+        }
+    }
+
+    /// Append statements to their partitions.
+    fn push_partitioned(&mut self, ready: Vec<(usize, OwnedQuad)>) -> Result<(), SplitterError> {
+        for (part, q) in ready {
+            self.push_to((PARTITION_SERIES.into(), part), q, true)?;
         }
-        let predicate = if key == "@type" {
-            "<http://www.w3.org/1999/02/22-rdf-syntax-ns#type>".to_owned()
+        Ok(())
+    }
+
+    /// Push a `--group-by` group; chunks are only cut before its first
+    /// statement in each series.
+    fn push_group(&mut self, group: Vec<OwnedQuad>) -> Result<(), SplitterError> {
+        if self.opts.partition.is_some() {
+            // partitions are never cut
+            return group.into_iter().try_for_each(|q| self.push(q));
+        }
+        let routed: Vec<(SeriesKey, OwnedQuad)> = if self.opts.separate_tbox {
+            let mut ready = Vec::new();
+            for q in group {
+                self.tbox_owners.route(q, is_tbox, &mut ready);
+            }
+            ready.into_iter().map(|(tbox, q)| (self.tbox_key(tbox, &q), q)).collect()
         } else {
-            expand_iri(key)
+            group.into_iter().map(|q| (self.route(&q), q)).collect()
         };
+        let mut started: Vec<SeriesKey> = Vec::new();
+        for (key, q) in routed {
+            let first = !started.contains(&key);
+            if first {
+                started.push(key.clone());
+            }
+            self.push_to(key, q, first)?;
+        }
+        Ok(())
+    }
 
-        let vals: Vec<&Value> = match values {
-            Value::Array(a) => a.iter().collect(),
-            other => vec![other],
+    /// Append `q` to the keyed series; `may_cut` allows ending the current
+    /// chunk before it.
+    fn push_to(&mut self, key: SeriesKey, q: OwnedQuad, may_cut: bool) -> Result<(), SplitterError> {
+        let opts = self.opts;
+        let first = key.1 + self.first_chunk;
+        let series = self.series.entry(key.clone()).or_insert_with(|| Series::new(first, opts));
+        // the TBox is kept in one piece for reasoners, partitions are never cut
+        if ![TBOX_SERIES, PARTITION_SERIES].contains(&&*key.0)
+            && may_cut
+            && series.is_full(&q, self.opts)
+            && series.can_cut_before(&q.triple)
+        {
+            self.flush(&key)?;
+        }
+        self.make_resident(&key)?;
+        let series = self.series.get_mut(&key).expect("series created above");
+        for guard in &mut series.guards {
+            guard.observe(&q.triple);
+        }
+        let chunk = match &mut series.open {
+            Some(chunk) => chunk,
+            None => series
+                .open
+                .insert(OpenChunk::create(
+                    self.input,
+                    self.fmt,
+                    &key.0,
+                    series.chunk,
+                    &self.prefixes,
+                    self.source.is_some(),
+                    opts,
+                )?),
         };
+        chunk.write(&q, self.source)
+    }
 
-        for val in vals {
-            if let Some(o) = jsonld_value_to_nt_object(key, val) {
-                if let Some(g) = graph {
-                    out.push_str(&format!("{subject} {predicate} {o} {g} .\n"));
-                } else {
-                    out.push_str(&format!("{subject} {predicate} {o} .\n"));
-                }
+    /// Close the open chunk of a series.
+    fn flush(&mut self, key: &SeriesKey) -> Result<(), SplitterError> {
+        if self.series.get(key).is_none_or(|s| s.open.is_none()) {
+            return Ok(());
+        }
+        // closing a suspended chunk reopens it for its trailer
+        self.make_resident(key)?;
+        self.release(key);
+        let series = self.series.get_mut(key).expect("series has an open chunk");
+        let chunk = series.open.take().expect("series has an open chunk");
+        let info = chunk.close(self.opts)?;
+        if let Some(checkpoint) = &self.opts.checkpoint {
+            checkpoint.borrow_mut().chunk_done(self.input, &info)?;
+        }
+        series.chunk += 1;
+        if let Some(adaptive) = &mut series.adaptive {
+            adaptive.observe(info.bytes, info.records);
+            series.limit = adaptive.records(self.opts.chunk_size);
+            debug!("  next chunk: {} records", series.limit);
+        }
+        self.summary.records += info.records;
+        self.summary.chunks.push(info);
+        for guard in &mut series.guards {
+            guard.chunk_closed();
+        }
+        Ok(())
+    }
+
+    /// Close every open chunk, returning how many; the series go on in new
+    /// chunks.  Boundary guards are not waited for.
+    fn close_open(&mut self) -> Result<usize, SplitterError> {
+        let mut keys: Vec<SeriesKey> = self
+            .series
+            .iter()
+            .filter(|(_, s)| s.open.is_some())
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort();
+        for key in &keys {
+            self.flush(key)?;
+        }
+        Ok(keys.len())
+    }
+
+    /// Close every partially filled series.
+    pub(crate) fn finish(mut self) -> Result<SplitSummary, SplitterError> {
+        if let Some(parts) = self.opts.partition {
+            let mut ready = Vec::new();
+            self.partition_owners.finish(|q| parts.of(q), &mut ready);
+            self.push_partitioned(ready)?;
+        } else if self.opts.separate_tbox {
+            let mut ready = Vec::new();
+            self.tbox_owners.finish(is_tbox, &mut ready);
+            self.push_separated(ready)?;
+        }
+        self.close_open()?;
+        Ok(self.summary)
+    }
+
+    /// Remove the chunks still open after a failed input, so no truncated
+    /// chunk is left behind.  Completed chunks are kept.  A chunk already
+    /// streamed to standard output cannot be taken back.
+    pub(crate) fn abandon(self) {
+        for chunk in self.series.into_values().filter_map(|s| s.open) {
+            if self.opts.stdout.is_some() {
+                warn!("{}: incomplete chunk on standard output", chunk.path.display());
+            } else if !self.opts.dry_run {
+                chunk.discard();
             }
         }
     }
 }
 
-fn jsonld_value_to_nt_object(key: &str, val: &serde_json::Value) -> Option<String> {
-    use serde_json::Value;
-    match val {
-        Value::Object(m) => {
-            if let Some(iri) = m.get("@id").and_then(|v| v.as_str()) {
-                return Some(expand_iri(iri));
+/// True for a schema statement (`--separate-tbox`).
+fn is_tbox(q: &OwnedQuad) -> bool {
+    vocab::is_tbox(&q.triple.predicate, &q.triple.object)
+}
+
+/// True for a prefix name set with `--prefix`.
+fn is_configured(prefix: &str, opts: &SplitOptions) -> bool {
+    opts.prefixes.iter().any(|(p, _)| p == prefix)
+}
+
+/// A chunk file being written, with its sidecars.
+struct OpenChunk {
+    path: PathBuf,
+    writer: ChunkWriter<ChunkFile>,
+    records: usize,
+    links: Option<ChunkLinks>,
+    literals: Option<SidecarWriter>,
+    lines: Option<LineMapWriter>,
+    /// Prefix of the chunk's blank-node labels (`--bnode-strategy rename`).
+    bnode_prefix: Option<String>,
+    /// Records and size of the chunk as an earlier run completed it
+    /// (`--resume`); it is only counted, not written again.
+    kept: Option<(usize, u64)>,
+}
+
+impl OpenChunk {
+    fn create(
+        input: &Path,
+        fmt: RdfFormat,
+        series: &str,
+        chunk: usize,
+        prefixes: &[(String, String)],
+        source_tracked: bool,
+        opts: &SplitOptions,
+    ) -> Result<Self, SplitterError> {
+        let path = chunk_path(input, fmt, series, chunk, opts);
+        let appending = opts.append && path.exists();
+        let kept = opts.checkpoint.as_ref().and_then(|c| c.borrow().kept(&path));
+        let mut file = if kept.is_some() {
+            debug!("  keeping chunk {} → {}", chunk, path.display());
+            ChunkFile::discard()
+        } else {
+            debug!("  writing chunk {} → {}", chunk, path.display());
+            create_chunk_file(input, &path, series, chunk, opts)?
+        };
+        if let Some(text) = &opts.chunk_header {
+            file.write_all(text.as_bytes())?;
+        }
+        let mut writer = ChunkWriter::compacted(file, fmt, opts.jsonld_context.clone())?;
+        let input_prefixes = prefixes.iter().filter(|(p, _)| !is_configured(p, opts));
+        for (prefix, ns) in opts.prefixes.iter().chain(input_prefixes) {
+            writer.declare_prefix(prefix, ns)?;
+        }
+        // header statements are repeated verbatim at the top of every chunk,
+        // once per file when appending; prefixes are declared again
+        for q in opts.header.iter().filter(|_| !appending) {
+            writer.write(q)?;
+        }
+        let literals = match opts.literal_index {
+            Some(kind) if !opts.dry_run && kept.is_none() => {
+                Some(SidecarWriter::create(&path, kind)?)
             }
-            let value = m.get("@value")?.as_str()?;
-            if let Some(lang) = m.get("@language").and_then(|v| v.as_str()) {
-                return Some(format!(r#""{}"@{}"#, nt_escape(value), lang));
+            _ => None,
+        };
+        let lines = match source_tracked && !opts.dry_run && kept.is_none() {
+            true => Some(LineMapWriter::create(&path)?),
+            false => None,
+        };
+        let bnode_prefix = (opts.bnode_strategy == BnodeStrategy::Rename).then(|| {
+            bnodes::chunk_prefix(&path.file_name().unwrap_or_default().to_string_lossy())
+        });
+        Ok(Self {
+            path,
+            writer,
+            records: 0,
+            links: opts.collect_links.then(ChunkLinks::default),
+            literals,
+            lines,
+            bnode_prefix,
+            kept,
+        })
+    }
+
+    fn write(&mut self, q: &OwnedQuad, source: Option<SourceSpan>) -> Result<(), SplitterError> {
+        let relabelled;
+        let q = match &self.bnode_prefix {
+            Some(prefix) => {
+                relabelled = bnodes::relabel(q, prefix);
+                &relabelled
             }
-            if let Some(dt) = m.get("@type").and_then(|v| v.as_str()) {
-                return Some(format!(
-                    r#""{}"^^{}"#,
-                    nt_escape(value),
-                    expand_iri(dt)
-                ));
+            None => q,
+        };
+        self.writer.write(q)?;
+        self.records += 1;
+        if let (Some(lines), Some(span)) = (&mut self.lines, source) {
+            lines.add(span)?;
+        }
+        if let Some(links) = &mut self.links {
+            links.subjects.insert(q.triple.subject.clone());
+            if q.triple.object.starts_with('<') {
+                links.objects.insert(q.triple.object.clone());
             }
-            Some(format!(r#""{}""#, nt_escape(value)))
         }
-        Value::String(s) => {
-            if key == "@type" {
-                Some(expand_iri(s))
-            } else {
-                Some(format!(r#""{}""#, nt_escape(s)))
+        if let Some(literals) = &mut self.literals {
+            literals.add(q)?;
+        }
+        Ok(())
+    }
+
+    fn close(self, opts: &SplitOptions) -> Result<ChunkInfo, SplitterError> {
+        let mut file = self.writer.finish()?;
+        if let Some(text) = &opts.chunk_footer {
+            file.write_all(text.as_bytes())?;
+        }
+        if let Some(literals) = self.literals {
+            paths::set_mode(&literals.finish()?, opts.file_mode)?;
+        }
+        if let Some(lines) = self.lines {
+            paths::set_mode(&lines.finish()?, opts.file_mode)?;
+        }
+        if let Some((records, _)) = self.kept.filter(|&(records, _)| records != self.records) {
+            return Err(SplitterError::Other(anyhow::anyhow!(
+                "{}: {} record(s) now, {} in the earlier run; split again without --resume",
+                self.path.display(),
+                self.records,
+                records
+            )));
+        }
+        let info = finish_chunk_file(file, self.path, self.records, opts)?;
+        Ok(ChunkInfo { links: self.links, ..info })
+    }
+
+    /// Close the chunk's files until its next record.
+    fn suspend(&mut self) -> Result<(), SplitterError> {
+        self.writer.get_mut().suspend()?;
+        if let Some(literals) = &mut self.literals {
+            literals.suspend()?;
+        }
+        if let Some(lines) = &mut self.lines {
+            lines.suspend()?;
+        }
+        Ok(())
+    }
+
+    fn discard(self) {
+        if let Some(literals) = self.literals {
+            if let Ok(sidecar) = literals.finish() {
+                let _ = fs::remove_file(sidecar);
             }
         }
-        Value::Bool(b) => Some(format!(
-            r#""{}"^^<http://www.w3.org/2001/XMLSchema#boolean>"#,
-            b
-        )),
-        Value::Number(n) => Some(format!(
-            r#""{}"^^<http://www.w3.org/2001/XMLSchema#decimal>"#,
-            n
-        )),
-        _ => None,
+        if let Some(lines) = self.lines {
+            if let Ok(sidecar) = lines.finish() {
+                let _ = fs::remove_file(sidecar);
+            }
+        }
+        drop(self.writer);
+        if self.kept.is_none() {
+            let _ = fs::remove_file(&self.path);
+        }
     }
 }
 
-fn nt_escape(s: &str) -> String {
+pub(crate) fn nt_escape(s: &str) -> String {
     s.replace('\\', "\\\\")
         .replace('"', "\\\"")
         .replace('\n', "\\n")
@@ -537,39 +1731,160 @@ fn nt_escape(s: &str) -> String {
 
 // ─── path helpers ────────────────────────────────────────────────────────────
 
-fn file_base_iri(path: &Path) -> String {
-    // Produce a valid file:/// IRI usable as RDF base
-    let abs = path
-        .canonicalize()
-        .unwrap_or_else(|_| path.to_path_buf());
-    let s = abs.display().to_string().replace('\\', "/");
-    if s.starts_with('/') {
-        format!("file://{s}")
+/// `<stem>_NNNN.<ext>` for the default series, `<stem>_<series>_NNNN.<ext>` otherwise.
+pub(crate) fn chunk_path(
+    input: &Path,
+    fmt: RdfFormat,
+    series: &str,
+    chunk: usize,
+    opts: &SplitOptions,
+) -> PathBuf {
+    let stem = chunk_stem(input, opts);
+    let mut name = if opts.unnumbered && series.is_empty() {
+        format!("{}.{}", stem, fmt.extension())
+    } else if series.is_empty() {
+        format!("{}_{:04}.{}", stem, chunk, fmt.extension())
     } else {
-        format!("file:///{s}")
+        format!("{}_{}_{:04}.{}", stem, series, chunk, fmt.extension())
+    };
+    if let Some(compression) = opts.compress {
+        name = format!("{name}.{}", compression.extension());
+    }
+    if let Some(encryption) = &opts.encrypt {
+        name = format!("{name}.{}", encryption.extension());
     }
+    let dir = staging_dir(input, opts).unwrap_or_else(|| input_output_dir(input, opts));
+    paths::output_path(dir.join(name))
+}
+
+/// Stem of the chunk names of `input`: its file stem, with
+/// `--suffix-input-hash` followed by `_` and [`input_hash`].
+pub fn chunk_stem(input: &Path, opts: &SplitOptions) -> String {
+    if opts.suffix_input_hash {
+        format!("{}_{}", input_stem(input), input_hash(input))
+    } else {
+        input_stem(input)
+    }
+}
+
+/// First 8 hex digits of the SHA-256 of the input path as given.
+pub fn input_hash(input: &Path) -> String {
+    let mut digest = sha256_hex(input.to_string_lossy().as_bytes()).unwrap_or_default();
+    digest.truncate(8);
+    digest
+}
+
+/// Directory receiving the chunks of `input`: the output directory, or with
+/// `--preserve-structure` the mirror of the input's directory below it.
+pub fn input_output_dir(input: &Path, opts: &SplitOptions) -> PathBuf {
+    if opts.preserve_structure {
+        opts.output_dir.join(paths::mirrored_dir(input))
+    } else {
+        opts.output_dir.clone()
+    }
+}
+
+/// Create the file of a chunk, compressed and encrypted if requested; a gzip
+/// header records the source input and the chunk's place in its series.
+/// With `--stdout` the chunk goes to standard output instead.
+pub(crate) fn create_chunk_file(
+    input: &Path,
+    path: &Path,
+    series: &str,
+    chunk: usize,
+    opts: &SplitOptions,
+) -> Result<ChunkFile, SplitterError> {
+    let file = if let Some(mode) = opts.stdout {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let marker = (mode == StdoutMode::Marked).then(|| stdio::chunk_marker(&name));
+        ChunkFile::stdout(marker.as_deref())?
+    } else {
+        // a staged chunk replaces its namesake in the output directory later
+        let target = input_output_dir(input, opts).join(path.file_name().unwrap_or_default());
+        let resuming = opts.checkpoint.as_ref().is_some_and(|c| c.borrow().is_resuming());
+        if !opts.append {
+            check_overwrite(&target, opts.force || resuming)?;
+        }
+        if opts.dry_run {
+            ChunkFile::discard()
+        } else {
+            let source = input.file_name().unwrap_or_default().to_string_lossy();
+            let comment = if series.is_empty() {
+                format!("source={source} chunk={chunk}")
+            } else {
+                format!("source={source} series={series} chunk={chunk}")
+            };
+            match opts.append {
+                true => ChunkFile::append(path, opts.compress, opts.compress_tuning, &comment)?,
+                false => ChunkFile::create(
+                    path,
+                    opts.compress,
+                    opts.compress_tuning,
+                    opts.encrypt.as_ref(),
+                    &comment,
+                )?,
+            }
+        }
+    };
+    Ok(match opts.fault_injection {
+        Some(spec) => file.with_faults(spec, chunk),
+        None => file,
+    })
 }
 
-fn chunk_path(input: &Path, fmt: RdfFormat, chunk: usize, opts: &SplitOptions) -> PathBuf {
-    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
-    let name = format!("{}_{:04}.{}", stem, chunk, fmt.extension());
-    opts.output_dir.join(name)
+/// Complete a chunk started with [`create_chunk_file`].
+pub(crate) fn finish_chunk_file(
+    file: ChunkFile,
+    path: PathBuf,
+    records: usize,
+    opts: &SplitOptions,
+) -> Result<ChunkInfo, SplitterError> {
+    let written = file.written();
+    file.finish()?;
+    let bytes = if opts.stdout.is_some() || opts.dry_run {
+        written
+    } else {
+        paths::set_mode(&path, opts.file_mode)?;
+        fs::metadata(&path)?.len()
+    };
+    Ok(ChunkInfo { path, records, bytes, links: None })
 }
 
-fn check_overwrite(path: &Path, force: bool) -> Result<(), SplitterError> {
+pub(crate) fn check_overwrite(path: &Path, force: bool) -> Result<(), SplitterError> {
     if path.exists() && !force {
         return Err(SplitterError::OutputExists(path.display().to_string()));
     }
     Ok(())
 }
 
-fn prepare_output_dir(dir: &Path, force: bool) -> Result<(), SplitterError> {
+pub(crate) fn prepare_output_dir(opts: &SplitOptions) -> Result<(), SplitterError> {
+    let dir = &opts.output_dir;
     if dir.exists() {
         return Ok(());
     }
-    if !force {
+    if !opts.force {
         return Err(SplitterError::OutputDirMissing(dir.display().to_string()));
     }
+    if opts.dry_run {
+        return Ok(());
+    }
     fs::create_dir_all(dir)?;
+    paths::set_mode(dir, opts.dir_mode)?;
+    Ok(())
+}
+
+/// Create the mirror of `input`'s directory with `--preserve-structure`.
+fn prepare_input_dir(input: &Path, opts: &SplitOptions) -> Result<(), SplitterError> {
+    if !opts.preserve_structure || opts.dry_run {
+        return Ok(());
+    }
+    let mut dir = opts.output_dir.clone();
+    for part in paths::mirrored_dir(input).components() {
+        dir.push(part);
+        if !dir.exists() {
+            fs::create_dir(&dir)?;
+            paths::set_mode(&dir, opts.dir_mode)?;
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,70 @@
+//! Leftover chunks of earlier runs (`--clean-stale`).
+//!
+//! A run with `--force` overwrites the chunks it writes, but when an earlier
+//! run into the same directory wrote more chunks (or other series) the rest
+//! of them stay behind, and a consumer picking up `<stem>_*` gets a half-old,
+//! half-new chunk set.  Files matching the chunk names of an input are listed
+//! before it is split; those the run did not write are stale.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+
+use crate::format::RdfFormat;
+
+/// Whether `name` looks like a chunk of `stem`: `<stem>_[<series>_]NNNN.<ext>`,
+/// optionally compressed and encrypted.  Sidecars such as
+/// `<stem>_0000.lines.tsv` do not match.
+pub fn is_chunk_name(name: &str, stem: &str) -> bool {
+    let pattern = format!(
+        r"^{}_(?:.+_)?\d{{4,}}\.([^.]+)(?:\.[A-Za-z0-9]+)*$",
+        regex::escape(stem)
+    );
+    let Ok(re) = Regex::new(&pattern) else {
+        return false;
+    };
+    re.captures(name)
+        .is_some_and(|c| RdfFormat::from_extension(&c[1]).is_some())
+}
+
+/// Files in `dir` named like chunks of `stem`, sorted; none when `dir` does
+/// not exist yet.
+pub fn chunk_files(dir: &Path, stem: &str) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name();
+        if entry.file_type()?.is_file() && is_chunk_name(&name.to_string_lossy(), stem) {
+            files.push(entry.path());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_chunk_names_of_a_stem() {
+        assert!(is_chunk_name("data_0000.nt", "data"));
+        assert!(is_chunk_name("data_0012.ttl.gz", "data"));
+        assert!(is_chunk_name("data_part_0003.nq", "data"));
+        assert!(is_chunk_name("data_data.sncf.fr_0001.nt.gz.age", "data"));
+        assert!(is_chunk_name("my.data_10000.trig", "my.data"));
+        assert!(!is_chunk_name("data_0000.lines.tsv", "data"));
+        assert!(!is_chunk_name("data.nt", "data"));
+        assert!(!is_chunk_name("data_12.nt", "data"));
+        assert!(!is_chunk_name("other_0000.nt", "data"));
+        assert!(!is_chunk_name("mydata_0000.nt", "data"));
+    }
+}
@@ -0,0 +1,235 @@
+//! Dataset statistics (`rdfsplitter stats`).
+//!
+//! Answers "what is in this dump?" before deciding how to split it: the
+//! statement total, a per-named-graph breakdown and instance counts per class.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use log::warn;
+
+use crate::{
+    format::{RdfFormat, SplitterError},
+    inputs::expand_inputs,
+    serialise::OwnedQuad,
+    splitter, vocab,
+};
+
+/// Label used for the default graph.
+const DEFAULT_GRAPH: &str = "(default)";
+
+#[derive(Debug, Default)]
+struct GraphStats {
+    triples: usize,
+    subjects: HashSet<String>,
+}
+
+/// One line of the graph table.
+#[derive(Debug, PartialEq)]
+pub struct GraphRow {
+    pub graph: String,
+    pub triples: usize,
+    pub subjects: usize,
+    /// Share of all statements, in percent.
+    pub share: f64,
+}
+
+/// Statistics accumulated over all statements of all inputs.
+#[derive(Debug, Default)]
+pub struct Stats {
+    total: usize,
+    graphs: HashMap<String, GraphStats>,
+    /// Distinct instances per `rdf:type` object.
+    classes: HashMap<String, HashSet<String>>,
+}
+
+impl Stats {
+    pub fn observe(&mut self, q: &OwnedQuad) {
+        self.total += 1;
+        let graph = q.graph_name.as_deref().unwrap_or(DEFAULT_GRAPH);
+        let g = match self.graphs.get_mut(graph) {
+            Some(g) => g,
+            None => self.graphs.entry(graph.to_string()).or_default(),
+        };
+        g.triples += 1;
+        if !g.subjects.contains(&q.triple.subject) {
+            g.subjects.insert(q.triple.subject.clone());
+        }
+        if q.triple.predicate == vocab::RDF_TYPE {
+            self.classes
+                .entry(q.triple.object.clone())
+                .or_default()
+                .insert(q.triple.subject.clone());
+        }
+    }
+
+    /// `(class, instances)` by descending instance count (ties by class).
+    pub fn class_rows(&self) -> Vec<(&str, usize)> {
+        let mut rows: Vec<_> = self
+            .classes
+            .iter()
+            .map(|(class, instances)| (class.as_str(), instances.len()))
+            .collect();
+        rows.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        rows
+    }
+
+    /// Write every class count as CSV: `class,instances`.
+    pub fn write_classes_csv(&self, path: &Path) -> io::Result<()> {
+        let mut w = BufWriter::new(fs::File::create(path)?);
+        writeln!(w, "class,instances")?;
+        for (class, n) in self.class_rows() {
+            writeln!(w, "\"{}\",{}", class.replace('"', "\"\""), n)?;
+        }
+        w.flush()
+    }
+
+    /// Graphs by descending triple count (ties by name).
+    pub fn graph_rows(&self) -> Vec<GraphRow> {
+        let mut rows: Vec<GraphRow> = self
+            .graphs
+            .iter()
+            .map(|(name, g)| GraphRow {
+                graph: name.clone(),
+                triples: g.triples,
+                subjects: g.subjects.len(),
+                share: 100.0 * g.triples as f64 / self.total.max(1) as f64,
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            b.triples
+                .cmp(&a.triples)
+                .then_with(|| a.graph.cmp(&b.graph))
+        });
+        rows
+    }
+
+    /// Print the report, limiting tables to `top` rows.
+    pub fn write_report(&self, w: &mut impl Write, top: usize) -> io::Result<()> {
+        writeln!(w, "Statements: {}", self.total)?;
+        let rows = self.graph_rows();
+        writeln!(w)?;
+        writeln!(w, "Graphs (top {} of {}):", top.min(rows.len()), rows.len())?;
+        writeln!(
+            w,
+            "  {:>12}  {:>12}  {:>7}  graph",
+            "triples", "subjects", "share"
+        )?;
+        for r in rows.iter().take(top) {
+            writeln!(
+                w,
+                "  {:>12}  {:>12}  {:>6.2}%  {}",
+                r.triples, r.subjects, r.share, r.graph
+            )?;
+        }
+
+        let classes = self.class_rows();
+        writeln!(w)?;
+        writeln!(
+            w,
+            "Classes (top {} of {}):",
+            top.min(classes.len()),
+            classes.len()
+        )?;
+        writeln!(w, "  {:>12}  class", "instances")?;
+        for (class, n) in classes.iter().take(top) {
+            writeln!(w, "  {n:>12}  {class}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Gather statistics over `inputs` and print them to stdout; optionally
+/// write the full class table to `classes_csv`.
+pub fn run_stats(
+    inputs: &[String],
+    recursive: bool,
+    top: usize,
+    classes_csv: Option<&Path>,
+) -> Result<(), SplitterError> {
+    let files = expand_inputs(inputs, recursive).map_err(SplitterError::Other)?;
+    if files.is_empty() {
+        return Err(SplitterError::Parse(
+            "No input files found. Check your patterns or paths.".into(),
+        ));
+    }
+
+    let mut stats = Stats::default();
+    for path in &files {
+        match RdfFormat::from_path(path) {
+            Some(fmt) if !fmt.is_sparql_results() => {
+                splitter::parse_quads(path, fmt, &mut |q| {
+                    stats.observe(&q);
+                    Ok(())
+                })?;
+            }
+            _ => warn!("Skipping '{}': not an RDF file", path.display()),
+        }
+    }
+
+    stats.write_report(&mut io::stdout().lock(), top)?;
+    if let Some(path) = classes_csv {
+        stats.write_classes_csv(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialise::OwnedTriple;
+
+    fn q(s: &str, g: Option<&str>) -> OwnedQuad {
+        typed(s, "<p>", "<o>", g)
+    }
+
+    fn typed(s: &str, p: &str, o: &str, g: Option<&str>) -> OwnedQuad {
+        OwnedQuad {
+            triple: OwnedTriple {
+                subject: s.into(),
+                predicate: p.into(),
+                object: o.into(),
+            },
+            graph_name: g.map(String::from),
+        }
+    }
+
+    #[test]
+    fn counts_triples_and_subjects_per_graph() {
+        let mut stats = Stats::default();
+        for quad in [
+            q("<a>", Some("<g1>")),
+            q("<a>", Some("<g1>")),
+            q("<b>", Some("<g1>")),
+            q("<a>", None),
+        ] {
+            stats.observe(&quad);
+        }
+        let rows = stats.graph_rows();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            (rows[0].graph.as_str(), rows[0].triples, rows[0].subjects),
+            ("<g1>", 3, 2)
+        );
+        assert_eq!(rows[0].share, 75.0);
+        assert_eq!(rows[1].graph, DEFAULT_GRAPH);
+    }
+
+    #[test]
+    fn counts_distinct_instances_per_class() {
+        let mut stats = Stats::default();
+        for quad in [
+            typed("<a>", vocab::RDF_TYPE, "<C>", None),
+            typed("<a>", vocab::RDF_TYPE, "<C>", Some("<g>")),
+            typed("<b>", vocab::RDF_TYPE, "<C>", None),
+            typed("<b>", vocab::RDF_TYPE, "<D>", None),
+        ] {
+            stats.observe(&quad);
+        }
+        assert_eq!(stats.class_rows(), [("<C>", 2), ("<D>", 1)]);
+    }
+}
@@ -0,0 +1,49 @@
+//! Standard input and output (`-`, `--stdout`).
+//!
+//! An input path of `-` reads statements from standard input; its format
+//! cannot be guessed from an extension and must be given with `--format`.
+//! Chunks are named `stdin_NNNN.<ext>`.  Since standard input can be read
+//! only once, options needing a second pass over the input are refused.
+//!
+//! With `--stdout` the chunks are written to standard output instead of
+//! files, each preceded by a [`chunk_marker`] line unless the plain mode
+//! asks for one uninterrupted stream.
+
+use std::path::Path;
+
+/// The input path naming standard input.
+pub const STDIN: &str = "-";
+
+/// True for the `-` input path.
+pub fn is_stdin(path: &Path) -> bool {
+    path == Path::new(STDIN)
+}
+
+/// How chunks are written to standard output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum StdoutMode {
+    /// Precede every chunk with a `# rdfsplitter-chunk: <name>` line
+    #[default]
+    Marked,
+    /// Concatenate the chunks without boundaries
+    Plain,
+}
+
+/// The line announcing chunk `name` on standard output.  It is a comment
+/// in N-Triples, N-Quads, Turtle and TriG, so a marked stream of those
+/// formats is still one valid document.
+pub fn chunk_marker(name: &str) -> String {
+    format!("# rdfsplitter-chunk: {name}\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_stdin() {
+        assert!(is_stdin(Path::new("-")));
+        assert!(!is_stdin(Path::new("./-")));
+        assert!(!is_stdin(Path::new("data.nt")));
+    }
+}
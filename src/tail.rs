@@ -0,0 +1,123 @@
+//! Resumable splitting of growing inputs (`--tail`).
+//!
+//! Rotating exports keep appending N-Triples or N-Quads to a file.  With
+//! `--tail` an input is split up to its last complete line, and a hidden
+//! state file next to the chunks records the byte offset reached and the
+//! number of the next chunk; the next run starts reading there and only
+//! writes chunks for the records appended since.  A partial last line, still
+//! being written, is left for the next run.  An input shorter than the
+//! recorded offset was rotated and is read from the start again, its chunks
+//! numbered on.
+
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+
+/// Where the previous `--tail` run of an input stopped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TailState {
+    /// Bytes of the input split so far.
+    pub offset: u64,
+    /// Number of the next chunk of the input.
+    pub next_chunk: usize,
+}
+
+/// `.<stem>.rdfsplitter-tail.json` in the directory of the chunks.
+pub fn state_path(dir: &Path, stem: &str) -> PathBuf {
+    dir.join(format!(".{stem}.rdfsplitter-tail.json"))
+}
+
+impl TailState {
+    /// The recorded state, or the start of the input before the first run.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let raw = match fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        let doc: Value = serde_json::from_str(&raw).map_err(io::Error::other)?;
+        let field = |name: &str| {
+            doc[name]
+                .as_u64()
+                .ok_or_else(|| io::Error::other(format!("{}: '{name}' missing", path.display())))
+        };
+        Ok(Self {
+            offset: field("offset")?,
+            next_chunk: field("next_chunk")? as usize,
+        })
+    }
+
+    pub fn save(&self, path: &Path, input: &Path) -> io::Result<()> {
+        let doc = json!({
+            "input": input.display().to_string(),
+            "offset": self.offset,
+            "next_chunk": self.next_chunk,
+        });
+        fs::write(path, format!("{doc:#}\n"))
+    }
+}
+
+/// Bytes of `input` to split: from `offset` (from the start if the input
+/// is shorter, i.e. rotated) to the end of its last complete line.
+pub fn pending_range(input: &Path, offset: u64) -> io::Result<Range<u64>> {
+    let mut file = fs::File::open(input)?;
+    let len = file.metadata()?.len();
+    let start = if offset > len { 0 } else { offset };
+    // search backwards for the last newline
+    let mut end = len;
+    let mut buf = vec![0u8; 64 * 1024];
+    while end > start {
+        let n = (end - start).min(buf.len() as u64) as usize;
+        file.seek(SeekFrom::Start(end - n as u64))?;
+        file.read_exact(&mut buf[..n])?;
+        match buf[..n].iter().rposition(|&b| b == b'\n') {
+            Some(i) => return Ok(start..end - n as u64 + i as u64 + 1),
+            None => end -= n as u64,
+        }
+    }
+    Ok(start..start)
+}
+
+/// Reader over `range` of `input`.
+pub fn open_range(input: &Path, range: &Range<u64>) -> io::Result<Box<dyn BufRead>> {
+    let mut file = fs::File::open(input)?;
+    file.seek(SeekFrom::Start(range.start))?;
+    Ok(Box::new(BufReader::new(file.take(range.end - range.start))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_range_stops_at_the_last_complete_line() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let input = dir.path().join("log.nt");
+        fs::write(&input, "a\nbb\ncc").unwrap();
+        assert_eq!(pending_range(&input, 0).unwrap(), 0..5);
+        assert_eq!(pending_range(&input, 5).unwrap(), 5..5);
+        fs::write(&input, "a\nbb\nccc\n").unwrap();
+        assert_eq!(pending_range(&input, 5).unwrap(), 5..9);
+        // rotated: shorter than the offset
+        fs::write(&input, "d\n").unwrap();
+        assert_eq!(pending_range(&input, 9).unwrap(), 0..2);
+    }
+
+    #[test]
+    fn state_round_trips() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = state_path(dir.path(), "log");
+        assert_eq!(TailState::load(&path).unwrap(), TailState::default());
+        let state = TailState {
+            offset: 1234,
+            next_chunk: 3,
+        };
+        state.save(&path, Path::new("log.nt")).unwrap();
+        assert_eq!(TailState::load(&path).unwrap(), state);
+    }
+}
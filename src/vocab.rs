@@ -0,0 +1,74 @@
+//! Well-known vocabulary IRIs (N-Triples form) and statement classifiers.
+
+pub const RDFS_NS: &str = "http://www.w3.org/2000/01/rdf-schema#";
+pub const OWL_NS: &str = "http://www.w3.org/2002/07/owl#";
+
+pub const RDF_TYPE: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#type>";
+pub const RDF_FIRST: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#first>";
+pub const RDF_REST: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#rest>";
+pub const RDF_NIL: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#nil>";
+pub const RDF_SUBJECT: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#subject>";
+pub const RDF_PREDICATE: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#predicate>";
+pub const RDF_OBJECT: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#object>";
+pub const RDF_STATEMENT: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#Statement>";
+pub const RDF_PROPERTY: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#Property>";
+
+/// `<iri>` → true when `iri` starts with `ns`.
+fn in_namespace(term: &str, ns: &str) -> bool {
+    term.strip_prefix('<')
+        .is_some_and(|iri| iri.starts_with(ns))
+}
+
+/// Heuristic TBox test for a single statement: RDFS schema predicates, OWL
+/// axioms, and `rdf:type` declarations of classes, properties and ontologies.
+/// Classification is per statement, so e.g. `rdfs:label` on a class stays in
+/// the instance data.
+pub fn is_tbox(predicate: &str, object: &str) -> bool {
+    if predicate == RDF_TYPE {
+        return object == RDF_PROPERTY
+            || in_namespace(object, RDFS_NS)
+            || (in_namespace(object, OWL_NS)
+                && object != "<http://www.w3.org/2002/07/owl#NamedIndividual>"
+                && object != "<http://www.w3.org/2002/07/owl#Thing>");
+    }
+    if in_namespace(predicate, RDFS_NS) {
+        return matches!(
+            &predicate[1 + RDFS_NS.len()..predicate.len() - 1],
+            "subClassOf" | "subPropertyOf" | "domain" | "range"
+        );
+    }
+    in_namespace(predicate, OWL_NS)
+        && !matches!(
+            &predicate[1 + OWL_NS.len()..predicate.len() - 1],
+            "sameAs" | "differentFrom"
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_and_property_declarations_are_tbox() {
+        let owl_class = "<http://www.w3.org/2002/07/owl#Class>";
+        assert!(is_tbox(RDF_TYPE, owl_class));
+        assert!(is_tbox(RDF_TYPE, RDF_PROPERTY));
+        assert!(is_tbox(
+            "<http://www.w3.org/2000/01/rdf-schema#subClassOf>",
+            "<http://ex.org/A>"
+        ));
+        assert!(is_tbox(
+            "<http://www.w3.org/2002/07/owl#inverseOf>",
+            "<http://ex.org/p>"
+        ));
+    }
+
+    #[test]
+    fn instance_statements_are_not_tbox() {
+        assert!(!is_tbox(RDF_TYPE, "<http://ex.org/Person>"));
+        assert!(!is_tbox(RDF_TYPE, "<http://www.w3.org/2002/07/owl#NamedIndividual>"));
+        assert!(!is_tbox("<http://www.w3.org/2002/07/owl#sameAs>", "<http://ex.org/b>"));
+        assert!(!is_tbox("<http://www.w3.org/2000/01/rdf-schema#label>", "\"A\""));
+        assert!(!is_tbox("<http://ex.org/p>", "<http://ex.org/o>"));
+    }
+}
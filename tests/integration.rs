@@ -24,6 +24,18 @@ fn out(dir: &TempDir) -> String {
     dir.path().to_str().unwrap().to_owned()
 }
 
+/// Write `content` to `name` inside `dir` and return its path.
+fn write_input(dir: &TempDir, name: &str, content: &str) -> String {
+    let path = dir.path().join(name);
+    fs::write(&path, content).unwrap();
+    path.to_str().unwrap().to_owned()
+}
+
+/// Number of statements in a chunk, parsed back with the library.
+fn statements(path: &std::path::Path) -> usize {
+    rdfsplitter::load_statements(path).unwrap().len()
+}
+
 // ── help / version ────────────────────────────────────────────────────────────
 
 #[test]
@@ -70,6 +82,34 @@ fn nt_file_count_single_file() {
     assert_eq!(count_files(&dir), 1);
 }
 
+#[test]
+fn chunk_size_max_caps_the_file_count_chunk_size() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "-c", "2", "--chunk-size-max", "3", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    // 10 triples would make 2 chunks of 5; the cap makes 4 chunks of at most 3
+    assert_eq!(count_files(&dir), 4);
+    assert_eq!(statements(&dir.path().join("small_0000.nt")), 3);
+}
+
+#[test]
+fn counting_up_to_a_limit_stops_there_and_keeps_real_errors() {
+    let dir = TempDir::new().unwrap();
+    let body = "<http://e/a> <http://e/p> \"1\" .\n\
+                <http://e/b> <http://e/p> \"2\" .\n\
+                <http://e/c> <http://e/p> \"3\" .\n\
+                not a statement\n";
+    let input = write_input(&dir, "in.nt", body);
+    let filter = rdfsplitter::StatementFilter::default();
+    let nt = rdfsplitter::RdfFormat::NTriples;
+    let count = |limit| rdfsplitter::count_records_up_to(input.as_ref(), nt, &filter, limit);
+    // the count stops past the limit, before the bad line
+    assert_eq!(count(1).unwrap(), 2);
+    assert!(matches!(count(5), Err(rdfsplitter::SplitterError::Parse(_))));
+}
+
 #[test]
 fn nt_output_files_have_nt_extension() {
     let dir = TempDir::new().unwrap();
@@ -127,6 +167,260 @@ fn nt_last_chunk_contains_remainder() {
     assert_eq!(triple_lines, 1);
 }
 
+#[test]
+fn nt_exact_chunks_produces_equal_chunks() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "--exact-chunks", "5", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    assert_eq!(count_files(&dir), 5);
+    for i in 0..5 {
+        let content = fs::read_to_string(dir.path().join(format!("small_{i:04}.nt"))).unwrap();
+        assert_eq!(content.lines().filter(|l| !l.trim().is_empty()).count(), 2);
+    }
+}
+
+#[test]
+fn nt_exact_chunks_fails_when_not_divisible() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "--exact-chunks", "3", "-o", &out(&dir), "-f"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be split into 3 equal chunks"));
+    assert_eq!(count_files(&dir), 0);
+}
+
+#[test]
+fn exact_chunks_counts_after_dedupe() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "d.nt",
+        "<http://ex.org/a> <http://ex.org/p> \"1\" .\n\
+         <http://ex.org/a> <http://ex.org/p> \"1\" .\n\
+         <http://ex.org/b> <http://ex.org/p> \"2\" .\n",
+    );
+    cmd()
+        .args([&input, "--dedupe", "--exact-chunks", "2", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("d_0000.nt")), 1);
+    assert_eq!(statements(&dir.path().join("d_0001.nt")), 1);
+}
+
+#[test]
+fn file_count_counts_after_dedupe() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let body: String = (0..8)
+        .map(|i| format!("<http://ex.org/s{i}> <http://ex.org/p> \"{i}\" .\n"))
+        .collect();
+    let input = write_input(&src, "d.nt", &body.repeat(4));
+    cmd()
+        .args([&input, "--dedupe", "-c", "4", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(count_files(&dir), 4);
+    for i in 0..4 {
+        assert_eq!(statements(&dir.path().join(format!("d_{i:04}.nt"))), 2);
+    }
+}
+
+#[test]
+fn exact_chunks_fails_when_kept_statements_make_chunks_uneven() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let rdf = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+    let input = write_input(
+        &src,
+        "l.nt",
+        &format!(
+            "<http://ex.org/s1> <http://ex.org/p> <http://ex.org/o1> .\n\
+             <http://ex.org/a> <http://ex.org/list> _:l1 .\n\
+             _:l1 <{rdf}first> <http://ex.org/x> .\n\
+             _:l1 <{rdf}rest> _:l2 .\n\
+             _:l2 <{rdf}first> <http://ex.org/y> .\n\
+             _:l2 <{rdf}rest> <{rdf}nil> .\n\
+             <http://ex.org/s3> <http://ex.org/p> <http://ex.org/o3> .\n\
+             <http://ex.org/s4> <http://ex.org/p> <http://ex.org/o4> .\n"
+        ),
+    );
+    cmd()
+        .args([&input, "--exact-chunks", "4", "-o", &out(&dir)])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("wrote chunks of 6, 2 record(s)"));
+    // the uneven chunks are not left behind
+    assert_eq!(count_files(&dir), 0);
+    cmd()
+        .args([&input, "--exact-chunks", "4", "--keep-axioms", "-o", &out(&dir), "-f"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("use --file-count"));
+}
+
+#[test]
+fn nt_max_bytes_caps_chunk_size_in_bytes() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "--max-bytes", "200", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    assert!(count_files(&dir) > 1);
+    let mut triples = 0;
+    for entry in fs::read_dir(dir.path()).unwrap() {
+        let path = entry.unwrap().path();
+        assert!(fs::metadata(&path).unwrap().len() <= 200, "{}", path.display());
+        triples += statements(&path);
+    }
+    assert_eq!(triples, 10);
+}
+
+#[test]
+fn adaptive_sizes_chunks_from_the_record_size() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    // 20 statements of 43 bytes each
+    let nt: String = (10..30)
+        .map(|i| format!("<http://ex.org/s{i}> <http://ex.org/p> \"x\" .\n"))
+        .collect();
+    let input = write_input(&src, "data.nt", &nt);
+    cmd()
+        .args([&input, "-n", "2", "--adaptive", "215", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    // the first chunk has --chunk-size statements, the next ones 215 / 43
+    assert_eq!(statements(&dir.path().join("data_0000.nt")), 2);
+    assert_eq!(statements(&dir.path().join("data_0001.nt")), 5);
+    assert_eq!(count_files(&dir), 5);
+}
+
+#[test]
+fn predicate_filters_drop_statements_before_chunking() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "mixed.nt",
+        "<http://ex.org/s1> <http://ex.org/name> \"a\" .\n\
+         <http://ex.org/s1> <http://ex.org/age> \"1\" .\n\
+         <http://ex.org/s2> <http://ex.org/name> \"b\" .\n\
+         <http://ex.org/s2> <http://ex.org/note> \"x\" .\n",
+    );
+    cmd()
+        .args([&input, "-n", "10", "-o", &out(&dir), "-f"])
+        .args(["--include-predicate", "http://ex.org/name"])
+        .args(["--include-predicate", "http://ex.org/note"])
+        .args(["--exclude-predicate", "http://ex.org/note"])
+        .assert()
+        .success();
+    let chunk = dir.path().join("mixed_0000.nt");
+    assert_eq!(statements(&chunk), 2);
+    assert!(!fs::read_to_string(&chunk).unwrap().contains("note"));
+}
+
+#[test]
+fn namespace_filters_select_subjects_and_objects() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "infra.nt",
+        "<http://ex.org/op/1> <http://ex.org/track> <http://ex.org/track/1> .\n\
+         <http://ex.org/op/1> <http://ex.org/name> \"Gent\" .\n\
+         <http://ex.org/op/2> <http://ex.org/track> <http://other.org/t> .\n\
+         <http://ex.org/line/1> <http://ex.org/track> <http://ex.org/track/2> .\n",
+    );
+    cmd()
+        .args([&input, "-n", "10", "-o", &out(&dir), "-f"])
+        .args(["--subject-prefix", "http://ex.org/op/"])
+        .args(["--object-prefix", "http://ex.org/track/"])
+        .assert()
+        .success();
+    let text = fs::read_to_string(dir.path().join("infra_0000.nt")).unwrap();
+    assert_eq!(
+        text,
+        "<http://ex.org/op/1> <http://ex.org/track> <http://ex.org/track/1> .\n"
+    );
+}
+
+#[test]
+fn low_memory_refuses_in_memory_grouping() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "--low-memory", "--group-by", "subject", "-o", &out(&dir)])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--group-by"));
+}
+
+#[test]
+fn low_memory_still_dedupes() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let line = "<http://ex.org/s> <http://ex.org/p> <http://ex.org/o> .\n";
+    let input = write_input(&src, "twice.nt", &line.repeat(2));
+    cmd()
+        .args([&input, "--low-memory", "--dedupe-inputs", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("twice_0000.nt")), 1);
+}
+
+#[test]
+fn filter_type_keeps_instances_with_their_blank_nodes() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "infra.ttl",
+        "@prefix ex: <http://ex.org/> .\n\
+         ex:t1 ex:length [ ex:value 12 ] .\n\
+         ex:op1 a ex:OperationalPoint ; ex:name \"Gent\" .\n\
+         ex:t1 a ex:Track .\n\
+         ex:t2 a ex:Track .\n",
+    );
+    cmd()
+        .args([&input, "-n", "100", "--filter-type", "http://ex.org/Track"])
+        .args(["--output-format", "nt", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    let text = fs::read_to_string(dir.path().join("infra_0000.nt")).unwrap();
+    // t1's blank node description comes before its type in the input
+    assert_eq!(statements(&dir.path().join("infra_0000.nt")), 4);
+    assert!(text.contains("<http://ex.org/value> \"12\""));
+    assert!(!text.contains("Gent"));
+}
+
+#[test]
+fn format_flag_reads_files_with_unknown_extensions() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(&src, "dump.dat", &fs::read_to_string(fixture("small.nt")).unwrap());
+    cmd()
+        .args([&input, "--format", "nt", "-n", "5", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(count_files(&dir), 2);
+    assert!(dir.path().join("dump_0000.nt").exists());
+}
+
+#[test]
+fn format_is_sniffed_when_the_name_has_no_extension() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(&src, "download", &fs::read_to_string(fixture("small.nq")).unwrap());
+    cmd()
+        .args([&input, "-n", "100", "-o", &out(&dir)])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("content looks like N-Quads"));
+    assert!(dir.path().join("download_0000.nq").exists());
+}
+
 // ── Turtle ────────────────────────────────────────────────────────────────────
 
 #[test]
@@ -153,6 +447,26 @@ fn ttl_output_files_have_ttl_extension() {
     assert!(files.iter().all(|f| f.ends_with(".ttl")));
 }
 
+#[test]
+fn ttl_chunks_keep_the_input_prefixes() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.ttl"), "-n", "3", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    let second = dir.path().join("small_0001.ttl");
+    let text = fs::read_to_string(&second).unwrap();
+    assert!(text.starts_with("@prefix ex: <http://example.org/> .\n"));
+    assert!(text.contains("ex:s4 ex:p ex:o4 .\n"));
+    // the chunk is a Turtle document of its own
+    let again = TempDir::new().unwrap();
+    cmd()
+        .args([second.to_str().unwrap(), "-n", "10", "-o", &out(&again), "-f"])
+        .assert()
+        .success();
+    assert_eq!(statements(&again.path().join("small_0001_0000.ttl")), 3);
+}
+
 // ── N-Quads ───────────────────────────────────────────────────────────────────
 
 #[test]
@@ -191,6 +505,96 @@ fn trig_chunk_size_produces_correct_file_count() {
     assert_eq!(count_files(&dir), 4);
 }
 
+#[test]
+fn prefix_option_compacts_converted_chunks() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "-n", "4", "--output-format", "ttl"])
+        .args(["--prefix", "ex=http://example.org/", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    let first = dir.path().join("small_0000.ttl");
+    let text = fs::read_to_string(&first).unwrap();
+    assert!(text.starts_with("@prefix ex: <http://example.org/> .\n"));
+    assert!(text.contains("\nex:s1 ex:p ex:o1 .\n"));
+    assert_eq!(statements(&first), 4);
+}
+
+#[test]
+fn trig_chunks_are_valid_trig() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.trig"), "-n", "3", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    let first = dir.path().join("small_0000.trig");
+    assert!(fs::read_to_string(&first)
+        .unwrap()
+        .contains("ex:g1 {\n    ex:s1 ex:p ex:o1 .\n"));
+    let again = TempDir::new().unwrap();
+    cmd()
+        .args([first.to_str().unwrap(), "-n", "10", "-o", &out(&again), "-f"])
+        .assert()
+        .success();
+    assert_eq!(statements(&again.path().join("small_0000_0000.trig")), 3);
+}
+
+#[test]
+fn sparql_style_and_version_directives_are_read() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "v12.trig",
+        "VERSION \"1.2\"\nPREFIX ex: <http://example.org/>\nBASE <http://example.org/>\n\
+         @version '1.2' .\nGRAPH ex:g { ex:s ex:p <o>, \"\"\"\nVERSION \"1.2\"\n\"\"\" . }\n",
+    );
+    cmd()
+        .args([&input, "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    let chunk = dir.path().join("v12_0000.trig");
+    let text = fs::read_to_string(&chunk).unwrap();
+    assert!(text.starts_with("@prefix ex: <http://example.org/> .\n"));
+    // a long literal looking like a directive is kept
+    assert!(text.contains(r#""\nVERSION \"1.2\"\n""#));
+    assert_eq!(statements(&chunk), 2);
+}
+
+// ── plain JSON ────────────────────────────────────────────────────────────────
+
+#[test]
+fn plain_json_warns_and_can_be_mapped() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "ops.json",
+        r#"{"items": [{"id": "BE 1", "name": "Zuid"}, {"id": 2}, {"id": 3, "name": "Noord"}]}"#,
+    );
+    cmd()
+        .args([&input, "-o", &out(&dir), "-f"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("no JSON-LD keywords"));
+    cmd()
+        .args([&input, "--json-pointer", "/items", "--output-format", "nt", "-o", &out(&dir), "-f"])
+        .args(["--json-map", r#"<http://ex.org/op/{id}> <http://ex.org/name> "{name}" ."#])
+        .assert()
+        .success();
+    let text = fs::read_to_string(dir.path().join("ops_0000.nt")).unwrap();
+    assert_eq!(
+        text,
+        "<http://ex.org/op/BE%201> <http://ex.org/name> \"Zuid\" .\n\
+         <http://ex.org/op/3> <http://ex.org/name> \"Noord\" .\n"
+    );
+    cmd()
+        .args([&input, "--json-map", "<http://ex.org/{id}> <http://ex.org/p> {v} ."])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("--json-map"));
+}
+
 // ── RDF/XML ───────────────────────────────────────────────────────────────────
 
 #[test]
@@ -243,131 +647,2776 @@ fn jsonld_output_files_have_jsonld_extension() {
     assert!(files.iter().all(|f| f.ends_with(".jsonld")));
 }
 
-// ── output directory / force ──────────────────────────────────────────────────
-
 #[test]
-fn force_creates_missing_output_directory() {
+fn jsonld_context_is_applied() {
+    let src = TempDir::new().unwrap();
     let dir = TempDir::new().unwrap();
-    let sub = dir.path().join("brand_new_dir");
-    assert!(!sub.exists());
+    let input = write_input(
+        &src,
+        "ops.jsonld",
+        r#"{
+          "@context": {
+            "@vocab": "http://ex.org/vocab#",
+            "ex": "http://ex.org/",
+            "next": { "@id": "ex:next", "@type": "@id" }
+          },
+          "@graph": [
+            { "@id": "ex:op1", "@type": "Station", "name": "Gent", "next": "ex:op2" },
+            { "@id": "ex:op2", "@type": "Station", "name": "Brugge" }
+          ]
+        }"#,
+    );
     cmd()
-        .args([
-            &fixture("small.nt"),
-            "-n", "10",
-            "-o", sub.to_str().unwrap(),
-            "-f",
-        ])
+        .args([&input, "--output-format", "nt", "-o", &out(&dir)])
         .assert()
         .success();
-    assert!(sub.exists());
+    let chunk = fs::read_to_string(dir.path().join("ops_0000.nt")).unwrap();
+    assert_eq!(chunk.lines().count(), 5);
+    assert!(chunk.contains("<http://ex.org/op1> <http://ex.org/next> <http://ex.org/op2> ."));
+    assert!(chunk.contains("<http://ex.org/op2> <http://ex.org/vocab#name> \"Brugge\" ."));
 }
 
 #[test]
-fn no_force_fails_when_output_directory_is_missing() {
+fn jsonld_chunks_are_compacted_against_a_given_context() {
     let dir = TempDir::new().unwrap();
-    let sub = dir.path().join("nonexistent");
+    let ctx = write_input(
+        &dir,
+        "ctx.json",
+        r#"{ "@context": { "ex": "http://example.org/", "p": { "@id": "ex:p", "@type": "@id" } } }"#,
+    );
     cmd()
-        .args([&fixture("small.nt"), "-n", "10", "-o", sub.to_str().unwrap()])
+        .args([
+            &fixture("small.nt"),
+            "--output-format",
+            "jsonld",
+            "--jsonld-context",
+            &ctx,
+            "-o",
+            &out(&dir),
+        ])
         .assert()
-        .failure();
+        .success();
+    let chunk_path = dir.path().join("small_0000.jsonld");
+    let chunk = fs::read_to_string(&chunk_path).unwrap();
+    assert!(chunk.contains(r#""@context": {"#));
+    assert!(chunk.contains(r#""@graph": ["#));
+    assert!(chunk.contains(r#""p": "ex:o1""#));
+    // the compacted chunk reads back to the same statements
+    assert_eq!(statements(&chunk_path), 10);
+
 }
 
 #[test]
-fn no_force_fails_when_output_file_already_exists() {
+fn jsonld_context_is_fetched_from_a_url() {
+    if std::process::Command::new("curl").arg("--version").output().is_err() {
+        return; // curl not installed
+    }
+    let url = serve(
+        r#"{ "@context": { "ex": "http://example.org/", "p": { "@id": "ex:p", "@type": "@id" } } }"#,
+        "application/ld+json",
+    );
     let dir = TempDir::new().unwrap();
-    // First run creates files
     cmd()
-        .args([&fixture("small.nt"), "-n", "10", "-o", &out(&dir), "-f"])
+        .args([&fixture("small.nt"), "--output-format", "jsonld", "-o", &out(&dir)])
+        .args(["--jsonld-context", &format!("{url}/ctx.jsonld")])
         .assert()
         .success();
-    // Second run without -f should fail because outputs exist
+    let chunk_path = dir.path().join("small_0000.jsonld");
+    let chunk = fs::read_to_string(&chunk_path).unwrap();
+    assert!(chunk.contains(r#""ex": "http://example.org/""#));
+    assert!(chunk.contains(r#""p": "ex:o1""#));
+    assert_eq!(statements(&chunk_path), 10);
+
+    let missing = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let closed = format!("http://{}/ctx.jsonld", missing.local_addr().unwrap());
+    drop(missing);
     cmd()
-        .args([&fixture("small.nt"), "-n", "10", "-o", &out(&dir)])
+        .args([&fixture("small.nt"), "--output-format", "jsonld", "-o", &out(&dir), "-f"])
+        .args(["--jsonld-context", &closed])
         .assert()
-        .failure();
+        .code(1)
+        .stderr(predicate::str::contains("--jsonld-context: download of"));
 }
 
 #[test]
-fn force_overwrites_existing_output_files() {
+fn jsonld_inputs_load_remote_contexts() {
+    if std::process::Command::new("curl").arg("--version").output().is_err() {
+        return; // curl not installed
+    }
+    let url = serve(
+        r#"{ "@context": { "@vocab": "http://ex.org/vocab#", "ex": "http://ex.org/" } }"#,
+        "application/ld+json",
+    );
+    let src = TempDir::new().unwrap();
     let dir = TempDir::new().unwrap();
+    let body = format!(r#"{{ "@context": "{url}/ctx.jsonld", "@id": "ex:op1", "name": "Gent" }}"#);
+    let input = write_input(&src, "ops.jsonld", &body);
     cmd()
-        .args([&fixture("small.nt"), "-n", "10", "-o", &out(&dir), "-f"])
-        .assert()
-        .success();
-    // Second run with -f must succeed
-    cmd()
-        .args([&fixture("small.nt"), "-n", "10", "-o", &out(&dir), "-f"])
+        .args([&input, "--output-format", "nt", "-o", &out(&dir)])
         .assert()
         .success();
+    let chunk = fs::read_to_string(dir.path().join("ops_0000.nt")).unwrap();
+    assert_eq!(
+        chunk,
+        "<http://ex.org/op1> <http://ex.org/vocab#name> \"Gent\" .\n"
+    );
 }
 
-// ── verbose output ────────────────────────────────────────────────────────────
-
 #[test]
-fn verbose_flag_prints_debug_info() {
+fn invalid_jsonld_inputs_fail() {
+    let src = TempDir::new().unwrap();
     let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "bad.jsonld",
+        r#"{ "@context": { "p": { "@id": 5 } }, "p": "x" }"#,
+    );
     cmd()
-        .args([&fixture("small.nt"), "-n", "10", "-o", &out(&dir), "-f", "-v"])
+        .args([&input, "-o", &out(&dir)])
         .assert()
-        .success()
-        .stderr(predicate::str::contains("writing chunk"));
+        .code(2);
 }
 
-// ── recursive ─────────────────────────────────────────────────────────────────
+// ── SPARQL query results ─────────────────────────────────────────────────────
 
 #[test]
-fn recursive_finds_nt_files_in_subdirectory() {
+fn srj_chunk_size_produces_correct_file_count() {
     let dir = TempDir::new().unwrap();
-    let fixtures_dir = format!("{}/tests/fixtures", env!("CARGO_MANIFEST_DIR"));
     cmd()
-        .args([&fixtures_dir, "-r", "-n", "100", "-o", &out(&dir), "-f"])
+        .args([&fixture("small.srj"), "-n", "3", "-o", &out(&dir), "-f"])
         .assert()
         .success();
-    // subdir/sub.nt has 6 triples in one chunk; plus all top-level fixtures → many files
-    assert!(count_files(&dir) > 0);
-    // The subdir fixture should produce sub_0000.nt
-    assert!(dir.path().join("sub_0000.nt").exists());
+    assert_eq!(count_files(&dir), 4);
 }
 
 #[test]
-fn without_recursive_flag_subdir_is_not_walked() {
+fn srj_chunks_keep_head_and_slice_bindings() {
     let dir = TempDir::new().unwrap();
-    let fixtures_dir = format!("{}/tests/fixtures", env!("CARGO_MANIFEST_DIR"));
-    // Pass the directory without -r; tool should still walk top-level only
-    // (top-level RDF files should still be processed)
     cmd()
-        .args([&fixtures_dir, "-n", "100", "-o", &out(&dir), "-f"])
+        .args([&fixture("small.srj"), "-n", "4", "-o", &out(&dir), "-f"])
         .assert()
         .success();
-    // sub.nt should NOT be present because -r was omitted
-    assert!(!dir.path().join("sub_0000.nt").exists());
+    let content = fs::read_to_string(dir.path().join("small_0002.srj")).unwrap();
+    let v: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(v["head"]["vars"][0], "s");
+    assert_eq!(v["results"]["bindings"].as_array().unwrap().len(), 2);
 }
 
-// ── glob patterns ─────────────────────────────────────────────────────────────
-
 #[test]
-fn glob_star_nt_matches_all_nt_fixtures() {
+fn srx_chunks_are_complete_results_documents() {
     let dir = TempDir::new().unwrap();
-    let pat = format!(
-        "{}/tests/fixtures/*.nt",
-        env!("CARGO_MANIFEST_DIR")
-    );
     cmd()
-        .args([&pat, "-n", "100", "-o", &out(&dir), "-f"])
+        .args([&fixture("small.srx"), "-n", "3", "-o", &out(&dir), "-f"])
         .assert()
         .success();
-    // small.nt → 1 chunk
-    assert!(dir.path().join("small_0000.nt").exists());
+    assert_eq!(count_files(&dir), 4);
+    let content = fs::read_to_string(dir.path().join("small_0000.srx")).unwrap();
+    assert!(content.contains(r#"<variable name="s"/>"#));
+    assert_eq!(content.matches("<result>").count(), 3);
+    assert!(content.trim_end().ends_with("</sparql>"));
 }
 
-// ── conflicting options ───────────────────────────────────────────────────────
+// ── gzip input / output ───────────────────────────────────────────────────────
 
 #[test]
-fn chunk_size_and_file_count_are_mutually_exclusive() {
+fn gzipped_input_is_decompressed_while_splitting() {
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+    let src = TempDir::new().unwrap();
     let dir = TempDir::new().unwrap();
+    let gz = src.path().join("small.nt.gz");
+    let mut enc = GzEncoder::new(fs::File::create(&gz).unwrap(), Compression::default());
+    enc.write_all(&fs::read(fixture("small.nt")).unwrap()).unwrap();
+    enc.finish().unwrap();
     cmd()
-        .args([
-            &fixture("small.nt"),
+        .arg(&gz)
+        .args(["-n", "1000", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(
+        statements(&dir.path().join("small_0000.nt")),
+        statements(std::path::Path::new(&fixture("small.nt")))
+    );
+}
+
+#[test]
+fn compress_gzip_writes_readable_gz_chunks() {
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "-n", "1000", "--compress", "gzip", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let path = dir.path().join("small_0000.nt.gz");
+    let mut gz = GzDecoder::new(fs::File::open(&path).unwrap());
+    let mut text = String::new();
+    gz.read_to_string(&mut text).unwrap();
+    assert_eq!(text, fs::read_to_string(fixture("small.nt")).unwrap().replace("\r\n", "\n"));
+    let header = gz.header().unwrap();
+    assert_eq!(header.filename(), Some(&b"small_0000.nt"[..]));
+    assert_eq!(header.comment(), Some(&b"source=small.nt chunk=0"[..]));
+}
+
+#[test]
+fn compress_threads_write_multi_member_gzip() {
+    use flate2::read::MultiGzDecoder;
+    use std::io::Read;
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "-n", "1000", "--compress", "gzip"])
+        .args(["--compress-level", "9", "--compress-threads", "4", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let path = dir.path().join("small_0000.nt.gz");
+    let mut text = String::new();
+    MultiGzDecoder::new(fs::File::open(&path).unwrap())
+        .read_to_string(&mut text)
+        .unwrap();
+    assert_eq!(text, fs::read_to_string(fixture("small.nt")).unwrap().replace("\r\n", "\n"));
+}
+
+#[test]
+fn chunks_past_max_open_files_are_reopened_as_new_gzip_members() {
+    use flate2::read::MultiGzDecoder;
+    use std::io::Read;
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let body: String = (0..3)
+        .flat_map(|round| {
+            ["a", "b", "c"]
+                .map(|h| format!("<http://{h}.org/s> <http://ex.org/p> \"{round}\" .\n"))
+        })
+        .collect();
+    let input = write_input(&src, "hosts.nt", &body);
+    cmd()
+        .args([&input, "--route-by-authority", "--compress", "gzip", "--max-open-files", "1"])
+        .args(["-o", &out(&dir)])
+        .assert()
+        .success();
+    for h in ["a", "b", "c"] {
+        let path = dir.path().join(format!("hosts_{h}.org_0000.nt.gz"));
+        let mut text = String::new();
+        MultiGzDecoder::new(fs::File::open(&path).unwrap())
+            .read_to_string(&mut text)
+            .unwrap();
+        let expected: String = (0..3)
+            .map(|round| format!("<http://{h}.org/s> <http://ex.org/p> \"{round}\" .\n"))
+            .collect();
+        assert_eq!(text, expected);
+    }
+}
+
+#[test]
+fn compress_level_requires_compress() {
+    cmd()
+        .args([&fixture("small.nt"), "--compress-level", "3"])
+        .assert()
+        .failure();
+}
+
+// ── encryption ────────────────────────────────────────────────────────────────
+
+#[test]
+fn encrypt_gpg_writes_only_encrypted_chunks() {
+    let home = TempDir::new().unwrap();
+    let gpg = |args: &[&str]| {
+        std::process::Command::new("gpg")
+            .env("GNUPGHOME", home.path())
+            .args(args)
+            .output()
+    };
+    let key = ["--batch", "--passphrase", "", "--quick-gen-key", "split@example.org", "default", "default", "never"];
+    match gpg(&key) {
+        Ok(o) if o.status.success() => {}
+        _ => return, // gpg not installed
+    }
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .env("GNUPGHOME", home.path())
+        .args([&fixture("small.nt"), "-n", "5", "--encrypt", "gpg:split@example.org", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(count_files(&dir), 2);
+    let chunk = dir.path().join("small_0000.nt.gpg");
+    assert!(!fs::read_to_string(&chunk).is_ok_and(|s| s.contains("<http")));
+    let plain = gpg(&["--batch", "--decrypt", chunk.to_str().unwrap()]).unwrap();
+    let text = String::from_utf8(plain.stdout).unwrap();
+    assert_eq!(text.lines().filter(|l| !l.trim().is_empty()).count(), 5);
+    let _ = std::process::Command::new("gpgconf")
+        .env("GNUPGHOME", home.path())
+        .args(["--kill", "gpg-agent"])
+        .status();
+}
+
+// ── remote inputs ─────────────────────────────────────────────────────────────
+
+/// Serve `body` as `content_type` to every request on a local port, for as
+/// long as the test runs; returns the base URL.
+fn serve(body: &'static str, content_type: &'static str) -> String {
+    use std::io::{BufRead, BufReader, Write};
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(&stream);
+            let mut request = String::new();
+            let _ = reader.read_line(&mut request);
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                line.clear();
+            }
+            let mut response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            if !request.starts_with("HEAD") {
+                response.push_str(body);
+            }
+            let _ = (&stream).write_all(response.as_bytes());
+        }
+    });
+    url
+}
+
+#[test]
+fn url_inputs_are_streamed_and_typed_by_content_type() {
+    if std::process::Command::new("curl").arg("--version").output().is_err() {
+        return; // curl not installed
+    }
+    let body = fs::read_to_string(fixture("small.nt")).unwrap();
+    let body: &'static str = Box::leak(body.into_boxed_str());
+    let url = serve(body, "application/n-triples; charset=utf-8");
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&format!("{url}/dumps/latest?version=2"), "-n", "4", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(count_files(&dir), 3);
+    assert_eq!(statements(&dir.path().join("latest_0000.nt")), 4);
+
+    cmd()
+        .args([&format!("{url}/ops.nt"), "--file-count", "2", "-o", &out(&dir)])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--file-count cannot be used with a URL input"));
+}
+
+#[test]
+fn url_content_type_wins_over_the_extension() {
+    if std::process::Command::new("curl").arg("--version").output().is_err() {
+        return; // curl not installed
+    }
+    let turtle = "@prefix ex: <http://ex.org/> .\nex:a ex:p ex:b .\nex:b ex:p ex:c .\n";
+    let url = format!("{}/ops.nt", serve(turtle, "application/x-turtle"));
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&url, "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("ops_0000.ttl")), 2);
+
+    // --trust-extension reads it as the N-Triples its name claims, and fails
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&url, "--trust-extension", "-o", &out(&dir)])
+        .assert()
+        .code(2);
+    assert_eq!(count_files(&dir), 0);
+}
+
+#[test]
+fn url_extension_wins_over_a_generic_content_type() {
+    if std::process::Command::new("curl").arg("--version").output().is_err() {
+        return; // curl not installed
+    }
+    let body = fs::read_to_string(fixture("small.nt")).unwrap();
+    let body: &'static str = Box::leak(body.into_boxed_str());
+    let url = serve(body, "application/json");
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&format!("{url}/small.nt"), "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("small_0000.nt")), 10);
+}
+
+// ── standard input / output ───────────────────────────────────────────────────
+
+#[test]
+fn stdin_is_split_into_stdin_named_chunks() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args(["-", "--format", "nt", "-n", "4", "-o", &out(&dir)])
+        .write_stdin(fs::read(fixture("small.nt")).unwrap())
+        .assert()
+        .success();
+    assert_eq!(count_files(&dir), 3);
+    assert_eq!(statements(&dir.path().join("stdin_0000.nt")), 4);
+}
+
+#[test]
+fn stdin_without_format_fails() {
+    cmd()
+        .args(["-", "-n", "4"])
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("requires --format"));
+}
+
+#[test]
+fn slow_stdin_chunks_are_closed_when_idle() {
+    use std::io::Write;
+    use std::time::{Duration, Instant};
+    let dir = TempDir::new().unwrap();
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("rdfsplitter"))
+        .args(["-", "--format", "nt", "--chunk-idle-timeout", "200ms", "-o", &out(&dir)])
+        .stdin(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let lines = fs::read_to_string(fixture("small.nt")).unwrap();
+    let lines: Vec<&str> = lines.lines().filter(|l| l.starts_with('<')).collect();
+    for line in &lines[..3] {
+        writeln!(stdin, "{line}").unwrap();
+    }
+    stdin.flush().unwrap();
+    // the producer is still running; the first chunk is closed all the same
+    let first = dir.path().join("stdin_0000.nt");
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while fs::read_to_string(&first).map_or(true, |c| c.lines().count() < 3) {
+        assert!(Instant::now() < deadline, "idle chunk was not closed");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    assert!(child.try_wait().unwrap().is_none());
+    for line in &lines[3..5] {
+        writeln!(stdin, "{line}").unwrap();
+    }
+    drop(stdin);
+    assert!(child.wait().unwrap().success());
+    assert_eq!(statements(&first), 3);
+    assert_eq!(statements(&dir.path().join("stdin_0001.nt")), 2);
+}
+
+#[test]
+fn chunk_idle_timeout_needs_line_based_stdin() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "--chunk-idle-timeout", "1s", "-o", &out(&dir)])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("applies to standard input"));
+    cmd()
+        .args(["-", "--format", "ttl", "--chunk-idle-timeout", "1s", "-o", &out(&dir)])
+        .write_stdin("")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("--format nt"));
+}
+
+#[test]
+fn stdout_marks_chunk_boundaries() {
+    let dir = TempDir::new().unwrap();
+    let output = cmd()
+        .args([&fixture("small.nt"), "-n", "4", "--stdout", "-o", &out(&dir)])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(count_files(&dir), 0);
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(text.matches("# rdfsplitter-chunk: small_").count(), 3);
+    assert!(text.starts_with("# rdfsplitter-chunk: small_0000.nt\n"));
+    assert_eq!(text.lines().filter(|l| l.starts_with('<')).count(), 10);
+}
+
+// ── hot/cold predicate partitioning ──────────────────────────────────────────
+
+#[test]
+fn partition_predicates_routes_hot_and_cold_series() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "mixed.nt",
+        "<http://ex.org/s1> <http://ex.org/name> \"a\" .\n\
+         <http://ex.org/s1> <http://ex.org/blob> \"b\" .\n\
+         <http://ex.org/s2> <http://ex.org/name> \"c\" .\n\
+         <http://ex.org/s2> <http://ex.org/blob> \"d\" .\n\
+         <http://ex.org/s3> <http://ex.org/blob> \"e\" .\n",
+    );
+    let hot = write_input(&src, "hot.txt", "# frequently queried\nhttp://ex.org/name\n");
+    cmd()
+        .args([&input, "--partition-predicates", &hot, "-n", "2", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("mixed_hot_0000.nt")), 2);
+    assert_eq!(statements(&dir.path().join("mixed_cold_0000.nt")), 2);
+    assert_eq!(statements(&dir.path().join("mixed_cold_0001.nt")), 1);
+    assert_eq!(count_files(&dir), 3);
+}
+
+// ── TBox separation ──────────────────────────────────────────────────────────
+
+#[test]
+fn separate_tbox_keeps_schema_in_one_file() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "onto.ttl",
+        "@prefix ex: <http://ex.org/> .\n\
+         @prefix owl: <http://www.w3.org/2002/07/owl#> .\n\
+         @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .\n\
+         ex:Person a owl:Class .\n\
+         ex:Student rdfs:subClassOf ex:Person .\n\
+         ex:knows a owl:ObjectProperty ; rdfs:domain ex:Person .\n\
+         ex:alice a ex:Person .\n\
+         ex:bob a ex:Student .\n\
+         ex:alice ex:knows ex:bob .\n",
+    );
+    cmd()
+        .args([&input, "--separate-tbox", "-n", "2", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    // 4 schema statements stay together despite -n 2
+    assert_eq!(statements(&dir.path().join("onto_tbox_0000.ttl")), 4);
+    assert!(!dir.path().join("onto_tbox_0001.ttl").exists());
+    assert_eq!(statements(&dir.path().join("onto_0000.ttl")), 2);
+    assert_eq!(statements(&dir.path().join("onto_0001.ttl")), 1);
+}
+
+#[test]
+fn separate_tbox_keeps_class_expressions_with_the_schema() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "onto.ttl",
+        "@prefix ex: <http://ex.org/> .\n\
+         @prefix owl: <http://www.w3.org/2002/07/owl#> .\n\
+         ex:Pet owl:equivalentClass [ owl:unionOf ( ex:Cat ex:Dog ) ] .\n\
+         ex:Colour owl:oneOf ( ex:red ex:green ) .\n\
+         ex:tom a ex:Cat .\n\
+         ex:tom ex:likes ( ex:rex ) .\n",
+    );
+    cmd()
+        .args([&input, "--separate-tbox", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    // the equivalence, the union and its 2 cells, oneOf and its 2 cells
+    assert_eq!(statements(&dir.path().join("onto_tbox_0000.ttl")), 11);
+    // the instance list stays with the instances
+    assert_eq!(statements(&dir.path().join("onto_0000.ttl")), 4);
+}
+
+// ── ontology header ──────────────────────────────────────────────────────────
+
+#[test]
+fn ontology_header_is_prepended_to_every_chunk() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let header = write_input(
+        &src,
+        "header.ttl",
+        "@prefix owl: <http://www.w3.org/2002/07/owl#> .\n\
+         <http://ex.org/onto> a owl:Ontology ;\n\
+             owl:imports <http://ex.org/core> .\n",
+    );
+    cmd()
+        .args([&fixture("small.nt"), "--ontology-header", &header, "-n", "4", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    assert_eq!(count_files(&dir), 3);
+    for (name, records) in [("small_0000.nt", 4), ("small_0002.nt", 2)] {
+        let content = fs::read_to_string(dir.path().join(name)).unwrap();
+        assert!(content.starts_with("<http://ex.org/onto> "));
+        assert!(content.contains("<http://www.w3.org/2002/07/owl#imports>"));
+        assert_eq!(statements(&dir.path().join(name)), records + 2);
+    }
+}
+
+// ── structure-preserving boundaries ──────────────────────────────────────────
+
+#[test]
+fn rdf_lists_are_never_split_across_chunks() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "lists.ttl",
+        "@prefix ex: <http://ex.org/> .\n\
+         ex:s1 ex:p ex:o1 .\n\
+         ex:s2 ex:items ( ex:a ex:b ex:c ) .\n\
+         ex:s3 ex:p ex:o3 .\n",
+    );
+    cmd()
+        .args([&input, "-n", "2", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    // the cut after 2 statements is deferred until the collection and its owner are complete
+    let first = fs::read_to_string(dir.path().join("lists_0000.ttl")).unwrap();
+    assert_eq!(first.matches("rdf-syntax-ns#first").count(), 3);
+    assert!(first.contains(" ex:items "));
+    assert_eq!(statements(&dir.path().join("lists_0000.ttl")), 8);
+    assert_eq!(statements(&dir.path().join("lists_0001.ttl")), 1);
+}
+
+#[test]
+fn owner_first_ntriples_lists_stay_with_their_owner() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let rdf = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+    let input = write_input(
+        &src,
+        "l.nt",
+        &format!(
+            "<http://ex.org/s1> <http://ex.org/p> <http://ex.org/o1> .\n\
+             <http://ex.org/a> <http://ex.org/list> _:l1 .\n\
+             _:l1 <{rdf}first> <http://ex.org/x> .\n\
+             _:l1 <{rdf}rest> _:l2 .\n\
+             _:l2 <{rdf}first> <http://ex.org/y> .\n\
+             _:l2 <{rdf}rest> <{rdf}nil> .\n\
+             <http://ex.org/s3> <http://ex.org/p> <http://ex.org/o3> .\n"
+        ),
+    );
+    cmd()
+        .args([&input, "-n", "2", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let first = fs::read_to_string(dir.path().join("l_0000.nt")).unwrap();
+    assert!(first.contains("<http://ex.org/list>"));
+    assert_eq!(first.matches("rdf-syntax-ns#first").count(), 2);
+    assert_eq!(statements(&dir.path().join("l_0000.nt")), 6);
+    assert_eq!(statements(&dir.path().join("l_0001.nt")), 1);
+}
+
+#[test]
+fn keep_axioms_keeps_owl_restrictions_whole() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "axioms.ttl",
+        "@prefix ex: <http://ex.org/> .\n\
+         @prefix owl: <http://www.w3.org/2002/07/owl#> .\n\
+         ex:C owl:equivalentClass [ a owl:Restriction ;\n\
+             owl:onProperty ex:p ; owl:someValuesFrom ex:D ] .\n\
+         ex:D a owl:Class .\n",
+    );
+    cmd()
+        .args([&input, "--keep-axioms", "-n", "1", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("axioms_0000.ttl")), 4);
+    assert_eq!(statements(&dir.path().join("axioms_0001.ttl")), 1);
+}
+
+#[test]
+fn bnode_strategy_clusters_or_renames_blank_nodes() {
+    let src = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "bnodes.nt",
+        "_:b0 <http://ex.org/p> \"1\" .\n\
+         _:b0 <http://ex.org/p> \"2\" .\n\
+         <http://ex.org/s> <http://ex.org/q> _:b0 .\n\
+         _:b1 <http://ex.org/p> \"3\" .\n",
+    );
+
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&input, "--bnode-strategy", "cluster", "-n", "1", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("bnodes_0000.nt")), 3);
+    assert_eq!(statements(&dir.path().join("bnodes_0001.nt")), 1);
+
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&input, "--bnode-strategy", "rename", "-n", "2", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let first = fs::read_to_string(dir.path().join("bnodes_0000.nt")).unwrap();
+    let second = fs::read_to_string(dir.path().join("bnodes_0001.nt")).unwrap();
+    assert!(first.starts_with("_:bnodes_0000_b0 <http://ex.org/p> \"1\" ."));
+    assert!(second.contains("<http://ex.org/s> <http://ex.org/q> _:bnodes_0001_b0 ."));
+    assert!(second.contains("_:bnodes_0001_b1 "));
+}
+
+#[test]
+fn chunk_header_and_footer_frame_every_chunk() {
+    let dir = TempDir::new().unwrap();
+    let header = write_input(&dir, "header.txt", "# licensed CC-BY-4.0\n");
+    let footer = write_input(&dir, "footer.txt", "# end of chunk\n");
+    let chunks = dir.path().join("chunks");
+    cmd()
+        .args([
+            &fixture("small.nt"),
+            "-n",
+            "6",
+            "--chunk-header",
+            &header,
+            "--chunk-footer",
+            &footer,
+            "-o",
+            chunks.to_str().unwrap(),
+            "-f",
+        ])
+        .assert()
+        .success();
+    for name in ["small_0000.nt", "small_0001.nt"] {
+        let chunk = fs::read_to_string(chunks.join(name)).unwrap();
+        assert!(chunk.starts_with("# licensed CC-BY-4.0\n<http://example.org/"));
+        assert!(chunk.ends_with(" .\n# end of chunk\n"));
+    }
+    assert_eq!(statements(&chunks.join("small_0001.nt")), 4);
+}
+
+#[test]
+fn keep_annotations_keeps_rdf_star_annotations_with_statement() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "star.ttl",
+        "@prefix ex: <http://ex.org/> .\n\
+         ex:a ex:b ex:c {| ex:src ex:x ; ex:date \"2020\" |} .\n\
+         ex:d ex:b ex:c .\n",
+    );
+    cmd()
+        .args([&input, "--keep-annotations", "all", "-n", "1", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("star_0000.ttl")), 3);
+    assert_eq!(statements(&dir.path().join("star_0001.ttl")), 1);
+}
+
+// ── link report ───────────────────────────────────────────────────────────────
+
+#[test]
+fn link_report_lists_references_into_other_chunks() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "linked.nt",
+        "<http://ex.org/a> <http://ex.org/knows> <http://ex.org/b> .\n\
+         <http://ex.org/b> <http://ex.org/name> \"B\" .\n",
+    );
+    let report = dir.path().join("links.csv");
+    cmd()
+        .args([&input, "-n", "1", "-o", &out(&dir), "-f", "--link-report"])
+        .arg(&report)
+        .assert()
+        .success();
+    let csv = std::fs::read_to_string(&report).unwrap();
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(rows[0], "source_chunk,target_chunk,references,examples");
+    assert_eq!(rows.len(), 2);
+    assert!(rows[1].contains("linked_0000.nt"));
+    assert!(rows[1].contains(",1,\"<http://ex.org/b>\""));
+}
+
+#[test]
+fn load_order_lists_referenced_chunk_first() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "ordered.nt",
+        "<http://ex.org/a> <http://ex.org/knows> <http://ex.org/b> .\n\
+         <http://ex.org/b> <http://ex.org/name> \"B\" .\n",
+    );
+    let order = dir.path().join("order.txt");
+    cmd()
+        .args([&input, "-n", "1", "-o", &out(&dir), "-f", "--load-order"])
+        .arg(&order)
+        .assert()
+        .success();
+    let listed = std::fs::read_to_string(&order).unwrap();
+    let lines: Vec<&str> = listed.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].ends_with("ordered_0001.nt"));
+    assert!(lines[1].ends_with("ordered_0000.nt"));
+}
+
+// ── RO-Crate ──────────────────────────────────────────────────────────────────
+
+#[test]
+fn ro_crate_describes_every_chunk() {
+    let dir = TempDir::new().unwrap();
+    let license = "https://creativecommons.org/licenses/by/4.0/";
+    cmd()
+        .args([&fixture("small.nt"), "-n", "4", "--ro-crate", "--license", license, "-o", &out(&dir)])
+        .assert()
+        .success();
+    let raw = fs::read_to_string(dir.path().join("ro-crate-metadata.json")).unwrap();
+    let v: serde_json::Value = serde_json::from_str(&raw).unwrap();
+    let graph = v["@graph"].as_array().unwrap();
+    let entity = |id: &str| graph.iter().find(|e| e["@id"] == id).unwrap();
+    let root = entity("./");
+    assert_eq!(root["@type"], "Dataset");
+    assert_eq!(root["license"]["@id"], license);
+    assert_eq!(root["hasPart"].as_array().unwrap().len(), 3);
+    let chunk = entity("small_0002.nt");
+    assert_eq!(chunk["encodingFormat"], "application/n-triples");
+    assert_eq!(chunk["contentSize"], fs::metadata(dir.path().join("small_0002.nt")).unwrap().len().to_string());
+    assert_eq!(entity("#split")["result"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn metadata_triples_are_stated_in_every_chunk() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([
+            &fixture("small.nt"),
+            "-n", "5",
+            "--metadata-triples",
+            "--title", "Small sample",
+            "--publisher", "https://example.org/org/matdata",
+            "-o", &out(&dir),
+        ])
+        .assert()
+        .success();
+    for i in 0..2 {
+        let content = fs::read_to_string(dir.path().join(format!("small_{i:04}.nt"))).unwrap();
+        assert!(content.contains("<http://purl.org/dc/terms/title> \"Small sample\""));
+        assert!(content.contains("<http://purl.org/dc/terms/publisher> <https://example.org/org/matdata>"));
+        assert_eq!(content.matches("<http://example.org/p>").count(), 5);
+    }
+}
+
+// ── run profiles ──────────────────────────────────────────────────────────────
+
+#[test]
+fn run_profile_applies_profile_options() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let profiles = write_input(
+        &src,
+        "profiles.toml",
+        &format!(
+            "[small]\ninputs = [{:?}]\nchunk-size = 3\noutput = {:?}\nforce = true\n",
+            fixture("small.nt"),
+            out(&dir)
+        ),
+    );
+    cmd()
+        .args(["run", "--profile", "small", "--profile-file", &profiles])
+        .assert()
+        .success();
+    assert_eq!(count_files(&dir), 4);
+}
+
+#[test]
+fn run_unknown_profile_fails() {
+    let src = TempDir::new().unwrap();
+    let profiles = write_input(&src, "profiles.toml", "[a]\ninputs = [\"x.nt\"]\n");
+    cmd()
+        .args(["run", "--profile", "b", "--profile-file", &profiles])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no profile 'b'"));
+}
+
+#[test]
+fn queue_moves_jobs_to_done_and_failed() {
+    let queue = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    write_input(
+        &queue,
+        "a.toml",
+        &format!(
+            "inputs = [{:?}]\nchunk-size = 5\noutput = {:?}\nforce = true\n",
+            fixture("small.nt"),
+            out(&dir)
+        ),
+    );
+    write_input(&queue, "b.json", r#"{"inputs": ["does-not-exist.nt"]}"#);
+    cmd()
+        .args(["queue", &out(&queue)])
+        .assert()
+        .code(2);
+    assert!(queue.path().join("done/a.toml").exists());
+    assert!(queue.path().join("failed/b.json").exists());
+    let report = fs::read_to_string(queue.path().join("done/a.toml.report.json")).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report["records"], 10);
+    assert_eq!(report["chunks"]["count"], 2);
+    assert_eq!(report["chunks"]["records"]["max"], 5);
+    assert_eq!(report["skipped"], serde_json::json!([]));
+    assert_eq!(count_files(&dir), 2);
+}
+
+#[test]
+fn queue_runs_convert_jobs_and_rejects_other_commands() {
+    let queue = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    write_input(
+        &queue,
+        "a.toml",
+        &format!(
+            "command = \"convert\"\ninputs = [{:?}]\nto = \"nt\"\noutput = {:?}\n",
+            fixture("small.ttl"),
+            out(&dir)
+        ),
+    );
+    write_input(
+        &queue,
+        "b.toml",
+        &format!("command = \"upload\"\ninputs = [{:?}]\n", fixture("small.ttl")),
+    );
+    cmd()
+        .args(["queue", &out(&queue)])
+        .assert()
+        .code(2);
+    let report = fs::read_to_string(queue.path().join("done/a.toml.report.json")).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report["files"], 1);
+    assert_eq!(report["records"], 10);
+    assert_eq!(report["chunks"]["count"], 1);
+    assert_eq!(statements(&dir.path().join("small.nt")), 10);
+    let report = fs::read_to_string(queue.path().join("failed/b.toml.report.json")).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+    let error = report["error"].as_str().unwrap();
+    assert!(error.contains("b.toml") && error.contains("unknown command \"upload\""), "{error}");
+}
+
+#[test]
+fn job_report_lists_skipped_inputs() {
+    let queue = TempDir::new().unwrap();
+    let data = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    fs::copy(fixture("small.nt"), data.path().join("small.nt")).unwrap();
+    let notes = write_input(&data, "notes.txt", "not RDF\n");
+    let mystery = write_input(&queue, "mystery.dat", "\u{1}\u{2}\u{3}\n");
+    write_input(
+        &queue,
+        "job.toml",
+        &format!(
+            "inputs = [{:?}, {:?}]\noutput = {:?}\nforce = true\n",
+            out(&data),
+            mystery,
+            out(&dir)
+        ),
+    );
+    cmd().args(["queue", &out(&queue)]).assert().success();
+    let report = fs::read_to_string(queue.path().join("done/job.toml.report.json")).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report["records"], 10);
+    assert_eq!(
+        report["skipped"],
+        serde_json::json!([
+            {"path": notes, "reason": "filtered out"},
+            {"path": mystery, "reason": "unknown extension"},
+        ])
+    );
+}
+
+#[test]
+fn stdin_list_reports_each_input() {
+    let dir = TempDir::new().unwrap();
+    let output = cmd()
+        .args(["--stdin-list", "--chunk-size", "5", "-o", &out(&dir)])
+        .write_stdin(format!("{}\n\ndoes-not-exist.nt\n", fixture("small.nt")))
+        .assert()
+        .code(2)
+        .get_output()
+        .stdout
+        .clone();
+    let reports: Vec<serde_json::Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(reports.len(), 2);
+    // the same lines as --json
+    assert_eq!(reports[0]["type"], "input");
+    assert_eq!(reports[0]["input"], fixture("small.nt"));
+    assert_eq!(reports[0]["status"], "done");
+    assert_eq!(reports[0]["records"], 10);
+    assert_eq!(reports[0]["chunks"].as_array().unwrap().len(), 2);
+    assert_eq!(reports[0]["chunks"][0]["records"], 5);
+    assert!(reports[0]["seconds"].is_f64());
+    assert_eq!(reports[1]["type"], "input");
+    assert_eq!(reports[1]["status"], "failed");
+    assert!(reports[1]["error"].is_string());
+    assert_eq!(reports[1]["input"], "does-not-exist.nt");
+    assert_eq!(count_files(&dir), 2);
+}
+
+#[test]
+fn json_reports_each_input_and_a_summary() {
+    let data = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let bad = write_input(&data, "bad.nt", "<a> <b> .\n");
+    let output = cmd()
+        .args([&fixture("small.nt"), &bad, "--json", "--chunk-size", "5", "-o", &out(&dir)])
+        .assert()
+        .code(2)
+        .get_output()
+        .stdout
+        .clone();
+    let lines: Vec<serde_json::Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 3);
+    let done = lines.iter().find(|l| l["status"] == "done").unwrap();
+    assert_eq!(done["type"], "input");
+    assert_eq!(done["input"], fixture("small.nt"));
+    assert_eq!(done["records"], 10);
+    assert_eq!(done["chunks"].as_array().unwrap().len(), 2);
+    assert_eq!(done["chunks"][0]["records"], 5);
+    assert!(done["seconds"].is_f64());
+    let failed = lines.iter().find(|l| l["input"] == bad).unwrap();
+    assert_eq!(failed["status"], "failed");
+    assert!(failed["error"].is_string());
+    let summary = &lines[2];
+    assert_eq!(summary["type"], "summary");
+    assert_eq!(summary["status"], "failed");
+    assert_eq!(summary["files"], 1);
+    assert_eq!(summary["records"], 10);
+    assert_eq!(summary["errors"], 1);
+    assert_eq!(summary["chunks"]["count"], 2);
+}
+
+// ── input verification ────────────────────────────────────────────────────────
+
+const WRONG_DIGEST: &str =
+    "sha256:0000000000000000000000000000000000000000000000000000000000000000";
+
+#[test]
+fn verify_input_mismatch_fails_without_output() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "--verify-input", WRONG_DIGEST, "-o", &out(&dir)])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Checksum mismatch"));
+    assert_eq!(count_files(&dir), 0);
+}
+
+#[test]
+fn verify_input_reads_sidecar() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(&src, "abc.nt", "<http://ex.org/a> <http://ex.org/b> \"abc\" .\n");
+    fs::write(
+        format!("{input}.sha256"),
+        "7dfeec29e04b62b9e8defb48bac65434e20b3dd46167ee56e291f575525e7f47  abc.nt\n",
+    )
+    .unwrap();
+    cmd()
+        .args([&input, "--verify-input", "sidecar", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(count_files(&dir), 1);
+}
+
+// ── IRI encoding ──────────────────────────────────────────────────────────────
+
+#[test]
+fn iri_encoding_percent_encodes_non_ascii() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "unicode.nt",
+        "<http://ex.org/Zürich> <http://ex.org/name> \"Zürich\" .\n",
+    );
+    cmd()
+        .args([&input, "--iri-encoding", "percent-encode", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let chunk = fs::read_to_string(dir.path().join("unicode_0000.nt")).unwrap();
+    assert!(chunk.contains("<http://ex.org/Z%C3%BCrich>"));
+    assert!(chunk.contains("\"Zürich\""));
+}
+
+// ── literal index ─────────────────────────────────────────────────────────────
+
+#[test]
+fn literal_index_writes_jsonl_sidecar_per_chunk() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "labels.nt",
+        "<http://ex.org/a> <http://ex.org/label> \"Alpha\"@en .\n\
+         <http://ex.org/a> <http://ex.org/size> \"3\"^^<http://www.w3.org/2001/XMLSchema#int> .\n\
+         <http://ex.org/b> <http://ex.org/label> \"Beta\" .\n",
+    );
+    cmd()
+        .args([&input, "-n", "2", "--literal-index", "jsonl", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let first = fs::read_to_string(dir.path().join("labels_0000.literals.jsonl")).unwrap();
+    let rec: serde_json::Value = serde_json::from_str(first.trim()).unwrap();
+    assert_eq!(first.lines().count(), 1);
+    assert_eq!(rec["subject"], "http://ex.org/a");
+    assert_eq!(rec["value"], "Alpha");
+    assert_eq!(rec["lang"], "en");
+    let second = fs::read_to_string(dir.path().join("labels_0001.literals.jsonl")).unwrap();
+    assert!(second.contains("\"Beta\""));
+}
+
+// ── fragment description ──────────────────────────────────────────────────────
+
+#[test]
+fn describe_fragments_states_size_count_and_checksum() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "-n", "6", "--describe-fragments", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let description = dir.path().join("fragments.ttl");
+    let statements = rdfsplitter::load_statements(&description).unwrap();
+    let chunk = "/small_0001.nt>";
+    let object = |predicate: &str| {
+        statements
+            .iter()
+            .find(|q| q.triple.subject.ends_with(chunk) && q.triple.predicate.contains(predicate))
+            .map(|q| q.triple.object.clone())
+            .unwrap_or_else(|| panic!("no {predicate} for {chunk}"))
+    };
+    assert_eq!(object("void#triples"), "\"4\"^^<http://www.w3.org/2001/XMLSchema#integer>");
+    let size = fs::metadata(dir.path().join("small_0001.nt")).unwrap().len();
+    assert!(object("dcat#byteSize").starts_with(&format!("\"{size}\"")));
+    assert!(object("prov#wasDerivedFrom").ends_with("small.nt>"));
+    let text = fs::read_to_string(&description).unwrap();
+    let digest = rdfsplitter::HashAlgorithm::Sha256
+        .hex_digest(fs::File::open(dir.path().join("small_0001.nt")).unwrap())
+        .unwrap();
+    assert!(text.contains(&digest));
+}
+
+#[test]
+fn hash_chooses_the_checksum_digest() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([
+            &fixture("small.nt"),
+            "-n",
+            "6",
+            "--describe-fragments",
+            "--ro-crate",
+            "--hash",
+            "blake3",
+            "-o",
+            &out(&dir),
+        ])
+        .assert()
+        .success();
+    let digest = rdfsplitter::HashAlgorithm::Blake3
+        .hex_digest(fs::File::open(dir.path().join("small_0001.nt")).unwrap())
+        .unwrap();
+    let text = fs::read_to_string(dir.path().join("fragments.ttl")).unwrap();
+    assert!(text.contains("spdx:checksumAlgorithm_blake3"));
+    assert!(text.contains(&digest));
+    let crate_json = fs::read_to_string(dir.path().join("ro-crate-metadata.json")).unwrap();
+    assert!(crate_json.contains(&format!("\"blake3\": \"{digest}\"")));
+    assert!(!crate_json.contains("\"sha256\""));
+}
+
+#[test]
+fn manifest_lists_chunks_with_counts_and_checksums() {
+    let dir = TempDir::new().unwrap();
+    let meta = TempDir::new().unwrap();
+    let manifest_path = meta.path().join("manifest.json");
+    cmd()
+        .args([&fixture("small.nt"), "-n", "6", "-o", &out(&dir)])
+        .arg("--manifest")
+        .arg(&manifest_path)
+        .assert()
+        .success();
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap()).unwrap();
+    assert_eq!(manifest["checksum"], "sha256");
+    assert_eq!(manifest["records"], 10);
+    let chunks = manifest["chunks"].as_array().unwrap();
+    assert_eq!(chunks.len(), 2);
+    let chunk = dir.path().join("small_0001.nt");
+    assert_eq!(chunks[1]["path"], "small_0001.nt");
+    assert_eq!(chunks[1]["records"], 4);
+    assert_eq!(chunks[1]["bytes"], fs::metadata(&chunk).unwrap().len());
+    assert_eq!(chunks[1]["inputs"][0], fixture("small.nt"));
+    let digest = rdfsplitter::HashAlgorithm::Sha256
+        .hex_digest(fs::File::open(&chunk).unwrap())
+        .unwrap();
+    assert_eq!(chunks[1]["sha256"], digest);
+}
+
+#[test]
+fn xxh3_checksums_the_manifest_and_check_verifies_it() {
+    let dir = TempDir::new().unwrap();
+    let meta = TempDir::new().unwrap();
+    let manifest_path = meta.path().join("manifest.json");
+    cmd()
+        .args([&fixture("small.nt"), "-n", "6", "--hash", "xxh3", "-o", &out(&dir)])
+        .arg("--manifest")
+        .arg(&manifest_path)
+        .assert()
+        .success();
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap()).unwrap();
+    assert_eq!(manifest["checksum"], "xxh3");
+    let digest = rdfsplitter::HashAlgorithm::Xxh3
+        .hex_digest(fs::File::open(dir.path().join("small_0001.nt")).unwrap())
+        .unwrap();
+    assert_eq!(digest.len(), 32);
+    assert_eq!(manifest["chunks"][1]["xxh3"], digest);
+    cmd()
+        .args(["check", &out(&dir), "--manifest"])
+        .arg(&manifest_path)
+        .assert()
+        .success();
+    cmd()
+        .args([&fixture("small.nt"), "--hash", "xxh3", "--describe-fragments", "-o", &out(&dir)])
+        .args(["-f"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("use --hash blake3"));
+}
+
+#[test]
+fn chunks_of_every_output_format_pass_check() {
+    let src = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "mixed.nt",
+        "<http://ex.org/a> <http://ex.org/ns#knows> _:b1 .\n\
+         _:b1 <http://ex.org/ns#name> \"say \\\"hi\\\" \\\\ bye\"@en .\n\
+         _:b1 <http://ex.org/ns#age> \"7\"^^<http://www.w3.org/2001/XMLSchema#int> .\n\
+         <http://ex.org/a> <http://ex.org/ns#name> \"A\" .\n",
+    );
+    for format in ["ttl", "nt", "nq", "trig", "rdf", "jsonld"] {
+        let dir = TempDir::new().unwrap();
+        let meta = TempDir::new().unwrap();
+        let manifest = meta.path().join("manifest.json");
+        cmd()
+            .args([&input, "-n", "2", "--output-format", format, "-o", &out(&dir)])
+            .arg("--manifest")
+            .arg(&manifest)
+            .assert()
+            .success();
+        cmd()
+            .args(["check", &out(&dir), "--manifest"])
+            .arg(&manifest)
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("2 of 2 chunk(s) match"));
+        let total: usize = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| statements(&e.unwrap().path()))
+            .sum();
+        assert_eq!(total, 4, "{format}");
+    }
+}
+
+#[test]
+fn check_reports_chunks_that_drifted_from_the_manifest() {
+    let dir = TempDir::new().unwrap();
+    let meta = TempDir::new().unwrap();
+    let manifest = meta.path().join("manifest.json");
+    cmd()
+        .args([&fixture("small.nt"), "-n", "3", "-o", &out(&dir)])
+        .arg("--manifest")
+        .arg(&manifest)
+        .assert()
+        .success();
+    let check = || {
+        let mut c = cmd();
+        c.args(["check", &out(&dir), "--manifest"]).arg(&manifest);
+        c
+    };
+    check()
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("4 of 4 chunk(s) match"));
+
+    fs::remove_file(dir.path().join("small_0000.nt")).unwrap();
+    let second = dir.path().join("small_0001.nt");
+    let mut body = fs::read_to_string(&second).unwrap();
+    body = body.replacen("s4", "s5", 1);
+    fs::write(&second, body).unwrap();
+    fs::write(dir.path().join("small_0003.nt"), "<http://example.org/s10> <http\n").unwrap();
+    check()
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("small_0000.nt: missing"))
+        .stderr(predicate::str::contains("small_0001.nt: sha256 checksum"))
+        .stderr(predicate::str::contains("small_0003.nt: "))
+        .stderr(predicate::str::contains("1 of 4 chunk(s) match"));
+}
+
+// ── merge ─────────────────────────────────────────────────────────────────────
+
+#[test]
+fn merge_chunks_all_inputs_together() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let inputs: Vec<String> = (0..5)
+        .map(|i| {
+            write_input(
+                &src,
+                &format!("part{i}.nt"),
+                &format!(
+                    "<http://ex.org/s{i}> <http://ex.org/p> \"a\" .\n\
+                     <http://ex.org/s{i}> <http://ex.org/p> \"b\" .\n"
+                ),
+            )
+        })
+        .collect();
+    cmd()
+        .args(&inputs)
+        .args(["--merge", "all", "-c", "2", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("all_0000.nt")), 5);
+    assert_eq!(statements(&dir.path().join("all_0001.nt")), 5);
+    assert!(!dir.path().join("all_0002.nt").exists());
+    assert!(!dir.path().join("part0_0000.nt").exists());
+}
+
+#[test]
+fn merge_keeps_blank_nodes_of_inputs_apart() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let n1 = write_input(
+        &src,
+        "n1.nt",
+        "_:b0 <http://ex.org/q> \"x\" .\n_:b0 <http://ex.org/r> \"z\" .\n",
+    );
+    let n2 = write_input(&src, "n2.nt", "_:b0 <http://ex.org/q> \"y\" .\n");
+    cmd()
+        .args([&n1, &n2, "--merge", "all", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let quads = rdfsplitter::load_statements(&dir.path().join("all_0000.nt")).unwrap();
+    let subjects: std::collections::HashSet<_> =
+        quads.iter().map(|q| q.triple.subject.clone()).collect();
+    assert_eq!(quads.len(), 3);
+    assert_eq!(subjects.len(), 2);
+}
+
+#[test]
+fn merge_of_mixed_formats_writes_nquads() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let nt = write_input(&src, "a.nt", "<http://ex.org/a> <http://ex.org/p> \"1\" .\n");
+    let nq = write_input(
+        &src,
+        "b.nq",
+        "<http://ex.org/b> <http://ex.org/p> \"2\" <http://ex.org/g> .\n",
+    );
+    cmd()
+        .args([&nt, &nq, "--merge", "mixed", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let chunk = fs::read_to_string(dir.path().join("mixed_0000.nq")).unwrap();
+    assert_eq!(chunk.lines().count(), 2);
+    assert!(chunk.contains("<http://ex.org/g>"));
+}
+
+#[test]
+fn repack_keeps_blank_node_labels_shared_by_chunks() {
+    let src = TempDir::new().unwrap();
+    let chunks = TempDir::new().unwrap();
+    let merged = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "l.nt",
+        "_:b0 <http://ex.org/p> \"1\" .\n_:b0 <http://ex.org/p> \"2\" .\n",
+    );
+    cmd()
+        .args([&input, "-n", "1", "-o", &out(&chunks)])
+        .assert()
+        .success();
+    cmd()
+        .args(["repack", &out(&chunks), "--max-bytes", "1MB", "-o", &out(&merged)])
+        .assert()
+        .success();
+    let quads = rdfsplitter::load_statements(&merged.path().join("l_0000.nt")).unwrap();
+    assert_eq!(quads.len(), 2);
+    assert_eq!(quads[0].triple.subject, quads[1].triple.subject);
+}
+
+#[test]
+fn repack_merges_small_chunks_and_cuts_large_ones() {
+    let src = TempDir::new().unwrap();
+    let merged = TempDir::new().unwrap();
+    let cut = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "-n", "2", "-o", &out(&src)])
+        .assert()
+        .success();
+    assert_eq!(count_files(&src), 5);
+
+    cmd()
+        .args(["repack", &out(&src), "--max-bytes", "1MB", "-o", &out(&merged)])
+        .assert()
+        .success();
+    assert_eq!(count_files(&merged), 1);
+    assert_eq!(statements(&merged.path().join("small_0000.nt")), 10);
+
+    cmd()
+        .args(["repack", &out(&merged), "--max-bytes", "300", "-o", &out(&cut)])
+        .assert()
+        .success();
+    assert_eq!(count_files(&cut), 3);
+    let total: usize = fs::read_dir(cut.path())
+        .unwrap()
+        .map(|e| statements(&e.unwrap().path()))
+        .sum();
+    assert_eq!(total, 10);
+
+    cmd()
+        .args(["repack", &out(&src), "--max-bytes", "1MB", "-o", &out(&src), "-f"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("would overwrite the chunks being read"));
+}
+
+// ── hash partitions ───────────────────────────────────────────────────────────
+
+#[test]
+fn partitions_keep_each_subject_in_one_file() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let body: String = (0..20)
+        .flat_map(|s| (0..3).map(move |o| format!("<http://ex.org/s{s}> <http://ex.org/p> \"{o}\" .\n")))
+        .collect();
+    let input = write_input(&src, "data.nt", &body);
+    cmd()
+        .args([&input, "--partition-by", "subject", "--partitions", "4", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let mut files: Vec<_> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    files.sort();
+    assert!(files.len() > 1 && files.len() <= 4, "{files:?}");
+    assert!(files.iter().all(|f| f.starts_with("data_part_000") && f.ends_with(".nt")));
+    let mut total = 0;
+    for s in 0..20 {
+        let subject = format!("<http://ex.org/s{s}> ");
+        let holding: Vec<_> = files
+            .iter()
+            .filter(|f| fs::read_to_string(dir.path().join(f)).unwrap().contains(&subject))
+            .collect();
+        assert_eq!(holding.len(), 1, "{subject} is in {holding:?}");
+        total += fs::read_to_string(dir.path().join(holding[0]))
+            .unwrap()
+            .matches(&subject)
+            .count();
+    }
+    assert_eq!(total, 60);
+}
+
+#[test]
+fn partitions_keep_lists_with_their_owner() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let body: String = (0..8)
+        .map(|s| format!("<http://ex.org/s{s}> <http://ex.org/l> (1 2 3 4) .\n"))
+        .collect();
+    let input = write_input(&src, "lists.ttl", &body);
+    cmd()
+        .args([&input, "--partition-by", "subject", "--partitions", "4", "-o", &out(&dir)])
+        .args(["--output-format", "nt"])
+        .assert()
+        .success();
+    let mut total = 0;
+    for entry in fs::read_dir(dir.path()).unwrap() {
+        let quads = rdfsplitter::load_statements(&entry.unwrap().path()).unwrap();
+        let owners = quads.iter().filter(|q| q.triple.subject.starts_with('<')).count();
+        // every list is complete: four cells per owner, nothing left over
+        assert_eq!(quads.len(), owners * 9);
+        total += quads.len();
+    }
+    assert_eq!(total, 72);
+}
+
+/// `rdfsplitter ARGS` under `ulimit -n FILES`.
+#[cfg(unix)]
+fn cmd_with_file_limit(files: u32, args: &[&str]) -> Command {
+    let mut c = Command::new("sh");
+    c.arg("-c")
+        .arg(format!("ulimit -n {files} && exec \"$0\" \"$@\""))
+        .arg(assert_cmd::cargo::cargo_bin!("rdfsplitter"))
+        .args(args);
+    c
+}
+
+#[cfg(unix)]
+#[test]
+fn partitions_outnumber_the_open_file_limit() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let body: String = (0..2000)
+        .map(|s| format!("<http://ex.org/s{s}> <http://ex.org/p> \"{s}\" .\n"))
+        .collect();
+    let input = write_input(&src, "data.nt", &body);
+    cmd_with_file_limit(64, &[&input, "--partition-by", "subject", "--partitions", "500"])
+        .args(["-o", &out(&dir)])
+        .assert()
+        .success();
+    let files = count_files(&dir);
+    assert!(files > 64 && files <= 500, "{files} partitions");
+    let total: usize = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| statements(&e.unwrap().path()))
+        .sum();
+    assert_eq!(total, 2000);
+}
+
+#[test]
+fn append_accumulates_partitions_across_runs() {
+    let old = TempDir::new().unwrap();
+    let new = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let prefixed = |from: usize| -> String {
+        let body: String = (from..from + 10)
+            .map(|s| format!("ex:s{} ex:p \"{s}\" .\n", s % 12))
+            .collect();
+        format!("@prefix ex: <http://ex.org/> .\n{body}")
+    };
+    let first = write_input(&old, "data.ttl", &prefixed(0));
+    let second = write_input(&new, "data.ttl", &prefixed(10));
+    let split = |input: &str| {
+        let mut c = cmd();
+        c.args([input, "--partition-by", "subject", "--partitions", "3", "-o", &out(&dir)]);
+        c
+    };
+    split(&first).assert().success();
+    split(&second).assert().failure().stderr(predicate::str::contains("already exists"));
+    split(&second).arg("--append").assert().success();
+    let files: Vec<_> = fs::read_dir(dir.path()).unwrap().map(|e| e.unwrap().path()).collect();
+    assert!(files.len() <= 3, "{files:?}");
+    let mut total = 0;
+    for s in 0..12 {
+        let subject = format!("<http://ex.org/s{s}>");
+        let holding: Vec<_> = files
+            .iter()
+            .filter(|f| {
+                let quads = rdfsplitter::load_statements(f).unwrap();
+                quads.iter().any(|q| q.triple.subject == subject)
+            })
+            .collect();
+        assert_eq!(holding.len(), 1, "{subject} is in {holding:?}");
+    }
+    for f in &files {
+        total += statements(f);
+    }
+    assert_eq!(total, 20);
+    split(&second)
+        .args(["--append", "--output-format", "jsonld"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("--output-format nt"));
+}
+
+#[test]
+fn partitions_refuse_a_chunk_size() {
+    cmd()
+        .args([&fixture("small.nt"), "--partition-by", "subject", "--partitions", "4", "-n", "5"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn route_by_authority_writes_a_series_per_host() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "eu.nt",
+        "<https://data.deutschebahn.com/op/1> <http://ex.org/p> \"1\" .\n\
+         <https://data.sncf.fr/op/1> <http://ex.org/p> \"2\" .\n\
+         <https://Data.DeutscheBahn.com/op/2> <http://ex.org/p> \"3\" .\n\
+         _:b0 <http://ex.org/p> \"4\" .\n",
+    );
+    cmd()
+        .args([&input, "--route-by-authority", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("eu_data.deutschebahn.com_0000.nt")), 2);
+    assert_eq!(statements(&dir.path().join("eu_data.sncf.fr_0000.nt")), 1);
+    assert_eq!(statements(&dir.path().join("eu_0000.nt")), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn route_by_authority_keeps_more_hosts_than_open_files() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let body: String = (0..2)
+        .flat_map(|round| {
+            (0..300).map(move |h| {
+                format!("<http://h{h}.example.org/s> <http://ex.org/p> \"{round}\" .\n")
+            })
+        })
+        .collect();
+    let input = write_input(&src, "many.nt", &body);
+    cmd_with_file_limit(64, &[&input, "--route-by-authority", "--line-map", "-o", &out(&dir)])
+        .assert()
+        .success();
+    for h in [0, 150, 299] {
+        let chunk = dir.path().join(format!("many_h{h}.example.org_0000.nt"));
+        assert_eq!(statements(&chunk), 2);
+        let map = dir.path().join(format!("many_h{h}.example.org_0000.lines.tsv"));
+        assert_eq!(fs::read_to_string(map).unwrap().lines().count(), 3);
+    }
+}
+
+// ── line map ──────────────────────────────────────────────────────────────────
+
+#[test]
+fn line_map_points_at_the_source_lines() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let body = "# header\n\
+                <http://ex.org/a> <http://ex.org/p> \"1\" .\n\
+                \n\
+                <http://ex.org/b> <http://ex.org/p> \"22\" .\n\
+                <http://ex.org/c> <http://ex.org/p> \"333\" .\n";
+    let input = write_input(&src, "lines.nt", body);
+    cmd()
+        .args([&input, "-n", "2", "--line-map", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let row = |line: usize| {
+        let text = body.split_inclusive('\n').nth(line - 1).unwrap();
+        let offset = body.find(text).unwrap();
+        format!("{line}\t{offset}\t{}", text.len())
+    };
+    let first = fs::read_to_string(dir.path().join("lines_0000.lines.tsv")).unwrap();
+    assert_eq!(
+        first,
+        format!("record\tline\toffset\tbytes\n1\t{}\n2\t{}\n", row(2), row(4))
+    );
+    let second = fs::read_to_string(dir.path().join("lines_0001.lines.tsv")).unwrap();
+    assert_eq!(second.lines().nth(1), Some(format!("1\t{}", row(5)).as_str()));
+}
+
+// ── stats ─────────────────────────────────────────────────────────────────────
+
+#[test]
+fn stats_reports_graphs_by_size() {
+    let src = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "graphs.nq",
+        "<http://ex.org/a> <http://ex.org/p> \"1\" <http://ex.org/g1> .\n\
+         <http://ex.org/b> <http://ex.org/p> \"2\" <http://ex.org/g2> .\n\
+         <http://ex.org/c> <http://ex.org/p> \"3\" <http://ex.org/g2> .\n",
+    );
+    let output = cmd().args(["stats", &input, "--top", "1"]).output().unwrap();
+    assert!(output.status.success());
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.contains("Statements: 3"));
+    assert!(report.contains("Graphs (top 1 of 2)"));
+    assert!(report.contains("<http://ex.org/g2>"));
+    assert!(!report.contains("<http://ex.org/g1>"));
+}
+
+#[test]
+fn stats_counts_class_instances() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "typed.nt",
+        "<http://ex.org/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://ex.org/C> .\n\
+         <http://ex.org/b> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://ex.org/C> .\n\
+         <http://ex.org/b> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://ex.org/D> .\n",
+    );
+    let csv = dir.path().join("classes.csv");
+    let output = cmd()
+        .args(["stats", &input, "--classes-csv"])
+        .arg(&csv)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.contains("Classes (top 2 of 2)"));
+    let csv = fs::read_to_string(&csv).unwrap();
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(rows, ["class,instances", "\"<http://ex.org/C>\",2", "\"<http://ex.org/D>\",1"]);
+}
+
+// ── explain ───────────────────────────────────────────────────────────────────
+
+#[test]
+fn explain_prints_effective_configuration() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "-n", "4", "--explain", "-o", &out(&dir)])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("chunk size      : 4 records"))
+        .stdout(predicate::str::contains("small.nt [N-Triples]"));
+    assert_eq!(count_files(&dir), 3);
+}
+
+#[test]
+fn run_logs_chunk_size_distribution() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "-n", "4", "-o", &out(&dir)])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Chunk records: min 2 / median 4 / max 4"));
+}
+
+// ── dated output ──────────────────────────────────────────────────────────────
+
+#[test]
+fn dated_output_nests_runs_by_date() {
+    let dir = TempDir::new().unwrap();
+    for _ in 0..2 {
+        cmd()
+            .args([&fixture("small.nt"), "-n", "5", "--dated-output", "-o", &out(&dir)])
+            .assert()
+            .success();
+    }
+    let days: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+    assert_eq!(days.len(), 1);
+    let day = days[0].as_ref().unwrap().path();
+    let name = day.file_name().unwrap().to_string_lossy().to_string();
+    assert_eq!(name.len(), 10);
+    assert_eq!(&name[4..5], "-");
+    let runs: Vec<_> = fs::read_dir(&day).unwrap().map(|e| e.unwrap().path()).collect();
+    assert_eq!(runs.len(), 2);
+    for run in runs {
+        assert_eq!(fs::read_dir(run).unwrap().count(), 2);
+    }
+}
+
+// ── deduplication ─────────────────────────────────────────────────────────────
+
+#[test]
+fn dedupe_inputs_drops_statements_seen_in_earlier_inputs() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let a = write_input(
+        &src,
+        "a.nt",
+        "<http://ex.org/s> <http://ex.org/p> \"1\" .\n\
+         <http://ex.org/s> <http://ex.org/p> \"2\" .\n",
+    );
+    let b = write_input(
+        &src,
+        "b.nt",
+        "<http://ex.org/s> <http://ex.org/p> \"2\" .\n\
+         <http://ex.org/s> <http://ex.org/p> \"3\" .\n",
+    );
+    cmd()
+        .args([&a, &b, "--dedupe-inputs", "-o", &out(&dir)])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("1 duplicate statement(s) dropped"));
+    assert_eq!(statements(&dir.path().join("a_0000.nt")), 2);
+    assert_eq!(statements(&dir.path().join("b_0000.nt")), 1);
+}
+
+#[test]
+fn dedupe_inputs_keeps_blank_nodes_of_other_inputs() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let line = "_:b0 <http://ex.org/q> \"x\" .\n";
+    let a = write_input(&src, "a.nt", &format!("{line}{line}"));
+    let b = write_input(&src, "b.nt", line);
+    cmd()
+        .args([&a, &b, "--dedupe-inputs", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("a_0000.nt")), 1);
+    assert_eq!(statements(&dir.path().join("b_0000.nt")), 1);
+}
+
+#[test]
+fn filter_expr_keeps_matching_statements() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "people.nt",
+        "<http://ex.org/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://xmlns.com/foaf/0.1/Person> .\n\
+         <http://ex.org/a> <http://xmlns.com/foaf/0.1/name> \"A\" .\n\
+         <http://ex.org/b> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://ex.org/Robot> .\n\
+         <http://ex.org/b> <http://ex.org/serial> \"7\" .\n",
+    );
+    cmd()
+        .args([
+            &input,
+            "--prefix",
+            "ex=http://ex.org/",
+            "--filter-expr",
+            "(?p = rdf:type && ?o = foaf:Person) || (isLiteral(?o) && ?s != ex:b)",
+            "-o",
+            &out(&dir),
+        ])
+        .assert()
+        .success();
+    let chunk = fs::read_to_string(dir.path().join("people_0000.nt")).unwrap();
+    assert_eq!(statements(&dir.path().join("people_0000.nt")), 2);
+    assert!(chunk.contains("foaf/0.1/Person"));
+    assert!(chunk.contains("\"A\""));
+
+    cmd()
+        .args([&input, "--filter-expr", "?p = nope:x", "-o", &out(&dir), "-f"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown prefix nope"));
+}
+
+#[test]
+fn dedupe_drops_repeats_within_an_input() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "dump.nt",
+        "<http://ex.org/s> <http://ex.org/p> \"1\" .\n\
+         <http://ex.org/s> <http://ex.org/p> \"2\" .\n\
+         <http://ex.org/s> <http://ex.org/p> \"1\" .\n\
+         <http://ex.org/s> <http://ex.org/p> \"2\" .\n",
+    );
+    cmd()
+        .args([&input, "-n", "2", "--dedupe", "-o", &out(&dir)])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("2 duplicate statement(s) dropped"));
+    assert_eq!(statements(&dir.path().join("dump_0000.nt")), 2);
+    assert!(!dir.path().join("dump_0001.nt").exists());
+}
+
+#[test]
+fn dedupe_key_triple_ignores_the_graph() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "graphs.nq",
+        "<http://ex.org/s> <http://ex.org/p> \"1\" <http://ex.org/g1> .\n\
+         <http://ex.org/s> <http://ex.org/p> \"1\" <http://ex.org/g2> .\n",
+    );
+    cmd()
+        .args([&input, "--dedupe", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("graphs_0000.nq")), 2);
+    cmd()
+        .args([&input, "--dedupe", "--dedupe-key", "triple", "-f", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let chunk = fs::read_to_string(dir.path().join("graphs_0000.nq")).unwrap();
+    assert_eq!(chunk.lines().count(), 1);
+    assert!(chunk.contains("<http://ex.org/g1>"));
+}
+
+// ── delta ─────────────────────────────────────────────────────────────────────
+
+#[test]
+fn delta_writes_added_and_removed_series() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let old = write_input(
+        &src,
+        "old.nt",
+        "<http://ex.org/s> <http://ex.org/p> \"1\" .\n\
+         <http://ex.org/s> <http://ex.org/p> \"2\" .\n",
+    );
+    let new = write_input(
+        &src,
+        "new.nq",
+        "<http://ex.org/s> <http://ex.org/p> \"2\" .\n\
+         <http://ex.org/s> <http://ex.org/p> \"3\" .\n\
+         <http://ex.org/s> <http://ex.org/p> \"4\" .\n",
+    );
+    cmd()
+        .args(["delta", &old, &new, "-n", "1", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let added = fs::read_to_string(dir.path().join("delta_added_0000.nq")).unwrap();
+    assert!(added.contains("\"3\""));
+    assert!(dir.path().join("delta_added_0001.nq").exists());
+    let removed = fs::read_to_string(dir.path().join("delta_removed_0000.nq")).unwrap();
+    assert!(removed.contains("\"1\""));
+    assert_eq!(count_files(&dir), 3);
+}
+
+#[test]
+fn delta_keeps_blank_nodes_of_dump_files_apart() {
+    let old = TempDir::new().unwrap();
+    let new = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let line = "_:b0 <http://ex.org/q> \"x\" .\n";
+    write_input(&old, "a.nt", line);
+    write_input(&new, "a.nt", line);
+    write_input(&new, "b.nt", line);
+    cmd()
+        .args(["delta", &out(&old), &out(&new), "-o", &out(&dir)])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("1 added, 0 removed"));
+}
+
+#[test]
+fn delta_writes_sparql_update_capped_by_bytes() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let old = write_input(&src, "old.nt", "<http://ex.org/s> <http://ex.org/p> \"0\" .\n");
+    let new = write_input(
+        &src,
+        "new.nt",
+        "<http://ex.org/s> <http://ex.org/p> \"1\" .\n\
+         <http://ex.org/s> <http://ex.org/p> \"2\" .\n\
+         <http://ex.org/s> <http://ex.org/p> \"3\" .\n",
+    );
+    cmd()
+        .args(["delta", &old, &new, "--delta-format", "sparql", "--max-bytes", "120"])
+        .args(["-o", &out(&dir)])
+        .assert()
+        .success();
+    let removed = fs::read_to_string(dir.path().join("delta_removed_0000.ru")).unwrap();
+    assert!(removed.starts_with("DELETE DATA {"));
+    let added = fs::read_to_string(dir.path().join("delta_added_0000.ru")).unwrap();
+    assert!(added.starts_with("INSERT DATA {"));
+    assert!(added.len() <= 120);
+    assert!(dir.path().join("delta_added_0001.ru").exists());
+}
+
+// ── long lines ────────────────────────────────────────────────────────────────
+
+#[test]
+fn max_line_bytes_rejects_oversized_line() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let long = "x".repeat(10_000);
+    let input = write_input(
+        &src,
+        "long.nt",
+        &format!("<http://ex.org/a> <http://ex.org/p> \"{long}\" .\n"),
+    );
+    cmd()
+        .args([&input, "--max-line-bytes", "1000", "-o", &out(&dir)])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("line 1 is longer than 1000 bytes"));
+    cmd()
+        .args([&input, "--max-line-bytes", "0", "-o", &out(&dir)])
+        .assert()
+        .success();
+}
+
+// ── bad records ───────────────────────────────────────────────────────────────
+
+#[test]
+fn skip_bad_records_passes_over_lines_that_do_not_parse() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let meta = TempDir::new().unwrap();
+    let mut body = fs::read_to_string(fixture("small.nt")).unwrap();
+    body.insert_str(0, "<http://ex.org/a> <http://ex.org/p> .\n");
+    body.push_str("<http://ex.org/b> <http://ex.org/p> \"open\n");
+    let input = write_input(&src, "dirty.nt", &body);
+    cmd().args([&input, "-o", &out(&dir)]).assert().code(2);
+
+    let rejects = meta.path().join("rejects.nt");
+    cmd()
+        .args([&input, "--skip-bad-records", "-o", &out(&dir), "-f", "--rejects"])
+        .arg(&rejects)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("line 1 skipped"))
+        .stderr(predicate::str::contains("2 bad record(s) skipped"));
+    assert_eq!(statements(&dir.path().join("dirty_0000.nt")), 10);
+    let rejected = fs::read_to_string(&rejects).unwrap();
+    assert_eq!(rejected.lines().count(), 4);
+    assert!(rejected.starts_with(&format!("# {input} line 1: ")));
+    assert!(rejected.contains("\n<http://ex.org/b> <http://ex.org/p> \"open\n"));
+
+    cmd()
+        .args([&input, "--skip-bad-records=1", "-o", &out(&dir), "-f"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("more than 1 bad record(s)"));
+}
+
+// ── output format ─────────────────────────────────────────────────────────────
+
+#[test]
+fn output_format_converts_turtle_to_ntriples() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.ttl"), "-n", "5", "--output-format", "nt", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("small_0000.nt")), 5);
+    assert!(!dir.path().join("small_0000.ttl").exists());
+}
+
+#[test]
+fn output_format_warns_when_graphs_are_dropped() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nq"), "--output-format", "jsonld", "-o", &out(&dir)])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("named graphs are not kept"));
+    assert!(dir.path().join("small_0000.jsonld").exists());
+}
+
+#[test]
+fn output_format_rejects_unknown_format() {
+    cmd()
+        .args([&fixture("small.ttl"), "--output-format", "csv"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected one of"));
+}
+
+// ── paths ─────────────────────────────────────────────────────────────────────
+
+#[test]
+fn relative_iris_resolve_against_input_with_spaces_in_path() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let sub = src.path().join("my data");
+    fs::create_dir(&sub).unwrap();
+    fs::write(sub.join("rel.ttl"), "<a> <b> <c> .\n").unwrap();
+    cmd()
+        .arg(sub.join("rel.ttl"))
+        .args(["--output-format", "nt", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let chunk = fs::read_to_string(dir.path().join("rel_0000.nt")).unwrap();
+    assert!(chunk.starts_with("<file://"));
+    assert!(chunk.contains("my%20data/a>"));
+}
+
+// ── group by ──────────────────────────────────────────────────────────────────
+
+#[test]
+fn group_by_subject_keeps_resources_in_one_chunk() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "people.nt",
+        "<http://ex.org/a> <http://ex.org/p> \"1\" .\n\
+         <http://ex.org/b> <http://ex.org/p> \"2\" .\n\
+         <http://ex.org/a> <http://ex.org/p> \"3\" .\n\
+         <http://ex.org/a> <http://ex.org/p> \"4\" .\n\
+         <http://ex.org/c> <http://ex.org/p> \"5\" .\n",
+    );
+    cmd()
+        .args([&input, "-n", "2", "--group-by", "subject", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let first = fs::read_to_string(dir.path().join("people_0000.nt")).unwrap();
+    assert_eq!(first.matches("<http://ex.org/a>").count(), 3);
+    assert_eq!(statements(&dir.path().join("people_0001.nt")), 2);
+    assert_eq!(count_files(&dir), 2);
+}
+
+#[test]
+fn group_by_graph_writes_whole_graphs_only() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "entities.nq",
+        "<http://ex.org/a> <http://ex.org/p> \"1\" <http://ex.org/g1> .\n\
+         <http://ex.org/a> <http://ex.org/p> \"2\" <http://ex.org/g1> .\n\
+         <http://ex.org/b> <http://ex.org/p> \"3\" <http://ex.org/g2> .\n\
+         <http://ex.org/a> <http://ex.org/p> \"4\" <http://ex.org/g1> .\n",
+    );
+    cmd()
+        .args([&input, "-n", "2", "--group-by", "graph", "-o", &out(&dir)])
+        .assert()
+        .success();
+    let first = fs::read_to_string(dir.path().join("entities_0000.nq")).unwrap();
+    assert_eq!(first.matches("<http://ex.org/g1>").count(), 3);
+    let second = fs::read_to_string(dir.path().join("entities_0001.nq")).unwrap();
+    assert_eq!(second.matches("<http://ex.org/g2>").count(), 1);
+    assert_eq!(count_files(&dir), 2);
+}
+
+#[test]
+fn group_by_connected_keeps_linked_records_together() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "records.nt",
+        "<http://ex.org/r1> <http://ex.org/title> \"One\" .\n\
+         <http://ex.org/r2> <http://ex.org/title> \"Two\" .\n\
+         <http://ex.org/r1> <http://ex.org/author> _:a .\n\
+         _:a <http://ex.org/name> \"Ann\" .\n\
+         <http://ex.org/p1> <http://ex.org/partOf> <http://ex.org/r1> .\n\
+         <http://ex.org/r2> <http://ex.org/seeAlso> <http://ex.org/r1> .\n",
+    );
+    cmd()
+        .args([
+            &input,
+            "-n",
+            "1",
+            "--group-by",
+            "connected",
+            "--connect-predicate",
+            "http://ex.org/partOf",
+            "-o",
+            &out(&dir),
+        ])
+        .assert()
+        .success();
+    let first = fs::read_to_string(dir.path().join("records_0000.nt")).unwrap();
+    assert_eq!(first.lines().count(), 4);
+    assert!(first.contains("<http://ex.org/p1> <http://ex.org/partOf>"));
+    assert_eq!(statements(&dir.path().join("records_0001.nt")), 2);
+    assert_eq!(count_files(&dir), 2);
+}
+
+// ── permissions ───────────────────────────────────────────────────────────────
+
+#[cfg(unix)]
+#[test]
+fn mode_and_dir_mode_apply_to_created_outputs() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = TempDir::new().unwrap();
+    let sub = dir.path().join("shared");
+    cmd()
+        .args([&fixture("small.nt"), "--mode", "0640", "--dir-mode", "0750", "-f", "-o"])
+        .arg(&sub)
+        .assert()
+        .success();
+    let mode = |p: &std::path::Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode(&sub), 0o750);
+    assert_eq!(mode(&sub.join("small_0000.nt")), 0o640);
+}
+
+// ── streaming chunks ──────────────────────────────────────────────────────────
+
+#[test]
+fn parse_error_removes_partially_written_chunk() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "broken.nt",
+        "<http://ex.org/a> <http://ex.org/p> \"1\" .\n\
+         <http://ex.org/b> <http://ex.org/p> \"2\" .\n\
+         <http://ex.org/c> <http://ex.org/p> \"3\" .\n\
+         <http://ex.org/d> <http://ex.org/p> .\n",
+    );
+    cmd()
+        .args([&input, "-n", "2", "-o", &out(&dir)])
+        .assert()
+        .code(2);
+    assert_eq!(statements(&dir.path().join("broken_0000.nt")), 2);
+    assert!(!dir.path().join("broken_0001.nt").exists());
+}
+
+#[test]
+fn staged_output_keeps_nothing_of_a_failed_input() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "broken.nt",
+        "<http://ex.org/a> <http://ex.org/p> \"1\" .\n\
+         <http://ex.org/b> <http://ex.org/p> \"2\" .\n\
+         <http://ex.org/c> <http://ex.org/p> \"3\" .\n\
+         <http://ex.org/d> <http://ex.org/p> .\n",
+    );
+    cmd()
+        .args([&input, "-n", "2", "--staged", "-o", &out(&dir)])
+        .assert()
+        .code(2);
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+
+    cmd()
+        .args([&fixture("small.nt"), "-n", "4", "--staged", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 3);
+    assert_eq!(statements(&dir.path().join("small_0000.nt")), 4);
+}
+
+// ── stratified sample ─────────────────────────────────────────────────────────
+
+#[test]
+fn stratified_sample_takes_n_instances_per_class() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let ty = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#type>";
+    let mut data = String::new();
+    for i in 0..20 {
+        data.push_str(&format!("<http://ex.org/big{i}> {ty} <http://ex.org/Big> .\n"));
+        data.push_str(&format!("<http://ex.org/big{i}> <http://ex.org/n> \"{i}\" .\n"));
+    }
+    for i in 0..2 {
+        data.push_str(&format!("<http://ex.org/small{i}> {ty} <http://ex.org/Small> .\n"));
+    }
+    let input = write_input(&src, "classes.nt", &data);
+    cmd()
+        .args([
+            &input,
+            "--stratified-sample",
+            "type=<http://ex.org/Big>:3",
+            "--stratified-sample",
+            "type=<http://ex.org/Small>:3",
+            "-o",
+            &out(&dir),
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("only 2 instance(s) of <http://ex.org/Small>"));
+    let chunk = fs::read_to_string(dir.path().join("classes_0000.nt")).unwrap();
+    assert_eq!(chunk.matches("<http://ex.org/Big> .").count(), 3);
+    assert_eq!(chunk.matches("<http://ex.org/n>").count(), 3);
+    assert_eq!(chunk.matches("<http://ex.org/Small> .").count(), 2);
+}
+
+// ── graph from file name ──────────────────────────────────────────────────────
+
+#[test]
+fn graph_from_filename_moves_triples_into_named_graphs() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let a = write_input(&src, "infra_2026.nt", "<http://ex.org/a> <http://ex.org/p> \"1\" .\n");
+    let other = write_input(&src, "notes.nt", "<http://ex.org/b> <http://ex.org/p> \"2\" .\n");
+    cmd()
+        .args([
+            &a,
+            &other,
+            "--graph-from-filename",
+            r"^(?<src>[a-z]+)_(\d+)\.nt$",
+            "--graph-iri",
+            "http://example.org/graph/${src}/$2",
+            "-o",
+            &out(&dir),
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("does not match --graph-from-filename"));
+    let quads = fs::read_to_string(dir.path().join("infra_2026_0000.nq")).unwrap();
+    assert_eq!(
+        quads.trim_end(),
+        "<http://ex.org/a> <http://ex.org/p> \"1\" <http://example.org/graph/infra/2026> ."
+    );
+    assert!(dir.path().join("notes_0000.nt").exists());
+}
+
+// ── output directory / force ──────────────────────────────────────────────────
+
+#[test]
+fn force_creates_missing_output_directory() {
+    let dir = TempDir::new().unwrap();
+    let sub = dir.path().join("brand_new_dir");
+    assert!(!sub.exists());
+    cmd()
+        .args([
+            &fixture("small.nt"),
+            "-n", "10",
+            "-o", sub.to_str().unwrap(),
+            "-f",
+        ])
+        .assert()
+        .success();
+    assert!(sub.exists());
+}
+
+#[test]
+fn min_free_space_fails_before_writing_when_the_output_would_not_fit() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "--min-free-space", "1000000000GB", "-o", &out(&dir)])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not enough free space"));
+    assert_eq!(count_files(&dir), 0);
+    cmd()
+        .args([&fixture("small.nt"), "--min-free-space", "1KB", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(count_files(&dir), 1);
+}
+
+#[test]
+fn no_force_fails_when_output_directory_is_missing() {
+    let dir = TempDir::new().unwrap();
+    let sub = dir.path().join("nonexistent");
+    cmd()
+        .args([&fixture("small.nt"), "-n", "10", "-o", sub.to_str().unwrap()])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn no_force_fails_when_output_file_already_exists() {
+    let dir = TempDir::new().unwrap();
+    // First run creates files
+    cmd()
+        .args([&fixture("small.nt"), "-n", "10", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    // Second run without -f should fail because outputs exist
+    cmd()
+        .args([&fixture("small.nt"), "-n", "10", "-o", &out(&dir)])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn force_overwrites_existing_output_files() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "-n", "10", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    // Second run with -f must succeed
+    cmd()
+        .args([&fixture("small.nt"), "-n", "10", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn preserve_structure_mirrors_the_input_tree() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    for sub in ["data/a", "data/b"] {
+        fs::create_dir_all(src.path().join(sub)).unwrap();
+        fs::copy(fixture("small.nt"), src.path().join(sub).join("x.nt")).unwrap();
+    }
+    cmd()
+        .current_dir(src.path())
+        .args(["data/a/x.nt", "data/b/x.nt", "-n", "10", "--staged"])
+        .args(["--preserve-structure", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("data/a/x_0000.nt")), 10);
+    assert_eq!(statements(&dir.path().join("data/b/x_0000.nt")), 10);
+    assert!(!dir.path().join("x_0000.nt").exists());
+}
+
+#[test]
+fn preserve_structure_resolves_parent_components() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    for sub in ["data", "b"] {
+        fs::create_dir_all(src.path().join(sub)).unwrap();
+    }
+    fs::copy(fixture("small.nt"), src.path().join("b/x.nt")).unwrap();
+    fs::copy(fixture("small.ttl"), src.path().join("b/x.ttl")).unwrap();
+    cmd()
+        .current_dir(src.path())
+        .args(["./data/../b/x.nt", "-n", "10", "--preserve-structure", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("b/x_0000.nt")), 10);
+    // only --suffix-input-hash still tells the chunks of both apart
+    cmd()
+        .current_dir(src.path())
+        .args(["b/x.nt", "b/x.ttl", "--output-format", "nt", "--preserve-structure"])
+        .args(["-o", &out(&dir), "-f"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("same names (use --suffix-input-hash)"));
+}
+
+#[test]
+fn inputs_sharing_a_stem_collide_unless_disambiguated() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    for year in ["2023", "2024"] {
+        fs::create_dir(src.path().join(year)).unwrap();
+        fs::copy(fixture("small.nt"), src.path().join(year).join("data.nt")).unwrap();
+    }
+    cmd()
+        .current_dir(src.path())
+        .args(["2023/data.nt", "2024/data.nt", "-n", "10", "-o", &out(&dir)])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("would write chunks of the same names"));
+    assert_eq!(count_files(&dir), 0);
+    cmd()
+        .current_dir(src.path())
+        .args(["2023/data.nt", "2024/data.nt", "-n", "10", "--suffix-input-hash"])
+        .args(["-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(count_files(&dir), 2);
+    for entry in fs::read_dir(dir.path()).unwrap() {
+        let name = entry.unwrap().file_name().into_string().unwrap();
+        assert!(name.starts_with("data_") && name.ends_with("_0000.nt") && name.len() == 21);
+    }
+}
+
+#[test]
+fn tail_splits_only_appended_records() {
+    let src = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let line = |i: usize| format!("<http://ex.org/s{i}> <http://ex.org/p> \"{i}\" .\n");
+    let mut body: String = (0..4).map(line).collect();
+    let partial = line(4);
+    body.push_str(&partial[..10]);
+    let input = write_input(&src, "log.nt", &body);
+    cmd()
+        .args([&input, "--tail", "-n", "2", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("log_0000.nt")), 2);
+    assert_eq!(statements(&dir.path().join("log_0001.nt")), 2);
+
+    body.push_str(&partial[10..]);
+    body.extend((5..7).map(line));
+    fs::write(&input, &body).unwrap();
+    cmd()
+        .args([&input, "--tail", "-n", "2", "-o", &out(&dir)])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("stale").not());
+    assert_eq!(statements(&dir.path().join("log_0002.nt")), 2);
+    assert_eq!(statements(&dir.path().join("log_0003.nt")), 1);
+    let last = fs::read_to_string(dir.path().join("log_0003.nt")).unwrap();
+    assert!(last.contains("<http://ex.org/s6>"));
+
+    // nothing appended: no new chunk
+    cmd()
+        .args([&input, "--tail", "-n", "2", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert!(!dir.path().join("log_0004.nt").exists());
+}
+
+#[test]
+fn resume_keeps_the_chunks_of_an_interrupted_run() {
+    let dir = TempDir::new().unwrap();
+    let run = || cmd().args([&fixture("small.nt"), "-n", "3", "--resume", "-o", &out(&dir)]).assert();
+    run().success();
+    let manifest_path = dir.path().join(".rdfsplitter-manifest.json");
+    let mut manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap()).unwrap();
+    assert_eq!(manifest["inputs"][fixture("small.nt")]["chunks"][3]["path"], "small_0003.nt");
+
+    // interrupted while writing the third chunk
+    let input = &mut manifest["inputs"][fixture("small.nt")];
+    input["complete"] = false.into();
+    input["chunks"].as_array_mut().unwrap().truncate(2);
+    fs::write(&manifest_path, manifest.to_string()).unwrap();
+    fs::write(dir.path().join("small_0002.nt"), "<http://example.org/s7> <http").unwrap();
+    fs::remove_file(dir.path().join("small_0003.nt")).unwrap();
+    // a kept chunk is not written again
+    let first = dir.path().join("small_0000.nt");
+    let kept = "#".repeat(fs::metadata(&first).unwrap().len() as usize);
+    fs::write(&first, &kept).unwrap();
+
+    run()
+        .success()
+        .stderr(predicate::str::contains("2 chunk(s) of an earlier run kept"));
+    assert_eq!(fs::read_to_string(&first).unwrap(), kept);
+    assert_eq!(statements(&dir.path().join("small_0002.nt")), 3);
+    assert_eq!(statements(&dir.path().join("small_0003.nt")), 1);
+
+    run()
+        .success()
+        .stderr(predicate::str::contains("split by an earlier run, passed over"));
+    // options that leave the chunks as they are may change between runs
+    cmd()
+        .args([&fixture("small.nt"), "-n", "3", "--resume", "-o", &out(&dir)])
+        .args(["--no-progress", "--max-open-files", "64", "--fail-fast", "--json"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("split by an earlier run, passed over"));
+    cmd()
+        .args([&fixture("small.nt"), "-n", "4", "--resume", "-o", &out(&dir)])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("written with other options"));
+}
+
+#[test]
+fn stale_chunks_of_an_earlier_run_are_reported_and_cleaned() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "-n", "2", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(count_files(&dir), 5);
+    cmd()
+        .args([&fixture("small.nt"), "-n", "5", "-f", "-o", &out(&dir)])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("3 file(s) named like this run's chunks"));
+    assert_eq!(count_files(&dir), 5);
+    cmd()
+        .args([&fixture("small.nt"), "-n", "5", "-f", "--clean-stale", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(count_files(&dir), 2);
+    assert!(!dir.path().join("small_0002.nt").exists());
+}
+
+// ── subcommands ───────────────────────────────────────────────────────────────
+
+#[test]
+fn split_and_merge_commands_take_the_split_options() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args(["split", &fixture("small.nt"), "-n", "4", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(count_files(&dir), 3);
+    cmd()
+        .args(["merge", "all", &fixture("small.nt"), &fixture("small.nq"), "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("all_0000.nq")), 20);
+}
+
+#[test]
+fn count_prints_statistics_per_input() {
+    cmd()
+        .args(["count", &fixture("small.nt"), &fixture("small.trig")])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("records\tsubjects\tpredicates\tgraphs\t"))
+        .stdout(predicate::str::contains("10\t10\t1\t1\t10\t0\t0\t"))
+        .stdout(predicate::str::contains("\n20\t10\t1\t1\t20\t0\t0\ttotal\n"));
+}
+
+#[test]
+fn count_json_reports_inputs_and_total() {
+    let src = TempDir::new().unwrap();
+    let input = write_input(
+        &src,
+        "mixed.nt",
+        "<http://ex.org/s> <http://ex.org/p> \"1\" .\n\
+         <http://ex.org/s> <http://ex.org/q> _:b .\n\
+         _:b <http://ex.org/p> <http://ex.org/o> .\n",
+    );
+    let output = cmd()
+        .args(["count", "--json", &input, &fixture("small.srj")])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let doc: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let counts = &doc["inputs"][0];
+    assert_eq!(counts["records"], 3);
+    assert_eq!(counts["subjects"], 2);
+    assert_eq!(counts["predicates"], 2);
+    assert_eq!(counts["graphs"], 0);
+    assert_eq!(counts["literal_objects"], 1);
+    assert_eq!(counts["blank_objects"], 1);
+    assert_eq!(counts["iri_objects"], 1);
+    assert_eq!(doc["inputs"][1]["records"], 10);
+    assert!(doc["inputs"][1].get("subjects").is_none());
+    assert_eq!(doc["total"]["records"], 13);
+}
+
+#[test]
+fn validate_fails_on_an_invalid_input() {
+    let src = TempDir::new().unwrap();
+    let bad = write_input(&src, "bad.nt", "<http://ex.org/s> <http://ex.org/p> .\n");
+    cmd()
+        .args(["validate", &fixture("small.ttl"), &bad])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("bad.nt: invalid"));
+}
+
+#[test]
+fn convert_writes_each_input_whole() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args(["convert", &fixture("small.ttl"), "--to", "nq", "-o", &out(&dir)])
+        .assert()
+        .success();
+    assert_eq!(statements(&dir.path().join("small.nq")), 10);
+    assert_eq!(count_files(&dir), 1);
+
+    let src = TempDir::new().unwrap();
+    let input = write_input(&src, "data.nt", "<http://ex.org/s> <http://ex.org/p> <http://ex.org/o> .\n");
+    cmd()
+        .args(["convert", &input, "--to", "nt", "-o", &out(&src), "-f"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("would overwrite the input"));
+    assert_eq!(statements(&src.path().join("data.nt")), 1);
+
+    cmd()
+        .args(["convert", &fixture("small.srj"), "--to", "nt", "-o", &out(&dir)])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "SPARQL Results JSON files hold query result rows, not RDF statements",
+        ));
+}
+
+#[test]
+fn generate_writes_reproducible_data() {
+    let dir = TempDir::new().unwrap();
+    let first = dir.path().join("a.nq");
+    cmd()
+        .args(["generate", "--triples", "1e3", "--classes", "5", "--format", "nq", "-o"])
+        .arg(&first)
+        .assert()
+        .success();
+    assert_eq!(statements(&first), 1000);
+    let again = cmd()
+        .args(["generate", "--triples", "1_000", "--classes", "5", "--format", "nq"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(fs::read(&first).unwrap(), again);
+    cmd()
+        .args(["generate", "--triples", "10", "-o"])
+        .arg(&first)
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn dry_run_lists_chunks_without_writing() {
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join("new");
+    let output = cmd()
+        .args([&fixture("small.nt"), "-c", "3", "--dry-run", "-f", "-o"])
+        .arg(&target)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let rows: Vec<String> = String::from_utf8(output).unwrap().lines().map(str::to_owned).collect();
+    assert_eq!(rows[0], "chunk\trecords\tbytes");
+    assert_eq!(rows.len(), 4);
+    assert!(rows[1].contains("small_0000.nt\t4\t"));
+    assert!(rows[3].contains("small_0002.nt\t2\t"));
+    assert!(!target.exists());
+}
+
+// ── fault injection ───────────────────────────────────────────────────────────
+
+#[test]
+fn failed_chunk_write_is_removed() {
+    let dir = TempDir::new().unwrap();
+    // each chunk of 4 statements is about 290 bytes: the first one fails
+    cmd()
+        .args([&fixture("small.nt"), "-n", "4", "-o", &out(&dir)])
+        .args(["--fault-inject", "after=200"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("injected write failure"));
+    assert_eq!(count_files(&dir), 0);
+}
+
+#[test]
+fn failed_staged_input_leaves_nothing() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "-n", "2", "--staged", "-o", &out(&dir)])
+        .args(["--fault-inject", "fail=0.2,seed=5"])
+        .assert()
+        .code(2);
+    assert_eq!(count_files(&dir), 0);
+}
+
+// ── verbose output ────────────────────────────────────────────────────────────
+
+#[test]
+fn verbose_flag_prints_debug_info() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([&fixture("small.nt"), "-n", "10", "-o", &out(&dir), "-f", "-v"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("writing chunk"));
+}
+
+#[test]
+fn progress_is_not_drawn_into_redirected_output() {
+    let dir = TempDir::new().unwrap();
+    for extra in [None, Some("--no-progress")] {
+        cmd()
+            .args([&fixture("small.nt"), "-n", "10", "-o", &out(&dir), "-f"])
+            .args(extra)
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("ETA").not())
+            .stderr(predicate::str::contains("\r").not());
+    }
+}
+
+// ── recursive ─────────────────────────────────────────────────────────────────
+
+#[test]
+fn recursive_finds_nt_files_in_subdirectory() {
+    let dir = TempDir::new().unwrap();
+    let fixtures_dir = format!("{}/tests/fixtures", env!("CARGO_MANIFEST_DIR"));
+    cmd()
+        .args([&fixtures_dir, "-r", "-n", "100", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    // subdir/sub.nt has 6 triples in one chunk; plus all top-level fixtures → many files
+    assert!(count_files(&dir) > 0);
+    // The subdir fixture should produce sub_0000.nt
+    assert!(dir.path().join("sub_0000.nt").exists());
+}
+
+#[test]
+fn without_recursive_flag_subdir_is_not_walked() {
+    let dir = TempDir::new().unwrap();
+    let fixtures_dir = format!("{}/tests/fixtures", env!("CARGO_MANIFEST_DIR"));
+    // Pass the directory without -r; tool should still walk top-level only
+    // (top-level RDF files should still be processed)
+    cmd()
+        .args([&fixtures_dir, "-n", "100", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    // sub.nt should NOT be present because -r was omitted
+    assert!(!dir.path().join("sub_0000.nt").exists());
+}
+
+// ── glob patterns ─────────────────────────────────────────────────────────────
+
+#[test]
+fn glob_star_nt_matches_all_nt_fixtures() {
+    let dir = TempDir::new().unwrap();
+    let pat = format!(
+        "{}/tests/fixtures/*.nt",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    cmd()
+        .args([&pat, "-n", "100", "-o", &out(&dir), "-f"])
+        .assert()
+        .success();
+    // small.nt → 1 chunk
+    assert!(dir.path().join("small_0000.nt").exists());
+}
+
+// ── conflicting options ───────────────────────────────────────────────────────
+
+#[test]
+fn chunk_size_and_file_count_are_mutually_exclusive() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .args([
+            &fixture("small.nt"),
             "-n", "10",
             "-c", "2",
             "-o", &out(&dir),
@@ -377,6 +3426,49 @@ fn chunk_size_and_file_count_are_mutually_exclusive() {
         .stderr(predicate::str::contains("cannot be used with"));
 }
 
+#[test]
+fn mismatched_option_combinations_are_refused_with_a_suggestion() {
+    let dir = TempDir::new().unwrap();
+    let refused = |args: &[&str], suggestion: &str| {
+        cmd()
+            .args(args)
+            .args(["-o", &out(&dir)])
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains(suggestion));
+    };
+    let nq = fixture("small.nq");
+    let nt = fixture("small.nt");
+    refused(
+        &[&nq, "--group-by", "graph", "--output-format", "nt"],
+        "use --output-format nq (or trig), or --group-by subject",
+    );
+    refused(&[&nt, "--group-by", "graph"], "Use --group-by subject");
+    refused(
+        &[&nt, "--group-by", "subject", "--connect-predicate", "http://ex.org/p"],
+        "use --group-by connected",
+    );
+    refused(
+        &[&nt, "--jsonld-context", "ctx.json"],
+        "add --output-format jsonld",
+    );
+    refused(&[&fixture("small.ttl"), "--line-map"], "rdfsplitter convert --to nt");
+    refused(&[&fixture("small.srj"), &nt, "--merge", "all"], "split them without --merge");
+    let srx = fixture("small.srx");
+    for filter in [
+        ["--include-predicate", "http://ex.org/p"],
+        ["--exclude-predicate", "http://ex.org/p"],
+        ["--subject-prefix", "http://ex.org/"],
+        ["--object-prefix", "http://ex.org/"],
+        ["--filter-expr", "?p = <http://ex.org/p>"],
+        ["--filter-type", "http://ex.org/C"],
+        ["--stratified-sample", "type=http://ex.org/C:5"],
+    ] {
+        refused(&[&srx, filter[0], filter[1]], "narrow the rows in the query");
+    }
+    assert_eq!(count_files(&dir), 0);
+}
+
 // ── nonexistent input ─────────────────────────────────────────────────────────
 
 #[test]
@@ -406,3 +3498,40 @@ fn multiple_input_files_all_split() {
     // each has 10 triples / 5 per chunk → 2 files each → 4 total
     assert_eq!(count_files(&dir), 4);
 }
+
+#[test]
+fn fail_fast_stops_at_the_first_failed_input() {
+    let data = TempDir::new().unwrap();
+    let bad = write_input(&data, "bad.nt", "<a> <b> .\n");
+    let split = |dir: &TempDir, flag: &str| {
+        cmd()
+            .args([&bad, &fixture("small.nt"), flag, "-o", &out(dir)])
+            .assert()
+            .code(2)
+    };
+    let kept_going = TempDir::new().unwrap();
+    split(&kept_going, "--keep-going");
+    assert_eq!(count_files(&kept_going), 1);
+
+    let stopped = TempDir::new().unwrap();
+    split(&stopped, "--fail-fast")
+        .stderr(predicate::str::contains("1 input(s) not split"));
+    assert_eq!(count_files(&stopped), 0);
+}
+
+#[test]
+fn strict_counts_skipped_inputs_as_failed() {
+    let data = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let mystery = write_input(&data, "mystery.dat", "\u{1}\u{2}\u{3}\n");
+    cmd()
+        .args([&mystery, &fixture("small.nt"), "-o", &out(&dir)])
+        .assert()
+        .success();
+    cmd()
+        .args([&mystery, &fixture("small.nt"), "--strict", "-o", &out(&dir), "-f"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("mystery.dat: unrecognised RDF extension"));
+    assert_eq!(count_files(&dir), 1);
+}